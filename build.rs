@@ -1,34 +1,221 @@
+/// The SPTAG library to link: the shared one, or its static build for
+/// the `static-link` feature
+const SPTAG_LIB: &str = if cfg!(feature = "static-link") {
+    "SPTAGLibStatic"
+} else {
+    "SPTAGLib"
+};
+
+/// How the target names and links native libraries. Read from the
+/// variables cargo sets, since `cfg!` in a build script describes the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Toolchain {
+    /// GCC-style: libX.so / libX.a, libstdc++ and libgomp
+    Gnu,
+    /// Visual C++: X.lib (import or static library) next to X.dll
+    Msvc,
+    /// Apple clang: libX.dylib / libX.a, libc++ and Homebrew's libomp
+    Apple,
+}
+
+impl Toolchain {
+    fn target() -> Self {
+        if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("macos") {
+            return Self::Apple;
+        }
+        match std::env::var("CARGO_CFG_TARGET_ENV").as_deref() {
+            Ok("msvc") => Self::Msvc,
+            _ => Self::Gnu,
+        }
+    }
+
+    /// File name of library `name` as the linker looks for it
+    fn lib_file(self, name: &str, static_link: bool) -> String {
+        match (self, static_link) {
+            (Self::Msvc, _) => format!("{}.lib", name),
+            (Self::Gnu | Self::Apple, true) => format!("lib{}.a", name),
+            (Self::Gnu, false) => format!("lib{}.so", name),
+            (Self::Apple, false) => format!("lib{}.dylib", name),
+        }
+    }
+
+    /// Where an in-tree SPTAG build leaves its libraries
+    fn default_lib_dir(self, sptag_path: &std::path::Path) -> std::path::PathBuf {
+        match self {
+            // Visual Studio builds go to x64/Release
+            Self::Msvc if sptag_path.join("x64/Release").exists() => sptag_path.join("x64/Release"),
+            _ => sptag_path.join("Release"),
+        }
+    }
+}
+
 fn main() {
+    // gRPC stubs; use the vendored protoc unless one is provided
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+        // SAFETY: build scripts are single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc) };
+    }
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_protos(&["proto/vector_search.proto"], &["proto"])
+        .expect("Failed to compile protos");
+
     println!("cargo:rerun-if-changed=src/spfresh_wrapper.cpp");
     println!("cargo:rerun-if-changed=SPFresh/");
+    println!("cargo:rerun-if-env-changed=SPFRESH_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=SPFRESH_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=FAISS_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=USEARCH_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=LIBOMP_PREFIX");
+
+    // FAISS_LIB_DIR / USEARCH_LIB_DIR: where libfaiss_c / libusearch_c are
+    // installed, if not on the default linker path (`faiss` / `usearch`
+    // features)
+    if let Some(faiss_lib) = env_dir("FAISS_LIB_DIR").filter(|_| cfg!(feature = "faiss")) {
+        println!("cargo:rustc-link-search=native={}", faiss_lib.display());
+    }
+    if let Some(usearch_lib) = env_dir("USEARCH_LIB_DIR").filter(|_| cfg!(feature = "usearch")) {
+        println!("cargo:rustc-link-search=native={}", usearch_lib.display());
+    }
+
+    // SPFRESH_INCLUDE_DIR: the SPFresh source root (the directory holding
+    // AnnService/). SPFRESH_LIB_DIR: where libSPTAGLib was built or installed.
+    // Both default to the in-tree checkout.
+    let toolchain = Toolchain::target();
+    let sptag_path = env_dir("SPFRESH_INCLUDE_DIR")
+        .unwrap_or_else(|| std::path::PathBuf::from("SPFresh/SPFresh"));
+    let lib_path = if cfg!(feature = "vendored") {
+        build_vendored(&sptag_path, toolchain)
+    } else {
+        env_dir("SPFRESH_LIB_DIR").unwrap_or_else(|| toolchain.default_lib_dir(&sptag_path))
+    };
 
-    let sptag_path = std::path::Path::new("SPFresh/SPFresh");
-    
-    // Forcing path to 'Release' as confirmed by user.
-    let lib_path = sptag_path.join("Release");
-    
     // Debug: print what files exist in the lib directory
     println!("cargo:warning=Looking for SPFresh libs in: {}", lib_path.display());
     if lib_path.exists() {
         if let Ok(entries) = std::fs::read_dir(&lib_path) {
             for entry in entries.flatten() {
                 if let Some(name) = entry.file_name().to_str() {
-                    if name.contains("SPTAG") || name.contains("Distance") || name.ends_with(".so") || name.ends_with(".a") {
+                    if name.contains("SPTAG") || name.contains("Distance") || name.ends_with(".so") || name.ends_with(".dylib") || name.ends_with(".a") || name.ends_with(".lib") {
                         println!("cargo:warning=Found lib file: {}", name);
                     }
                 }
             }
         }
     } else {
-        println!("cargo:warning=Library path confirmed by user does not exist: {}", lib_path.display());
+        println!(
+            "cargo:warning=SPFresh library directory does not exist: {} (set SPFRESH_LIB_DIR)",
+            lib_path.display()
+        );
+    }
+    if !sptag_path.join("AnnService/inc").exists() {
+        println!(
+            "cargo:warning=SPFresh headers not found under {} (set SPFRESH_INCLUDE_DIR)",
+            sptag_path.display()
+        );
+    }
+
+    let static_link = cfg!(feature = "static-link");
+
+    // Compile our C++ wrapper. It goes first: static archives must be named
+    // before the libraries they use.
+    let mut wrapper = cc::Build::new();
+    wrapper
+        .cpp(true)
+        .file("src/spfresh_wrapper.cpp")
+        .include(&sptag_path)
+        .include(sptag_path.join("AnnService"))
+        .include(sptag_path.join("AnnService/inc"))
+        .warnings(false);
+    match toolchain {
+        Toolchain::Gnu => {
+            wrapper
+                .flag("-std=c++14")
+                .flag("-O3")
+                .flag("-fopenmp")
+                // Explicitly link C++ stdlib (statically below for static-link)
+                .cpp_link_stdlib(if static_link { None } else { Some("stdc++") });
+        }
+        // The C++ runtime and OpenMP (vcomp) come in as default libraries
+        Toolchain::Msvc => {
+            wrapper
+                .flag("/std:c++14")
+                .flag("/O2")
+                .flag("/EHsc")
+                .flag("/openmp")
+                .cpp_link_stdlib(None);
+        }
+        // Apple clang has no OpenMP runtime of its own; it takes the
+        // pragmas through the preprocessor and libomp from Homebrew
+        Toolchain::Apple => {
+            wrapper
+                .include(libomp_prefix().join("include"))
+                .flag("-std=c++14")
+                .flag("-O3")
+                .flag("-Xpreprocessor")
+                .flag("-fopenmp")
+                .cpp_link_stdlib(Some("c++"));
+        }
     }
-    
+    wrapper.compile("spfresh_wrapper");
+
     // Link pre-built SPFresh libraries
     println!("cargo:rustc-link-search=native={}", lib_path.display());
-    
+
+    if toolchain == Toolchain::Msvc {
+        // SPTAGLib.lib is the import library of SPTAGLib.dll, which must sit
+        // next to the executable or on PATH at runtime
+        let kind = if static_link { "static" } else { "dylib" };
+        println!("cargo:rustc-link-lib={}={}", kind, SPTAG_LIB);
+        if lib_path.join(toolchain.lib_file("DistanceUtils", true)).exists() {
+            println!("cargo:rustc-link-lib=static=DistanceUtils");
+        }
+        return;
+    }
+
+    if toolchain == Toolchain::Apple {
+        // libc++ and the system libraries (pthread, m, dl) are all part of
+        // libSystem; there is no libgcc_s to link
+        let omp_lib = libomp_prefix().join("lib");
+        let kind = if static_link { "static" } else { "dylib" };
+        println!("cargo:rustc-link-search=native={}", omp_lib.display());
+        println!("cargo:rustc-link-lib={}={}", kind, SPTAG_LIB);
+        if lib_path.join(toolchain.lib_file("DistanceUtils", true)).exists() {
+            println!("cargo:rustc-link-lib=static=DistanceUtils");
+        }
+        println!("cargo:rustc-link-lib={}=omp", kind);
+        if !static_link {
+            // Find the dylibs where they were linked from, without
+            // DYLD_LIBRARY_PATH (which SIP strips from child processes)
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_path.display());
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", omp_lib.display());
+        }
+        return;
+    }
+
+    if static_link {
+        // SPTAG builds its static library as SPTAGLibStatic
+        println!("cargo:rustc-link-lib=static={}", SPTAG_LIB);
+        if lib_path.join("libDistanceUtils.a").exists() {
+            println!("cargo:rustc-link-lib=static=DistanceUtils");
+        }
+
+        // GCC runtime libraries are covered by the GCC Runtime Library
+        // Exception. They live in the compiler's own directory, so the final
+        // link finds them rather than rustc (-bundle). glibc stays dynamic.
+        println!("cargo:rustc-link-lib=static:-bundle=stdc++");
+        println!("cargo:rustc-link-lib=static:-bundle=gomp");
+        println!("cargo:rustc-link-arg=-static-libgcc");
+        println!("cargo:rustc-link-lib=dylib=pthread");
+        println!("cargo:rustc-link-lib=dylib=m");
+        println!("cargo:rustc-link-lib=dylib=dl");
+        return;
+    }
+
     // Try linking shared library first, fall back to static if needed
-    println!("cargo:rustc-link-lib=dylib=SPTAGLib");
-    
+    println!("cargo:rustc-link-lib=dylib={}", SPTAG_LIB);
+
     // DistanceUtils might not exist in all builds - try to link if available
     if lib_path.join("libDistanceUtils.so").exists() {
         println!("cargo:rustc-link-lib=dylib=DistanceUtils");
@@ -37,7 +224,7 @@ fn main() {
     } else {
         println!("cargo:warning=DistanceUtils library not found - SPFresh might not need it or it's embedded in SPTAGLib");
     }
-    
+
     // Link system libraries - order matters for some linkers
     // Link stdc++ dynamically
     println!("cargo:rustc-link-lib=dylib=stdc++");
@@ -46,18 +233,62 @@ fn main() {
     println!("cargo:rustc-link-lib=dylib=pthread");
     println!("cargo:rustc-link-lib=dylib=m");        // Math library
     println!("cargo:rustc-link-lib=dylib=dl");       // Dynamic loading
-    
-    // Compile our C++ wrapper
-    cc::Build::new()
-        .cpp(true)
-        .file("src/spfresh_wrapper.cpp")
-        .include(sptag_path)
-        .include(sptag_path.join("AnnService"))
-        .include(sptag_path.join("AnnService/inc"))
-        .flag("-std=c++14")
-        .flag("-O3")
-        .flag("-fopenmp")
-        .cpp_link_stdlib("stdc++")  // Explicitly link C++ stdlib
-        .warnings(false)
-        .compile("spfresh_wrapper");
+}
+
+/// Where libomp is installed for macOS builds: LIBOMP_PREFIX, else
+/// Homebrew's default for the target architecture (`brew install libomp`)
+fn libomp_prefix() -> std::path::PathBuf {
+    env_dir("LIBOMP_PREFIX").unwrap_or_else(|| {
+        match std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+            Ok("aarch64") => "/opt/homebrew/opt/libomp".into(),
+            _ => "/usr/local/opt/libomp".into(),
+        }
+    })
+}
+
+/// A directory named by an environment variable, if set and non-empty
+fn env_dir(name: &str) -> Option<std::path::PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(std::path::PathBuf::from)
+}
+
+/// Build SPTAG from the SPFresh sources at `source` with CMake and
+/// return the directory holding it (`vendored` feature)
+#[cfg(feature = "vendored")]
+fn build_vendored(source: &std::path::Path, toolchain: Toolchain) -> std::path::PathBuf {
+    if !source.join("CMakeLists.txt").exists() {
+        panic!(
+            "vendored: no SPFresh sources at {} (check SPFresh out there with its submodules, \
+             or set SPFRESH_INCLUDE_DIR)",
+            source.display()
+        );
+    }
+    if env_dir("SPFRESH_LIB_DIR").is_some() {
+        println!("cargo:warning=SPFRESH_LIB_DIR is ignored by the vendored build");
+    }
+
+    let mut config = cmake::Config::new(source);
+    config
+        .define("GPU", "OFF")
+        .define("LIBRARYONLY", "ON")
+        .build_target(SPTAG_LIB);
+    if toolchain == Toolchain::Apple {
+        // CMake's FindOpenMP doesn't look in Homebrew's keg-only prefix
+        config.define("OpenMP_ROOT", libomp_prefix());
+    }
+    let dst = config.build();
+
+    // SPTAG's CMakeLists sends its libraries to <source>/Release whatever
+    // the build directory is
+    let file_name = toolchain.lib_file(SPTAG_LIB, cfg!(feature = "static-link"));
+    [dst.join("build/Release"), dst.join("build"), source.join("Release"), source.join("x64/Release")]
+        .into_iter()
+        .find(|dir| dir.join(&file_name).exists())
+        .unwrap_or_else(|| panic!("vendored: CMake finished but {} was not found", file_name))
+}
+
+#[cfg(not(feature = "vendored"))]
+fn build_vendored(_source: &std::path::Path, _toolchain: Toolchain) -> std::path::PathBuf {
+    unreachable!("built without the vendored feature")
 }
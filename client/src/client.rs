@@ -0,0 +1,586 @@
+use crate::error::{Error, Result};
+use crate::models::*;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Async client for one server (or coordinator)
+///
+/// Cheap to clone; clones share the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    namespace: Option<String>,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+/// Settings for a [`Client`]
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    base_url: String,
+    api_key: Option<String>,
+    namespace: Option<String>,
+    timeout: Duration,
+    connect_timeout: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl ClientBuilder {
+    /// Sent as `Authorization: Bearer <key>`
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Sent as `X-Namespace`, for API keys with several tenant namespaces
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Per-attempt request timeout (default 30s)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Connection timeout (default 5s)
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Retries after the first attempt (default 3; 0 disables)
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled for each one after (default
+    /// 200ms); a longer `Retry-After` from the server wins
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let base_url = self.base_url.trim_end_matches('/').to_string();
+        if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+            return Err(Error::InvalidBaseUrl(self.base_url));
+        }
+
+        let http = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .build()?;
+
+        Ok(Client {
+            http,
+            base_url,
+            api_key: self.api_key,
+            namespace: self.namespace,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+        })
+    }
+}
+
+impl Client {
+    /// A client with default settings
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            api_key: None,
+            namespace: None,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(5),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(200),
+        }
+    }
+
+    /// `GET /health`
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.call(Method::GET, "/health", true, |r| r).await
+    }
+
+    /// `GET /health?deep=true`: probes the model, index and metadata store
+    pub async fn health_deep(&self) -> Result<HealthResponse> {
+        self.call(Method::GET, "/health", true, |r| r.query(&[("deep", true)]))
+            .await
+    }
+
+    /// `GET /readyz`; a server that is still loading is not an error
+    pub async fn readyz(&self) -> Result<ReadyzResponse> {
+        let response = self.send(Method::GET, "/readyz", true, |r| r).await?;
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(response.json().await?);
+        }
+        decode(response).await
+    }
+
+    /// `POST /reviews`
+    pub async fn add_review(&self, request: &AddReviewRequest) -> Result<AddReviewResponse> {
+        self.call(Method::POST, "/reviews", false, |r| r.json(request))
+            .await
+    }
+
+    /// `POST /reviews?durability=async`: acknowledged (202) once the
+    /// review is stored, before it is embedded and becomes searchable
+    pub async fn add_review_async(&self, request: &AddReviewRequest) -> Result<AddReviewResponse> {
+        let query = AddReviewQuery { durability: Durability::Async };
+        self.call(Method::POST, "/reviews", false, |r| r.query(&query).json(request))
+            .await
+    }
+
+    /// `GET /reviews/{id}`
+    pub async fn get_review(&self, vector_id: usize) -> Result<ReviewRecord> {
+        self.call(Method::GET, &format!("/reviews/{}", vector_id), true, |r| r)
+            .await
+    }
+
+    /// `GET /vectors/{id}`: the stored embedding of a review
+    pub async fn get_vector(&self, vector_id: usize) -> Result<VectorRecord> {
+        self.call(Method::GET, &format!("/vectors/{}", vector_id), true, |r| r)
+            .await
+    }
+
+    /// `PUT /reviews/{id}`: the updated review gets a new vector ID
+    ///
+    /// Retrying is safe: a repeat of an applied update fails with 409.
+    pub async fn update_review(
+        &self,
+        vector_id: usize,
+        request: &UpdateReviewRequest,
+    ) -> Result<ReviewRecord> {
+        self.call(Method::PUT, &format!("/reviews/{}", vector_id), true, |r| r.json(request))
+            .await
+    }
+
+    /// `DELETE /reviews/{id}`
+    pub async fn delete_review(&self, vector_id: usize, expected_revision: u64) -> Result<()> {
+        let query = DeleteReviewQuery { expected_revision };
+        let path = format!("/reviews/{}", vector_id);
+        let response = self.send(Method::DELETE, &path, true, |r| r.query(&query)).await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    /// `POST /reviews/delete_by_filter`: returns how many reviews were
+    /// tombstoned
+    pub async fn delete_by_filter(&self, filter: &ReviewFilter) -> Result<DeleteByFilterResponse> {
+        let request = DeleteByFilterRequest { filter: filter.clone() };
+        // Matches are already tombstoned on a retry, so it deletes nothing more
+        self.call(Method::POST, "/reviews/delete_by_filter", true, |r| r.json(&request))
+            .await
+    }
+
+    /// `POST /reviews/scroll`: one page of the reviews matching
+    /// `request.filter`; pass each page's `next_cursor` on to the next
+    pub async fn scroll(&self, request: &ScrollRequest) -> Result<ScrollResponse> {
+        self.call(Method::POST, "/reviews/scroll", true, |r| r.json(request))
+            .await
+    }
+
+    /// `POST /reviews/delete`: tombstones every listed review, reporting each
+    /// ID's outcome
+    pub async fn delete_reviews(&self, vector_ids: &[usize]) -> Result<DeleteReviewsResponse> {
+        let request = DeleteReviewsRequest {
+            vector_ids: vector_ids.to_vec(),
+        };
+        // A retry reports the already applied deletes as already_deleted
+        self.call(Method::POST, "/reviews/delete", true, |r| r.json(&request))
+            .await
+    }
+
+    /// `POST /reviews/search`
+    pub async fn search(&self, request: &SearchRequest) -> Result<SearchResponse> {
+        self.call(Method::POST, "/reviews/search", true, |r| r.json(request))
+            .await
+    }
+
+    /// `POST /reviews/answer`: an LLM answer grounded in the best-matching
+    /// reviews, citing their vector IDs
+    pub async fn answer(&self, request: &AnswerRequest) -> Result<AnswerResponse> {
+        self.call(Method::POST, "/reviews/answer", true, |r| r.json(request))
+            .await
+    }
+
+    /// `GET /suggest`: up to `limit` completions of `prefix`
+    pub async fn suggest(&self, prefix: &str, limit: usize) -> Result<SuggestResponse> {
+        let query = SuggestQuery {
+            q: prefix.to_string(),
+            limit,
+        };
+        self.call(Method::GET, "/suggest", true, |r| r.query(&query))
+            .await
+    }
+
+    /// `POST /collections`
+    pub async fn create_collection(
+        &self,
+        request: &CreateCollectionRequest,
+    ) -> Result<CollectionInfo> {
+        // A retried create that already went through answers 409
+        self.call(Method::POST, "/collections", true, |r| r.json(request))
+            .await
+    }
+
+    /// `GET /collections`
+    pub async fn list_collections(&self) -> Result<CollectionList> {
+        self.call(Method::GET, "/collections", true, |r| r).await
+    }
+
+    /// `GET /collections/{name}`
+    pub async fn collection(&self, name: &str) -> Result<CollectionInfo> {
+        self.call(Method::GET, &format!("/collections/{}", name), true, |r| r)
+            .await
+    }
+
+    /// `DELETE /collections/{name}`: removes the collection and its files
+    pub async fn drop_collection(&self, name: &str) -> Result<()> {
+        let path = format!("/collections/{}", name);
+        let response = self.send(Method::DELETE, &path, true, |r| r).await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    /// `GET /aliases`
+    pub async fn aliases(&self) -> Result<AliasList> {
+        self.call(Method::GET, "/aliases", true, |r| r).await
+    }
+
+    /// `PUT /aliases/{alias}`: create the alias or switch it to `collection`
+    pub async fn set_alias(&self, alias: &str, collection: &str) -> Result<AliasInfo> {
+        let request = SetAliasRequest {
+            collection: collection.to_string(),
+        };
+        let path = format!("/aliases/{}", alias);
+        self.call(Method::PUT, &path, true, |r| r.json(&request))
+            .await
+    }
+
+    /// `DELETE /aliases/{alias}`
+    pub async fn remove_alias(&self, alias: &str) -> Result<()> {
+        let path = format!("/aliases/{}", alias);
+        let response = self.send(Method::DELETE, &path, true, |r| r).await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    /// `POST /collections/{name}/reviews`
+    pub async fn add_collection_review(
+        &self,
+        name: &str,
+        request: &AddReviewRequest,
+    ) -> Result<AddReviewResponse> {
+        let path = format!("/collections/{}/reviews", name);
+        self.call(Method::POST, &path, false, |r| r.json(request))
+            .await
+    }
+
+    /// `POST /collections/{name}/search`
+    pub async fn search_collection(
+        &self,
+        name: &str,
+        request: &SearchRequest,
+    ) -> Result<SearchResponse> {
+        let path = format!("/collections/{}/search", name);
+        self.call(Method::POST, &path, true, |r| r.json(request))
+            .await
+    }
+
+    /// `POST /jobs/import`: starts a background import of `body`
+    pub async fn import(&self, body: Vec<u8>, format: ImportFormat) -> Result<JobProgress> {
+        self.call(Method::POST, "/jobs/import", false, |r| {
+            r.query(&[("format", format)]).body(body.clone())
+        })
+        .await
+    }
+
+    /// `POST /jobs/rebuild`: starts a background index rebuild
+    pub async fn rebuild(&self) -> Result<JobProgress> {
+        self.call(Method::POST, "/jobs/rebuild", false, |r| r).await
+    }
+
+    /// `GET /jobs/{id}`
+    pub async fn job(&self, id: u64) -> Result<JobProgress> {
+        self.call(Method::GET, &format!("/jobs/{}", id), true, |r| r)
+            .await
+    }
+
+    /// Poll a job every `interval` until it is no longer running
+    pub async fn wait_for_job(&self, id: u64, interval: Duration) -> Result<JobProgress> {
+        loop {
+            let progress = self.job(id).await?;
+            if progress.state != JobState::Running {
+                return Ok(progress);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// `GET /admin/audit`
+    pub async fn audit(&self, query: &AuditQuery) -> Result<AuditResponse> {
+        self.call(Method::GET, "/admin/audit", true, |r| r.query(query))
+            .await
+    }
+
+    /// `GET /admin/usage`
+    pub async fn usage(&self) -> Result<UsageResponse> {
+        self.call(Method::GET, "/admin/usage", true, |r| r).await
+    }
+
+    /// `GET /admin/analytics/top-queries`
+    pub async fn top_queries(&self, query: &AnalyticsQuery) -> Result<QueryReport> {
+        self.call(Method::GET, "/admin/analytics/top-queries", true, |r| r.query(query))
+            .await
+    }
+
+    /// `GET /admin/analytics/zero-result-queries`
+    pub async fn zero_result_queries(&self, query: &AnalyticsQuery) -> Result<QueryReport> {
+        self.call(Method::GET, "/admin/analytics/zero-result-queries", true, |r| {
+            r.query(query)
+        })
+        .await
+    }
+
+    /// `POST /admin/config/reload`
+    pub async fn reload_config(&self) -> Result<ConfigReloadResponse> {
+        self.call(Method::POST, "/admin/config/reload", true, |r| r)
+            .await
+    }
+
+    /// `POST /admin/reembed`: re-embed everything with `model` (default: the
+    /// configured one) and swap the new index in
+    pub async fn reembed(&self, model: Option<&str>) -> Result<JobProgress> {
+        self.call(Method::POST, "/admin/reembed", false, |r| match model {
+            Some(model) => r.query(&[("model", model)]),
+            None => r,
+        })
+        .await
+    }
+
+    /// `GET /admin/index/rebuild/status`: the latest rebuild or re-embed
+    pub async fn rebuild_status(&self) -> Result<JobProgress> {
+        self.call(Method::GET, "/admin/index/rebuild/status", true, |r| r)
+            .await
+    }
+
+    /// `GET /admin/shadow`
+    pub async fn shadow_status(&self) -> Result<ShadowStatus> {
+        self.call(Method::GET, "/admin/shadow", true, |r| r).await
+    }
+
+    /// `POST /admin/shadow/cutover`
+    pub async fn shadow_cutover(&self) -> Result<ShadowCutoverResponse> {
+        self.call(Method::POST, "/admin/shadow/cutover", false, |r| r)
+            .await
+    }
+
+    /// `GET /admin/log-level`
+    pub async fn log_level(&self) -> Result<LogLevelResponse> {
+        self.call(Method::GET, "/admin/log-level", true, |r| r).await
+    }
+
+    /// `PUT /admin/log-level`
+    pub async fn set_log_level(&self, level: &str) -> Result<LogLevelResponse> {
+        let request = LogLevelRequest {
+            level: level.to_string(),
+        };
+        self.call(Method::PUT, "/admin/log-level", true, |r| r.json(&request))
+            .await
+    }
+
+    /// `GET /admin/storage`: disk usage and free space
+    pub async fn storage_usage(&self) -> Result<DiskUsage> {
+        self.call(Method::GET, "/admin/storage", true, |r| r).await
+    }
+
+    /// `POST /admin/snapshots`: archive the index and metadata under a name
+    pub async fn create_snapshot(&self, name: Option<&str>) -> Result<SnapshotInfo> {
+        let request = CreateSnapshotRequest {
+            name: name.map(str::to_string),
+        };
+        self.call(Method::POST, "/admin/snapshots", false, |r| r.json(&request))
+            .await
+    }
+
+    /// `GET /admin/snapshots`
+    pub async fn snapshots(&self) -> Result<SnapshotList> {
+        self.call(Method::GET, "/admin/snapshots", true, |r| r).await
+    }
+
+    /// `GET /admin/snapshots/{name}/download`: the tar.gz archive; stream
+    /// the body with [`Response::chunk`]
+    pub async fn download_snapshot(&self, name: &str) -> Result<Response> {
+        let path = format!("/admin/snapshots/{}/download", name);
+        let response = self.send(Method::GET, &path, true, |r| r).await?;
+        check(response).await
+    }
+
+    /// `POST /admin/snapshots/restore`: replace the index and metadata with
+    /// a snapshot archive, e.g. one from [`download_snapshot`](Self::download_snapshot)
+    pub async fn restore_snapshot(&self, archive: Vec<u8>) -> Result<RestoreSnapshotResponse> {
+        self.call(Method::POST, "/admin/snapshots/restore", false, |r| {
+            r.body(archive.clone())
+        })
+        .await
+    }
+
+    /// `GET /replication/status` (primaries only)
+    pub async fn replication_status(&self) -> Result<ReplicationStatus> {
+        self.call(Method::GET, "/replication/status", true, |r| r)
+            .await
+    }
+
+    /// `GET /replication/metadata` (primaries only)
+    pub async fn replication_metadata(
+        &self,
+        query: &MetadataRangeQuery,
+    ) -> Result<Vec<ReviewMetadata>> {
+        self.call(Method::GET, "/replication/metadata", true, |r| r.query(query))
+            .await
+    }
+
+    /// `GET /replication/snapshot` (primaries only); stream the body with
+    /// [`Response::chunk`]
+    pub async fn replication_snapshot(&self) -> Result<Response> {
+        let response = self.send(Method::GET, "/replication/snapshot", true, |r| r).await?;
+        check(response).await
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        idempotent: bool,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<T> {
+        let response = self.send(method, path, idempotent, build).await?;
+        decode(response).await
+    }
+
+    /// Send with retries; the response may still carry an error status
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        idempotent: bool,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut attempt = 0;
+
+        loop {
+            let mut request = build(self.http.request(method.clone(), &url));
+            if let Some(key) = &self.api_key {
+                request = request.bearer_auth(key);
+            }
+            if let Some(namespace) = &self.namespace {
+                request = request.header("x-namespace", namespace);
+            }
+
+            let outcome = request.send().await;
+            let retry = match &outcome {
+                Ok(response) => should_retry_status(response.status(), idempotent),
+                Err(e) => should_retry_error(e, idempotent),
+            };
+            if !retry || attempt >= self.max_retries {
+                return Ok(outcome?);
+            }
+
+            // An overloaded server says how long to back off
+            let backoff = self.retry_backoff * 2u32.saturating_pow(attempt);
+            let retry_after = outcome.as_ref().ok().and_then(|response| retry_after(response.headers()));
+            tokio::time::sleep(retry_after.map_or(backoff, |after| after.max(backoff))).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// 429 and 503 are returned before a request is processed, so they're
+/// safe to retry for any call. Gateway errors only for idempotent calls.
+fn should_retry_status(status: StatusCode, idempotent: bool) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent,
+        _ => false,
+    }
+}
+
+/// `Retry-After` in seconds (the HTTP-date form isn't sent by the server)
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs = headers.get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// A request that never connected can always be retried; one that timed
+/// out may have been applied
+fn should_retry_error(error: &reqwest::Error, idempotent: bool) -> bool {
+    error.is_connect() || (idempotent && error.is_timeout())
+}
+
+/// Turn an error status into [`Error::Api`]
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let text = response.text().await?;
+    let body = serde_json::from_str(&text).unwrap_or_else(|_| ErrorResponse {
+        error: status.canonical_reason().unwrap_or("Error").to_string(),
+        message: text,
+        request_id: None,
+        violations: Vec::new(),
+        existing_id: None,
+    });
+    Err(Error::Api { status, body })
+}
+
+async fn decode<T: DeserializeOwned>(response: Response) -> Result<T> {
+    Ok(check(response).await?.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy() {
+        assert!(should_retry_status(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(should_retry_status(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(should_retry_status(StatusCode::BAD_GATEWAY, true));
+        assert!(!should_retry_status(StatusCode::BAD_GATEWAY, false));
+        assert!(!should_retry_status(StatusCode::BAD_REQUEST, true));
+        assert!(!should_retry_status(StatusCode::OK, true));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_builder_rejects_bad_base_url() {
+        assert!(matches!(
+            Client::new("localhost:8000"),
+            Err(Error::InvalidBaseUrl(_))
+        ));
+        let client = Client::new("http://localhost:8000/").unwrap();
+        assert_eq!(client.base_url, "http://localhost:8000");
+    }
+}
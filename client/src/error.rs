@@ -0,0 +1,34 @@
+use crate::models::ErrorResponse;
+use reqwest::StatusCode;
+
+/// Errors returned by [`Client`](crate::Client) calls
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The server answered with a non-success status
+    #[error("{status}: {}", .body.message)]
+    Api {
+        status: StatusCode,
+        body: ErrorResponse,
+    },
+
+    /// Connection failure, timeout or undecodable response
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// `base_url` could not be used
+    #[error("invalid base URL: {0}")]
+    InvalidBaseUrl(String),
+}
+
+impl Error {
+    /// HTTP status of an API error
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Api { status, .. } => Some(*status),
+            Error::Http(e) => e.status(),
+            Error::InvalidBaseUrl(_) => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,24 @@
+//! Typed async client for the vector search API
+//!
+//! Request and response bodies are the same structs the server
+//! (de)serializes, so the two can't drift apart.
+//!
+//! ```no_run
+//! # async fn demo() -> vector_search_client::Result<()> {
+//! use vector_search_client::{models::SearchRequest, Client};
+//!
+//! let client = Client::builder("http://localhost:8000").api_key("secret").build()?;
+//! let response = client.search(&SearchRequest::new("great battery life")).await?;
+//! for hit in response.results {
+//!     println!("{:.3} {}", hit.similarity_score, hit.review_title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod error;
+pub mod models;
+
+pub use client::{Client, ClientBuilder};
+pub use error::{Error, Result};
@@ -0,0 +1,1303 @@
+//! Request and response bodies, shared with the server's `api::models`
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Review metadata stored in JSONL format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewMetadata {
+    pub review_title: String,
+    pub review_body: String,
+    pub product_id: String,
+    pub review_rating: u8,
+
+    /// Bumped by every update or delete (optimistic concurrency)
+    #[serde(default = "first_revision")]
+    pub revision: u64,
+
+    /// Tombstone: the line keeps its vector ID but is no longer served
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+
+    /// Unix time in milliseconds after which the review is no longer served
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+
+    /// Unix time in milliseconds the review was stored (unset for reviews
+    /// stored before this was recorded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+
+    /// Language tag, e.g. "en" or a detected ISO 639-3 code like "eng"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+}
+
+pub fn first_revision() -> u64 {
+    1
+}
+
+/// Request to add a new review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddReviewRequest {
+    pub review_title: String,
+    pub review_body: String,
+    pub product_id: String,
+    pub review_rating: u8,
+
+    /// Expiry as Unix time in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+
+    /// Expiry relative to now; alternative to `expires_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+
+    /// Language tag; detected when omitted and `language.detect` is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Extra fields, checked against `validation.attributes` when declared
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+
+    /// Embed this image instead of the title and body; only collections
+    /// created with a CLIP model accept images. The image itself isn't
+    /// stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInput>,
+}
+
+/// A product photo to embed: fetched from a URL, or sent inline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageInput {
+    /// `http(s)` URL the server downloads the image from
+    Url(String),
+    /// Base64-encoded image bytes (PNG, JPEG, WebP, ...)
+    Base64(String),
+}
+
+/// When an add is acknowledged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Durability {
+    /// Once the review is embedded and indexed (200)
+    #[default]
+    Sync,
+    /// Once its metadata line is written (202); embedding and indexing
+    /// happen in the background
+    Async,
+}
+
+/// `POST /reviews` query parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AddReviewQuery {
+    #[serde(default)]
+    pub durability: Durability,
+}
+
+/// Response after adding a review
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddReviewResponse {
+    pub vector_id: usize,
+    pub status: String,
+    pub message: String,
+
+    /// Existing review this one duplicates, exactly or nearly
+    /// (`dedup.action = "flag"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<usize>,
+
+    /// What was masked before storing, when redaction changed anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redactions: Option<RedactionReport>,
+}
+
+/// Personal data masked in one review
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RedactionReport {
+    /// Matches masked per kind ("email", "phone", "name" or a configured
+    /// pattern name)
+    pub counts: BTreeMap<String, usize>,
+
+    /// Fields that changed ("review_title", "review_body")
+    pub fields: Vec<String>,
+}
+
+impl RedactionReport {
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+/// A stored review with its ID and revision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewRecord {
+    pub vector_id: usize,
+    pub revision: u64,
+    pub review_title: String,
+    pub review_body: String,
+    pub product_id: String,
+    pub review_rating: u8,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+}
+
+/// A stored review's embedding, as held by the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorRecord {
+    pub vector_id: usize,
+    /// Embedding model that produced the vector
+    pub model: String,
+    pub metric: DistanceMetric,
+    pub vector: Vec<f32>,
+}
+
+/// Replace a review; fails with 409 unless `expected_revision` is current
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateReviewRequest {
+    pub expected_revision: u64,
+
+    #[serde(flatten)]
+    pub review: AddReviewRequest,
+}
+
+/// Delete query parameters; fails with 409 unless `expected_revision` is current
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteReviewQuery {
+    pub expected_revision: u64,
+}
+
+/// Conditions on review metadata; every condition that is set must hold
+///
+/// Filters nest into boolean expressions with `and`, `or` and `not`, and
+/// compare any review field with `field` and the bounds of
+/// [`AttributeCondition`], e.g.
+/// `{"and": [{"field": "rating", "gte": 4}, {"not": {"field": "product_id", "eq": "X"}}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReviewFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_rating: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rating: Option<u8>,
+
+    /// Stored at or after this Unix time in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<u64>,
+
+    /// Stored before this Unix time in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<u64>,
+
+    /// Language tag, compared case-insensitively; routed to the language's
+    /// collection when `language.collections` has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Conditions on `attributes`, keyed by attribute name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, AttributeCondition>,
+
+    /// Every one of these must match as well
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub and: Vec<ReviewFilter>,
+
+    /// At least one of these must match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub or: Vec<ReviewFilter>,
+
+    /// This one must not match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<ReviewFilter>>,
+
+    /// Review field the bounds below compare: `rating`, `product_id`,
+    /// `title`, `body`, `language`, `created_at`, or an attribute (bare or
+    /// as `attributes.<name>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+
+    #[serde(flatten)]
+    pub condition: AttributeCondition,
+}
+
+/// Comparison against one attribute; declared numeric attributes compare as
+/// numbers, everything else compares by its JSON type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AttributeCondition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<serde_json::Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<serde_json::Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gte: Option<serde_json::Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<serde_json::Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lte: Option<serde_json::Value>,
+
+    /// Equal to one of these
+    #[serde(rename = "in", default, skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<serde_json::Value>>,
+}
+
+/// Request to search for similar reviews
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+
+    #[serde(alias = "k", default = "default_top_k")]
+    pub top_k: usize,
+
+    /// Only return reviews matching this filter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ReviewFilter>,
+
+    /// Also return [`SearchAggregations`] over the matched candidates
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub aggregate: bool,
+
+    /// Return each result's embedding in [`SearchResultItem::vector`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_vectors: bool,
+
+    /// Return each result's raw `distance` and the `metric` it was
+    /// measured with, next to `similarity_score`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_distance: bool,
+
+    /// Search by this image instead of `query` (which may then be empty);
+    /// only collections created with a CLIP model accept images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInput>,
+
+    /// Also search every language's collection (`language.collections`),
+    /// so a query in one language finds reviews in others; needs a
+    /// multilingual embedding model
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cross_lingual: bool,
+
+    /// Order of the returned results, by the first key that tells two
+    /// apart; similarity settles the rest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<SortKey>,
+
+    /// Search these named collections (or aliases) instead of the default
+    /// one, merging their hits into one list tagged with
+    /// [`SearchResultItem::collection`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<String>,
+}
+
+/// One key of a search's `sort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub field: SortField,
+
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[serde(alias = "review_rating")]
+    Rating,
+    /// Reviews stored before `created_at` was recorded come last
+    CreatedAt,
+    Similarity,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Tombstone every review matching `filter`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteByFilterRequest {
+    pub filter: ReviewFilter,
+}
+
+/// Result of a delete-by-filter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteByFilterResponse {
+    pub deleted: usize,
+}
+
+/// One page of `POST /reviews/scroll`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScrollRequest {
+    /// Only return reviews matching this filter; send the same one with
+    /// every page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ReviewFilter>,
+
+    /// Reviews per page (default 100, at most 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+
+    /// `next_cursor` of the previous page; omitted for the first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// Live reviews in vector ID order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScrollResponse {
+    pub reviews: Vec<ReviewRecord>,
+
+    /// Where the next page starts; absent once the corpus is exhausted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Tombstone the reviews with these vector IDs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteReviewsRequest {
+    pub vector_ids: Vec<usize>,
+}
+
+/// What happened to one ID of a bulk delete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteStatus {
+    Deleted,
+    /// Tombstoned before (or earlier in the same request)
+    AlreadyDeleted,
+    NotFound,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteOutcome {
+    pub vector_id: usize,
+    pub status: DeleteStatus,
+}
+
+/// Result of a bulk delete, one outcome per requested ID in order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteReviewsResponse {
+    pub deleted: usize,
+    pub results: Vec<DeleteOutcome>,
+}
+
+pub fn default_top_k() -> usize {
+    10
+}
+
+/// A single search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResultItem {
+    pub review_title: String,
+    pub review_body: String,
+    pub product_id: String,
+    pub review_rating: u8,
+    pub similarity_score: f32,
+    pub vector_id: usize,
+
+    /// Index into `coordinator.shards` (coordinated searches only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<usize>,
+
+    /// Collection the hit came from (searches across `collections`, and
+    /// `cross_lingual` hits outside the default collection)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// When the review was added (Unix ms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+
+    /// The stored embedding, with `include_vectors`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<Vec<f32>>,
+
+    /// Distance from the query as the index measured it, with
+    /// `include_distance`; `similarity_score` is derived from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f32>,
+
+    /// Metric behind `distance`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<DistanceMetric>,
+}
+
+/// Response from search endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResultItem>,
+    pub total_found: usize,
+    pub query: String,
+
+    /// The query as searched, when `search.spell_correction` changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrected_query: Option<String>,
+
+    /// Which arm of the running experiment served this search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<ExperimentAssignment>,
+
+    /// Present when the request set `aggregate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregations: Option<SearchAggregations>,
+}
+
+/// Question for `POST /reviews/answer`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerRequest {
+    pub query: String,
+
+    /// Reviews to ground the answer in (default: `rag.top_k`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+
+    /// Only ground the answer in reviews matching this filter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ReviewFilter>,
+
+    /// Prompt template from `rag.templates` (default:
+    /// `rag.default_template`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
+    /// Answer as server-sent events: `sources` first, then a `token` per
+    /// piece of the answer as the LLM writes it, then `done`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+impl AnswerRequest {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            top_k: None,
+            filter: None,
+            template: None,
+            stream: false,
+        }
+    }
+}
+
+/// A generated answer and the reviews it was grounded in
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnswerResponse {
+    pub query: String,
+    pub answer: String,
+
+    /// Vector IDs of the `sources` the answer cites, in order of first
+    /// citation
+    pub citations: Vec<usize>,
+
+    /// The reviews put in the prompt, best match first
+    pub sources: Vec<SearchResultItem>,
+
+    /// LLM that wrote the answer
+    pub model: String,
+}
+
+/// Ratings and products of every candidate that survived the filters,
+/// before results are cut to `top_k`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchAggregations {
+    pub count: usize,
+    /// `None` when nothing matched
+    pub average_rating: Option<f64>,
+    /// Rating -> matched reviews with it
+    pub rating_histogram: BTreeMap<u8, usize>,
+    /// Product ID -> matched reviews of it
+    pub product_counts: BTreeMap<String, usize>,
+}
+
+impl SearchAggregations {
+    /// Fold in another shard's aggregates
+    pub fn merge(&mut self, other: SearchAggregations) {
+        self.count += other.count;
+        for (rating, count) in other.rating_histogram {
+            *self.rating_histogram.entry(rating).or_default() += count;
+        }
+        for (product_id, count) in other.product_counts {
+            *self.product_counts.entry(product_id).or_default() += count;
+        }
+        self.average_rating = self.average();
+    }
+
+    fn average(&self) -> Option<f64> {
+        let sum: f64 = self
+            .rating_histogram
+            .iter()
+            .map(|(&rating, &count)| rating as f64 * count as f64)
+            .sum();
+        (self.count > 0).then(|| sum / self.count as f64)
+    }
+}
+
+/// Aggregates over (rating, product ID) pairs
+impl<'a> FromIterator<(u8, &'a str)> for SearchAggregations {
+    fn from_iter<I: IntoIterator<Item = (u8, &'a str)>>(iter: I) -> Self {
+        let mut aggregations = Self::default();
+        for (rating, product_id) in iter {
+            aggregations.count += 1;
+            *aggregations.rating_histogram.entry(rating).or_default() += 1;
+            *aggregations
+                .product_counts
+                .entry(product_id.to_string())
+                .or_default() += 1;
+        }
+        aggregations.average_rating = aggregations.average();
+        aggregations
+    }
+}
+
+/// Side of an A/B experiment a search was assigned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentArm {
+    /// The server's own model and index
+    Control,
+    /// The experiment's candidate collection
+    Candidate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentAssignment {
+    pub experiment: String,
+    pub arm: ExperimentArm,
+    /// Embedding model that served the search
+    pub model: String,
+}
+
+/// Distance function an index is built with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    /// Squared Euclidean distance, unbounded
+    #[default]
+    L2,
+    /// `1 - cos(a, b)`
+    Cosine,
+}
+
+/// Request to create a named collection
+///
+/// Settings left out fall back to the server's `embedding` and `index`
+/// config; they can't be changed after creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCollectionRequest {
+    pub name: String,
+
+    /// Embedding model, e.g. "BAAI/bge-small-en-v1.5"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Must match the model's output dimension
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector_dim: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<DistanceMetric>,
+
+    /// "BKT" or "KDT"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_type: Option<String>,
+
+    /// Text preprocessing before embedding, fixed for the collection's life
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<NormalizationSettings>,
+}
+
+/// A named collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub model: String,
+    pub vector_dim: usize,
+    pub metric: DistanceMetric,
+    pub index_type: String,
+    pub vector_count: usize,
+    pub review_count: usize,
+
+    #[serde(default)]
+    pub normalization: NormalizationSettings,
+}
+
+/// Text preprocessing a collection embeds with (the server's
+/// `embedding.normalization` steps)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizationSettings {
+    #[serde(default)]
+    pub lowercase: bool,
+
+    /// Drop HTML tags and decode common entities
+    #[serde(default)]
+    pub strip_html: bool,
+
+    /// Trim and turn every whitespace run into one space
+    #[serde(default)]
+    pub collapse_whitespace: bool,
+
+    /// Unicode NFC normalization
+    #[serde(default)]
+    pub unicode_nfc: bool,
+
+    /// Drop common English stopwords
+    #[serde(default)]
+    pub remove_stopwords: bool,
+}
+
+/// All collections, ordered by name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CollectionList {
+    pub collections: Vec<CollectionInfo>,
+}
+
+/// Request to point an alias at a collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetAliasRequest {
+    pub collection: String,
+}
+
+/// An alias after it was set or switched
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AliasInfo {
+    pub alias: String,
+    pub collection: String,
+
+    /// Collection the alias pointed at before this change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+/// Every alias and its collection, ordered by alias
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AliasList {
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// Autocomplete query parameters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestQuery {
+    /// Prefix typed so far; matched case-insensitively against product IDs
+    /// and the start of any word in a title
+    pub q: String,
+    #[serde(default = "default_suggest_limit")]
+    pub limit: usize,
+}
+
+fn default_suggest_limit() -> usize {
+    10
+}
+
+/// What a suggestion completes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Title,
+    ProductId,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+    /// Live reviews with this title or product ID
+    pub count: u64,
+}
+
+/// Completions for a prefix, most common first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestResponse {
+    pub query: String,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Health check response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub status: String,
+    pub version: String,
+    pub total_reviews: usize,
+
+    /// Vectors in the default index (0 while it is still loading)
+    #[serde(default)]
+    pub vector_count: usize,
+    /// Metadata lines of the default collection, deleted reviews included
+    #[serde(default)]
+    pub metadata_count: usize,
+    /// Whether every metadata line has its vector, counting reviews still
+    /// being indexed after `durability=async` adds as covered
+    #[serde(default)]
+    pub consistent: bool,
+    /// When the default index was last saved (Unix ms), if it ever was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_snapshot_at: Option<u64>,
+
+    /// Per-component results (deep checks only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<ComponentHealth>>,
+}
+
+/// Result of probing one component
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComponentHealth {
+    pub name: String,
+    /// "ok", "error" or "skipped"
+    pub status: String,
+    pub latency_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ComponentHealth {
+    /// A component that could not be probed
+    pub fn skipped(name: &str, reason: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: "skipped".to_string(),
+            latency_ms: 0.0,
+            error: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Load state of one startup component
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "state", content = "error")]
+pub enum ComponentState {
+    Loading,
+    Ready,
+    Failed(String),
+}
+
+/// Readiness probe response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadyzResponse {
+    pub ready: bool,
+    pub components: BTreeMap<String, ComponentState>,
+    /// Present when a memory limit is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryStatus>,
+}
+
+/// Memory use against the configured limits (0: no limit)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStatus {
+    /// Resident set size, where the platform reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rss_bytes: Option<u64>,
+    /// Estimated size of the default index's vectors
+    pub index_bytes: u64,
+    pub max_rss_bytes: u64,
+    pub max_index_bytes: u64,
+    /// Writes get 503 until usage drops back under the limits
+    pub writes_paused: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Snapshot of a job's progress, as streamed to clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub id: u64,
+    /// "import" or "rebuild"
+    pub kind: String,
+    pub state: JobState,
+    /// Current step, e.g. "parsing", "embedding", "indexing"
+    pub phase: String,
+    pub processed: usize,
+    pub total: usize,
+    /// Items per second in the current phase
+    pub throughput: f64,
+    /// Estimated time left in the current phase, once it has a total and
+    /// some progress
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eta_ms: Option<u64>,
+    pub started_ms: u64,
+    pub elapsed_ms: u64,
+    pub error_count: usize,
+    pub errors: Vec<String>,
+}
+
+/// Body format of an import job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+/// Config reload response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigReloadResponse {
+    pub status: String,
+    pub changed: Vec<String>,
+}
+
+/// Request to change the log filter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogLevelRequest {
+    /// Filter directive, e.g. "debug" or "info,vector_search_api=trace"
+    pub level: String,
+}
+
+/// Active log filter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogLevelResponse {
+    pub level: String,
+}
+
+/// One audited mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+
+    /// Operation, e.g. "add", "import", "rebuild"
+    pub action: String,
+
+    /// Fingerprint of the caller's API key (never the key itself)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+
+    /// SHA-256 of the request payload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_hash: Option<String>,
+
+    /// Vector IDs created or affected
+    #[serde(default)]
+    pub ids: Vec<usize>,
+
+    /// Free-form context (source, input file, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<serde_json::Value>,
+}
+
+impl AuditEntry {
+    pub fn new(action: &str) -> Self {
+        Self {
+            timestamp_ms: now_ms(),
+            action: action.to_string(),
+            api_key: None,
+            payload_hash: None,
+            ids: Vec::new(),
+            detail: None,
+        }
+    }
+}
+
+/// Audit log query parameters
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since_ms: Option<u64>,
+    #[serde(default = "default_audit_limit")]
+    pub limit: usize,
+}
+
+fn default_audit_limit() -> usize {
+    100
+}
+
+/// Audit log entries, newest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditResponse {
+    pub entries: Vec<AuditEntry>,
+    pub total: usize,
+}
+
+/// Limits that apply to a namespace, as reported with its usage; unset
+/// fields are unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageQuota {
+    /// Reviews stored in the namespace (403 once reached)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_vectors: Option<usize>,
+
+    /// Requests per minute (429 once exceeded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+
+    /// Embedding tokens since the server started (403 once exceeded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_embedding_tokens: Option<u64>,
+}
+
+/// Usage of one tenant namespace or operator key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountUsage {
+    /// Namespace name, or the fingerprint of an operator key
+    pub account: String,
+    pub requests: u64,
+    pub embedding_tokens: u64,
+
+    /// Reviews stored (namespaces only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vectors: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<UsageQuota>,
+}
+
+/// Usage since the server started, ordered by account
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageResponse {
+    pub accounts: Vec<AccountUsage>,
+}
+
+/// Analytics report query parameters
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnalyticsQuery {
+    /// Only searches at or after this Unix time in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since_ms: Option<u64>,
+    #[serde(default = "default_analytics_limit")]
+    pub limit: usize,
+}
+
+fn default_analytics_limit() -> usize {
+    20
+}
+
+/// Recorded searches for one query, compared case- and
+/// whitespace-insensitively
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryStats {
+    pub query: String,
+    pub count: u64,
+    /// Searches that returned nothing
+    pub zero_results: u64,
+    pub avg_latency_ms: f64,
+    pub avg_results: f64,
+    /// Mean best similarity score over searches that found something
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_top_score: Option<f32>,
+    pub last_seen_ms: u64,
+}
+
+/// Queries from the recorded searches, most frequent first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryReport {
+    /// Recorded searches the report covers
+    pub searches: usize,
+    pub queries: Vec<QueryStats>,
+}
+
+/// Progress of the shadow index named by `shadow.collection`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShadowStatus {
+    pub collection: String,
+    /// Model the shadow index is embedded with
+    pub model: String,
+    /// Reviews embedded into the shadow index so far
+    pub shadowed: usize,
+    /// Reviews in the default collection
+    pub total: usize,
+    /// Whether the shadow holds every review and can be cut over
+    pub backfilled: bool,
+}
+
+/// Result of `POST /admin/shadow/cutover`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShadowCutoverResponse {
+    pub status: String,
+    /// Model the default collection now uses
+    pub model: String,
+    pub vector_count: usize,
+}
+
+/// What a primary holds, polled by followers
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplicationStatus {
+    pub vector_count: usize,
+    pub metadata_count: usize,
+    /// Tombstoned reviews; followers catch up on deletes when it grows
+    #[serde(default)]
+    pub deleted_count: usize,
+}
+
+/// Metadata page requested by a follower
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataRangeQuery {
+    pub from: usize,
+    #[serde(default = "default_metadata_range_limit")]
+    pub limit: usize,
+}
+
+pub fn default_metadata_range_limit() -> usize {
+    1000
+}
+
+/// Request to take a named snapshot
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateSnapshotRequest {
+    /// Letters, digits, `-` and `_` (default: `snapshot-<unix ms>`)
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// `manifest.json` inside a snapshot archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Unix time in milliseconds
+    pub created_at: u64,
+    pub vector_count: usize,
+    pub metadata_count: usize,
+    /// Embedding model the vectors were made with
+    pub model: String,
+    pub metric: DistanceMetric,
+    pub dimension: usize,
+}
+
+/// A snapshot stored under `storage.snapshots_dir`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    /// Size of the tar.gz archive
+    pub size_bytes: u64,
+    pub manifest: SnapshotManifest,
+}
+
+/// All snapshots, ordered by name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotList {
+    pub snapshots: Vec<SnapshotInfo>,
+}
+
+/// Disk usage reported by `GET /admin/storage`, in bytes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiskUsage {
+    /// The default collection's index archive
+    pub index_bytes: u64,
+    /// The default collection's metadata, which doubles as the write-ahead
+    /// record of adds
+    pub metadata_bytes: u64,
+    pub audit_bytes: u64,
+    /// Every named collection's index and metadata
+    pub collections_bytes: u64,
+    pub snapshots_bytes: u64,
+    /// Index saves, loads and snapshot work folders in the system temp
+    /// directory, including any left behind by a crash
+    pub temp_bytes: u64,
+    /// Space left on the volume holding `storage.data_dir` (unix only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub free_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_bytes: Option<u64>,
+    /// Conditions that will make saves fail, e.g. too little free space
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Result of `POST /admin/snapshots/restore`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreSnapshotResponse {
+    pub status: String,
+    /// Manifest of the archive now being served
+    pub manifest: SnapshotManifest,
+}
+
+/// Error response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub message: String,
+
+    /// Matches the `x-request-id` response header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// Every rule a rejected review broke (422 responses)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<String>,
+
+    /// Vector ID of the stored review a rejected duplicate matches (409
+    /// responses)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub existing_id: Option<usize>,
+}
+
+impl ReviewRecord {
+    pub fn new(vector_id: usize, metadata: ReviewMetadata) -> Self {
+        Self {
+            vector_id,
+            revision: metadata.revision,
+            review_title: metadata.review_title,
+            review_body: metadata.review_body,
+            product_id: metadata.product_id,
+            review_rating: metadata.review_rating,
+            expires_at: metadata.expires_at,
+            language: metadata.language,
+            attributes: metadata.attributes,
+        }
+    }
+}
+
+impl ReviewMetadata {
+    /// Past its `expires_at` at `now_ms` (see [`now_ms`])
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now_ms)
+    }
+
+    /// Neither deleted nor expired
+    pub fn is_live(&self, now_ms: u64) -> bool {
+        !self.deleted && !self.is_expired(now_ms)
+    }
+}
+
+impl CreateCollectionRequest {
+    /// A collection with the server's default settings
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            model: None,
+            vector_dim: None,
+            metric: None,
+            index_type: None,
+            normalization: None,
+        }
+    }
+}
+
+impl SearchRequest {
+    /// A search for the default number of results
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            top_k: default_top_k(),
+            filter: None,
+            aggregate: false,
+            include_vectors: false,
+            include_distance: false,
+            image: None,
+            cross_lingual: false,
+            sort: Vec::new(),
+            collections: Vec::new(),
+        }
+    }
+
+    /// Validate the request
+    pub fn validate(&self, max_top_k: usize) -> Result<(), String> {
+        if self.query.trim().is_empty() && self.image.is_none() {
+            return Err("Query cannot be empty".to_string());
+        }
+        if self.top_k == 0 || self.top_k > max_top_k {
+            return Err(format!("top_k must be between 1 and {}", max_top_k));
+        }
+        if let Some(filter) = &self.filter {
+            filter.validate()?;
+        }
+        for (i, name) in self.collections.iter().enumerate() {
+            if self.collections[..i].contains(name) {
+                return Err(format!("Collection {} is listed twice", name));
+            }
+        }
+        if !self.collections.is_empty() && self.cross_lingual {
+            return Err("collections and cross_lingual can't be combined".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl ReviewFilter {
+    /// No conditions: matches every review
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Validate the filter
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(min), Some(max)) = (self.min_rating, self.max_rating)
+            && min > max
+        {
+            return Err("min_rating cannot exceed max_rating".to_string());
+        }
+        if let (Some(after), Some(before)) = (self.created_after, self.created_before)
+            && after >= before
+        {
+            return Err("created_after must be before created_before".to_string());
+        }
+        for (name, condition) in &self.attributes {
+            if *condition == AttributeCondition::default() {
+                return Err(format!("Condition on attribute {} is empty", name));
+            }
+        }
+        match (&self.field, self.condition == AttributeCondition::default()) {
+            (Some(field), true) => return Err(format!("Condition on field {} is empty", field)),
+            (None, false) => return Err("eq, gt, gte, lt and lte need a field".to_string()),
+            _ => {}
+        }
+        if self.or.iter().any(ReviewFilter::is_empty) {
+            return Err("An empty filter in or matches everything".to_string());
+        }
+        self.and
+            .iter()
+            .chain(&self.or)
+            .chain(self.not.as_deref())
+            .try_for_each(ReviewFilter::validate)
+    }
+}
+
+/// Current Unix time in milliseconds
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_aggregations_merge() {
+        let mut first: SearchAggregations = [(5, "B01"), (4, "B01"), (3, "B02")].into_iter().collect();
+        assert_eq!(first.count, 3);
+        assert_eq!(first.average_rating, Some(4.0));
+        assert_eq!(first.product_counts["B01"], 2);
+
+        first.merge([(1, "B02")].into_iter().collect());
+        assert_eq!(first.count, 4);
+        assert_eq!(first.average_rating, Some(3.25));
+        assert_eq!(first.rating_histogram, BTreeMap::from([(1, 1), (3, 1), (4, 1), (5, 1)]));
+        assert_eq!(first.product_counts["B02"], 2);
+
+        assert_eq!(SearchAggregations::from_iter(Vec::new()).average_rating, None);
+    }
+}
@@ -0,0 +1,549 @@
+use crate::api::extract::{ApiJson, CallerKey};
+use crate::api::models::*;
+use crate::config::ReloadError;
+use crate::shadow;
+use crate::snapshot;
+use crate::snapshot::RestoreError;
+use crate::storage::{archive, disk, AuditEntry, Collection};
+use std::io::{ErrorKind, SeekFrom};
+use std::sync::Arc;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
+
+pub async fn reload_config_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ConfigReloadResponse>, AppError> {
+    let changed = state.config.reload().map_err(|e| match e {
+        ReloadError::NotReloadable(_) => AppError::Conflict(e.to_string()),
+        ReloadError::Load(_) => AppError::BadRequest(e.to_string()),
+    })?;
+
+    info!(?changed, "Configuration reloaded");
+
+    Ok(Json(ConfigReloadResponse {
+        status: "success".to_string(),
+        changed,
+    }))
+}
+
+pub async fn set_log_level_handler(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<LogLevelRequest>,
+) -> Result<Json<LogLevelResponse>, AppError> {
+    state
+        .log_control
+        .set_level(&request.level)
+        .map_err(|e| AppError::BadRequest(format!("{:#}", e)))?;
+
+    let level = state.log_control.current();
+    info!(level = %level, "Log level changed");
+
+    Ok(Json(LogLevelResponse { level }))
+}
+
+pub async fn get_log_level_handler(State(state): State<AppState>) -> Json<LogLevelResponse> {
+    Json(LogLevelResponse {
+        level: state.log_control.current(),
+    })
+}
+
+pub async fn audit_handler(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditResponse>, AppError> {
+    let limit = query.limit.clamp(1, 1000);
+    let entries = state
+        .audit_log
+        .query(query.action.as_deref(), query.since_ms, limit)
+        .map_err(|e| AppError::Internal(format!("Audit log read failed: {:#}", e)))?;
+
+    Ok(Json(AuditResponse {
+        total: entries.len(),
+        entries,
+    }))
+}
+
+/// Usage per namespace and operator key; configured namespaces are listed
+/// even before their first request
+pub async fn usage_handler(State(state): State<AppState>) -> Json<UsageResponse> {
+    let config = state.config.current();
+    let mut usage = state.usage.snapshot();
+    for namespace in config.tenancy.keys.iter().flat_map(|k| &k.namespaces) {
+        usage.entry(namespace.clone()).or_default();
+    }
+
+    let mut accounts = Vec::with_capacity(usage.len());
+    for (account, counters) in usage {
+        let is_namespace = config
+            .tenancy
+            .keys
+            .iter()
+            .any(|k| k.namespaces.contains(&account));
+        let vectors = if is_namespace {
+            match state.collections.get(&account) {
+                Some(collection) => Some(collection.index.read().await.vector_count()),
+                None => Some(0),
+            }
+        } else {
+            None
+        };
+        accounts.push(AccountUsage {
+            quota: is_namespace.then(|| config.tenancy.quota(&account).into()),
+            account,
+            requests: counters.requests,
+            embedding_tokens: counters.embedding_tokens,
+            vectors,
+        });
+    }
+
+    Json(UsageResponse { accounts })
+}
+
+pub async fn top_queries_handler(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Json<QueryReport> {
+    let limit = query.limit.clamp(1, 1000);
+    Json(state.analytics.top_queries(query.since_ms, limit))
+}
+
+pub async fn zero_result_queries_handler(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Json<QueryReport> {
+    let limit = query.limit.clamp(1, 1000);
+    Json(state.analytics.zero_result_queries(query.since_ms, limit))
+}
+
+pub async fn shadow_status_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ShadowStatus>, AppError> {
+    let collection = shadow_collection(&state)?;
+    shadow::status(&state, &collection).await.map(Json)
+}
+
+pub async fn shadow_cutover_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+) -> Result<Json<ShadowCutoverResponse>, AppError> {
+    let collection = shadow_collection(&state)?;
+    shadow::cut_over(&state, collection, api_key).await.map(Json)
+}
+
+/// The collection named by `shadow.collection`
+fn shadow_collection(state: &AppState) -> Result<Arc<Collection>, AppError> {
+    let name = state
+        .config
+        .current()
+        .shadow
+        .collection
+        .clone()
+        .ok_or_else(|| AppError::NotFound("No shadow collection is configured".to_string()))?;
+    state
+        .collections
+        .get(&name)
+        .ok_or_else(|| AppError::NotFound(format!("Shadow collection {} not found", name)))
+}
+
+/// `GET /admin/storage`: what the stored data takes up and what's left
+pub async fn storage_usage_handler(
+    State(state): State<AppState>,
+) -> Result<Json<DiskUsage>, AppError> {
+    let storage = state.config.current().storage.clone();
+    let usage = tokio::task::spawn_blocking(move || disk::usage(&storage))
+        .await
+        .map_err(|e| AppError::Internal(format!("Disk usage task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Failed to measure disk usage: {:#}", e)))?;
+    for warning in &usage.warnings {
+        warn!("Storage: {}", warning);
+    }
+    Ok(Json(usage))
+}
+
+/// `POST /admin/snapshots`: archive the index and metadata under a name
+pub async fn create_snapshot_handler(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<CreateSnapshotRequest>,
+) -> Result<(StatusCode, Json<SnapshotInfo>), AppError> {
+    let name = request.name.unwrap_or_else(snapshot::default_name);
+    let config = state.config.current();
+    let dir = config.storage.snapshots_dir.clone();
+    let path = snapshot::snapshot_path(&dir, &name).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "Invalid snapshot name {:?}: use 1-64 letters, digits, '-' or '_'",
+            name
+        ))
+    })?;
+    if path.exists() {
+        return Err(AppError::Conflict(format!("Snapshot {} already exists", name)));
+    }
+
+    let model = config.embedding.model_name.clone();
+    let metric = config.index.metric;
+    let info = tokio::task::spawn_blocking(move || {
+        let index = state.vector_index.blocking_read();
+        snapshot::create_named(&index, state.metadata_store.as_ref(), &model, metric, &dir, &name)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Snapshot task failed: {}", e)))?
+    .map_err(|e| AppError::Internal(format!("Snapshot failed: {:#}", e)))?;
+
+    info!(
+        snapshot = %info.name,
+        vectors = info.manifest.vector_count,
+        bytes = info.size_bytes,
+        "📸 Named snapshot created"
+    );
+    Ok((StatusCode::CREATED, Json(info)))
+}
+
+pub async fn list_snapshots_handler(
+    State(state): State<AppState>,
+) -> Result<Json<SnapshotList>, AppError> {
+    let dir = state.config.current().storage.snapshots_dir.clone();
+    let snapshots = tokio::task::spawn_blocking(move || snapshot::list_named(&dir))
+        .await
+        .map_err(|e| AppError::Internal(format!("Snapshot task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Failed to list snapshots: {:#}", e)))?;
+    Ok(Json(SnapshotList { snapshots }))
+}
+
+/// `GET /admin/snapshots/{name}/download`: the tar.gz archive, whole or the
+/// one byte range asked for, so interrupted downloads can resume
+pub async fn download_snapshot_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let not_found = || AppError::NotFound(format!("Snapshot {} not found", name));
+    let path = snapshot::snapshot_path(&state.config.current().storage.snapshots_dir, &name)
+        .ok_or_else(not_found)?;
+    let mut file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Err(not_found()),
+        Err(e) => return Err(AppError::Internal(format!("Failed to open snapshot: {}", e))),
+    };
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to stat snapshot: {}", e)))?
+        .len();
+
+    let range = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+    let (start, end) = match byte_range(range, len) {
+        ByteRange::Full => {
+            info!(snapshot = %name, bytes = len, "📤 Serving snapshot");
+            return Ok((
+                archive_headers(&name),
+                [(header::CONTENT_LENGTH, len.to_string())],
+                Body::from_stream(ReaderStream::new(file)),
+            )
+                .into_response());
+        }
+        ByteRange::Partial(start, end) => (start, end),
+        ByteRange::Unsatisfiable => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", len))],
+            )
+                .into_response());
+        }
+    };
+
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to seek snapshot: {}", e)))?;
+    let length = end - start + 1;
+    Ok((
+        StatusCode::PARTIAL_CONTENT,
+        archive_headers(&name),
+        [
+            (header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len)),
+            (header::CONTENT_LENGTH, length.to_string()),
+        ],
+        Body::from_stream(ReaderStream::new(file.take(length))),
+    )
+        .into_response())
+}
+
+/// `POST /admin/snapshots/restore`: replace the index and metadata with an
+/// uploaded snapshot archive (the body, as downloaded)
+///
+/// The upload is streamed to disk and checked in full before anything
+/// changes; searches keep using the old data until the swap.
+pub async fn restore_snapshot_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    body: Body,
+) -> Result<Json<RestoreSnapshotResponse>, AppError> {
+    state.check_writable()?;
+    let _write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let upload_dir = archive::temp_dir("snapshot_upload")
+        .map_err(|e| AppError::Internal(format!("Failed to create temp dir: {}", e)))?;
+    let result = restore_upload(&state, body, upload_dir.join("upload.tar.gz")).await;
+    let _ = std::fs::remove_dir_all(&upload_dir);
+    let manifest = result?;
+
+    state.search_cache.invalidate();
+    state.suggester.invalidate();
+    state.content_hashes.invalidate();
+    if state.config.current().dedup.exact {
+        let metadata = state.metadata_store.clone();
+        state.content_hashes.refresh(move || metadata.read_all());
+    }
+    state.point_ids.invalidate();
+    state.spelling.invalidate();
+    state.field_vectors.invalidate();
+    state.record_mutation(AuditEntry {
+        api_key,
+        detail: Some(serde_json::json!({
+            "vector_count": manifest.vector_count,
+            "created_at": manifest.created_at,
+        })),
+        ..AuditEntry::new("restore_snapshot")
+    });
+    info!(
+        vectors = manifest.vector_count,
+        created_at = manifest.created_at,
+        "♻️ Snapshot restored"
+    );
+
+    Ok(Json(RestoreSnapshotResponse {
+        status: "success".to_string(),
+        manifest,
+    }))
+}
+
+/// Write the upload to `path`, check it and swap it in
+async fn restore_upload(
+    state: &AppState,
+    body: Body,
+    path: std::path::PathBuf,
+) -> Result<SnapshotManifest, AppError> {
+    let max_bytes = state.config.current().limits.restore_body_bytes;
+    let mut file = tokio::fs::File::create(&path)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to create upload file: {}", e)))?;
+    let mut stream = body.into_data_stream();
+    let mut received = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| AppError::BadRequest(format!("Failed to read upload: {}", e)))?;
+        received += chunk.len() as u64;
+        if received > max_bytes {
+            return Err(AppError::PayloadTooLarge(format!(
+                "Snapshot upload is larger than {} bytes",
+                max_bytes
+            )));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write upload: {}", e)))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to write upload: {}", e)))?;
+    drop(file);
+
+    let config = state.config.current();
+    let restore = tokio::task::spawn_blocking(move || {
+        snapshot::prepare_restore(&path, &config).map_err(|e| match e {
+            RestoreError::Invalid(message) => AppError::BadRequest(message),
+            RestoreError::Failed(e) => AppError::Internal(format!("Restore failed: {:#}", e)),
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Restore task failed: {}", e)))??;
+
+    // Once queued async adds are indexed, so none lands in the new index
+    let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let manifest = restore.manifest.clone();
+        restore
+            .swap_in(&mut index, state.metadata_store.as_ref(), &state.index_path())
+            .map_err(|e| AppError::Internal(format!("Restore failed: {:#}", e)))?;
+        Ok(manifest)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Restore task failed: {}", e)))?
+}
+
+fn archive_headers(name: &str) -> [(header::HeaderName, String); 3] {
+    [
+        (header::CONTENT_TYPE, "application/gzip".to_string()),
+        (header::ACCEPT_RANGES, "bytes".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.tar.gz\"", name),
+        ),
+    ]
+}
+
+/// What a `Range` header asks of a file
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    Full,
+    /// Inclusive start and end offsets
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Resolve a single `bytes=` range against a `len`-byte file. Anything else
+/// (several ranges, other units, bad syntax) is ignored, as HTTP allows, and
+/// the whole file is served.
+fn byte_range(header: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    let Some((start, end)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    // `bytes=-N`: the last N bytes
+    if start.is_empty() {
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Partial(len - suffix.min(len), len - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let Ok(start) = start.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end.min(len - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{review_request, TestApp};
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::json;
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range(None, 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=0-9"), 100), ByteRange::Partial(0, 9));
+        assert_eq!(byte_range(Some("bytes=90-"), 100), ByteRange::Partial(90, 99));
+        assert_eq!(byte_range(Some("bytes=90-500"), 100), ByteRange::Partial(90, 99));
+        assert_eq!(byte_range(Some("bytes=-10"), 100), ByteRange::Partial(90, 99));
+        assert_eq!(byte_range(Some("bytes=-500"), 100), ByteRange::Partial(0, 99));
+        assert_eq!(byte_range(Some("bytes=100-"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range(Some("bytes=-0"), 100), ByteRange::Unsatisfiable);
+        // Ignored rather than rejected
+        assert_eq!(byte_range(Some("bytes=0-1,5-6"), 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=9-0"), 100), ByteRange::Full);
+        assert_eq!(byte_range(Some("items=0-9"), 100), ByteRange::Full);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_download() {
+        let app = TestApp::builder().build().unwrap();
+        let review = review_request("Great battery life");
+        let (status, _) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = app.post("/admin/snapshots", &json!({ "name": "nightly" })).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        assert_eq!(body["manifest"]["vector_count"], 1);
+        assert_eq!(body["manifest"]["metadata_count"], 1);
+        let size = body["size_bytes"].as_u64().unwrap();
+        let (status, _) = app.post("/admin/snapshots", &json!({ "name": "nightly" })).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (_, body) = app.get("/admin/snapshots").await;
+        assert_eq!(body["snapshots"][0]["name"], "nightly");
+        let (_, body) = app.get("/admin/storage").await;
+        assert_eq!(body["snapshots_bytes"], size);
+
+        let download = |range: Option<&str>| {
+            let mut request = Request::get("/admin/snapshots/nightly/download");
+            if let Some(range) = range {
+                request = request.header(header::RANGE, range);
+            }
+            app.request(request.body(Body::empty()).unwrap())
+        };
+        let response = download(None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        let whole = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(whole.len() as u64, size);
+
+        // Resuming after the gzip magic
+        let response = download(Some("bytes=2-")).await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response.headers()[header::CONTENT_RANGE],
+            format!("bytes 2-{}/{}", size - 1, size).as_str()
+        );
+        let rest = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(rest, whole[2..]);
+
+        let response = download(Some(&format!("bytes={}-", size))).await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        let (status, _) = app.get("/admin/snapshots/weekly/download").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore() {
+        let app = TestApp::builder().build().unwrap();
+        for title in ["First", "Second", "Third"] {
+            if title == "Third" {
+                let (status, _) = app.post("/admin/snapshots", &json!({ "name": "before" })).await;
+                assert_eq!(status, StatusCode::CREATED);
+            }
+            let review = review_request(title);
+            let (status, _) = app.post("/reviews", &review).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let response = app
+            .request(Request::get("/admin/snapshots/before/download").body(Body::empty()).unwrap())
+            .await;
+        let archive = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let restore = |body: Body| Request::post("/admin/snapshots/restore").body(body).unwrap();
+
+        let (status, body) = app.send(restore(Body::from("not a snapshot"))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        app.state.config.update(|c| c.limits.restore_body_bytes = 8);
+        let (status, body) = app.send(restore(Body::from(archive.clone()))).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{}", body);
+        app.state.config.update(|c| c.limits.restore_body_bytes = u64::MAX);
+        assert_eq!(app.get("/reviews/2").await.0, StatusCode::OK);
+
+        let (status, body) = app.send(restore(Body::from(archive))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["manifest"]["vector_count"], 2);
+        assert_eq!(app.state.vector_index.read().await.vector_count(), 2);
+        assert_eq!(app.get("/reviews/1").await.0, StatusCode::OK);
+        assert_eq!(app.get("/reviews/2").await.0, StatusCode::NOT_FOUND);
+    }
+}
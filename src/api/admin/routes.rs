@@ -0,0 +1,36 @@
+use crate::api::admin::handlers::{
+    audit_handler, create_snapshot_handler, download_snapshot_handler, get_log_level_handler,
+    list_snapshots_handler, reload_config_handler, restore_snapshot_handler, set_log_level_handler,
+    shadow_cutover_handler, shadow_status_handler, storage_usage_handler, top_queries_handler,
+    usage_handler, zero_result_queries_handler,
+};
+use crate::api::jobs::handlers::{rebuild_status_handler, reembed_handler};
+use crate::api::models::AppState;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/audit", get(audit_handler))
+        .route("/admin/config/reload", post(reload_config_handler))
+        .route("/admin/usage", get(usage_handler))
+        .route("/admin/storage", get(storage_usage_handler))
+        .route("/admin/analytics/top-queries", get(top_queries_handler))
+        .route("/admin/analytics/zero-result-queries", get(zero_result_queries_handler))
+        .route("/admin/reembed", post(reembed_handler))
+        .route("/admin/index/rebuild/status", get(rebuild_status_handler))
+        .route("/admin/shadow", get(shadow_status_handler))
+        .route("/admin/shadow/cutover", post(shadow_cutover_handler))
+        .route(
+            "/admin/snapshots",
+            get(list_snapshots_handler).post(create_snapshot_handler),
+        )
+        .route("/admin/snapshots/restore", post(restore_snapshot_handler))
+        .route("/admin/snapshots/{name}/download", get(download_snapshot_handler))
+        .route(
+            "/admin/log-level",
+            get(get_log_level_handler).put(set_log_level_handler),
+        )
+}
@@ -0,0 +1,175 @@
+//! Search analytics: a rolling window of recent searches and the
+//! top-queries and zero-result-queries reports built from it
+//!
+//! The window lives in memory and starts empty with the server.
+
+use crate::config::AnalyticsConfig;
+use crate::storage::now_ms;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use vector_search_client::models::{QueryReport, QueryStats, SearchResponse};
+
+/// One recorded search
+#[derive(Debug, Clone)]
+struct SearchRecord {
+    timestamp_ms: u64,
+    query: String,
+    latency_ms: f64,
+    results: usize,
+    top_score: Option<f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchAnalytics {
+    records: Mutex<VecDeque<SearchRecord>>,
+}
+
+impl SearchAnalytics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a served search, dropping the oldest past `capacity`
+    pub fn record(&self, config: &AnalyticsConfig, response: &SearchResponse, latency: Duration) {
+        let mut records = self.records.lock().unwrap();
+        if config.capacity == 0 {
+            records.clear();
+            return;
+        }
+        records.push_back(SearchRecord {
+            timestamp_ms: now_ms(),
+            query: response.query.clone(),
+            latency_ms: latency.as_secs_f64() * 1000.0,
+            results: response.results.len(),
+            top_score: response
+                .results
+                .iter()
+                .map(|r| r.similarity_score)
+                .max_by(f32::total_cmp),
+        });
+        while records.len() > config.capacity {
+            records.pop_front();
+        }
+    }
+
+    /// The most frequent queries since `since_ms`
+    pub fn top_queries(&self, since_ms: Option<u64>, limit: usize) -> QueryReport {
+        self.report(since_ms, limit, |_| true)
+    }
+
+    /// The most frequent queries since `since_ms` that found nothing
+    pub fn zero_result_queries(&self, since_ms: Option<u64>, limit: usize) -> QueryReport {
+        self.report(since_ms, limit, |record| record.results == 0)
+    }
+
+    fn report(
+        &self,
+        since_ms: Option<u64>,
+        limit: usize,
+        include: impl Fn(&SearchRecord) -> bool,
+    ) -> QueryReport {
+        let records = self.records.lock().unwrap();
+        let mut searches = 0;
+        let mut groups: HashMap<String, Vec<&SearchRecord>> = HashMap::new();
+        for record in records
+            .iter()
+            .filter(|record| since_ms.is_none_or(|since| record.timestamp_ms >= since))
+            .filter(|record| include(record))
+        {
+            searches += 1;
+            groups.entry(query_key(&record.query)).or_default().push(record);
+        }
+
+        let mut queries: Vec<QueryStats> = groups
+            .into_iter()
+            .map(|(query, group)| stats(query, &group))
+            .collect();
+        queries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+        queries.truncate(limit);
+        QueryReport { searches, queries }
+    }
+}
+
+/// Queries differing only in case or spacing are counted together
+fn query_key(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn stats(query: String, group: &[&SearchRecord]) -> QueryStats {
+    let count = group.len() as f64;
+    let scores: Vec<f32> = group.iter().filter_map(|record| record.top_score).collect();
+    QueryStats {
+        query,
+        count: group.len() as u64,
+        zero_results: group.iter().filter(|record| record.results == 0).count() as u64,
+        avg_latency_ms: group.iter().map(|record| record.latency_ms).sum::<f64>() / count,
+        avg_results: group.iter().map(|record| record.results as f64).sum::<f64>() / count,
+        avg_top_score: (!scores.is_empty())
+            .then(|| scores.iter().sum::<f32>() / scores.len() as f32),
+        last_seen_ms: group.iter().map(|record| record.timestamp_ms).max().unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vector_search_client::models::SearchResultItem;
+
+    fn response(query: &str, scores: &[f32]) -> SearchResponse {
+        SearchResponse {
+            results: scores
+                .iter()
+                .map(|&score| SearchResultItem {
+                    review_title: String::new(),
+                    review_body: String::new(),
+                    product_id: String::new(),
+                    review_rating: 5,
+                    similarity_score: score,
+                    vector_id: 0,
+                    shard: None,
+                    collection: None,
+                    language: None,
+                    created_at: None,
+                    attributes: Default::default(),
+                    vector: None,
+                    distance: None,
+                    metric: None,
+                })
+                .collect(),
+            total_found: scores.len(),
+            query: query.to_string(),
+            corrected_query: None,
+            experiment: None,
+            aggregations: None,
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        let analytics = SearchAnalytics::new();
+        let config = AnalyticsConfig { capacity: 4 };
+        let latency = Duration::from_millis(10);
+        analytics.record(&config, &response("dropped", &[]), latency);
+        analytics.record(&config, &response("Battery life", &[0.9, 0.5]), latency);
+        analytics.record(&config, &response("battery  LIFE", &[0.7]), latency);
+        analytics.record(&config, &response("xyzzy", &[]), latency);
+        analytics.record(&config, &response("headphones", &[0.8]), latency);
+
+        let top = analytics.top_queries(None, 2);
+        assert_eq!(top.searches, 4);
+        assert_eq!(top.queries.len(), 2);
+        assert_eq!(top.queries[0].query, "battery life");
+        assert_eq!(top.queries[0].count, 2);
+        assert_eq!(top.queries[0].avg_results, 1.5);
+        assert!((top.queries[0].avg_top_score.unwrap() - 0.8).abs() < 1e-6);
+        assert_eq!(top.queries[1].query, "headphones");
+
+        let zero = analytics.zero_result_queries(None, 10);
+        assert_eq!(zero.searches, 1);
+        assert_eq!(zero.queries[0].query, "xyzzy");
+        assert_eq!(zero.queries[0].avg_top_score, None);
+
+        assert_eq!(analytics.top_queries(Some(u64::MAX), 10).searches, 0);
+    }
+}
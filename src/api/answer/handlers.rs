@@ -0,0 +1,394 @@
+use crate::api::extract::ApiJson;
+use crate::api::models::*;
+use crate::api::search::handlers::search;
+use crate::api::usage::count_tokens;
+use crate::config::{PromptTemplate, RagConfig};
+use crate::llm::{self, ChatMessage};
+use axum::{
+    extract::State,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+/// Answer sent without calling the LLM when nothing matched
+const NO_SOURCES: &str = "No reviews matched the question.";
+
+/// Answer a question from the best-matching reviews with the configured LLM
+///
+/// With `stream`, the answer is sent as server-sent events while the LLM
+/// writes it (see [`stream_answer`]).
+pub async fn answer_handler(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<AnswerRequest>,
+) -> Result<Response, AppError> {
+    let config = state.config.current();
+    let settings = &config.rag;
+    let template = settings.template(request.template.as_deref()).ok_or_else(|| {
+        let name = request.template.as_deref().unwrap_or(&settings.default_template);
+        AppError::BadRequest(format!("Unknown prompt template {}", name))
+    })?;
+
+    let search_request = SearchRequest {
+        top_k: request.top_k.unwrap_or(settings.top_k),
+        filter: request.filter.clone(),
+        ..SearchRequest::new(request.query.clone())
+    };
+    let sources = search(&state, search_request).await?.results;
+    let (messages, sources) = prompt(template, &request.query, sources);
+    if request.stream {
+        return stream_answer(settings, request.query, sources, messages).await;
+    }
+    if sources.is_empty() {
+        return Ok(Json(AnswerResponse {
+            query: request.query,
+            answer: NO_SOURCES.to_string(),
+            citations: Vec::new(),
+            sources,
+            model: settings.llm.model.clone(),
+        })
+        .into_response());
+    }
+
+    let answer = llm::complete(&settings.llm, &messages)
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("LLM request failed: {:#}", e)))?;
+    let citations = citations(&answer, &sources);
+    info!(
+        query = %request.query,
+        sources = sources.len(),
+        citations = citations.len(),
+        "Answer generated"
+    );
+
+    Ok(Json(AnswerResponse {
+        query: request.query,
+        answer,
+        citations,
+        sources,
+        model: settings.llm.model.clone(),
+    })
+    .into_response())
+}
+
+/// The answer as server-sent events: `sources` (`{sources, model}`), a
+/// `token` (`{text}`) per piece the LLM writes, then `done` (`{answer,
+/// citations}`), or `error` (`{message}`) if the LLM fails midway
+///
+/// An LLM that can't be reached at all is still a `503`.
+async fn stream_answer(
+    settings: &RagConfig,
+    query: String,
+    sources: Vec<SearchResultItem>,
+    messages: [ChatMessage; 2],
+) -> Result<Response, AppError> {
+    let mut pieces = if sources.is_empty() {
+        None
+    } else {
+        let pieces = llm::stream(&settings.llm, &messages)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("LLM request failed: {:#}", e)))?;
+        Some(pieces)
+    };
+
+    let (tx, rx) = mpsc::channel(16);
+    let first = Event::default()
+        .event("sources")
+        .json_data(json!({ "sources": sources, "model": settings.llm.model }));
+    tokio::spawn(async move {
+        if tx.send(first).await.is_err() {
+            return;
+        }
+
+        let mut answer = String::new();
+        match &mut pieces {
+            Some(pieces) => {
+                while let Some(piece) = pieces.recv().await {
+                    let text = match piece {
+                        Ok(text) => text,
+                        Err(e) => {
+                            warn!(query = %query, "LLM stream failed: {:#}", e);
+                            let message = format!("LLM request failed: {:#}", e);
+                            let event = Event::default().event("error").json_data(json!({ "message": message }));
+                            let _ = tx.send(event).await;
+                            return;
+                        }
+                    };
+                    let event = Event::default().event("token").json_data(json!({ "text": text }));
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
+                    answer.push_str(&text);
+                }
+            }
+            None => answer.push_str(NO_SOURCES),
+        }
+
+        let citations = citations(&answer, &sources);
+        info!(
+            query = %query,
+            sources = sources.len(),
+            citations = citations.len(),
+            "Answer streamed"
+        );
+        let event = Event::default()
+            .event("done")
+            .json_data(json!({ "answer": answer, "citations": citations }));
+        let _ = tx.send(event).await;
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx))
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+/// The system and user messages for `question`, and the `sources` that
+/// fit in the template's context budget (the best match always does)
+pub(crate) fn prompt(
+    template: &PromptTemplate,
+    question: &str,
+    mut sources: Vec<SearchResultItem>,
+) -> ([ChatMessage; 2], Vec<SearchResultItem>) {
+    let mut lines = Vec::with_capacity(sources.len());
+    let mut tokens = 0;
+    for item in &sources {
+        let line = fill(
+            &template.context_line,
+            &[
+                ("vector_id", &item.vector_id.to_string()),
+                ("title", &item.review_title),
+                ("body", &item.review_body),
+                ("rating", &item.review_rating.to_string()),
+                ("product_id", &item.product_id),
+            ],
+        );
+        tokens += count_tokens(&line) as usize;
+        if template.max_context_tokens > 0 && tokens > template.max_context_tokens && !lines.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    sources.truncate(lines.len());
+
+    let context = lines.join("\n");
+    let user = fill(&template.prompt, &[("context", &context), ("question", question)]);
+    let messages = [
+        ChatMessage::system(template.system_prompt.clone()),
+        ChatMessage::user(user),
+    ];
+    (messages, sources)
+}
+
+/// `template` with each `{name}` of `values` filled in
+///
+/// Filled in one pass, so braces in reviews or the question are kept as
+/// they are.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let placeholder = values.iter().find(|(name, _)| {
+            rest[start + 1..]
+                .strip_prefix(name)
+                .is_some_and(|tail| tail.starts_with('}'))
+        });
+        match placeholder {
+            Some((name, value)) => {
+                filled.push_str(value);
+                rest = &rest[start + name.len() + 2..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Vector IDs of `sources` cited as `[12]` or `[3, 7]` in `answer`, in
+/// order of first citation
+pub(crate) fn citations(answer: &str, sources: &[SearchResultItem]) -> Vec<usize> {
+    static CITATION: OnceLock<regex::Regex> = OnceLock::new();
+    let citation = CITATION.get_or_init(|| regex::Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").unwrap());
+
+    let known: HashSet<usize> = sources.iter().map(|item| item.vector_id).collect();
+    let mut seen = HashSet::new();
+    citation
+        .captures_iter(answer)
+        .flat_map(|captures| {
+            captures[1]
+                .split(',')
+                .filter_map(|id| id.trim().parse::<usize>().ok())
+                .collect::<Vec<_>>()
+        })
+        .filter(|id| known.contains(id) && seen.insert(*id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LlmApi;
+    use crate::test_utils::TestApp;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{header, Request, StatusCode};
+    use axum::{routing::post, Router};
+    use serde_json::{json, Value};
+
+    /// An Ollama stand-in that cites review 0 and echoes the prompt it got,
+    /// streamed in three pieces when asked to
+    async fn fake_ollama() -> String {
+        let app = Router::new().route(
+            "/api/chat",
+            post(|Json(body): Json<Value>| async move {
+                let prompt = body["messages"][1]["content"].as_str().unwrap_or_default().to_string();
+                if body["stream"] == true {
+                    let lines: Vec<String> = ["Good battery ", "[0, 9]. ", ""]
+                        .iter()
+                        .map(|piece| json!({ "message": { "content": piece }, "done": piece.is_empty() }).to_string())
+                        .collect();
+                    return (lines.join("\n") + "\n").into_response();
+                }
+                Json(json!({ "message": { "role": "assistant", "content": format!("Good battery [0, 9]. {}", prompt) } }))
+                    .into_response()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_answer_cites_sources() {
+        let url = fake_ollama().await;
+        let app = TestApp::builder()
+            .config(|config| {
+                config.rag.enabled = true;
+                config.rag.llm.api = LlmApi::Ollama;
+                config.rag.llm.url = url;
+            })
+            .build()
+            .unwrap();
+
+        let (_, body) = app.post("/reviews/answer", &json!({ "query": "battery" })).await;
+        assert_eq!(body["answer"], NO_SOURCES);
+
+        let review = json!({
+            "review_title": "Great battery",
+            "review_body": "Lasts {two} days",
+            "product_id": "P1",
+            "review_rating": 5,
+        });
+        app.post("/reviews", &review).await;
+        let (status, body) = app.post("/reviews/answer", &json!({ "query": "battery?" })).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        // 9 isn't one of the sources
+        assert_eq!(body["citations"], json!([0]));
+        assert_eq!(body["sources"][0]["product_id"], "P1");
+        let answer = body["answer"].as_str().unwrap();
+        assert!(answer.contains("[0] Great battery: Lasts {two} days (rating 5/5, product P1)"));
+        assert!(answer.ends_with("Question: battery?"));
+    }
+
+    #[tokio::test]
+    async fn test_answer_with_named_template() {
+        let url = fake_ollama().await;
+        let app = TestApp::builder()
+            .config(|config| {
+                config.rag.enabled = true;
+                config.rag.llm.url = url;
+                let brief = PromptTemplate {
+                    prompt: "{context}".to_string(),
+                    context_line: "{product_id} says {body}".to_string(),
+                    max_context_tokens: 4,
+                    ..PromptTemplate::default()
+                };
+                config.rag.templates.insert("brief".to_string(), brief);
+            })
+            .build()
+            .unwrap();
+        for product in ["P1", "P2"] {
+            let review = json!({
+                "review_title": "Great battery",
+                "review_body": "Lasts long",
+                "product_id": product,
+                "review_rating": 5,
+            });
+            app.post("/reviews", &review).await;
+        }
+
+        let request = json!({ "query": "battery", "template": "brief" });
+        let (status, body) = app.post("/reviews/answer", &request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        // The second review would take the context to 8 words
+        assert_eq!(body["sources"].as_array().unwrap().len(), 1);
+        let answer = body["answer"].as_str().unwrap();
+        assert!(answer.ends_with(&format!("{} says Lasts long", body["sources"][0]["product_id"].as_str().unwrap())));
+
+        let request = json!({ "query": "battery", "template": "verbose" });
+        let (status, _) = app.post("/reviews/answer", &request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_answer_streams_tokens() {
+        let url = fake_ollama().await;
+        let app = TestApp::builder()
+            .config(|config| {
+                config.rag.enabled = true;
+                config.rag.llm.url = url;
+            })
+            .build()
+            .unwrap();
+        for product in ["P1", "P2"] {
+            let review = json!({
+                "review_title": "Great battery",
+                "review_body": "Lasts two days",
+                "product_id": product,
+                "review_rating": 5,
+            });
+            app.post("/reviews", &review).await;
+        }
+
+        let request = Request::post("/reviews/answer")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "query": "battery", "stream": true }).to_string()))
+            .unwrap();
+        let response = app.request(request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let events: Vec<(&str, Value)> = std::str::from_utf8(&body)
+            .unwrap()
+            .split("\n\n")
+            .filter_map(|event| {
+                let name = event.lines().find_map(|line| line.strip_prefix("event: "))?;
+                let data = event.lines().find_map(|line| line.strip_prefix("data: "))?;
+                Some((name, serde_json::from_str(data).unwrap()))
+            })
+            .collect();
+
+        let names: Vec<&str> = events.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["sources", "token", "token", "done"]);
+        assert_eq!(events[0].1["sources"].as_array().unwrap().len(), 2);
+        assert_eq!(events[1].1["text"], "Good battery ");
+        assert_eq!(events[3].1["answer"], "Good battery [0, 9]. ");
+        assert_eq!(events[3].1["citations"], json!([0]));
+    }
+
+    #[test]
+    fn test_fill_keeps_other_braces() {
+        assert_eq!(fill("{q} {question}{", &[("question", "{context}")]), "{q} {context}{");
+    }
+}
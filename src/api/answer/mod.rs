@@ -0,0 +1,7 @@
+//! Retrieval-augmented answers: search the reviews, then have the
+//! configured LLM answer from the best matches, citing them
+
+pub mod handlers;
+pub mod routes;
+
+pub use routes::routes;
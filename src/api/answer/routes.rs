@@ -0,0 +1,7 @@
+use crate::api::answer::handlers::answer_handler;
+use crate::api::models::AppState;
+use axum::{routing::post, Router};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/reviews/answer", post(answer_handler))
+}
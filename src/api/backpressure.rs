@@ -0,0 +1,146 @@
+use crate::api::models::AppError;
+use crate::config::BackpressureConfig;
+use axum::http::StatusCode;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Admission control for the write path
+///
+/// Counters rather than a semaphore, so the limits can be reloaded: a
+/// write is turned away as soon as it would go over the current one.
+#[derive(Debug, Default)]
+pub struct Backpressure {
+    embeddings: AtomicUsize,
+    rejected_writes: AtomicU64,
+    rejected_embeddings: AtomicU64,
+}
+
+/// Held while a review is embedded; releases its slot on drop
+pub struct EmbeddingPermit {
+    backpressure: Arc<Backpressure>,
+}
+
+impl Backpressure {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 429 when `pending` writes are already in progress
+    pub fn admit_write(&self, pending: usize, settings: &BackpressureConfig) -> Result<(), AppError> {
+        if settings.max_pending_writes > 0 && pending >= settings.max_pending_writes {
+            self.rejected_writes.fetch_add(1, Ordering::Relaxed);
+            return Err(AppError::Overloaded {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                message: format!("{} writes are already pending; retry later", pending),
+                retry_after_secs: settings.retry_after_secs,
+            });
+        }
+        Ok(())
+    }
+
+    /// A slot for embedding one review, or 503 when all are taken
+    pub fn embedding(self: &Arc<Self>, settings: &BackpressureConfig) -> Result<EmbeddingPermit, AppError> {
+        let limit = settings.max_concurrent_embeddings;
+        let admitted = self
+            .embeddings
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (limit == 0 || n < limit).then_some(n + 1)
+            });
+        if admitted.is_err() {
+            self.rejected_embeddings.fetch_add(1, Ordering::Relaxed);
+            return Err(AppError::Overloaded {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                message: "Embedding is saturated; retry later".to_string(),
+                retry_after_secs: settings.retry_after_secs,
+            });
+        }
+        Ok(EmbeddingPermit { backpressure: self.clone() })
+    }
+
+    /// Reviews being embedded right now
+    pub fn embeddings_in_flight(&self) -> usize {
+        self.embeddings.load(Ordering::SeqCst)
+    }
+
+    pub fn rejected_writes(&self) -> u64 {
+        self.rejected_writes.load(Ordering::Relaxed)
+    }
+
+    pub fn rejected_embeddings(&self) -> u64 {
+        self.rejected_embeddings.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for EmbeddingPermit {
+    fn drop(&mut self) {
+        self.backpressure.embeddings.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{review_request, TestApp};
+    use axum::body::Body;
+    use axum::http::{header, Request};
+
+    #[test]
+    fn test_limits_reject_and_release() {
+        let settings = BackpressureConfig {
+            max_pending_writes: 2,
+            max_concurrent_embeddings: 1,
+            retry_after_secs: 3,
+        };
+        let backpressure = Arc::new(Backpressure::new());
+
+        assert!(backpressure.admit_write(1, &settings).is_ok());
+        let err = backpressure.admit_write(2, &settings).unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::TOO_MANY_REQUESTS);
+
+        let permit = backpressure.embedding(&settings).unwrap();
+        let err = backpressure.embedding(&settings).err().unwrap();
+        assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        drop(permit);
+        assert!(backpressure.embedding(&settings).is_ok());
+        assert_eq!(backpressure.embeddings_in_flight(), 0);
+        assert_eq!((backpressure.rejected_writes(), backpressure.rejected_embeddings()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_rejects_adds() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.backpressure.max_pending_writes = 1;
+                config.backpressure.max_concurrent_embeddings = 1;
+                config.backpressure.retry_after_secs = 7;
+            })
+            .build()
+            .unwrap();
+        let review = review_request("Great battery life");
+        let add = || {
+            Request::post("/reviews")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(review.to_string()))
+                .unwrap()
+        };
+
+        let write = app.state.write_gate.enter().unwrap();
+        let response = app.request(add()).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "7");
+        drop(write);
+
+        let settings = app.state.config.current().backpressure.clone();
+        let permit = app.state.backpressure.embedding(&settings).unwrap();
+        let response = app.request(add()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "7");
+        drop(permit);
+        assert_eq!(app.request(add()).await.status(), StatusCode::OK);
+
+        let metrics = app.metrics().await;
+        assert!(metrics.contains("\nvector_search_write_rejections_total 1\n"), "{}", metrics);
+        assert!(metrics.contains("\nvector_search_embedding_rejections_total 1\n"), "{}", metrics);
+        assert!(metrics.contains("\nvector_search_pending_writes 0\n"), "{}", metrics);
+    }
+}
@@ -0,0 +1,815 @@
+use crate::api::extract::{ApiJson, CallerKey};
+use crate::api::models::*;
+use crate::api::review::handlers::{find_duplicate, redact, tag_language};
+use crate::api::search::handlers::{
+    assemble_results, attach_vectors, candidate_count, correct_query, embed_queries, query_texts, search_fused,
+};
+use crate::embedding::EmbeddingService;
+use crate::language;
+use crate::storage::audit::sha256_hex;
+use crate::storage::{AuditEntry, Collection, CollectionError, CollectionManifest, IndexBackend, MetadataStore};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use crate::config::{DedupAction, LimitsConfig};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How long downloading an image may take
+const IMAGE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn create_collection_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    ApiJson(request): ApiJson<CreateCollectionRequest>,
+) -> Result<(StatusCode, Json<CollectionInfo>), AppError> {
+    state.check_writable()?;
+    let _write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let manifest = manifest_for(&state, &request)?;
+    let collection = state
+        .collections
+        .create(&request.name, manifest)
+        .map_err(collection_error)?;
+
+    state.record_mutation(AuditEntry {
+        api_key,
+        detail: Some(serde_json::json!({ "collection": request.name })),
+        ..AuditEntry::new("create_collection")
+    });
+    info!(collection = %request.name, "Collection created");
+
+    Ok((StatusCode::CREATED, Json(describe(&collection).await?)))
+}
+
+pub async fn list_collections_handler(
+    State(state): State<AppState>,
+) -> Result<Json<CollectionList>, AppError> {
+    let mut collections = Vec::new();
+    for collection in state.collections.list() {
+        collections.push(describe(&collection).await?);
+    }
+    Ok(Json(CollectionList { collections }))
+}
+
+pub async fn get_collection_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<CollectionInfo>, AppError> {
+    let collection = find(&state, &name)?;
+    Ok(Json(describe(&collection).await?))
+}
+
+pub async fn drop_collection_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(name): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state.check_deletable()?;
+    let _write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    state
+        .collections
+        .drop_collection(&name)
+        .map_err(collection_error)?;
+
+    state.record_mutation(AuditEntry {
+        api_key,
+        detail: Some(serde_json::json!({ "collection": name })),
+        ..AuditEntry::new("drop_collection")
+    });
+    info!(collection = %name, "Collection dropped");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_aliases_handler(State(state): State<AppState>) -> Json<AliasList> {
+    Json(AliasList {
+        aliases: state.collections.aliases(),
+    })
+}
+
+/// Create an alias or switch it to another collection in one step
+pub async fn set_alias_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(alias): Path<String>,
+    ApiJson(request): ApiJson<SetAliasRequest>,
+) -> Result<Json<AliasInfo>, AppError> {
+    state.check_writable()?;
+    let _write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let previous = state
+        .collections
+        .set_alias(&alias, &request.collection)
+        .map_err(collection_error)?;
+
+    state.record_mutation(AuditEntry {
+        api_key,
+        detail: Some(serde_json::json!({
+            "alias": alias,
+            "collection": request.collection,
+            "previous": previous,
+        })),
+        ..AuditEntry::new("set_alias")
+    });
+
+    Ok(Json(AliasInfo {
+        alias,
+        collection: request.collection,
+        previous,
+    }))
+}
+
+pub async fn remove_alias_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(alias): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state.check_writable()?;
+    let _write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let collection = state
+        .collections
+        .remove_alias(&alias)
+        .map_err(collection_error)?;
+
+    state.record_mutation(AuditEntry {
+        api_key,
+        detail: Some(serde_json::json!({ "alias": alias, "collection": collection })),
+        ..AuditEntry::new("remove_alias")
+    });
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn add_collection_review_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<AddReviewRequest>,
+) -> Result<Json<AddReviewResponse>, AppError> {
+    let collection = find(&state, &name)?;
+    add_to_collection(state, api_key, collection, request).await.map(Json)
+}
+
+/// Validate, embed and persist one review in a collection
+///
+/// Shared with tenant namespaces (see `api::tenancy`).
+pub(crate) async fn add_to_collection(
+    state: AppState,
+    api_key: Option<String>,
+    collection: Arc<Collection>,
+    mut request: AddReviewRequest,
+) -> Result<AddReviewResponse, AppError> {
+    state.check_writable()?;
+    let config = state.config.current();
+    if config.shadow.collection.as_deref() == Some(collection.name()) {
+        return Err(AppError::Conflict(format!(
+            "Collection {} is the shadow index; it is filled from the default collection",
+            collection.name()
+        )));
+    }
+    request.validate(&config.validation).map_err(AppError::validation)?;
+    let redactions = redact(&state, &mut request)?;
+    tag_language(&config, &mut request);
+
+    state
+        .backpressure
+        .admit_write(state.write_gate.in_flight() + state.ingest.pending(), &config.backpressure)?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let name = collection.name().to_string();
+    let permit = state.backpressure.embedding(&config.backpressure)?;
+    let embedding = match request.image.take() {
+        Some(image) => embed_image(&state, &collection, &image).await?,
+        None => {
+            let text = EmbeddingService::review_text(
+                &request.review_title,
+                &request.review_body,
+                &collection.manifest().normalization,
+            );
+            embed(&state, &collection, text).await?
+        }
+    };
+    drop(permit);
+
+    let metadata = request.into_metadata();
+    let payload_hash = serde_json::to_vec(&metadata)
+        .map(|bytes| sha256_hex(&bytes))
+        .ok();
+
+    // Detached like the default collection's writes, so the index and
+    // metadata stay in step if the client goes away
+    let dedup = config.dedup.clone();
+    let (vector_id, duplicate_of) = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = collection.index.write().await;
+
+        let exact = if dedup.exact {
+            collection
+                .content_hashes
+                .find(
+                    &metadata,
+                    index.vector_count(),
+                    || collection.metadata.read_all(),
+                    |ids| collection.metadata.read_batch(ids),
+                )
+                .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?
+        } else {
+            None
+        };
+        if let Some(id) = exact
+            && dedup.action == DedupAction::Reject
+        {
+            return Err(AppError::Duplicate {
+                message: format!("Review is an exact duplicate of review {}", id),
+                existing_id: id,
+            });
+        }
+
+        let duplicate = if dedup.enabled && exact.is_none() {
+            find_duplicate(&index, &embedding, collection.manifest().metric, dedup.threshold)
+        } else {
+            None
+        };
+        if let Some((id, score)) = duplicate
+            && dedup.action == DedupAction::Reject
+        {
+            return Err(AppError::Duplicate {
+                message: format!("Review is a near-duplicate of review {} (similarity {:.3})", id, score),
+                existing_id: id,
+            });
+        }
+        let duplicate_of = exact.or(duplicate.map(|(id, _)| id));
+
+        let vector_id = collection
+            .metadata
+            .append(&metadata)
+            .map_err(|e| AppError::Internal(format!("Metadata write failed: {}", e)))?;
+        collection.spelling.observe(&metadata.review_title, &metadata.review_body);
+        collection.content_hashes.observe(vector_id, &metadata);
+        if vector_id != index.vector_count() {
+            let _ = collection.metadata.truncate(vector_id);
+            return Err(AppError::Internal(format!(
+                "Metadata ({} reviews) and index ({} vectors) of collection {} are out of sync",
+                vector_id,
+                index.vector_count(),
+                collection.name()
+            )));
+        }
+        if let Err(e) = index.add_vector(&embedding) {
+            let _ = collection.metadata.truncate(vector_id);
+            return Err(AppError::Internal(format!("Add vector failed: {}", e)));
+        }
+        if let Err(e) = index.save(&collection.index_path()) {
+            warn!(collection = %collection.name(), vector_id, "Save index failed: {:#}", e);
+        }
+
+        state.record_mutation(AuditEntry {
+            api_key,
+            payload_hash,
+            ids: vec![vector_id],
+            detail: Some(match duplicate_of {
+                Some(id) => serde_json::json!({ "collection": collection.name(), "duplicate_of": id }),
+                None => serde_json::json!({ "collection": collection.name() }),
+            }),
+            ..AuditEntry::new("add")
+        });
+        Ok::<_, AppError>((vector_id, duplicate_of))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    info!(collection = %name, vector_id, "Review added");
+    let message = match duplicate_of {
+        Some(id) => format!("Review added to {} with ID {} (duplicate of {})", name, vector_id, id),
+        None => format!("Review added to {} with ID {}", name, vector_id),
+    };
+    Ok(AddReviewResponse {
+        vector_id,
+        status: "success".to_string(),
+        message,
+        duplicate_of,
+        redactions,
+    })
+}
+
+pub async fn search_collection_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<SearchRequest>,
+) -> Result<Json<SearchResponse>, AppError> {
+    let collection = find(&state, &name)?;
+    search_collection(&state, &collection, request).await.map(Json)
+}
+
+/// Embed the query with the collection's model and search its index
+pub(crate) async fn search_collection(
+    state: &AppState,
+    collection: &Arc<Collection>,
+    mut request: SearchRequest,
+) -> Result<SearchResponse, AppError> {
+    let config = state.config.current();
+    if let Some(filter) = &mut request.filter {
+        language::normalize_filter(filter);
+    }
+    request.validate(config.search.max_top_k).map_err(AppError::BadRequest)?;
+    if let Some(filter) = &request.filter {
+        filter
+            .check_types(&config.validation.attributes)
+            .map_err(AppError::BadRequest)?;
+    }
+    if !request.collections.is_empty() {
+        return Err(AppError::BadRequest(
+            "collections only applies to POST /reviews/search".to_string(),
+        ));
+    }
+
+    let (embeddings, corrected_query) = match &request.image {
+        Some(image) => (vec![embed_image(state, collection, image).await?], None),
+        None => {
+            let store = collection.clone();
+            let corrected_query = correct_query(
+                &collection.spelling,
+                move || store.metadata.read_all(),
+                &config.search,
+                &request.query,
+            );
+            let texts = query_texts(
+                state,
+                &config.search,
+                &collection.manifest().normalization,
+                corrected_query.as_deref().unwrap_or(&request.query),
+            );
+            (embed_all(state, collection, texts).await?, corrected_query)
+        }
+    };
+    let hits = search_fused(
+        &*collection.index.read().await,
+        &embeddings,
+        candidate_count(&request, &config.search),
+    )?;
+
+    let ids: Vec<usize> = hits.iter().map(|r| r.vector_id).collect();
+    let metadata_list = collection
+        .metadata
+        .read_batch(&ids)
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+
+    let (mut results, aggregations) = assemble_results(
+        &hits,
+        metadata_list,
+        &request,
+        collection.manifest().metric,
+        config.search.score_normalization,
+        &config.validation.attributes,
+    );
+    if request.include_vectors {
+        attach_vectors(&*collection.index.read().await, &mut results);
+    }
+
+    Ok(SearchResponse {
+        total_found: results.len(),
+        query: request.query,
+        results,
+        corrected_query,
+        experiment: None,
+        aggregations,
+    })
+}
+
+/// A tenant namespace's collection, created with the server's settings on
+/// first write
+pub(crate) fn namespace_collection(
+    state: &AppState,
+    namespace: &str,
+) -> Result<Arc<Collection>, AppError> {
+    let manifest = CollectionManifest::from_config(&state.config.current());
+    state
+        .collections
+        .get_or_create(namespace, manifest)
+        .map_err(collection_error)
+}
+
+/// The collection `language.collections` assigns to `language`, created
+/// with the server's settings on first use
+pub(crate) fn language_collection(
+    state: &AppState,
+    language: Option<&str>,
+) -> Result<Option<Arc<Collection>>, AppError> {
+    let config = state.config.current();
+    let Some(name) = language.and_then(|tag| config.language.collection_for(tag)) else {
+        return Ok(None);
+    };
+    let manifest = CollectionManifest::from_config(&config);
+    state
+        .collections
+        .get_or_create(name, manifest)
+        .map(Some)
+        .map_err(collection_error)
+}
+
+/// Settings for a new collection: the request's, else the server's
+fn manifest_for(
+    state: &AppState,
+    request: &CreateCollectionRequest,
+) -> Result<CollectionManifest, AppError> {
+    let mut manifest = CollectionManifest::from_config(&state.config.current());
+
+    if let Some(model) = &request.model {
+        manifest.model = model.clone();
+    }
+    let model_dim = EmbeddingService::model_dimension(&manifest.model).ok_or_else(|| {
+        AppError::BadRequest(format!("Unsupported embedding model: {}", manifest.model))
+    })?;
+    manifest.vector_dim = request.vector_dim.unwrap_or(model_dim);
+    if manifest.vector_dim != model_dim {
+        return Err(AppError::BadRequest(format!(
+            "vector_dim {} does not match model {} (dimension {})",
+            manifest.vector_dim, manifest.model, model_dim
+        )));
+    }
+
+    if let Some(metric) = request.metric {
+        manifest.metric = metric;
+    }
+    if let Some(normalization) = &request.normalization {
+        manifest.normalization = normalization.clone().into();
+    }
+    if let Some(index_type) = &request.index_type {
+        manifest.index_type = index_type.clone();
+    }
+    // Other backends check their own index types when the index is created
+    if manifest.backend == IndexBackend::Spfresh {
+        manifest.index_type = manifest.index_type.to_uppercase();
+        if !matches!(manifest.index_type.as_str(), "BKT" | "KDT") {
+            return Err(AppError::BadRequest(format!(
+                "index_type must be BKT or KDT, got {}",
+                manifest.index_type
+            )));
+        }
+    }
+
+    Ok(manifest)
+}
+
+pub(crate) fn find(state: &AppState, name: &str) -> Result<Arc<Collection>, AppError> {
+    state
+        .collections
+        .get(name)
+        .ok_or_else(|| collection_error(CollectionError::NotFound(name.to_string())))
+}
+
+/// Embed `text` with the collection's model, checking the dimension it
+/// was created with
+async fn embed(
+    state: &AppState,
+    collection: &Collection,
+    text: String,
+) -> Result<Vec<f32>, AppError> {
+    let mut embeddings = embed_all(state, collection, vec![text]).await?;
+    Ok(embeddings.remove(0))
+}
+
+/// Embed with the collection's model, checking the dimension
+async fn embed_all(
+    state: &AppState,
+    collection: &Collection,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, AppError> {
+    let manifest = collection.manifest();
+    let embedding_service = state.embedder_for(&manifest.model).await?;
+    let embeddings = embed_queries(embedding_service, texts).await?;
+
+    if let Some(embedding) = embeddings.iter().find(|e| e.len() != manifest.vector_dim) {
+        return Err(AppError::Internal(format!(
+            "Model {} produced {} dimensions, collection {} expects {}",
+            manifest.model,
+            embedding.len(),
+            collection.name(),
+            manifest.vector_dim
+        )));
+    }
+    Ok(embeddings)
+}
+
+/// Embed `image` with the image encoder paired with the collection's model
+async fn embed_image(
+    state: &AppState,
+    collection: &Collection,
+    image: &ImageInput,
+) -> Result<Vec<f32>, AppError> {
+    let manifest = collection.manifest();
+    let service = state.image_embedder_for(&manifest.model).await?;
+    let bytes = image_bytes(image, &state.config.current().limits).await?;
+    let embedding = tokio::task::spawn_blocking(move || service.embed(&bytes))
+        .await
+        .map_err(|e| AppError::Internal(format!("Embedding task failed: {}", e)))?
+        // Almost always an image that doesn't decode
+        .map_err(|e| AppError::BadRequest(format!("Image embedding failed: {:#}", e)))?;
+
+    if embedding.len() != manifest.vector_dim {
+        return Err(AppError::Internal(format!(
+            "Image model for {} produced {} dimensions, collection {} expects {}",
+            manifest.model,
+            embedding.len(),
+            collection.name(),
+            manifest.vector_dim
+        )));
+    }
+    Ok(embedding)
+}
+
+/// The encoded bytes of `image`, downloading it if needed
+async fn image_bytes(image: &ImageInput, limits: &LimitsConfig) -> Result<Vec<u8>, AppError> {
+    let max_bytes = limits.image_bytes;
+    let bytes = match image {
+        ImageInput::Base64(data) => {
+            // Data URLs too: data:image/png;base64,...
+            let data = data.split_once(";base64,").map_or(data.as_str(), |(_, data)| data);
+            BASE64_STANDARD
+                .decode(data.trim())
+                .map_err(|e| AppError::BadRequest(format!("image is not valid base64: {}", e)))?
+        }
+        ImageInput::Url(url) => fetch_image(url, limits).await?,
+    };
+    if bytes.len() > max_bytes {
+        return Err(AppError::PayloadTooLarge(format!(
+            "Image is larger than {} bytes",
+            max_bytes
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Download an image, giving up past `limits.image_bytes`
+///
+/// Only with `limits.image_urls` on, only from public addresses (and the
+/// allowed hosts, if listed), and without following redirects, so a URL
+/// can't reach services on the server's own network.
+async fn fetch_image(url: &str, limits: &LimitsConfig) -> Result<Vec<u8>, AppError> {
+    if !limits.image_urls {
+        return Err(AppError::BadRequest(
+            "Image URLs are disabled; send the image as base64".to_string(),
+        ));
+    }
+    let url = reqwest::Url::parse(url)
+        .map_err(|e| AppError::BadRequest(format!("Invalid image URL: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::BadRequest(format!(
+            "Image URL must be http or https, got {}",
+            url.scheme()
+        )));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| AppError::BadRequest("Image URL has no host".to_string()))?
+        .to_string();
+    if !limits.image_url_hosts.is_empty()
+        && !limits.image_url_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(&host))
+    {
+        return Err(AppError::BadRequest(format!("Image host {} is not allowed", host)));
+    }
+    let addr = resolve_public(&host, url.port_or_known_default().unwrap_or(80)).await?;
+
+    let download_error = |e: reqwest::Error| AppError::BadRequest(format!("Failed to download image: {}", e));
+    // Pinned to the address just checked, so a second lookup can't swap it
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve(&host, addr)
+        .timeout(IMAGE_FETCH_TIMEOUT)
+        .build()
+        .map_err(download_error)?;
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(download_error)?;
+    if response.status().is_redirection() {
+        return Err(AppError::BadRequest(
+            "Image URL redirects; give the final URL".to_string(),
+        ));
+    }
+    let max_bytes = limits.image_bytes;
+    if response.content_length().is_some_and(|length| length > max_bytes as u64) {
+        return Err(AppError::PayloadTooLarge(format!(
+            "Image is larger than {} bytes",
+            max_bytes
+        )));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(download_error)? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > max_bytes {
+            return Err(AppError::PayloadTooLarge(format!(
+                "Image is larger than {} bytes",
+                max_bytes
+            )));
+        }
+    }
+    Ok(bytes)
+}
+
+/// The address `host` resolves to, if every address it resolves to is public
+async fn resolve_public(host: &str, port: u16) -> Result<SocketAddr, AppError> {
+    // IPv6 literals come bracketed from the URL
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((literal, port))
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Failed to resolve image host {}: {}", host, e)))?
+        .collect();
+    if addrs.iter().any(|addr| !is_public(addr.ip())) {
+        return Err(AppError::BadRequest(format!(
+            "Image host {} resolves to a non-public address",
+            host
+        )));
+    }
+    addrs
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::BadRequest(format!("Image host {} did not resolve", host)))
+}
+
+/// Rules out loopback, private, link-local (cloud metadata included) and
+/// other addresses that don't belong to the public internet
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+async fn describe(collection: &Collection) -> Result<CollectionInfo, AppError> {
+    let review_count = collection
+        .metadata
+        .count_lines()
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+    let manifest = collection.manifest();
+    Ok(CollectionInfo {
+        name: collection.name().to_string(),
+        model: manifest.model.clone(),
+        vector_dim: manifest.vector_dim,
+        metric: manifest.metric,
+        index_type: manifest.index_type.clone(),
+        vector_count: collection.index.read().await.vector_count(),
+        review_count,
+        normalization: (&manifest.normalization).into(),
+    })
+}
+
+pub(crate) fn collection_error(e: CollectionError) -> AppError {
+    match e {
+        CollectionError::InvalidName(_) => AppError::BadRequest(e.to_string()),
+        CollectionError::Exists(_) | CollectionError::Aliased { .. } => {
+            AppError::Conflict(e.to_string())
+        }
+        CollectionError::NotFound(_) | CollectionError::AliasNotFound(_) => {
+            AppError::NotFound(e.to_string())
+        }
+        CollectionError::Storage(e) => AppError::Internal(format!("{:#}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::image::{ImageEmbeddingProvider, ImageEmbeddingService};
+    use crate::test_utils::{HashEmbedder, TestApp};
+    use serde_json::json;
+
+    /// Embeds an image's bytes as if they were text
+    struct HashImages(HashEmbedder);
+
+    impl ImageEmbeddingProvider for HashImages {
+        fn embed_images(&self, images: &[&[u8]]) -> anyhow::Result<Vec<Vec<f32>>> {
+            Ok(images
+                .iter()
+                .map(|image| self.0.embed(&String::from_utf8_lossy(image)))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_reviews() {
+        let app = TestApp::builder().build().unwrap();
+        let clip = ImageEmbeddingService::from_provider("clip", 512, HashImages(HashEmbedder::new(512, 0)));
+        app.state.models.insert_vision("clip-vit-b-32", clip);
+
+        let (status, body) = app
+            .post("/collections", &json!({ "name": "photos", "model": "clip-vit-b-32" }))
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        for (photo, product) in [("red running shoe", "P1"), ("blue kettle", "P2")] {
+            let review = json!({
+                "review_title": "Photo",
+                "review_body": "As pictured",
+                "product_id": product,
+                "review_rating": 5,
+                "image": { "base64": BASE64_STANDARD.encode(photo) },
+            });
+            let (status, body) = app.post("/collections/photos/reviews", &review).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let query = json!({ "query": "", "image": { "base64": BASE64_STANDARD.encode("blue kettle") } });
+        let (status, body) = app.post("/collections/photos/search", &query).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["results"][0]["product_id"], "P2");
+
+        // Models without an image encoder, and the default collection, refuse images
+        let (status, _) = app.post("/reviews/search", &query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        app.post("/collections", &json!({ "name": "text" })).await;
+        let (status, _) = app.post("/collections/text/search", &query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let bad = json!({ "query": "", "image": { "base64": "not base64!" } });
+        let (status, _) = app.post("/collections/photos/search", &bad).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_is_public() {
+        let private = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ];
+        for ip in private {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_urls_refused() {
+        let mut limits = LimitsConfig::default();
+        let url = "http://127.0.0.1:9/photo.png";
+        // Off by default
+        assert!(matches!(fetch_image(url, &limits).await, Err(AppError::BadRequest(_))));
+
+        limits.image_urls = true;
+        let error = fetch_image(url, &limits).await.unwrap_err();
+        assert!(matches!(&error, AppError::BadRequest(message) if message.contains("non-public")), "{:?}", error);
+        let error = fetch_image("http://[::1]/photo.png", &limits).await.unwrap_err();
+        assert!(matches!(&error, AppError::BadRequest(message) if message.contains("non-public")), "{:?}", error);
+
+        limits.image_url_hosts = vec!["images.example.com".to_string()];
+        let error = fetch_image("https://example.org/photo.png", &limits).await.unwrap_err();
+        assert!(matches!(&error, AppError::BadRequest(message) if message.contains("not allowed")), "{:?}", error);
+    }
+}
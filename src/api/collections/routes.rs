@@ -0,0 +1,29 @@
+use crate::api::collections::handlers::{
+    add_collection_review_handler, create_collection_handler, drop_collection_handler,
+    get_collection_handler, list_aliases_handler, list_collections_handler,
+    remove_alias_handler, search_collection_handler, set_alias_handler,
+};
+use crate::api::models::AppState;
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/collections",
+            get(list_collections_handler).post(create_collection_handler),
+        )
+        .route(
+            "/collections/{name}",
+            get(get_collection_handler).delete(drop_collection_handler),
+        )
+        .route("/collections/{name}/reviews", post(add_collection_review_handler))
+        .route("/collections/{name}/search", post(search_collection_handler))
+        .route("/aliases", get(list_aliases_handler))
+        .route(
+            "/aliases/{alias}",
+            put(set_alias_handler).delete(remove_alias_handler),
+        )
+}
@@ -0,0 +1,168 @@
+use crate::api::extract::ApiJson;
+use crate::api::models::*;
+use crate::api::search::handlers::sort_results;
+use crate::api::timing::{record_if_slow, PhaseTimer};
+use axum::{
+    extract::State,
+    http::{HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// Set (to the number of failed shards) when results are partial
+pub const PARTIAL_RESULTS_HEADER: &str = "x-partial-results";
+
+/// Shared across requests for connection reuse
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Fan the search out to every shard and merge their top-k by score
+pub async fn coordinated_search_handler(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<SearchRequest>,
+) -> Result<Response, AppError> {
+    let mut timer = PhaseTimer::start();
+    let config = state.config.current();
+    let settings = &config.coordinator;
+
+    request.validate(config.search.max_top_k).map_err(AppError::BadRequest)?;
+    info!(
+        query = %request.query,
+        k = request.top_k,
+        shards = settings.shards.len(),
+        "Coordinating search"
+    );
+
+    let client = CLIENT.get_or_init(reqwest::Client::new).clone();
+    let request = Arc::new(request);
+    let mut calls = JoinSet::new();
+    for (shard, url) in settings.shards.iter().enumerate() {
+        let call = client
+            .post(format!("{}/reviews/search", url.trim_end_matches('/')))
+            .timeout(Duration::from_millis(settings.timeout_ms))
+            .json(request.as_ref());
+        let url = url.clone();
+        calls.spawn(async move {
+            let response = async {
+                call.send()
+                    .await?
+                    .error_for_status()?
+                    .json::<SearchResponse>()
+                    .await
+            }
+            .await;
+            (shard, url, response)
+        });
+    }
+
+    let mut results = Vec::new();
+    let mut aggregations: Option<SearchAggregations> = None;
+    let mut failed = 0;
+    while let Some(joined) = calls.join_next().await {
+        let (shard, url, response) =
+            joined.map_err(|e| AppError::Internal(format!("Shard task failed: {}", e)))?;
+        match response {
+            Ok(response) => {
+                results.extend(response.results.into_iter().map(|item| SearchResultItem {
+                    shard: Some(shard),
+                    ..item
+                }));
+                if let Some(shard_aggregations) = response.aggregations {
+                    aggregations.get_or_insert_default().merge(shard_aggregations);
+                }
+            }
+            Err(e) => {
+                warn!(shard, url = %url, "Shard search failed: {}", e);
+                failed += 1;
+            }
+        }
+    }
+    timer.mark("fan_out");
+
+    if failed == settings.shards.len() {
+        return Err(AppError::ServiceUnavailable("No shard answered the search".to_string()));
+    }
+    if failed > 0 && !settings.allow_partial {
+        return Err(AppError::ServiceUnavailable(format!(
+            "{} of {} shards failed",
+            failed,
+            settings.shards.len()
+        )));
+    }
+
+    let mut results = merge_top_k(results, request.top_k);
+    sort_results(&mut results, &request.sort);
+    let total = results.len();
+    state.latency.observe("coordinated_search", &timer);
+    record_if_slow(
+        &config.slow_log,
+        "coordinated_search",
+        &timer,
+        serde_json::json!({ "query": request.query, "top_k": request.top_k, "failed_shards": failed }),
+    );
+
+    let response = SearchResponse {
+        results,
+        total_found: total,
+        query: request.query.clone(),
+        corrected_query: None,
+        experiment: None,
+        aggregations,
+    };
+    state
+        .analytics
+        .record(&config.analytics, &response, timer.elapsed());
+    let mut response = Json(response).into_response();
+    if failed > 0 {
+        response.headers_mut().insert(
+            HeaderName::from_static(PARTIAL_RESULTS_HEADER),
+            HeaderValue::from(failed),
+        );
+    }
+    Ok(response)
+}
+
+/// Highest-scoring `k` results across shards
+fn merge_top_k(mut results: Vec<SearchResultItem>, k: usize) -> Vec<SearchResultItem> {
+    results.sort_by(|a, b| b.similarity_score.total_cmp(&a.similarity_score));
+    results.truncate(k);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(shard: usize, vector_id: usize, score: f32) -> SearchResultItem {
+        SearchResultItem {
+            review_title: String::new(),
+            review_body: String::new(),
+            product_id: String::new(),
+            review_rating: 5,
+            similarity_score: score,
+            vector_id,
+            shard: Some(shard),
+            collection: None,
+            language: None,
+            created_at: None,
+            attributes: Default::default(),
+            vector: None,
+            distance: None,
+            metric: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_top_k_orders_across_shards() {
+        let merged = merge_top_k(
+            vec![item(0, 1, 0.4), item(1, 1, 0.9), item(0, 2, 0.7), item(1, 3, 0.1)],
+            3,
+        );
+        let order: Vec<(Option<usize>, usize)> =
+            merged.iter().map(|r| (r.shard, r.vector_id)).collect();
+        assert_eq!(order, vec![(Some(1), 1), (Some(0), 2), (Some(0), 1)]);
+    }
+}
@@ -0,0 +1,8 @@
+use crate::api::coordinator::handlers::coordinated_search_handler;
+use crate::api::models::AppState;
+use axum::{routing::post, Router};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/reviews/search", post(coordinated_search_handler))
+}
@@ -0,0 +1,195 @@
+//! A/B experiments: searches split between the server's model (control) and
+//! a candidate collection embedded with another model
+
+use crate::api::middleware::current_request_id;
+use crate::api::tenancy::{key_fingerprint, presented_key};
+use crate::config::ExperimentConfig;
+use crate::storage::audit::sha256_hex;
+use crate::storage::now_ms;
+use axum::http::HeaderMap;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+use vector_search_client::models::{ExperimentArm, ExperimentAssignment, SearchResponse};
+
+/// Header naming the unit (user, session) assignments stick to
+const UNIT_HEADER: &str = "x-experiment-unit";
+
+/// Log lines waiting for the writer thread; searches drop lines past this
+/// rather than wait on the disk
+const LOG_QUEUE: usize = 1024;
+
+/// Lines for `experiment.log_path`, written by one background thread
+static LOG_WRITER: OnceLock<SyncSender<(PathBuf, String)>> = OnceLock::new();
+
+/// The caller's unit: `X-Experiment-Unit`, else their API key, else the
+/// request itself (a fresh draw per search)
+pub fn experiment_unit(headers: &HeaderMap) -> String {
+    headers
+        .get(UNIT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| presented_key(headers).map(key_fingerprint))
+        .or_else(current_request_id)
+        .unwrap_or_default()
+}
+
+/// The arm `unit` falls in, or `None` while no experiment is enabled
+///
+/// The same unit always lands in the same arm for a given experiment name
+/// and share.
+pub fn assign(config: &ExperimentConfig, unit: &str) -> Option<ExperimentArm> {
+    if !config.enabled {
+        return None;
+    }
+    let hash = sha256_hex(format!("{}:{}", config.name, unit).as_bytes());
+    let bucket = u32::from_str_radix(&hash[..8], 16).unwrap_or(0) as f64 / (u32::MAX as f64 + 1.0);
+    if bucket < config.candidate_share.clamp(0.0, 1.0) {
+        Some(ExperimentArm::Candidate)
+    } else {
+        Some(ExperimentArm::Control)
+    }
+}
+
+/// One assigned search, as written to `experiment.log_path`
+#[derive(Debug, Serialize)]
+struct ExperimentLogEntry<'a> {
+    timestamp_ms: u64,
+    #[serde(flatten)]
+    assignment: &'a ExperimentAssignment,
+    query: &'a str,
+    latency_ms: f64,
+    /// Product IDs in rank order; vector IDs differ between arms
+    product_ids: Vec<&'a str>,
+}
+
+/// Log an assigned search for offline comparison of the arms
+pub fn record(config: &ExperimentConfig, response: &SearchResponse, latency_ms: f64) {
+    let Some(assignment) = &response.experiment else {
+        return;
+    };
+    let entry = ExperimentLogEntry {
+        timestamp_ms: now_ms(),
+        assignment,
+        query: &response.query,
+        latency_ms,
+        product_ids: response
+            .results
+            .iter()
+            .map(|r| r.product_id.as_str())
+            .collect(),
+    };
+    let line = serde_json::to_string(&entry).unwrap_or_default();
+    info!(
+        experiment = %assignment.experiment,
+        arm = ?assignment.arm,
+        model = %assignment.model,
+        "🧪 Experiment search: {}",
+        line
+    );
+
+    if let Some(path) = &config.log_path {
+        match log_writer().try_send((path.clone(), line)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => warn!("Experiment log is behind; dropped a line"),
+            Err(TrySendError::Disconnected(_)) => warn!("Experiment log writer is gone; dropped a line"),
+        }
+    }
+}
+
+/// The queue to the writer thread, started on first use
+///
+/// The file stays open between lines and is reopened when `log_path`
+/// changes.
+fn log_writer() -> &'static SyncSender<(PathBuf, String)> {
+    LOG_WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::sync_channel::<(PathBuf, String)>(LOG_QUEUE);
+        std::thread::spawn(move || {
+            let mut open: Option<(PathBuf, File)> = None;
+            for (path, line) in rx {
+                if open.as_ref().is_none_or(|(current, _)| *current != path) {
+                    open = match OpenOptions::new().create(true).append(true).open(&path) {
+                        Ok(file) => Some((path.clone(), file)),
+                        Err(e) => {
+                            warn!("Failed to open experiment log {:?}: {}", path, e);
+                            continue;
+                        }
+                    };
+                }
+                if let Some((_, file)) = open.as_mut()
+                    && let Err(e) = writeln!(file, "{}", line)
+                {
+                    warn!("Failed to write experiment log {:?}: {}", path, e);
+                    open = None;
+                }
+            }
+        });
+        tx
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign() {
+        let mut config = ExperimentConfig::default();
+        assert_eq!(assign(&config, "user-1"), None);
+
+        config.enabled = true;
+        let first = assign(&config, "user-1");
+        assert_eq!(assign(&config, "user-1"), first);
+
+        let candidates = (0..1000)
+            .filter(|i| assign(&config, &format!("user-{}", i)) == Some(ExperimentArm::Candidate))
+            .count();
+        assert!((400..600).contains(&candidates), "{} of 1000 in candidate", candidates);
+
+        config.candidate_share = 0.0;
+        assert_eq!(assign(&config, "user-1"), Some(ExperimentArm::Control));
+        config.candidate_share = 1.0;
+        assert_eq!(assign(&config, "user-1"), Some(ExperimentArm::Candidate));
+    }
+
+    #[test]
+    fn test_record_writes_in_background() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = ExperimentConfig {
+            log_path: Some(dir.path().join("experiment.jsonl")),
+            ..ExperimentConfig::default()
+        };
+        let response = SearchResponse {
+            results: Vec::new(),
+            total_found: 0,
+            query: "battery".to_string(),
+            corrected_query: None,
+            experiment: Some(ExperimentAssignment {
+                experiment: "e5".to_string(),
+                arm: ExperimentArm::Candidate,
+                model: "multilingual-e5-small".to_string(),
+            }),
+            aggregations: None,
+        };
+        record(&config, &response, 1.5);
+        record(&config, &response, 2.5);
+
+        let path = config.log_path.as_ref().unwrap();
+        let mut lines = Vec::new();
+        for _ in 0..200 {
+            lines = std::fs::read_to_string(path).unwrap_or_default().lines().map(str::to_string).collect();
+            if lines.len() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+        let entry: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(entry["latency_ms"], 2.5);
+        assert_eq!(entry["arm"], "candidate");
+    }
+}
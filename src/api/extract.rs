@@ -0,0 +1,84 @@
+use crate::api::models::AppError;
+use crate::api::tenancy::{key_fingerprint, presented_key};
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+};
+use http_body_util::LengthLimitError;
+use std::convert::Infallible;
+use serde::de::DeserializeOwned;
+
+/// JSON extractor that reports rejections as `ErrorResponse` bodies
+/// (e.g., 413 when a route's body limit is exceeded) instead of plain text
+pub struct ApiJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(map_rejection(rejection.status(), rejection.body_text())),
+        }
+    }
+}
+
+/// Raw body extractor with the same `ErrorResponse` rejections as [`ApiJson`]
+pub struct ApiBytes(pub Bytes);
+
+impl<S> FromRequest<S> for ApiBytes
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Bytes::from_request(req, state).await {
+            Ok(bytes) => Ok(Self(bytes)),
+            Err(rejection) => Err(map_rejection(rejection.status(), rejection.body_text())),
+        }
+    }
+}
+
+/// 413 if reading a body stopped at its size limit, otherwise 400 (e.g. the
+/// client disconnected midway)
+pub(crate) fn body_read_error(err: axum::Error) -> AppError {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&err);
+    while let Some(cause) = source {
+        if cause.is::<LengthLimitError>() {
+            return AppError::PayloadTooLarge(format!("Failed to read request body: {}", err));
+        }
+        source = cause.source();
+    }
+    AppError::BadRequest(format!("Failed to read request body: {}", err))
+}
+
+fn map_rejection(status: StatusCode, body_text: String) -> AppError {
+    if status == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::PayloadTooLarge("Request body exceeds the size limit for this endpoint".to_string())
+    } else {
+        AppError::BadRequest(body_text)
+    }
+}
+
+/// Fingerprint of the caller's API key (`X-API-Key` or `Authorization: Bearer`),
+/// safe to log and audit
+pub struct CallerKey(pub Option<String>);
+
+impl<S> FromRequestParts<S> for CallerKey
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let key = presented_key(&parts.headers);
+
+        Ok(Self(key.map(key_fingerprint)))
+    }
+}
@@ -0,0 +1,131 @@
+use crate::api::models::AppState;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, warn};
+
+/// Shares one metadata sync and index save among writes that land close
+/// together (group commit)
+#[derive(Debug, Default)]
+pub struct GroupCommit {
+    /// Writes applied to the index, numbered under its write lock
+    written: AtomicU64,
+    progress: Mutex<Progress>,
+    saved: Notify,
+    saves: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Progress {
+    /// Every write numbered up to this one is covered by a finished save
+    saved: u64,
+    /// A writer is gathering the next group
+    leading: bool,
+}
+
+impl GroupCommit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number a write just applied to the index; call under its write lock
+    pub fn written(&self) -> u64 {
+        self.written.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Writes numbered since startup
+    pub fn writes(&self) -> u64 {
+        self.written.load(Ordering::SeqCst)
+    }
+
+    /// Index saves since startup, one per group
+    pub fn saves(&self) -> u64 {
+        self.saves.load(Ordering::Relaxed)
+    }
+
+    /// Wait until a save covers write `ticket`; call after releasing the
+    /// index write lock
+    ///
+    /// The first writer to arrive waits `snapshot.group_commit_ms` for
+    /// others to join, then syncs metadata and saves the index once for the
+    /// whole group. A failed save is logged: the index stays dirty, so the
+    /// next snapshot or the shutdown save retries it.
+    pub async fn commit(self: &Arc<Self>, ticket: u64, state: &AppState) {
+        loop {
+            let saved = self.saved.notified();
+            {
+                let mut progress = self.progress.lock().unwrap();
+                if progress.saved >= ticket {
+                    return;
+                }
+                if !progress.leading {
+                    progress.leading = true;
+                    break;
+                }
+            }
+            saved.await;
+        }
+
+        let window = Duration::from_millis(state.config.current().snapshot.group_commit_ms);
+        if !window.is_zero() {
+            tokio::time::sleep(window).await;
+        }
+
+        let group = self.clone();
+        let vector_index = state.vector_index.clone();
+        let metadata_store = state.metadata_store.clone();
+        let index_path = state.index_path();
+        let saved = tokio::task::spawn_blocking(move || {
+            let index = vector_index.blocking_read();
+            // Writers are locked out, so this covers everything numbered
+            let upto = group.writes();
+            // Metadata first so a saved index never references unsynced lines
+            let result = metadata_store.flush().and_then(|()| index.save(&index_path));
+            if let Err(e) = result {
+                warn!(upto, "Save index failed, retrying at the next snapshot: {:#}", e);
+            }
+            upto
+        })
+        .await;
+        let upto = saved.unwrap_or_else(|e| {
+            error!("Index save task failed: {}", e);
+            self.writes()
+        });
+
+        self.saves.fetch_add(1, Ordering::Relaxed);
+        let mut progress = self.progress.lock().unwrap();
+        progress.saved = progress.saved.max(upto);
+        progress.leading = false;
+        drop(progress);
+        self.saved.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::TestApp;
+
+    #[tokio::test]
+    async fn test_concurrent_writes_share_a_save() {
+        let app = TestApp::builder()
+            .config(|config| config.snapshot.group_commit_ms = 50)
+            .build()
+            .unwrap();
+        let group = app.state.group_commit.clone();
+
+        let tickets: Vec<u64> = (0..5).map(|_| group.written()).collect();
+        let commits = tickets.into_iter().map(|ticket| {
+            let (group, state) = (group.clone(), app.state.clone());
+            tokio::spawn(async move { group.commit(ticket, &state).await })
+        });
+        for commit in commits.collect::<Vec<_>>() {
+            commit.await.unwrap();
+        }
+        assert_eq!(group.saves(), 1);
+
+        let ticket = group.written();
+        group.commit(ticket, &app.state).await;
+        assert_eq!(group.saves(), 2);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::api::metrics::MetricsText;
+use crate::api::models::*;
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::path::Path;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Upper bound for each deep-check probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Text embedded by the deep check
+const CANARY_TEXT: &str = "health check canary";
+
+pub async fn health_handler(
+    State(state): State<AppState>,
+    Query(query): Query<HealthQuery>,
+) -> impl IntoResponse {
+    let total_reviews = state.metadata_store.count_lines().unwrap_or(0);
+    let vector_count = state.vector_index.read().await.vector_count();
+    let consistent = vector_count + state.ingest.pending() == total_reviews;
+    let last_snapshot_at = last_saved(&state.index_path());
+
+    let components = if query.deep {
+        Some(deep_check(&state, total_reviews).await)
+    } else {
+        None
+    };
+    let healthy = components
+        .as_ref()
+        .is_none_or(|c| c.iter().all(|c| c.status != "error"));
+
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(HealthResponse {
+        status: if healthy { "healthy" } else { "unhealthy" }.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        total_reviews,
+        vector_count,
+        metadata_count: total_reviews,
+        consistent,
+        last_snapshot_at,
+        components,
+    }))
+}
+
+/// Modification time of the index archive in Unix ms
+fn last_saved(index_path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(index_path).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_millis() as u64)
+}
+
+/// Embed a canary, run a k=1 search and read one metadata record
+async fn deep_check(state: &AppState, total_reviews: usize) -> Vec<ComponentHealth> {
+    let mut components = Vec::with_capacity(3);
+
+    let embedding = match state.embedder() {
+        Ok(embedding_service) => {
+            let (embedding, health) =
+                probe("embedding", move || embedding_service.embed(CANARY_TEXT)).await;
+            components.push(health);
+            embedding
+        }
+        Err(_) => {
+            components.push(ComponentHealth::skipped("embedding", "model still loading"));
+            None
+        }
+    };
+
+    match embedding {
+        Some(_) if !state.readiness.is_component_ready("index") => {
+            components.push(ComponentHealth::skipped("index", "index still loading"))
+        }
+        Some(embedding) => {
+            let vector_index = state.vector_index.clone();
+            let (_, health) = probe("index", move || {
+                vector_index.blocking_read().search(&embedding, 1)
+            })
+            .await;
+            components.push(health);
+        }
+        None => components.push(ComponentHealth::skipped("index", "embedding unavailable")),
+    }
+
+    if total_reviews > 0 {
+        let metadata_store = state.metadata_store.clone();
+        let (_, health) = probe("metadata", move || metadata_store.read_by_id(0)).await;
+        components.push(health);
+    } else {
+        components.push(ComponentHealth::skipped("metadata", "no reviews stored"));
+    }
+
+    components
+}
+
+/// Run a blocking probe with a timeout, so a wedged call can't hang the check
+async fn probe<T, F>(name: &str, f: F) -> (Option<T>, ComponentHealth)
+where
+    T: Send + 'static,
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+{
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(PROBE_TIMEOUT, tokio::task::spawn_blocking(f)).await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let (value, error) = match outcome {
+        Ok(Ok(Ok(value))) => (Some(value), None),
+        Ok(Ok(Err(e))) => (None, Some(format!("{:#}", e))),
+        Ok(Err(e)) => (None, Some(format!("probe panicked: {}", e))),
+        Err(_) => (None, Some(format!("timed out after {:?}", PROBE_TIMEOUT))),
+    };
+
+    let health = ComponentHealth {
+        name: name.to_string(),
+        status: if error.is_none() { "ok" } else { "error" }.to_string(),
+        latency_ms,
+        error,
+    };
+    (value, health)
+}
+
+/// Readiness probe: 200 once the model and index are loaded, 503 before
+///
+/// With a memory limit configured, memory use and whether writes are paused
+/// are included; readiness itself doesn't depend on them, as searches still
+/// work.
+pub async fn readyz_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let ready = state.readiness.is_ready();
+    let settings = &state.config.current().memory;
+    let memory = settings.enabled().then(|| state.memory.status(settings));
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(ReadyzResponse {
+        ready,
+        components: state
+            .readiness
+            .snapshot()
+            .into_iter()
+            .map(|(name, component)| (name.to_string(), component))
+            .collect(),
+        memory,
+    }))
+}
+
+/// Prometheus metrics
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let memory = state.memory.status(&state.config.current().memory);
+    let mut metrics = MetricsText::new();
+    metrics
+        .gauge(
+            "vector_search_memory_rss_bytes",
+            "Resident set size of the process (0 where unknown)",
+            memory.rss_bytes.unwrap_or(0) as f64,
+        )
+        .gauge(
+            "vector_search_index_bytes",
+            "Estimated size of the default index's vectors",
+            memory.index_bytes as f64,
+        )
+        .gauge(
+            "vector_search_writes_paused",
+            "1 while writes are paused for memory",
+            if memory.writes_paused { 1.0 } else { 0.0 },
+        )
+        .counter(
+            "vector_search_memory_pauses_total",
+            "Times writes were paused for memory",
+            state.memory.trips(),
+        )
+        .gauge(
+            "vector_search_pending_writes",
+            "Writes in progress, queued ones included",
+            state.write_gate.in_flight() as f64,
+        )
+        .counter(
+            "vector_search_index_writes_total",
+            "Adds, updates and deletes applied to the default index",
+            state.group_commit.writes(),
+        )
+        .counter(
+            "vector_search_index_saves_total",
+            "Index saves after writes; several writes can share one",
+            state.group_commit.saves(),
+        )
+        .gauge(
+            "vector_search_async_backlog",
+            "Reviews added with durability=async that aren't indexed yet",
+            state.ingest.pending() as f64,
+        )
+        .counter(
+            "vector_search_async_accepted_total",
+            "Reviews added with durability=async",
+            state.ingest.accepted(),
+        )
+        .counter(
+            "vector_search_async_dead_letters_total",
+            "Reviews added with durability=async that couldn't be embedded",
+            state.ingest.dead_lettered(),
+        )
+        .gauge(
+            "vector_search_embeddings_in_flight",
+            "Reviews being embedded for adds",
+            state.backpressure.embeddings_in_flight() as f64,
+        )
+        .counter(
+            "vector_search_write_rejections_total",
+            "Adds turned away with 429 because too many writes were pending",
+            state.backpressure.rejected_writes(),
+        )
+        .counter(
+            "vector_search_embedding_rejections_total",
+            "Adds turned away with 503 because embedding was saturated",
+            state.backpressure.rejected_embeddings(),
+        );
+    let latency = state.latency.snapshot();
+    metrics.histogram(
+        "vector_search_request_phase_seconds",
+        "Time successful requests spent in each phase, and in total",
+        latency.iter().map(|((endpoint, phase), histogram)| {
+            (format!("endpoint=\"{}\",phase=\"{}\"", endpoint, phase), histogram)
+        }),
+    );
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.finish(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{review_request, TestApp};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_health_reports_consistency() {
+        let app = TestApp::builder().build().unwrap();
+        let (status, body) = app.get("/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["consistent"], true);
+        assert!(body.get("last_snapshot_at").is_none());
+
+        let review = review_request("Great battery life");
+        app.post("/reviews", &review).await;
+        // Still being indexed counts as consistent
+        app.post("/reviews?durability=async", &review).await;
+        let (_, body) = app.get("/health").await;
+        assert_eq!((body["vector_count"].as_u64(), body["metadata_count"].as_u64()), (Some(1), Some(2)));
+        assert_eq!(body["consistent"], true);
+        assert!(body["last_snapshot_at"].as_u64().is_some());
+
+        // A metadata line nothing will index
+        let metadata: crate::storage::ReviewMetadata = serde_json::from_value(review).unwrap();
+        app.state.metadata_store.append(&metadata).unwrap();
+        let (status, body) = app.get("/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["consistent"], false);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_export_phase_latency() {
+        let app = TestApp::builder().build().unwrap();
+        let review = review_request("Great battery life");
+        let (status, _) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.post("/reviews/search", &json!({ "query": "battery" })).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let metrics = app.metrics().await;
+        for phase in ["validate", "embed", "ann_search", "metadata", "assemble", "total"] {
+            let count = format!(
+                "\nvector_search_request_phase_seconds_count{{endpoint=\"search\",phase=\"{}\"}} 1\n",
+                phase
+            );
+            assert!(metrics.contains(&count), "{}", metrics);
+        }
+        assert!(metrics.contains("phase_seconds_count{endpoint=\"add_review\",phase=\"write\"} 1\n"), "{}", metrics);
+    }
+}
@@ -0,0 +1,10 @@
+use crate::api::health::handlers::{health_handler, metrics_handler, readyz_handler};
+use crate::api::models::AppState;
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(health_handler))
+        .route("/readyz", get(readyz_handler))
+        .route("/metrics", get(metrics_handler))
+}
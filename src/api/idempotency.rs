@@ -0,0 +1,325 @@
+//! `Idempotency-Key` support for the review write routes: the first
+//! response for a key is kept for `idempotency.window_secs` and replayed to
+//! retries, so at-least-once clients don't store a review twice
+//!
+//! Keys live in memory and are forgotten when the server restarts.
+
+use crate::api::extract::body_read_error;
+use crate::api::models::{AppError, AppState};
+use crate::api::tenancy::{key_fingerprint, presented_key, Namespace};
+use crate::config::IdempotencyConfig;
+use crate::storage::audit::sha256_hex;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const KEY_HEADER: &str = "idempotency-key";
+
+/// Set on responses replayed from the cache
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// A response kept for replay
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// Hash of the method, path and body the key was first used with
+    fingerprint: String,
+    created: Instant,
+    /// `None` while the first request is still running
+    response: Option<StoredResponse>,
+}
+
+/// What to do with a request carrying a key
+#[derive(Debug)]
+enum Claim {
+    /// First use: run it and store the response
+    Run,
+    Replay(StoredResponse),
+}
+
+/// Responses by scoped idempotency key
+#[derive(Debug, Default)]
+pub struct IdempotencyCache {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// Keys in the order they were claimed, so the oldest is at the front;
+    /// a record whose `created` no longer matches its entry is stale
+    order: VecDeque<(Instant, String)>,
+}
+
+impl Inner {
+    /// Drop the oldest entry, returning false once there is none
+    fn pop_oldest(&mut self) -> bool {
+        while let Some((created, key)) = self.order.pop_front() {
+            if self.remove_if_created(&key, created) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Drop every entry claimed at or before `cutoff`
+    fn expire(&mut self, cutoff: Instant) {
+        while self.order.front().is_some_and(|(created, _)| *created <= cutoff) {
+            let (created, key) = self.order.pop_front().unwrap();
+            self.remove_if_created(&key, created);
+        }
+    }
+
+    fn remove_if_created(&mut self, key: &str, created: Instant) -> bool {
+        let live = self.entries.get(key).is_some_and(|entry| entry.created == created);
+        if live {
+            self.entries.remove(key);
+        }
+        live
+    }
+}
+
+impl IdempotencyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim `key` for a request, or find the response to replay
+    fn claim(&self, config: &IdempotencyConfig, key: &str, fingerprint: &str) -> Result<Claim, AppError> {
+        let now = Instant::now();
+        let window = Duration::from_secs(config.window_secs);
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(cutoff) = now.checked_sub(window) {
+            inner.expire(cutoff);
+        }
+
+        if let Some(entry) = inner.entries.get(key) {
+            if entry.fingerprint != fingerprint {
+                return Err(AppError::BadRequest(
+                    "Idempotency-Key was already used for a different request".to_string(),
+                ));
+            }
+            return match &entry.response {
+                Some(response) => Ok(Claim::Replay(response.clone())),
+                None => Err(AppError::Conflict(
+                    "A request with this Idempotency-Key is still being processed".to_string(),
+                )),
+            };
+        }
+
+        while inner.entries.len() >= config.max_keys.max(1) && inner.pop_oldest() {}
+        // Released keys leave stale records behind; sweep them out before
+        // they outnumber the live ones
+        if inner.order.len() > 2 * config.max_keys.max(1) {
+            let Inner { entries, order } = &mut *inner;
+            order.retain(|(created, key)| entries.get(key).is_some_and(|entry| entry.created == *created));
+        }
+        inner.order.push_back((now, key.to_string()));
+        inner.entries.insert(
+            key.to_string(),
+            Entry {
+                fingerprint: fingerprint.to_string(),
+                created: now,
+                response: None,
+            },
+        );
+        Ok(Claim::Run)
+    }
+
+    fn complete(&self, key: &str, response: StoredResponse) {
+        if let Some(entry) = self.inner.lock().unwrap().entries.get_mut(key) {
+            entry.response = Some(response);
+        }
+    }
+
+    fn release(&self, key: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.get(key).is_some_and(|entry| entry.response.is_none()) {
+            inner.entries.remove(key);
+        }
+    }
+}
+
+/// Frees a claimed key if the request never completes (e.g. it timed out),
+/// so a retry can run
+struct ClaimGuard {
+    cache: Arc<IdempotencyCache>,
+    key: String,
+}
+
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        self.cache.release(&self.key);
+    }
+}
+
+/// Replay the stored response for a repeated `Idempotency-Key`
+///
+/// Keys are scoped to the caller's namespace or API key. Reusing a key for a
+/// different request is a 400, and retrying while the first request is
+/// still running a 409. Server errors and 429s aren't stored, so those can
+/// be retried with the same key.
+pub async fn idempotent(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let config = state.config.current();
+    let key = request
+        .headers()
+        .get(KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let Some(key) = key.filter(|_| config.idempotency.window_secs > 0 && request.method() != Method::GET)
+    else {
+        return Ok(next.run(request).await);
+    };
+    if key.is_empty() || key.len() > 255 {
+        return Err(AppError::BadRequest(
+            "Idempotency-Key must be 1-255 characters".to_string(),
+        ));
+    }
+
+    let scope = match request.extensions().get::<Namespace>() {
+        Some(Namespace(namespace)) => namespace.clone(),
+        None => presented_key(request.headers()).map(key_fingerprint).unwrap_or_default(),
+    };
+    let scoped_key = format!("{}:{}", scope, key);
+
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, config.limits.review_body_bytes)
+        .await
+        .map_err(body_read_error)?;
+    let fingerprint = sha256_hex(
+        &[parts.method.as_str().as_bytes(), parts.uri.path().as_bytes(), &bytes].join(&b' '),
+    );
+
+    match state.idempotency.claim(&config.idempotency, &scoped_key, &fingerprint)? {
+        Claim::Replay(stored) => return Ok(replay(stored)),
+        Claim::Run => {}
+    }
+    let _guard = ClaimGuard {
+        cache: state.idempotency.clone(),
+        key: scoped_key.clone(),
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let status = response.status();
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read response body: {}", e)))?;
+    state.idempotency.complete(
+        &scoped_key,
+        StoredResponse {
+            status,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+        },
+    );
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = stored.status;
+    *response.headers_mut() = stored.headers;
+    response
+        .headers_mut()
+        .insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestApp;
+    use axum::http::header;
+    use serde_json::json;
+
+    fn review_request(key: &str, title: &str) -> Request {
+        let body = json!({
+            "review_title": title,
+            "review_body": "Bought it last week",
+            "product_id": "P1",
+            "review_rating": 4,
+        });
+        Request::post("/reviews")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(KEY_HEADER, key)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retry_is_replayed() {
+        let app = TestApp::builder().build().unwrap();
+
+        let first = app.request(review_request("abc", "Great")).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(first.headers().get(REPLAYED_HEADER).is_none());
+        let first_headers = first.headers().clone();
+        let first = to_bytes(first.into_body(), usize::MAX).await.unwrap();
+
+        let retry = app.request(review_request("abc", "Great")).await;
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert_eq!(retry.headers()[REPLAYED_HEADER], "true");
+        // Every header but the request ID, which belongs to the retry
+        for (name, value) in first_headers.iter().filter(|(name, _)| *name != "x-request-id") {
+            assert!(retry.headers().get_all(name).iter().any(|v| v == value), "{}", name);
+        }
+        assert_eq!(to_bytes(retry.into_body(), usize::MAX).await.unwrap(), first);
+        assert_eq!(app.state.metadata_store.count_lines().unwrap(), 1);
+
+        let reused = app.request(review_request("abc", "Different")).await;
+        assert_eq!(reused.status(), StatusCode::BAD_REQUEST);
+
+        let other = app.request(review_request("def", "Great")).await;
+        assert_eq!(other.status(), StatusCode::OK);
+        assert_eq!(app.state.metadata_store.count_lines().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_claim() {
+        let cache = IdempotencyCache::new();
+        let config = IdempotencyConfig {
+            window_secs: 60,
+            max_keys: 2,
+        };
+        assert!(matches!(cache.claim(&config, "a", "x"), Ok(Claim::Run)));
+        assert!(matches!(cache.claim(&config, "a", "x"), Err(AppError::Conflict(_))));
+
+        cache.release("a");
+        assert!(matches!(cache.claim(&config, "a", "x"), Ok(Claim::Run)));
+        let stored = StoredResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(b"{}"),
+        };
+        cache.complete("a", stored);
+        cache.release("a");
+        assert!(matches!(cache.claim(&config, "a", "x"), Ok(Claim::Replay(_))));
+
+        // Over max_keys, the oldest key is forgotten
+        assert!(matches!(cache.claim(&config, "b", "x"), Ok(Claim::Run)));
+        assert!(matches!(cache.claim(&config, "c", "x"), Ok(Claim::Run)));
+        assert!(matches!(cache.claim(&config, "a", "x"), Ok(Claim::Run)));
+    }
+}
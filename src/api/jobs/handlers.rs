@@ -0,0 +1,158 @@
+use crate::api::extract::{ApiBytes, CallerKey};
+use crate::api::jobs::{runners, JobProgress, JobState};
+use crate::api::models::*;
+use crate::cli::import::ImportFormat;
+use crate::embedding::EmbeddingService;
+use crate::storage::CollectionManifest;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tracing::info;
+
+/// Start a background import of a JSONL or CSV body
+pub async fn import_job_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Query(query): Query<ImportJobQuery>,
+    ApiBytes(body): ApiBytes,
+) -> Result<(StatusCode, Json<JobProgress>), AppError> {
+    state.check_writable()?;
+    if body.is_empty() {
+        return Err(AppError::BadRequest("Request body is empty".to_string()));
+    }
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let job = Arc::new(state.jobs.start("import"));
+    info!(job = job.id(), bytes = body.len(), "Import job started");
+
+    let format = query.format.unwrap_or(ImportFormat::Jsonl);
+    tokio::spawn(runners::import(
+        state.clone(),
+        job.clone(),
+        body,
+        format,
+        query.csv_columns(),
+        api_key,
+        write_guard,
+    ));
+
+    accepted(&state, job.id())
+}
+
+/// Start a background rebuild of the index from stored metadata
+pub async fn rebuild_job_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+) -> Result<(StatusCode, Json<JobProgress>), AppError> {
+    state.check_writable()?;
+    if state.jobs.is_running("rebuild") || state.jobs.is_running("reembed") {
+        return Err(AppError::Conflict("A rebuild is already running".to_string()));
+    }
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let job = Arc::new(state.jobs.start("rebuild"));
+    info!(job = job.id(), "Rebuild job started");
+
+    tokio::spawn(runners::rebuild(state.clone(), job.clone(), api_key, write_guard));
+
+    accepted(&state, job.id())
+}
+
+/// Start a background re-embed of every review into a new index, with
+/// `?model=` or the configured model
+pub async fn reembed_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Query(query): Query<ReembedQuery>,
+) -> Result<(StatusCode, Json<JobProgress>), AppError> {
+    state.check_writable()?;
+    if state.jobs.is_running("rebuild") || state.jobs.is_running("reembed") {
+        return Err(AppError::Conflict("A rebuild is already running".to_string()));
+    }
+
+    let mut target = CollectionManifest::from_config(&state.config.current());
+    if let Some(model) = query.model {
+        target.vector_dim = EmbeddingService::model_dimension(&model).ok_or_else(|| {
+            AppError::BadRequest(format!("Unsupported embedding model: {}", model))
+        })?;
+        target.model = model;
+    }
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let job = Arc::new(state.jobs.start("reembed"));
+    info!(job = job.id(), model = %target.model, "Re-embed job started");
+
+    tokio::spawn(runners::reembed(state.clone(), job.clone(), target, api_key, write_guard));
+
+    accepted(&state, job.id())
+}
+
+/// Progress of the latest rebuild or re-embed, running or finished
+pub async fn rebuild_status_handler(
+    State(state): State<AppState>,
+) -> Result<Json<JobProgress>, AppError> {
+    state
+        .jobs
+        .latest(&["rebuild", "reembed"])
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("No rebuild has run since startup".to_string()))
+}
+
+pub async fn get_job_handler(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobProgress>, AppError> {
+    state.jobs.get(id).map(Json).ok_or_else(|| job_not_found(id))
+}
+
+/// Stream `progress` events until the job finishes
+pub async fn job_events_handler(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    let mut progress_rx = state.jobs.subscribe(id).ok_or_else(|| job_not_found(id))?;
+
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        loop {
+            let progress = progress_rx.borrow_and_update().clone();
+            let finished = progress.state != JobState::Running;
+
+            let event = Event::default().event("progress").json_data(&progress);
+            if tx.send(event).await.is_err() || finished {
+                break;
+            }
+            // Sender dropped without a final update; nothing more to send
+            if progress_rx.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
+
+fn accepted(state: &AppState, id: u64) -> Result<(StatusCode, Json<JobProgress>), AppError> {
+    let progress = state.jobs.get(id).ok_or_else(|| job_not_found(id))?;
+    Ok((StatusCode::ACCEPTED, Json(progress)))
+}
+
+fn job_not_found(id: u64) -> AppError {
+    AppError::NotFound(format!("Job {} not found", id))
+}
@@ -0,0 +1,7 @@
+pub mod handlers;
+pub mod registry;
+pub mod routes;
+mod runners;
+
+pub use registry::{JobHandle, JobProgress, JobRegistry, JobState};
+pub use routes::routes;
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+pub use vector_search_client::models::{JobProgress, JobState};
+
+/// Finished jobs kept around for status queries
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// Errors kept per job (the count keeps going)
+const MAX_JOB_ERRORS: usize = 100;
+
+/// Writer side of one job's progress
+pub struct JobHandle {
+    started: Instant,
+    phase_started: Mutex<Instant>,
+    tx: watch::Sender<JobProgress>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> u64 {
+        self.tx.borrow().id
+    }
+
+    pub fn phase(&self, phase: &str, total: usize) {
+        *self.phase_started.lock().unwrap() = Instant::now();
+        self.update(|p| {
+            p.phase = phase.to_string();
+            p.processed = 0;
+            p.total = total;
+        });
+    }
+
+    pub fn processed(&self, processed: usize) {
+        self.update(|p| p.processed = processed);
+    }
+
+    pub fn error(&self, error: String) {
+        self.update(|p| {
+            p.error_count += 1;
+            if p.errors.len() < MAX_JOB_ERRORS {
+                p.errors.push(error);
+            }
+        });
+    }
+
+    pub fn complete(&self) {
+        self.update(|p| {
+            p.state = JobState::Completed;
+            p.phase = "done".to_string();
+            p.eta_ms = None;
+        });
+    }
+
+    pub fn fail(&self, error: String) {
+        self.error(error);
+        self.update(|p| {
+            p.state = JobState::Failed;
+            p.eta_ms = None;
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut JobProgress)) {
+        let elapsed = self.started.elapsed();
+        let phase_secs = self.phase_started.lock().unwrap().elapsed().as_secs_f64();
+        self.tx.send_modify(|p| {
+            f(p);
+            p.elapsed_ms = elapsed.as_millis() as u64;
+            p.throughput = if phase_secs > 0.0 {
+                p.processed as f64 / phase_secs
+            } else {
+                0.0
+            };
+            p.eta_ms = (p.state == JobState::Running && p.total > 0 && p.throughput > 0.0).then(|| {
+                let remaining = p.total.saturating_sub(p.processed) as f64;
+                (remaining / p.throughput * 1000.0) as u64
+            });
+        });
+    }
+}
+
+impl Drop for JobHandle {
+    // A runner that bailed out (or panicked) without finishing
+    fn drop(&mut self) {
+        if self.tx.borrow().state == JobState::Running {
+            self.fail("Job ended unexpectedly".to_string());
+        }
+    }
+}
+
+/// In-memory registry of background jobs
+#[derive(Default)]
+pub struct JobRegistry {
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, watch::Receiver<JobProgress>>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new running job
+    pub fn start(&self, kind: &str) -> JobHandle {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let (tx, rx) = watch::channel(JobProgress {
+            id,
+            kind: kind.to_string(),
+            state: JobState::Running,
+            phase: "queued".to_string(),
+            processed: 0,
+            total: 0,
+            throughput: 0.0,
+            eta_ms: None,
+            started_ms: now_ms(),
+            elapsed_ms: 0,
+            error_count: 0,
+            errors: Vec::new(),
+        });
+
+        let mut jobs = self.lock();
+        jobs.insert(id, rx);
+
+        // Forget the oldest finished jobs
+        let finished: Vec<u64> = jobs
+            .iter()
+            .filter(|(_, rx)| rx.borrow().state != JobState::Running)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_JOBS)) {
+            jobs.remove(id);
+        }
+
+        JobHandle {
+            started: Instant::now(),
+            phase_started: Mutex::new(Instant::now()),
+            tx,
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobProgress> {
+        self.lock().get(&id).map(|rx| rx.borrow().clone())
+    }
+
+    /// Receiver that sees every later update of the job
+    pub fn subscribe(&self, id: u64) -> Option<watch::Receiver<JobProgress>> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// The most recently started job of any of `kinds`
+    pub fn latest(&self, kinds: &[&str]) -> Option<JobProgress> {
+        self.lock()
+            .values()
+            .rev()
+            .map(|rx| rx.borrow().clone())
+            .find(|progress| kinds.contains(&progress.kind.as_str()))
+    }
+
+    /// Whether a job of this kind is still running
+    pub fn is_running(&self, kind: &str) -> bool {
+        self.lock().values().any(|rx| {
+            let progress = rx.borrow();
+            progress.kind == kind && progress.state == JobState::Running
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, watch::Receiver<JobProgress>>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_progress_updates() {
+        let registry = JobRegistry::new();
+        let job = registry.start("import");
+        let id = job.id();
+        assert!(registry.is_running("import"));
+
+        job.phase("embedding", 10);
+        job.processed(4);
+        job.error("Line 3: invalid review".to_string());
+
+        let progress = registry.get(id).unwrap();
+        assert_eq!(progress.phase, "embedding");
+        assert_eq!((progress.processed, progress.total), (4, 10));
+        assert_eq!(progress.error_count, 1);
+        assert!(progress.throughput > 0.0);
+        assert!(progress.eta_ms.is_some());
+
+        let rebuild = registry.start("rebuild");
+        assert_eq!(registry.latest(&["rebuild", "reembed"]).unwrap().id, rebuild.id());
+        assert_eq!(registry.latest(&["import"]).unwrap().id, id);
+        assert!(registry.latest(&["reembed"]).is_none());
+
+        job.complete();
+        let progress = registry.get(id).unwrap();
+        assert_eq!(progress.state, JobState::Completed);
+        assert_eq!(progress.eta_ms, None);
+        assert!(!registry.is_running("import"));
+    }
+}
@@ -0,0 +1,16 @@
+use crate::api::jobs::handlers::{
+    get_job_handler, import_job_handler, job_events_handler, rebuild_job_handler,
+};
+use crate::api::models::AppState;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/jobs/import", post(import_job_handler))
+        .route("/jobs/rebuild", post(rebuild_job_handler))
+        .route("/jobs/{id}", get(get_job_handler))
+        .route("/jobs/{id}/events", get(job_events_handler))
+}
@@ -0,0 +1,300 @@
+use crate::api::jobs::JobHandle;
+use crate::api::models::AppState;
+use crate::api::write_gate::WriteGuard;
+use crate::cli::{delete_tombstoned, embed_reviews, embed_reviews_with_progress};
+use crate::api::review::handlers::find_duplicate;
+use crate::cli::import::{describe, drop_exact_duplicates, parse_reviews, ColumnMapping, ImportFormat};
+use crate::config::{DedupAction, TextNormalization};
+use crate::embedding::EmbeddingService;
+use crate::storage::{AuditEntry, CollectionManifest, ReviewMetadata, VectorIndex};
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Reviews embedded per batch (also the progress granularity)
+const JOB_BATCH_SIZE: usize = 64;
+
+/// Embed and index an uploaded JSONL/CSV body into the live index
+///
+/// Rows that fail to parse or validate are reported as job errors and skipped.
+pub async fn import(
+    state: AppState,
+    job: Arc<JobHandle>,
+    body: Bytes,
+    format: ImportFormat,
+    columns: ColumnMapping,
+    api_key: Option<String>,
+    _write_guard: WriteGuard,
+) {
+    match run_import(&state, &job, body, format, columns).await {
+        Ok(ids) => {
+            info!(job = job.id(), imported = ids.len(), "✅ Import job complete");
+            state.record_mutation(AuditEntry {
+                api_key,
+                ids,
+                detail: Some(serde_json::json!({ "source": "api", "job": job.id() })),
+                ..AuditEntry::new("import")
+            });
+            job.complete();
+        }
+        Err(e) => {
+            warn!(job = job.id(), "Import job failed: {:#}", e);
+            job.fail(format!("{:#}", e));
+        }
+    }
+}
+
+async fn run_import(
+    state: &AppState,
+    job: &Arc<JobHandle>,
+    body: Bytes,
+    format: ImportFormat,
+    columns: ColumnMapping,
+) -> Result<Vec<usize>> {
+    job.phase("parsing", 0);
+    let config = state.config.current();
+    let rows = tokio::task::spawn_blocking(move || {
+        parse_reviews(body.as_ref(), format, &columns, &config)
+    })
+    .await??;
+
+    let mut reviews = Vec::with_capacity(rows.len());
+    for row in rows {
+        match row {
+            Ok(review) => reviews.push(review),
+            Err(e) => job.error(format!("{:#}", e)),
+        }
+    }
+    if reviews.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dedup = state.config.current().dedup.clone();
+    let task_state = state.clone();
+    let (reviews, duplicates) = tokio::task::spawn_blocking(move || {
+        let stored = task_state.metadata_store.count_lines()?;
+        let store = task_state.metadata_store.as_ref();
+        drop_exact_duplicates(reviews, &dedup, &task_state.content_hashes, store, stored)
+    })
+    .await??;
+    for duplicate in duplicates {
+        job.error(duplicate);
+    }
+    if reviews.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let reviews = Arc::new(reviews);
+    let normalization = state.config.current().embedding.normalization.clone();
+    let embeddings = embed(job, embedder(state)?, &normalization, reviews.clone()).await?;
+
+    job.phase("indexing", reviews.len());
+    let state = state.clone();
+    let job = job.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut index = state.vector_index.blocking_write();
+
+        let existing = state.metadata_store.count_lines()?;
+        if existing != index.vector_count() {
+            anyhow::bail!(
+                "Index ({} vectors) and metadata ({} reviews) are out of sync; run `verify` / `rebuild` first",
+                index.vector_count(),
+                existing
+            );
+        }
+
+        // Near-duplicates of stored reviews are skipped, as `POST /reviews`
+        // rejects them
+        let dedup = state.config.current().dedup.clone();
+        let (reviews, embeddings) = if existing > 0 && dedup.enabled && dedup.action == DedupAction::Reject {
+            let metric = state.config.current().index.metric;
+            let mut kept = (Vec::with_capacity(reviews.len()), Vec::with_capacity(reviews.len()));
+            for (review, embedding) in reviews.iter().zip(embeddings) {
+                match find_duplicate(&index, &embedding, metric, dedup.threshold) {
+                    Some((id, score)) => job.error(format!(
+                        "{}: near-duplicate of review {} (similarity {:.3}); skipped",
+                        describe(review),
+                        id,
+                        score
+                    )),
+                    None => {
+                        kept.0.push(review.clone());
+                        kept.1.push(embedding);
+                    }
+                }
+            }
+            kept
+        } else {
+            (reviews.to_vec(), embeddings)
+        };
+
+        // A fresh index is built in one pass; otherwise append to the existing one
+        if existing == 0 {
+            index.build_from_vectors(&embeddings)?;
+        } else {
+            for (done, embedding) in embeddings.iter().enumerate() {
+                index.add_vector(embedding)?;
+                if (done + 1) % JOB_BATCH_SIZE == 0 {
+                    job.processed(done + 1);
+                }
+            }
+        }
+        job.processed(reviews.len());
+
+        let first_id = state.metadata_store.append_batch(&reviews)?;
+        for (offset, review) in reviews.iter().enumerate() {
+            state.spelling.observe(&review.review_title, &review.review_body);
+            state.suggester.observe(review);
+            state.content_hashes.observe(first_id + offset, review);
+            state.point_ids.observe(first_id + offset, review);
+        }
+        state.metadata_store.flush()?;
+        index.save(&state.index_path())?;
+
+        Ok((first_id..first_id + reviews.len()).collect())
+    })
+    .await?
+}
+
+/// Re-embed all stored reviews and swap the result in for the live index
+pub async fn rebuild(
+    state: AppState,
+    job: Arc<JobHandle>,
+    api_key: Option<String>,
+    _write_guard: WriteGuard,
+) {
+    let target = CollectionManifest::from_config(&state.config.current());
+    match run_rebuild(&state, &job, target).await {
+        Ok(vectors) => {
+            info!(job = job.id(), vectors, "✅ Rebuild job complete");
+            state.record_mutation(AuditEntry {
+                api_key,
+                detail: Some(serde_json::json!({ "source": "api", "job": job.id(), "vectors": vectors })),
+                ..AuditEntry::new("rebuild")
+            });
+            job.complete();
+        }
+        Err(e) => {
+            warn!(job = job.id(), "Rebuild job failed: {:#}", e);
+            job.fail(format!("{:#}", e));
+        }
+    }
+}
+
+/// Re-embed every review with `target`'s model into a new index and swap
+/// it in
+///
+/// When the model differs from the running one, the server switches to it
+/// and records it next to the index, as a shadow cutover does.
+pub async fn reembed(
+    state: AppState,
+    job: Arc<JobHandle>,
+    target: CollectionManifest,
+    api_key: Option<String>,
+    _write_guard: WriteGuard,
+) {
+    let model = target.model.clone();
+    match run_rebuild(&state, &job, target).await {
+        Ok(vectors) => {
+            info!(job = job.id(), vectors, model = %model, "✅ Re-embed job complete");
+            state.record_mutation(AuditEntry {
+                api_key,
+                detail: Some(serde_json::json!({ "job": job.id(), "vectors": vectors, "model": model })),
+                ..AuditEntry::new("reembed")
+            });
+            job.complete();
+        }
+        Err(e) => {
+            warn!(job = job.id(), "Re-embed job failed: {:#}", e);
+            job.fail(format!("{:#}", e));
+        }
+    }
+}
+
+/// Embed every review with `target`'s model and normalization into a fresh
+/// index of its shape, then swap it in for the live one
+async fn run_rebuild(
+    state: &AppState,
+    job: &Arc<JobHandle>,
+    target: CollectionManifest,
+) -> Result<usize> {
+    let embedder = state
+        .embedder_for(&target.model)
+        .await
+        .map_err(|e| anyhow::anyhow!(e.message().to_string()))?;
+
+    job.phase("reading", 0);
+    let metadata_store = state.metadata_store.clone();
+    let reviews = Arc::new(tokio::task::spawn_blocking(move || metadata_store.read_all()).await??);
+    let embeddings = embed(job, embedder.clone(), &target.normalization, reviews.clone()).await?;
+
+    job.phase("indexing", reviews.len());
+    let config = state.config.current();
+    let state = state.clone();
+    let job = job.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut rebuilt = VectorIndex::new(&target.index_config());
+        rebuilt.initialize()?;
+        if !embeddings.is_empty() {
+            rebuilt.build_from_vectors(&embeddings)?;
+        }
+        job.processed(reviews.len());
+
+        let mut index = state.vector_index.blocking_write();
+
+        // Catch up on reviews added while we were embedding
+        let current = state.metadata_store.read_all()?;
+        let added = current.get(reviews.len()..).unwrap_or_default();
+        if !added.is_empty() {
+            info!(count = added.len(), "Indexing reviews added during rebuild");
+            for embedding in embed_reviews(&embedder, added, &target.normalization, JOB_BATCH_SIZE)? {
+                rebuilt.add_vector(&embedding)?;
+            }
+        }
+        // Including reviews deleted while we were embedding
+        delete_tombstoned(&mut rebuilt, &current, 0)?;
+
+        // Write next to the live archive, then swap it in
+        let index_path = &state.index_path();
+        let tmp_path = index_path.with_extension("rebuild.tmp");
+        rebuilt.save(&tmp_path)?;
+        // A new model must be recorded before its vectors go live
+        let switching = target != CollectionManifest::from_config(&config);
+        if switching {
+            target.write_default(index_path)?;
+        }
+        std::fs::rename(&tmp_path, index_path)
+            .with_context(|| format!("Failed to replace index: {}", index_path.display()))?;
+
+        *index = rebuilt;
+        if switching {
+            state.config.update(|config| target.apply(config));
+        }
+        Ok(index.vector_count())
+    })
+    .await?
+}
+
+/// Embed `reviews` off the async workers, reporting progress on `job`
+async fn embed(
+    job: &Arc<JobHandle>,
+    embedder: Arc<EmbeddingService>,
+    normalization: &TextNormalization,
+    reviews: Arc<Vec<ReviewMetadata>>,
+) -> Result<Vec<Vec<f32>>> {
+    job.phase("embedding", reviews.len());
+    let normalization = normalization.clone();
+    let job = job.clone();
+    tokio::task::spawn_blocking(move || {
+        embed_reviews_with_progress(&embedder, &reviews, &normalization, JOB_BATCH_SIZE, |n| {
+            job.processed(n)
+        })
+    })
+    .await?
+}
+
+fn embedder(state: &AppState) -> Result<Arc<EmbeddingService>> {
+    state
+        .embedder()
+        .map_err(|e| anyhow::anyhow!(e.message().to_string()))
+}
@@ -0,0 +1,118 @@
+use std::fmt::Write;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const BUCKETS: [f64; 12] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Latency histogram with fixed buckets
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    /// Observations per bucket, not cumulative; slower ones only count
+    /// towards `+Inf`
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = BUCKETS.iter().position(|&bound| seconds <= bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+/// Prometheus text exposition for `GET /metrics`, one metric at a time
+#[derive(Debug, Default)]
+pub struct MetricsText {
+    out: String,
+}
+
+impl MetricsText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) -> &mut Self {
+        self.metric("gauge", name, help, value)
+    }
+
+    /// `name` should end in `_total`
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) -> &mut Self {
+        self.metric("counter", name, help, value as f64)
+    }
+
+    /// One series per label set, e.g. `endpoint="search",phase="embed"`
+    pub fn histogram<'a>(
+        &mut self,
+        name: &str,
+        help: &str,
+        series: impl IntoIterator<Item = (String, &'a Histogram)>,
+    ) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} histogram", name);
+        for (labels, histogram) in series {
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(self.out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, cumulative);
+            }
+            let _ = writeln!(self.out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, histogram.count);
+            let _ = writeln!(self.out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+            let _ = writeln!(self.out, "{}_count{{{}}} {}", name, labels, histogram.count);
+        }
+        self
+    }
+
+    fn metric(&mut self, kind: &str, name: &str, help: &str, value: f64) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(self.out, "{} {}", name, value);
+        self
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_text() {
+        let mut metrics = MetricsText::new();
+        metrics
+            .gauge("writes_paused", "Whether writes are paused", 1.0)
+            .counter("trips_total", "Times writes were paused", 3);
+        assert_eq!(
+            metrics.finish(),
+            "# HELP writes_paused Whether writes are paused\n# TYPE writes_paused gauge\nwrites_paused 1\n\
+             # HELP trips_total Times writes were paused\n# TYPE trips_total counter\ntrips_total 3\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram_is_cumulative() {
+        let mut histogram = Histogram::default();
+        histogram.observe(0.002);
+        histogram.observe(0.02);
+        histogram.observe(30.0);
+
+        let mut metrics = MetricsText::new();
+        metrics.histogram("took_seconds", "How long it took", [("endpoint=\"search\"".to_string(), &histogram)]);
+        let text = metrics.finish();
+        assert!(text.contains("# TYPE took_seconds histogram\n"));
+        assert!(text.contains("took_seconds_bucket{endpoint=\"search\",le=\"0.001\"} 0\n"));
+        assert!(text.contains("took_seconds_bucket{endpoint=\"search\",le=\"0.0025\"} 1\n"));
+        assert!(text.contains("took_seconds_bucket{endpoint=\"search\",le=\"5\"} 2\n"));
+        assert!(text.contains("took_seconds_bucket{endpoint=\"search\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("took_seconds_count{endpoint=\"search\"} 3\n"));
+    }
+}
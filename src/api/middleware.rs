@@ -0,0 +1,237 @@
+use crate::api::extract::body_read_error;
+use crate::api::models::{AppError, AppState};
+use crate::config::BodyLogConfig;
+use axum::{
+    body::{to_bytes, Body},
+    error_handling::HandleErrorLayer,
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    BoxError, Router,
+};
+use std::any::Any;
+use std::time::Duration;
+use tower::{timeout::TimeoutLayer, ServiceBuilder};
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer};
+use tracing::{error, info};
+
+tokio::task_local! {
+    /// ID of the request being handled on this task
+    static REQUEST_ID: String;
+}
+
+/// The current request's ID, when called from inside a handler
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Tag requests with an `x-request-id` and turn handler panics into 500s
+///
+/// Incoming IDs are kept; otherwise a UUID is generated. Either way it is
+/// echoed back on the response.
+pub fn with_request_id(router: Router) -> Router {
+    router.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(middleware::from_fn(scope_request_id))
+            .layer(CatchPanicLayer::custom(handle_panic)),
+    )
+}
+
+async fn scope_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    REQUEST_ID.scope(id, next.run(request)).await
+}
+
+/// The backtrace is logged by the panic hook (see `logging::init`)
+fn handle_panic(payload: Box<dyn Any + Send + 'static>) -> Response {
+    let detail = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic");
+    error!(request_id = ?current_request_id(), "Handler panicked: {}", detail);
+
+    AppError::Internal("Internal server error".to_string()).into_response()
+}
+
+/// Apply a request timeout to every route in `router`
+pub fn with_timeout(router: Router<AppState>, timeout: Duration) -> Router<AppState> {
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(handle_timeout_error))
+            .layer(TimeoutLayer::new(timeout)),
+    )
+}
+
+async fn handle_timeout_error(err: BoxError) -> AppError {
+    if err.is::<tower::timeout::error::Elapsed>() {
+        AppError::Timeout("Request timed out".to_string())
+    } else {
+        AppError::Internal(format!("Unhandled middleware error: {}", err))
+    }
+}
+
+/// Reject requests with 503 until the model and index have loaded
+pub async fn require_ready(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !state.readiness.is_ready() {
+        return Err(AppError::ServiceUnavailable(format!(
+            "Service is starting up (waiting for: {})",
+            state.readiness.pending().join(", ")
+        )));
+    }
+    Ok(next.run(request).await)
+}
+
+/// Log request and response bodies on the routes in `logging.bodies.routes`
+///
+/// JSON bodies are logged with `redact_fields` masked; anything else is
+/// logged by size only. Responses that aren't JSON (snapshots, event
+/// streams) pass through without being buffered.
+pub async fn log_bodies(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let config = state.config.current();
+    let body_log = &config.logging.bodies;
+    let path = request.uri().path().to_string();
+    if !body_log.routes.iter().any(|prefix| path.starts_with(prefix.as_str())) {
+        return Ok(next.run(request).await);
+    }
+    let request_id = current_request_id();
+
+    // Nothing the routes accept is bigger than an import
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, config.limits.import_body_bytes)
+        .await
+        .map_err(body_read_error)?;
+    info!(
+        request_id = ?request_id,
+        method = %parts.method,
+        path,
+        body = %loggable_body(&bytes, body_log),
+        "Request body"
+    );
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    let status = response.status().as_u16();
+    if !is_json(response.headers()) {
+        info!(request_id = ?request_id, status, "Response body not logged (not JSON)");
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read response body: {}", e)))?;
+    info!(
+        request_id = ?request_id,
+        status,
+        body = %loggable_body(&bytes, body_log),
+        "Response body"
+    );
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// The body as it may appear in the log: redacted, cut to `max_bytes`
+fn loggable_body(bytes: &[u8], config: &BodyLogConfig) -> String {
+    if bytes.is_empty() {
+        return String::new();
+    }
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(bytes) else {
+        return format!("<{} bytes, not JSON>", bytes.len());
+    };
+    redact(&mut value, &config.redact_fields);
+
+    let mut text = value.to_string();
+    if text.len() > config.max_bytes {
+        let mut end = config.max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push('…');
+    }
+    text
+}
+
+fn redact(value: &mut serde_json::Value, fields: &[String]) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if fields.iter().any(|name| name == key) {
+                    *field = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact(field, fields);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loggable_body() {
+        let config = BodyLogConfig {
+            redact_fields: vec!["review_body".to_string(), "token".to_string()],
+            ..BodyLogConfig::default()
+        };
+        let body = serde_json::json!({
+            "review_body": "private",
+            "reviews": [{ "review_title": "Nice", "review_body": "private" }],
+            "auth": { "token": 42 },
+        });
+        let logged = loggable_body(body.to_string().as_bytes(), &config);
+        assert!(!logged.contains("private"));
+        assert!(!logged.contains("42"));
+        assert!(logged.contains("\"review_title\":\"Nice\""));
+
+        assert_eq!(loggable_body(b"a=b", &config), "<3 bytes, not JSON>");
+        assert_eq!(loggable_body(b"", &config), "");
+
+        let config = BodyLogConfig {
+            max_bytes: 8,
+            ..config
+        };
+        assert_eq!(loggable_body(b"\"\xc3\xa9\xc3\xa9\xc3\xa9\xc3\xa9\"", &config), "\"ééé…");
+    }
+
+    #[tokio::test]
+    async fn test_body_read_errors() {
+        let err = to_bytes(Body::from("too long"), 4).await.unwrap_err();
+        assert!(matches!(body_read_error(err), AppError::PayloadTooLarge(_)));
+
+        let broken = tokio_stream::iter([Err::<axum::body::Bytes, _>(std::io::Error::other("reset"))]);
+        let err = to_bytes(Body::from_stream(broken), 4).await.unwrap_err();
+        assert!(matches!(body_read_error(err), AppError::BadRequest(_)));
+    }
+}
@@ -1,18 +1,32 @@
+pub mod admin;
+pub mod analytics;
+pub mod answer;
+pub mod backpressure;
+pub mod collections;
+pub mod coordinator;
+pub mod experiment;
+pub mod extract;
+pub mod group_commit;
+pub mod health;
+pub mod idempotency;
+pub mod jobs;
+pub mod metrics;
+pub mod middleware;
 pub mod models;
+pub mod qdrant;
+pub mod readiness;
+pub mod replication;
 pub mod review;
+pub mod scoring;
+pub mod router;
 pub mod search;
+pub mod search_cache;
+pub mod tenancy;
+pub mod timing;
+pub mod usage;
+pub mod write_gate;
+pub mod ws;
 
 // Re-exports
 pub use models::*;
-
-// Health handler (simple, keep here)
-use axum::{extract::State, Json};
-
-pub async fn health_handler(State(state): State<AppState>) -> impl axum::response::IntoResponse {
-    let total_reviews = state.metadata_store.count_lines().unwrap_or(0);
-    Json(models::HealthResponse {
-        status: "healthy".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        total_reviews,
-    })
-}
+pub use router::{build_admin_router, build_router};
@@ -1,136 +1,807 @@
-use crate::embedding::EmbeddingService;
-use crate::storage::{JsonlStorage, VectorIndex};
+use crate::api::analytics::SearchAnalytics;
+use crate::api::backpressure::Backpressure;
+use crate::api::group_commit::GroupCommit;
+use crate::api::idempotency::IdempotencyCache;
+use crate::api::search_cache::SearchCache;
+use crate::api::timing::LatencyHistograms;
+use crate::api::jobs::JobRegistry;
+use crate::api::middleware::current_request_id;
+use crate::api::readiness::Readiness;
+use crate::api::usage::UsageTracker;
+use crate::redaction::RedactorCache;
+use crate::spelling::SpellChecker;
+use crate::suggest::Suggester;
+use crate::synonyms::Synonyms;
+use crate::api::write_gate::WriteGate;
+use crate::cli::import::{ColumnMapping, ImportFormat};
+use crate::config::{AttributeSpec, AttributeType, ConfigHandle, ReviewField, ValidationRules};
+use crate::content_hash::ContentHashes;
+use crate::api::qdrant::ids::PointIds;
+use crate::embedding::{EmbeddingService, ImageEmbeddingService, ModelCache};
+use crate::field_vectors::FieldVectors;
+use crate::ingest::IngestQueue;
+use crate::logging::LogControl;
+use crate::memory::MemoryWatchdog;
+use crate::webhooks::WebhookDispatcher;
+use crate::storage::{first_revision, AuditEntry, AuditLog, CollectionManager, MetadataStore, ReviewMetadata, VectorIndex};
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use tracing::warn;
 use tokio::sync::RwLock;
 
+// Request/response bodies live in the client crate so both sides share them
+pub use vector_search_client::models::{
+    default_metadata_range_limit, default_top_k, now_ms, AddReviewQuery, AddReviewRequest, AddReviewResponse,
+    AccountUsage, AliasInfo, AnswerRequest, AnswerResponse, ImageInput, AnalyticsQuery, AliasList, AttributeCondition, AuditQuery, AuditResponse, CollectionInfo, CollectionList, ComponentHealth,
+    ConfigReloadResponse, CreateCollectionRequest, CreateSnapshotRequest, DeleteByFilterRequest, DeleteByFilterResponse, DeleteOutcome, DeleteReviewQuery, DeleteReviewsRequest,
+    DeleteReviewsResponse, DeleteStatus, DiskUsage, DistanceMetric, Durability,
+    ErrorResponse, ExperimentArm, ExperimentAssignment, HealthResponse, LogLevelRequest, LogLevelResponse, MemoryStatus, MetadataRangeQuery,
+    NormalizationSettings, QueryReport, QueryStats, ReadyzResponse, RedactionReport, RestoreSnapshotResponse, ReplicationStatus, ReviewFilter, ReviewRecord, SearchRequest, SearchResponse,
+    ScrollRequest, ScrollResponse, SearchResultItem, SetAliasRequest, SortField, SortKey, SortOrder, ShadowCutoverResponse, ShadowStatus, SnapshotInfo, SnapshotList, SnapshotManifest, SearchAggregations, SuggestQuery, SuggestResponse, Suggestion, SuggestionKind, UpdateReviewRequest, UsageQuota, UsageResponse,
+    VectorRecord,
+};
+
 /// Application state
 #[derive(Clone)]
 pub struct AppState {
     pub vector_index: Arc<RwLock<VectorIndex>>,
-    pub metadata_store: Arc<JsonlStorage>,
-    pub embedding_service: Arc<EmbeddingService>,
+    pub metadata_store: Arc<dyn MetadataStore>,
+    /// Named collections besides the default one
+    pub collections: Arc<CollectionManager>,
+    /// Set once the model finishes loading in the background
+    pub embedding_service: Arc<OnceLock<Arc<EmbeddingService>>>,
+    /// Models used by collections besides the configured one
+    pub models: Arc<ModelCache>,
+    pub readiness: Arc<Readiness>,
+    pub write_gate: Arc<WriteGate>,
+    pub config: Arc<ConfigHandle>,
+    pub log_control: Arc<LogControl>,
+    pub audit_log: Arc<AuditLog>,
+    pub webhooks: Arc<WebhookDispatcher>,
+    pub jobs: Arc<JobRegistry>,
+    /// Per-tenant request and token counters
+    pub usage: Arc<UsageTracker>,
+    pub redactor: Arc<RedactorCache>,
+    /// Loaded from `storage.synonyms_path` at startup
+    pub synonyms: Arc<Synonyms>,
+    /// Vocabulary of the default collection for `search.spell_correction`
+    pub spelling: Arc<SpellChecker>,
+    /// Titles and product IDs of the default collection for `/suggest`
+    pub suggester: Arc<Suggester>,
+    /// Content hashes of the default collection for `dedup.exact`
+    pub content_hashes: Arc<ContentHashes>,
+    /// Client-chosen IDs of points upserted through the Qdrant API
+    pub point_ids: Arc<PointIds>,
+    /// Title and body vectors of the default collection for
+    /// `search.multi_vector`
+    pub field_vectors: Arc<FieldVectors>,
+    /// Recent searches for the /admin/analytics reports
+    pub analytics: Arc<SearchAnalytics>,
+    /// Stored write responses for `Idempotency-Key` retries
+    pub idempotency: Arc<IdempotencyCache>,
+    /// Recent search responses for `search.cache`
+    pub search_cache: Arc<SearchCache>,
+    /// Latest memory sample; writes pause while it's over `memory` limits
+    pub memory: Arc<MemoryWatchdog>,
+    /// Embeddings in progress and writes rejected by `backpressure` limits
+    pub backpressure: Arc<Backpressure>,
+    /// Reviews added with `durability=async` still waiting to be indexed
+    pub ingest: Arc<IngestQueue>,
+    /// Index saves shared by writes that land close together
+    pub group_commit: Arc<GroupCommit>,
+    /// Per-endpoint, per-phase request latency for `/metrics`
+    pub latency: Arc<LatencyHistograms>,
 }
 
-/// Request to add a new review
+impl AppState {
+    /// The embedding service, or 503 while the model is still loading
+    ///
+    /// After a cutover or re-embed switched models, the new one comes from
+    /// the model cache; if it isn't loaded yet, it starts loading in the
+    /// background and this answers 503 meanwhile.
+    pub fn embedder(&self) -> Result<Arc<EmbeddingService>, AppError> {
+        let service = self.embedding_service.get().cloned().ok_or_else(|| {
+            AppError::ServiceUnavailable("Embedding model is still loading".to_string())
+        })?;
+
+        // After a shadow cutover the configured model is no longer the one
+        // loaded at startup; the shadow sync already loaded it
+        let model_name = &self.config.current().embedding.model_name;
+        if EmbeddingService::same_model(service.model_name(), model_name) {
+            return Ok(service);
+        }
+        if let Some(service) = self.models.get(model_name) {
+            return Ok(service);
+        }
+
+        // Never load on the caller's thread, which may be an async worker
+        let models = self.models.clone();
+        let name = model_name.clone();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn_blocking(move || {
+                if let Err(e) = models.get_or_load(&name) {
+                    warn!(model = %name, "Model load failed: {:#}", e);
+                }
+            });
+        }
+        Err(AppError::ServiceUnavailable(format!(
+            "Embedding model {} is still loading",
+            model_name
+        )))
+    }
+
+    /// The embedding service for `model_name`, loading it if needed
+    pub async fn embedder_for(&self, model_name: &str) -> Result<Arc<EmbeddingService>, AppError> {
+        match self.embedding_service.get() {
+            Some(service) if EmbeddingService::same_model(service.model_name(), model_name) => {
+                return Ok(service.clone());
+            }
+            // The startup model is still loading
+            None if EmbeddingService::same_model(model_name, &self.config.current().embedding.model_name) => {
+                return self.embedder();
+            }
+            _ => {}
+        }
+
+        let models = self.models.clone();
+        let model_name = model_name.to_string();
+        tokio::task::spawn_blocking(move || models.get_or_load(&model_name))
+            .await
+            .map_err(|e| AppError::Internal(format!("Model load task failed: {}", e)))?
+            .map_err(|e| AppError::ServiceUnavailable(format!("Model unavailable: {:#}", e)))
+    }
+
+    /// The image encoder paired with `model_name`, loading it if needed;
+    /// 400 unless it is a CLIP model
+    pub async fn image_embedder_for(&self, model_name: &str) -> Result<Arc<ImageEmbeddingService>, AppError> {
+        if !ImageEmbeddingService::supports(model_name) {
+            return Err(AppError::BadRequest(format!(
+                "Model {} can't embed images; use a collection created with a CLIP model (clip-ViT-B-32)",
+                model_name
+            )));
+        }
+
+        let models = self.models.clone();
+        let model_name = model_name.to_string();
+        tokio::task::spawn_blocking(move || models.get_or_load_vision(&model_name))
+            .await
+            .map_err(|e| AppError::Internal(format!("Model load task failed: {}", e)))?
+            .map_err(|e| AppError::ServiceUnavailable(format!("Model unavailable: {:#}", e)))
+    }
+
+    /// Where the default collection's index is saved
+    /// (`storage.index_path`); named collections keep theirs under
+    /// `storage.collections_dir`
+    pub fn index_path(&self) -> PathBuf {
+        self.config.current().storage.index_path.clone()
+    }
+
+    /// 403 on read-only nodes, 503 while memory is over its limit;
+    /// checked before any mutation
+    pub fn check_writable(&self) -> Result<(), AppError> {
+        if self.config.current().is_read_only() {
+            return Err(AppError::Forbidden("Server is read-only".to_string()));
+        }
+        if self.memory.over_limit() {
+            return Err(AppError::ServiceUnavailable(
+                "Memory use is over its limit; writes are paused".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 403 on read-only nodes; checked before a delete
+    ///
+    /// Deletes go through while memory is over its limit, since they're how
+    /// an operator makes room (space comes back once the index is rebuilt).
+    pub fn check_deletable(&self) -> Result<(), AppError> {
+        if self.config.current().is_read_only() {
+            return Err(AppError::Forbidden("Server is read-only".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Audit a completed mutation and notify webhook subscribers
+    pub fn record_mutation(&self, entry: AuditEntry) {
+        self.webhooks.notify(&entry);
+        self.audit_log.record(entry);
+    }
+}
+
+/// One search over the WebSocket channel
 #[derive(Debug, Deserialize)]
-pub struct AddReviewRequest {
-    pub review_title: String,
-    pub review_body: String,
-    pub product_id: String,
-    pub review_rating: u8,
+pub struct WsSearchRequest {
+    /// Echoed back so clients can match replies to queries
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+
+    #[serde(flatten)]
+    pub search: SearchRequest,
 }
 
-/// Response after adding a review
+/// Reply to a WebSocket search
 #[derive(Debug, Serialize)]
-pub struct AddReviewResponse {
-    pub vector_id: usize,
-    pub status: String,
-    pub message: String,
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsReply {
+    Results {
+        id: Option<serde_json::Value>,
+        #[serde(flatten)]
+        response: SearchResponse,
+    },
+    Error {
+        id: Option<serde_json::Value>,
+        message: String,
+    },
 }
 
-/// Request to search for similar reviews
+/// `POST /admin/reembed` query parameters
 #[derive(Debug, Deserialize)]
-pub struct SearchRequest {
-    pub query: String,
-    
-    #[serde(alias = "k", default = "default_top_k")]
-    pub top_k: usize,
+pub struct ReembedQuery {
+    /// Model to switch to (default: the configured one)
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
-fn default_top_k() -> usize {
-    10
+/// Import job query parameters
+#[derive(Debug, Deserialize)]
+pub struct ImportJobQuery {
+    /// Body format (default: jsonl)
+    #[serde(default)]
+    pub format: Option<ImportFormat>,
+
+    /// CSV column mapping: header names or 0-based indexes
+    pub title_column: Option<String>,
+    pub body_column: Option<String>,
+    pub product_id_column: Option<String>,
+    pub rating_column: Option<String>,
+
+    /// Whether the first CSV row is a header (detected by default)
+    pub header: Option<bool>,
 }
 
-/// A single search result
-#[derive(Debug, Serialize)]
-pub struct SearchResultItem {
-    pub review_title: String,
-    pub review_body: String,
-    pub product_id: String,
-    pub review_rating: u8,
-    pub similarity_score: f32,
-    pub vector_id: usize,
+impl ImportJobQuery {
+    pub fn csv_columns(&self) -> ColumnMapping {
+        let defaults = ColumnMapping::default();
+        ColumnMapping {
+            title: self.title_column.clone().unwrap_or(defaults.title),
+            body: self.body_column.clone().unwrap_or(defaults.body),
+            product_id: self.product_id_column.clone().unwrap_or(defaults.product_id),
+            rating: self.rating_column.clone().unwrap_or(defaults.rating),
+            header: self.header,
+        }
+    }
 }
 
-/// Response from search endpoint
-#[derive(Debug, Serialize)]
-pub struct SearchResponse {
-    pub results: Vec<SearchResultItem>,
-    pub total_found: usize,
-    pub query: String,
+/// Health check query parameters
+#[derive(Debug, Deserialize)]
+pub struct HealthQuery {
+    /// Exercise embedding, index and metadata instead of a liveness ping
+    #[serde(default)]
+    pub deep: bool,
 }
 
-/// Health check response
-#[derive(Debug, Serialize)]
-pub struct HealthResponse {
-    pub status: String,
-    pub version: String,
-    pub total_reviews: usize,
+/// Application error type
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    /// A review was rejected as a duplicate of `existing_id` (409)
+    Duplicate {
+        message: String,
+        existing_id: usize,
+    },
+    PayloadTooLarge(String),
+    TooManyRequests(String),
+    /// A review broke one or more validation rules (422)
+    Unprocessable {
+        message: String,
+        violations: Vec<String>,
+    },
+    /// The route's time limit ran out (504)
+    Timeout(String),
+    ServiceUnavailable(String),
+    /// The write path is saturated (429 or 503); sent with `Retry-After`
+    Overloaded {
+        status: StatusCode,
+        message: String,
+        retry_after_secs: u64,
+    },
+    Internal(String),
 }
 
-/// Error response
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub message: String,
+impl AppError {
+    /// 422 listing every violated rule
+    pub fn validation(violations: Vec<String>) -> Self {
+        AppError::Unprocessable {
+            message: violations.join("; "),
+            violations,
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) | AppError::Duplicate { .. } => StatusCode::CONFLICT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unprocessable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Overloaded { status, .. } => *status,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::Duplicate { message: msg, .. }
+            | AppError::PayloadTooLarge(msg)
+            | AppError::TooManyRequests(msg)
+            | AppError::Unprocessable { message: msg, .. }
+            | AppError::Timeout(msg)
+            | AppError::ServiceUnavailable(msg)
+            | AppError::Overloaded { message: msg, .. }
+            | AppError::Internal(msg) => msg,
+        }
+    }
 }
 
-impl AddReviewRequest {
-    /// Validate the request
-    pub fn validate(&self) -> Result<(), String> {
-        if self.review_title.trim().is_empty() {
-            return Err("Review title cannot be empty".to_string());
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        let message = self.message().to_string();
+        let retry_after = match &self {
+            AppError::Overloaded { retry_after_secs, .. } => Some(*retry_after_secs),
+            _ => None,
+        };
+        let existing_id = match &self {
+            AppError::Duplicate { existing_id, .. } => Some(*existing_id),
+            _ => None,
+        };
+        let violations = match self {
+            AppError::Unprocessable { violations, .. } => violations,
+            _ => Vec::new(),
+        };
+
+        let mut response = (status, Json(ErrorResponse {
+            error: status.to_string(),
+            message,
+            request_id: current_request_id(),
+            violations,
+            existing_id,
+        }))
+        .into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
         }
-        if self.review_body.trim().is_empty() {
-            return Err("Review body cannot be empty".to_string());
+        response
+    }
+}
+
+/// Server-side handling of an added review: rule checks and the stored
+/// record
+pub trait ReviewRequestExt {
+    /// Check the request against `rules`, collecting every violation
+    fn validate(&self, rules: &ValidationRules) -> Result<(), Vec<String>>;
+
+    /// Convert into the stored metadata record
+    fn into_metadata(self) -> ReviewMetadata;
+}
+
+impl ReviewRequestExt for AddReviewRequest {
+    fn validate(&self, rules: &ValidationRules) -> Result<(), Vec<String>> {
+        let mut violations = Vec::new();
+
+        for &field in &rules.required_fields {
+            if field_value(self, field).trim().is_empty() {
+                violations.push(format!("{} cannot be empty", field.name()));
+            }
         }
-        if self.product_id.trim().is_empty() {
-            return Err("Product ID cannot be empty".to_string());
+        if self.review_title.trim().is_empty() && self.review_body.trim().is_empty() {
+            violations.push("A review needs a review_title or a review_body".to_string());
         }
-        if self.review_rating < 1 || self.review_rating > 5 {
-            return Err("Review rating must be between 1 and 5".to_string());
+        for (field, max) in [
+            (ReviewField::ReviewTitle, rules.max_title_chars),
+            (ReviewField::ReviewBody, rules.max_body_chars),
+        ] {
+            let len = field_value(self, field).chars().count();
+            if len > max {
+                violations.push(format!(
+                    "{} is {} characters, the limit is {}",
+                    field.name(),
+                    len,
+                    max
+                ));
+            }
         }
-        Ok(())
+        if !(rules.min_rating..=rules.max_rating).contains(&self.review_rating) {
+            violations.push(format!(
+                "review_rating must be between {} and {}",
+                rules.min_rating, rules.max_rating
+            ));
+        }
+        if self.expires_at.is_some() && self.ttl_secs.is_some() {
+            violations.push("Set either expires_at or ttl_secs, not both".to_string());
+        }
+        for (name, spec) in &rules.attributes {
+            match self.attributes.get(name) {
+                None | Some(serde_json::Value::Null) if spec.required => {
+                    violations.push(format!("Attribute {} is required", name));
+                }
+                Some(value) if !value.is_null() && !spec.kind.accepts(value) => {
+                    violations.push(format!("Attribute {} must be a {}", name, spec.kind.name()));
+                }
+                _ => {}
+            }
+        }
+        if !rules.allow_unknown_attributes {
+            for name in self.attributes.keys().filter(|name| !rules.attributes.contains_key(*name)) {
+                violations.push(format!("Attribute {} is not declared", name));
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    fn into_metadata(self) -> ReviewMetadata {
+        let expires_at = self
+            .expires_at
+            .or_else(|| self.ttl_secs.map(|ttl| now_ms().saturating_add(ttl.saturating_mul(1000))));
+        ReviewMetadata {
+            review_title: self.review_title,
+            review_body: self.review_body,
+            product_id: self.product_id,
+            review_rating: self.review_rating,
+            revision: first_revision(),
+            deleted: false,
+            expires_at,
+            created_at: Some(now_ms()),
+            language: self.language,
+            attributes: self.attributes,
+        }
+    }
+}
+
+fn field_value(request: &AddReviewRequest, field: ReviewField) -> &str {
+    match field {
+        ReviewField::ReviewTitle => &request.review_title,
+        ReviewField::ReviewBody => &request.review_body,
+        ReviewField::ProductId => &request.product_id,
     }
 }
 
-impl SearchRequest {
-    /// Validate the request
-    pub fn validate(&self) -> Result<(), String> {
-        if self.query.trim().is_empty() {
-            return Err("Query cannot be empty".to_string());
+/// Evaluating a [`ReviewFilter`] against stored reviews, typed by
+/// `validation.attributes`
+pub trait ReviewFilterExt {
+    /// Whether `review` meets every condition; reviews without a
+    /// `created_at` never match a date range, nor reviews without an
+    /// attribute a condition on it
+    ///
+    /// `schema` (`validation.attributes`) decides how attributes compare.
+    fn matches(&self, review: &ReviewMetadata, schema: &BTreeMap<String, AttributeSpec>) -> bool;
+
+    /// Check every bound against the type of what it is compared with: the
+    /// built-in fields, and attributes declared in `schema`
+    /// (`validation.attributes`); undeclared attributes take any bound
+    fn check_types(&self, schema: &BTreeMap<String, AttributeSpec>) -> Result<(), String>;
+}
+
+impl ReviewFilterExt for ReviewFilter {
+    fn matches(&self, review: &ReviewMetadata, schema: &BTreeMap<String, AttributeSpec>) -> bool {
+        let in_range = |at: u64| {
+            self.created_after.is_none_or(|after| at >= after)
+                && self.created_before.is_none_or(|before| at < before)
+        };
+        self.product_id.as_ref().is_none_or(|id| *id == review.product_id)
+            && self.min_rating.is_none_or(|min| review.review_rating >= min)
+            && self.max_rating.is_none_or(|max| review.review_rating <= max)
+            && self.language.as_ref().is_none_or(|language| {
+                review.language.as_ref().is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+            && (self.created_after.is_none() && self.created_before.is_none()
+                || review.created_at.is_some_and(in_range))
+            && self.attributes.iter().all(|(name, condition)| {
+                condition_matches(condition, review.attributes.get(name), schema.get(name).map(|spec| spec.kind))
+            })
+            && self.field.as_deref().is_none_or(|field| field_matches(self, field, review, schema))
+            && self.and.iter().all(|filter| filter.matches(review, schema))
+            && (self.or.is_empty() || self.or.iter().any(|filter| filter.matches(review, schema)))
+            && self.not.as_ref().is_none_or(|filter| !filter.matches(review, schema))
+    }
+
+    fn check_types(&self, schema: &BTreeMap<String, AttributeSpec>) -> Result<(), String> {
+        for (name, condition) in &self.attributes {
+            check_condition_type(condition, name, schema.get(name).map(|spec| spec.kind))?;
         }
-        if self.top_k == 0 || self.top_k > 100 {
-            return Err("top_k must be between 1 and 100".to_string());
+        if let Some(field) = &self.field {
+            check_condition_type(&self.condition, field, field_type(field, schema))?;
         }
-        Ok(())
+        self.and
+            .iter()
+            .chain(&self.or)
+            .chain(self.not.as_deref())
+            .try_for_each(|filter| filter.check_types(schema))
     }
 }
 
-/// Application error type
-#[derive(Debug)]
-pub enum AppError {
-    BadRequest(String),
-    Internal(String),
+fn field_matches(
+    filter: &ReviewFilter,
+    field: &str,
+    review: &ReviewMetadata,
+    schema: &BTreeMap<String, AttributeSpec>,
+) -> bool {
+    use serde_json::Value;
+
+    let string = |s: &str| Some(Value::String(s.to_string()));
+    let value = match field {
+        "rating" | "review_rating" => Some(Value::from(review.review_rating)),
+        "product_id" => string(&review.product_id),
+        "title" | "review_title" => string(&review.review_title),
+        "body" | "review_body" => string(&review.review_body),
+        "language" => review.language.as_deref().and_then(string),
+        "created_at" => review.created_at.map(Value::from),
+        _ => review.attributes.get(attribute_name(field)).cloned(),
+    };
+    condition_matches(&filter.condition, value.as_ref(), field_type(field, schema))
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+/// Type of a filter `field`; `None` for undeclared attributes
+fn field_type(field: &str, schema: &BTreeMap<String, AttributeSpec>) -> Option<AttributeType> {
+    match field {
+        "rating" | "review_rating" | "created_at" => Some(AttributeType::Integer),
+        "product_id" | "title" | "review_title" | "body" | "review_body" | "language" => Some(AttributeType::String),
+        _ => schema.get(attribute_name(field)).map(|spec| spec.kind),
+    }
+}
+
+fn attribute_name(field: &str) -> &str {
+    field.strip_prefix("attributes.").unwrap_or(field)
+}
+
+/// Whether `value` meets every bound of `condition`
+///
+/// Numeric attributes (declared, or undeclared with a number value)
+/// compare as numbers, parsing numeric strings on either side; declared
+/// string attributes compare as strings. Mismatched types never match.
+fn condition_matches(condition: &AttributeCondition, value: Option<&serde_json::Value>, kind: Option<AttributeType>) -> bool {
+    use std::cmp::Ordering::{Equal, Greater, Less};
+
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return false;
+    };
+    let numeric = kind.map_or(value.is_number(), AttributeType::is_numeric);
+    let cmp = |bound: &serde_json::Value| compare_attribute(value, bound, numeric);
+
+    condition.eq.as_ref().is_none_or(|b| cmp(b) == Some(Equal))
+        && condition.gt.as_ref().is_none_or(|b| cmp(b) == Some(Greater))
+        && condition.gte.as_ref().is_none_or(|b| matches!(cmp(b), Some(Greater | Equal)))
+        && condition.lt.as_ref().is_none_or(|b| cmp(b) == Some(Less))
+        && condition.lte.as_ref().is_none_or(|b| matches!(cmp(b), Some(Less | Equal)))
+        && condition.one_of.as_ref().is_none_or(|set| set.iter().any(|b| cmp(b) == Some(Equal)))
+}
+
+/// Fails if a bound of `condition`, `in` members included, doesn't have
+/// the type `kind` of what `name` holds; numeric types take any number
+fn check_condition_type(condition: &AttributeCondition, name: &str, kind: Option<AttributeType>) -> Result<(), String> {
+    let Some(kind) = kind else {
+        return Ok(());
+    };
+    let fits = |bound: &serde_json::Value| {
+        if kind.is_numeric() { bound.is_number() } else { kind.accepts(bound) }
+    };
+    let mut bounds = [&condition.eq, &condition.gt, &condition.gte, &condition.lt, &condition.lte]
+        .into_iter()
+        .flatten()
+        .chain(condition.one_of.iter().flatten());
+    match bounds.find(|bound| !fits(bound)) {
+        Some(bound) => Err(format!("{} must be compared with {} values, not {}", name, kind.name(), bound)),
+        None => Ok(()),
+    }
+}
+
+fn compare_attribute(
+    value: &serde_json::Value,
+    bound: &serde_json::Value,
+    numeric: bool,
+) -> Option<std::cmp::Ordering> {
+    use serde_json::Value;
+
+    if numeric {
+        let number = |v: &Value| match v {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
         };
+        return number(value)?.partial_cmp(&number(bound)?);
+    }
+    match (value, bound) {
+        (Value::String(a), Value::String(b)) => Some(a.as_str().cmp(b.as_str())),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
 
-        (status, Json(ErrorResponse {
-            error: status.to_string(),
-            message,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stored review with just the fields filters look at
+    fn review(rating: u8, product_id: &str, attributes: serde_json::Value) -> ReviewMetadata {
+        ReviewMetadata {
+            review_title: String::new(),
+            review_body: String::new(),
+            product_id: product_id.to_string(),
+            review_rating: rating,
+            revision: first_revision(),
+            deleted: false,
+            expires_at: None,
+            created_at: None,
+            language: None,
+            attributes: serde_json::from_value(attributes).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_every_violation() {
+        let mut request = AddReviewRequest {
+            review_title: "x".repeat(12),
+            review_body: String::new(),
+            product_id: " ".to_string(),
+            review_rating: 9,
+            expires_at: None,
+            ttl_secs: None,
+            language: None,
+            attributes: BTreeMap::new(),
+            image: None,
+        };
+        let rules = ValidationRules {
+            max_title_chars: 10,
+            required_fields: vec![ReviewField::ProductId],
+            ..ValidationRules::default()
+        };
+
+        let violations = request.validate(&rules).unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                "product_id cannot be empty",
+                "review_title is 12 characters, the limit is 10",
+                "review_rating must be between 1 and 5",
+            ]
+        );
+
+        request.review_title = "Fine".to_string();
+        request.product_id = "B01".to_string();
+        request.review_rating = 5;
+        assert!(request.validate(&rules).is_ok());
+    }
+
+    #[test]
+    fn test_attribute_conditions_follow_schema() {
+        let schema = BTreeMap::from([(
+            "size".to_string(),
+            AttributeSpec { kind: AttributeType::Number, required: false },
+        )]);
+        let review = |attributes: serde_json::Value| review(5, "", attributes);
+        let filter: ReviewFilter = serde_json::from_value(serde_json::json!({
+            "attributes": { "size": { "gt": 9 }, "sku": { "lt": "B" } }
         }))
-        .into_response()
+        .unwrap();
+
+        // Declared numeric: "10" > 9 numerically; undeclared strings compare
+        // lexicographically
+        assert!(filter.matches(&review(serde_json::json!({ "size": "10", "sku": "A1" })), &schema));
+        assert!(!filter.matches(&review(serde_json::json!({ "size": "10", "sku": "A1" })), &BTreeMap::new()));
+        assert!(!filter.matches(&review(serde_json::json!({ "size": 8, "sku": "A1" })), &schema));
+        assert!(!filter.matches(&review(serde_json::json!({ "size": 12 })), &schema));
+    }
+
+    #[test]
+    fn test_mixed_types_never_match() {
+        let condition = |bound: serde_json::Value| -> AttributeCondition {
+            serde_json::from_value(serde_json::json!({ "eq": bound.clone(), "lte": bound })).unwrap()
+        };
+        let matches = |value: serde_json::Value, bound: serde_json::Value| {
+            condition_matches(&condition(bound), Some(&value), None)
+        };
+
+        // Undeclared string attributes don't compare with numbers as text
+        assert!(!matches(serde_json::json!("5"), serde_json::json!(5)));
+        assert!(!matches(serde_json::json!(true), serde_json::json!("true")));
+        assert!(!matches(serde_json::json!(5), serde_json::json!("five")));
+        assert!(!matches(serde_json::json!("red"), serde_json::json!(["red"])));
+
+        // Same types still do, and numbers accept numeric strings
+        assert!(matches(serde_json::json!("5"), serde_json::json!("5")));
+        assert!(matches(serde_json::json!(true), serde_json::json!(true)));
+        assert!(matches(serde_json::json!(5), serde_json::json!("5")));
+    }
+
+    #[test]
+    fn test_filter_expressions() {
+        let review = |rating: u8, product: &str| review(rating, product, serde_json::json!({ "color": "red" }));
+        let filter: ReviewFilter = serde_json::from_value(serde_json::json!({
+            "and": [
+                { "field": "rating", "gte": 4 },
+                { "not": { "field": "product_id", "eq": "X" } },
+            ],
+            "or": [
+                { "field": "attributes.color", "eq": "red" },
+                { "field": "rating", "eq": 5 },
+            ],
+        }))
+        .unwrap();
+        assert!(filter.validate().is_ok());
+
+        let schema = BTreeMap::new();
+        assert!(filter.matches(&review(4, "Y"), &schema));
+        assert!(!filter.matches(&review(3, "Y"), &schema));
+        assert!(!filter.matches(&review(5, "X"), &schema));
+
+        let mut not_red = filter.clone();
+        not_red.or[0].field = Some("color".to_string());
+        not_red.or[0].condition.eq = Some(serde_json::json!("blue"));
+        assert!(!not_red.matches(&review(4, "Y"), &schema));
+        assert!(not_red.matches(&review(5, "Y"), &schema));
+
+        let invalid = |filter: serde_json::Value| {
+            serde_json::from_value::<ReviewFilter>(filter).unwrap().validate().is_err()
+        };
+        assert!(invalid(serde_json::json!({ "field": "rating" })));
+        assert!(invalid(serde_json::json!({ "not": { "gte": 4 } })));
+        assert!(invalid(serde_json::json!({ "or": [{}] })));
+    }
+
+    #[test]
+    fn test_in_and_type_checks() {
+        let schema = BTreeMap::from([(
+            "size".to_string(),
+            AttributeSpec { kind: AttributeType::Number, required: false },
+        )]);
+        let parse = |filter: serde_json::Value| serde_json::from_value::<ReviewFilter>(filter).unwrap();
+        let review = review(4, "B2", serde_json::json!({ "size": 10 }));
+
+        let filter = parse(serde_json::json!({
+            "and": [
+                { "field": "rating", "in": [4, 5] },
+                { "field": "product_id", "in": ["B1", "B2"] },
+            ],
+            "attributes": { "size": { "gte": 9.5, "lte": 10 } },
+        }));
+        assert!(filter.check_types(&schema).is_ok());
+        assert!(filter.matches(&review, &schema));
+        assert!(!parse(serde_json::json!({ "field": "rating", "in": [1, 2] })).matches(&review, &schema));
+
+        let mistyped = |filter: serde_json::Value| parse(filter).check_types(&schema).unwrap_err();
+        assert_eq!(
+            mistyped(serde_json::json!({ "field": "rating", "gte": "4" })),
+            "rating must be compared with integer values, not \"4\""
+        );
+        assert!(mistyped(serde_json::json!({ "not": { "field": "product_id", "in": ["B1", 2] } })).contains("product_id"));
+        assert!(mistyped(serde_json::json!({ "attributes": { "size": { "eq": true } } })).contains("size"));
+        // Undeclared attributes aren't checked
+        assert!(parse(serde_json::json!({ "field": "sku", "in": ["a", 1] })).check_types(&schema).is_ok());
     }
 }
@@ -0,0 +1,684 @@
+use crate::api::extract::{ApiJson, CallerKey};
+use crate::api::models::{default_metadata_range_limit, AddReviewRequest, AppError, AppState, DeleteStatus, ReviewRequestExt};
+use crate::api::qdrant::ids::{stored_point_id, POINT_ID_ATTRIBUTE};
+use crate::api::qdrant::models::*;
+use crate::api::review::handlers::{insert_review, redact, tag_language, ReviewVector};
+use crate::api::scoring::similarity_scores;
+use crate::config::DedupConfig;
+use crate::embedding::EmbeddingService;
+use crate::storage::{now_ms, tombstone_ids, AuditEntry, DistanceMetric, ReviewMetadata, VectorIndex};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use std::time::Instant;
+use tracing::info;
+
+type QdrantResult<T> = Result<Json<QdrantResponse<T>>, QdrantError>;
+
+pub async fn list_collections_handler(
+    State(state): State<AppState>,
+) -> QdrantResult<CollectionsList> {
+    let started = Instant::now();
+    let name = state.config.current().qdrant.collection.clone();
+
+    Ok(QdrantResponse::ok(
+        CollectionsList {
+            collections: vec![CollectionDescription { name }],
+        },
+        started,
+    ))
+}
+
+pub async fn collection_info_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> QdrantResult<CollectionInfo> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+
+    let (count, dim) = {
+        let index = state.vector_index.read().await;
+        (index.live_count(), index.dimension())
+    };
+
+    Ok(QdrantResponse::ok(
+        CollectionInfo {
+            status: "green",
+            optimizer_status: "ok",
+            vectors_count: count,
+            indexed_vectors_count: count,
+            points_count: count,
+            segments_count: 1,
+            config: serde_json::json!({
+                "params": { "vectors": { "size": dim, "distance": qdrant_distance(state.config.current().index.metric) } },
+            }),
+            payload_schema: serde_json::json!({}),
+        },
+        started,
+    ))
+}
+
+/// Qdrant's name for `metric`
+fn qdrant_distance(metric: DistanceMetric) -> &'static str {
+    match metric {
+        DistanceMetric::L2 => "Euclid",
+        DistanceMetric::Cosine => "Cosine",
+    }
+}
+
+/// The collection always exists; creating it again is a no-op
+///
+/// Clients like LangChain call this unconditionally before upserting.
+pub async fn create_collection_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> QdrantResult<bool> {
+    let started = Instant::now();
+    let collection = state.config.current().qdrant.collection.clone();
+    if name != collection {
+        return Err(AppError::BadRequest(format!(
+            "Only the configured collection `{}` is served",
+            collection
+        ))
+        .into());
+    }
+    Ok(QdrantResponse::ok(true, started))
+}
+
+/// Add points, embedding the payload text of any point sent without a vector
+///
+/// Points get the same validation, redaction and duplicate checks as
+/// `POST /reviews`. A point whose ID belongs to a live point replaces it.
+pub async fn upsert_points_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<UpsertPoints>,
+) -> QdrantResult<UpdateResult> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+    state.check_writable()?;
+
+    if request.points.is_empty() {
+        return Err(AppError::BadRequest("No points to upsert".to_string()).into());
+    }
+
+    let config = state.config.current();
+    let mut ids = Vec::with_capacity(request.points.len());
+    let mut vectors = Vec::with_capacity(request.points.len());
+    let mut requests = Vec::with_capacity(request.points.len());
+    for (i, point) in request.points.into_iter().enumerate() {
+        let id = point
+            .id
+            .map(PointId::normalize)
+            .transpose()
+            .map_err(|e| AppError::BadRequest(format!("Point {}: {}", i, e)))?;
+        let mut review = point.payload.into_request();
+        review.validate(&config.validation).map_err(|violations| {
+            AppError::validation(violations.into_iter().map(|v| format!("Point {}: {}", i, v)).collect())
+        })?;
+        tag_language(&config, &mut review);
+        redact(&state, &mut review)?;
+        ids.push(id);
+        vectors.push(point.vector);
+        requests.push(review);
+    }
+
+    state
+        .backpressure
+        .admit_write(state.write_gate.in_flight() + state.ingest.pending(), &config.backpressure)?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let vectors = {
+        let _permit = state.backpressure.embedding(&config.backpressure)?;
+        embed_missing(&state, vectors, &requests).await?
+    };
+    let dim = state.vector_index.read().await.dimension();
+    if let Some(i) = vectors.iter().position(|v| v.values.len() != dim) {
+        return Err(AppError::BadRequest(format!(
+            "Point {}: expected a vector of dimension {}, got {}",
+            i,
+            dim,
+            vectors[i].values.len()
+        ))
+        .into());
+    }
+
+    let points: Vec<_> = ids
+        .into_iter()
+        .zip(vectors)
+        .zip(requests)
+        .map(|((id, vector), request)| {
+            let mut metadata = request.into_metadata();
+            metadata.attributes.remove(POINT_ID_ATTRIBUTE);
+            if let Some(id) = &id {
+                metadata
+                    .attributes
+                    .insert(POINT_ID_ATTRIBUTE.to_string(), serde_json::json!(id));
+            }
+            (id, vector, metadata)
+        })
+        .collect();
+    info!(points = points.len(), "Upserting Qdrant points");
+
+    // Detached like the REST write path, so index and metadata stay together
+    let dedup = config.dedup.clone();
+    let ids = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+        let task_state = state.clone();
+        let (index, stored, outcome) = tokio::task::spawn_blocking(move || {
+            let mut stored = Vec::with_capacity(points.len());
+            let outcome = store_points(&task_state, &mut index, points, &dedup, &mut stored);
+            (index, stored, outcome)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))?;
+
+        // Points stored before a failure are kept, like the rest of the batch would be
+        let ticket = state.group_commit.written();
+        drop(index);
+        state.group_commit.commit(ticket, &state).await;
+        if !stored.is_empty() {
+            state.record_mutation(AuditEntry {
+                api_key,
+                ids: stored.clone(),
+                detail: Some(serde_json::json!({ "source": "qdrant" })),
+                ..AuditEntry::new("add")
+            });
+        }
+        outcome.map(|_| stored)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    Ok(QdrantResponse::ok(
+        UpdateResult {
+            operation_id: ids.last().copied().unwrap_or_default() as u64,
+            status: "completed",
+        },
+        started,
+    ))
+}
+
+/// Tombstone points by ID; unknown IDs are skipped, as Qdrant does
+pub async fn delete_points_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<DeletePoints>,
+) -> QdrantResult<UpdateResult> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+    if request.filter.is_some() {
+        return Err(AppError::BadRequest("Only deleting points by ID is supported".to_string()).into());
+    }
+    let points = request
+        .points
+        .ok_or_else(|| AppError::BadRequest("Expected `points` to delete".to_string()))?
+        .into_iter()
+        .map(PointId::normalize)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::BadRequest)?;
+    if points.len() > default_metadata_range_limit() {
+        return Err(AppError::BadRequest(format!("At most {} points per request", default_metadata_range_limit())).into());
+    }
+    state.check_deletable()?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let deleted = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+        let task_state = state.clone();
+        let deleted = tokio::task::spawn_blocking(move || {
+            let state = task_state;
+            let mut vector_ids = Vec::with_capacity(points.len());
+            for id in &points {
+                vector_ids.extend(resolve(&state, id)?);
+            }
+            let results = tombstone_ids(
+                state.metadata_store.as_ref(),
+                &mut index,
+                &state.index_path(),
+                &vector_ids,
+            )
+            .map_err(|e| AppError::Internal(format!("Delete failed: {}", e)))?;
+            Ok::<_, AppError>(
+                results
+                    .into_iter()
+                    .filter(|outcome| outcome.status == DeleteStatus::Deleted)
+                    .map(|outcome| outcome.vector_id)
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Delete task failed: {}", e)))??;
+
+        if !deleted.is_empty() {
+            state.suggester.invalidate();
+            state.spelling.invalidate();
+            state.record_mutation(AuditEntry {
+                api_key,
+                ids: deleted.clone(),
+                detail: Some(serde_json::json!({ "source": "qdrant" })),
+                ..AuditEntry::new("delete")
+            });
+        }
+        Ok::<_, AppError>(deleted)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    info!(deleted = deleted.len(), "Deleted Qdrant points");
+    Ok(QdrantResponse::ok(
+        UpdateResult {
+            operation_id: deleted.last().copied().unwrap_or_default() as u64,
+            status: "completed",
+        },
+        started,
+    ))
+}
+
+pub async fn retrieve_points_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<RetrievePoints>,
+) -> QdrantResult<Vec<Record>> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+
+    let ids = request
+        .ids
+        .into_iter()
+        .map(PointId::normalize)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::BadRequest)?;
+    let with_payload = wants_payload(&request.with_payload, false);
+
+    // Unknown IDs are left out, as Qdrant does
+    let records = blocking(&state, move |state| {
+        let mut vector_ids = Vec::with_capacity(ids.len());
+        for id in &ids {
+            vector_ids.extend(resolve(state, id)?);
+        }
+        read_points(state, &vector_ids, with_payload)
+    })
+    .await?
+    .into_iter()
+    .map(|(id, payload)| Record { id, payload })
+    .collect();
+
+    Ok(QdrantResponse::ok(records, started))
+}
+
+pub async fn get_point_handler(
+    State(state): State<AppState>,
+    Path((name, id)): Path<(String, String)>,
+) -> QdrantResult<Record> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+
+    let id = PointId::parse(&id).map_err(AppError::BadRequest)?;
+    let missing = AppError::NotFound(format!("No point with id {} found", serde_json::json!(id)));
+    let point = blocking(&state, move |state| {
+        let Some(vector_id) = resolve(state, &id)? else {
+            return Ok(None);
+        };
+        read_points(state, &[vector_id], true).map(|points| points.into_iter().next())
+    })
+    .await?;
+    let (id, payload) = point.ok_or(missing)?;
+
+    Ok(QdrantResponse::ok(Record { id, payload }, started))
+}
+
+pub async fn search_points_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<SearchPoints>,
+) -> QdrantResult<Vec<ScoredPoint>> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+
+    let points = search_points(
+        &state,
+        request.vector.into_vec(),
+        request.limit,
+        request.offset,
+        request.score_threshold,
+        wants_payload(&request.with_payload, true),
+    )
+    .await?;
+
+    Ok(QdrantResponse::ok(points, started))
+}
+
+pub async fn query_points_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ApiJson(request): ApiJson<QueryPoints>,
+) -> QdrantResult<QueryResponse> {
+    let started = Instant::now();
+    check_collection(&state, &name)?;
+
+    let query = request
+        .query
+        .ok_or_else(|| AppError::BadRequest("Only vector queries are supported".to_string()))?;
+    let points = search_points(
+        &state,
+        query.into_vec(),
+        request.limit,
+        request.offset,
+        request.score_threshold,
+        wants_payload(&request.with_payload, false),
+    )
+    .await?;
+
+    Ok(QdrantResponse::ok(QueryResponse { points }, started))
+}
+
+fn check_collection(state: &AppState, name: &str) -> Result<(), QdrantError> {
+    if name == state.config.current().qdrant.collection {
+        Ok(())
+    } else {
+        Err(QdrantError::not_found(name))
+    }
+}
+
+/// Search with a raw query vector; scores are normalized like `/reviews/search`
+async fn search_points(
+    state: &AppState,
+    vector: Vec<f32>,
+    limit: usize,
+    offset: usize,
+    score_threshold: Option<f32>,
+    with_payload: bool,
+) -> Result<Vec<ScoredPoint>, AppError> {
+    let config = state.config.current();
+    let max_top_k = config.search.max_top_k;
+    if limit == 0 || limit + offset > max_top_k {
+        return Err(AppError::BadRequest(format!(
+            "limit + offset must be between 1 and {}",
+            max_top_k
+        )));
+    }
+
+    let results = {
+        let index = state.vector_index.read().await;
+        if vector.len() != index.dimension() {
+            return Err(AppError::BadRequest(format!(
+                "Expected a query vector of dimension {}, got {}",
+                index.dimension(),
+                vector.len()
+            )));
+        }
+        index
+            .search(&vector, limit + offset)
+            .map_err(|e| AppError::Internal(format!("Search failed: {}", e)))?
+    };
+
+    let results = &results[offset.min(results.len())..];
+    let distances: Vec<f32> = results.iter().map(|r| r.distance).collect();
+    let scores = similarity_scores(
+        config.index.metric,
+        config.search.score_normalization,
+        &distances,
+    );
+    let hits: Vec<(usize, f32)> = results
+        .iter()
+        .zip(scores)
+        .map(|(r, score)| (r.vector_id, score))
+        .filter(|&(_, score)| score_threshold.is_none_or(|t| score >= t))
+        .collect();
+
+    let ids: Vec<usize> = hits.iter().map(|&(id, _)| id).collect();
+    let points = blocking(state, move |state| read_points(state, &ids, with_payload)).await?;
+
+    Ok(hits
+        .into_iter()
+        .zip(points)
+        .map(|((_, score), (id, payload))| ScoredPoint {
+            id,
+            version: 0,
+            score,
+            payload,
+        })
+        .collect())
+}
+
+/// Run metadata reads and writes off the async workers
+async fn blocking<T: Send + 'static>(
+    state: &AppState,
+    task: impl FnOnce(&AppState) -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || task(&state))
+        .await
+        .map_err(|e| AppError::Internal(format!("Metadata task failed: {}", e)))?
+}
+
+/// Point IDs and payloads of stored reviews
+fn read_points(
+    state: &AppState,
+    vector_ids: &[usize],
+    with_payload: bool,
+) -> Result<Vec<(PointId, Option<serde_json::Value>)>, AppError> {
+    let reviews = state
+        .metadata_store
+        .read_batch(vector_ids)
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+    Ok(reviews
+        .iter()
+        .zip(vector_ids)
+        .map(|(review, &vector_id)| {
+            let id = stored_point_id(review).unwrap_or(PointId::Num(vector_id as u64));
+            (id, with_payload.then(|| payload(review)))
+        })
+        .collect())
+}
+
+/// Vector ID of the live point `id`: the review upserted with that ID, or
+/// for an integer, the review with that vector ID if it has no point ID
+fn resolve(state: &AppState, id: &PointId) -> Result<Option<usize>, AppError> {
+    let read_failed = |e: anyhow::Error| AppError::Internal(format!("Metadata read failed: {}", e));
+    let stored = state.metadata_store.count_lines().map_err(read_failed)?;
+    let now = now_ms();
+
+    // IDs past the end belong to appends that were rolled back
+    let candidates: Vec<usize> = state
+        .point_ids
+        .candidates(id, || state.metadata_store.read_all())
+        .map_err(read_failed)?
+        .into_iter()
+        .filter(|&vector_id| vector_id < stored)
+        .collect();
+    if !candidates.is_empty() {
+        let reviews = state.metadata_store.read_batch(&candidates).map_err(read_failed)?;
+        let found = candidates
+            .iter()
+            .zip(&reviews)
+            .rev()
+            .find(|(_, review)| review.is_live(now) && stored_point_id(review).as_ref() == Some(id));
+        if let Some((&vector_id, _)) = found {
+            return Ok(Some(vector_id));
+        }
+    }
+
+    let &PointId::Num(num) = id else {
+        return Ok(None);
+    };
+    let Ok(vector_id) = usize::try_from(num) else {
+        return Ok(None);
+    };
+    if vector_id >= stored {
+        return Ok(None);
+    }
+    let review = state.metadata_store.read_by_id(vector_id).map_err(read_failed)?;
+    Ok((review.is_live(now) && stored_point_id(&review).is_none()).then_some(vector_id))
+}
+
+/// Fill in vectors for points sent without one
+async fn embed_missing(
+    state: &AppState,
+    vectors: Vec<Option<Vec<f32>>>,
+    reviews: &[AddReviewRequest],
+) -> Result<Vec<ReviewVector>, AppError> {
+    let config = state.config.current();
+    let normalization = &config.embedding.normalization;
+    let mut vectors: Vec<_> = vectors.into_iter().map(|vector| vector.map(ReviewVector::given)).collect();
+    let texts: Vec<(usize, String)> = vectors
+        .iter()
+        .zip(reviews)
+        .enumerate()
+        .filter(|(_, (vector, _))| vector.is_none())
+        .map(|(i, (_, r))| {
+            (i, EmbeddingService::review_text(&r.review_title, &r.review_body, normalization))
+        })
+        .collect();
+    if texts.is_empty() {
+        return Ok(vectors.into_iter().flatten().collect());
+    }
+
+    let embedding_service = state.embedder_for(&config.embedding.model_name).await?;
+    let embedded = tokio::task::spawn_blocking(move || {
+        let batch = texts.iter().map(|(_, text)| text.as_str()).collect();
+        embedding_service
+            .embed_batch(batch)
+            .map(|embeddings| texts.into_iter().map(|(i, _)| i).zip(embeddings).collect::<Vec<_>>())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Embedding task failed: {}", e)))?
+    .map_err(|e| AppError::Internal(format!("Embedding failed: {}", e)))?;
+
+    for (i, embedding) in embedded {
+        vectors[i] = Some(ReviewVector::embedded(embedding, &config));
+    }
+    Ok(vectors.into_iter().flatten().collect())
+}
+
+/// Insert the points in order under the caller's write lock, through the
+/// same path as `POST /reviews`; `stored` collects the new vector IDs
+fn store_points(
+    state: &AppState,
+    index: &mut VectorIndex,
+    points: Vec<(Option<PointId>, ReviewVector, ReviewMetadata)>,
+    dedup: &DedupConfig,
+    stored: &mut Vec<usize>,
+) -> Result<(), AppError> {
+    for (i, (id, vector, metadata)) in points.into_iter().enumerate() {
+        let replaces = match &id {
+            Some(id) => resolve(state, id)?,
+            None => None,
+        };
+        let (vector_id, _) = insert_review(state, index, &vector, metadata, dedup, replaces).map_err(|e| match e {
+            AppError::Conflict(message) => AppError::Conflict(format!("Point {}: {}", i, message)),
+            AppError::Duplicate { message, existing_id } => AppError::Duplicate {
+                message: format!("Point {}: {}", i, message),
+                existing_id,
+            },
+            other => other,
+        })?;
+        stored.push(vector_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ReviewField;
+    use crate::test_utils::TestApp;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use serde_json::json;
+
+    const UUID: &str = "5f0c6e1a-0000-4000-8000-000000000000";
+
+    async fn upsert(app: &TestApp, points: serde_json::Value) -> (StatusCode, serde_json::Value) {
+        let request = Request::put("/qdrant/collections/reviews/points")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({ "points": points })).unwrap()))
+            .unwrap();
+        app.send(request).await
+    }
+
+    #[tokio::test]
+    async fn test_points_keep_their_ids() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.qdrant.enabled = true;
+                config.validation.min_rating = 0;
+                config.validation.required_fields = vec![ReviewField::ReviewBody];
+            })
+            .build()
+            .unwrap();
+
+        let point = json!({ "id": UUID.to_uppercase(), "payload": { "page_content": "Battery lasts all day", "metadata": { "source": "faq" } } });
+        let (status, body) = upsert(&app, json!([point, { "id": 7, "payload": { "page_content": "Screen cracked" } }])).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        let (status, body) = app
+            .post("/qdrant/collections/reviews/points", &json!({ "ids": [UUID, 7, 1], "with_payload": true }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        // Vector ID 1 belongs to point 7
+        let records = body["result"].as_array().unwrap();
+        assert_eq!(records.len(), 2, "{}", body);
+        assert_eq!(records[0]["id"], UUID);
+        assert_eq!(records[0]["payload"]["metadata"]["source"], "faq");
+        assert!(records[0]["payload"].get("qdrant_id").is_none());
+        assert_eq!(records[1]["id"], 7);
+
+        // Upserting an ID again replaces the point
+        let (status, body) = upsert(&app, json!([{ "id": UUID, "payload": { "page_content": "Battery died in a week" } }])).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let (status, body) = app.get(&format!("/qdrant/collections/reviews/points/{}", UUID)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["result"]["payload"]["page_content"], "Battery died in a week");
+        let (_, body) = app.get("/qdrant/collections/reviews").await;
+        assert_eq!(body["result"]["points_count"], 2);
+        assert_eq!(body["result"]["config"]["params"]["vectors"]["distance"], "Euclid");
+
+        let (status, body) = app
+            .post("/qdrant/collections/reviews/points/delete", &json!({ "points": [UUID] }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let (status, _) = app.get(&format!("/qdrant/collections/reviews/points/{}", UUID)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app.get("/qdrant/collections/reviews/points/not-a-uuid").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_upserts_are_validated() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.qdrant.enabled = true;
+                config.validation.required_fields = vec![ReviewField::ReviewBody];
+                config.redaction.enabled = true;
+            })
+            .build()
+            .unwrap();
+
+        // An explicit 0 is checked against validation.min_rating
+        let point = json!({ "payload": { "review_body": "Fine", "review_rating": 0 } });
+        let (status, body) = upsert(&app, json!([point])).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", body);
+
+        let point = json!({ "payload": { "review_body": "Mail me at jane@example.com", "review_rating": 4 } });
+        let (status, body) = upsert(&app, json!([point])).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let (_, body) = app.get("/qdrant/collections/reviews/points/0").await;
+        let text = body["result"]["payload"]["review_body"].as_str().unwrap();
+        assert!(!text.contains("jane@example.com"), "{}", text);
+
+        let (status, _) = upsert(&app, json!([{ "id": "nope", "payload": { "review_body": "Fine" } }])).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
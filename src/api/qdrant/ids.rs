@@ -0,0 +1,81 @@
+//! Client-chosen point IDs
+//!
+//! Qdrant clients pick their own IDs (integers or UUIDs), while vector IDs
+//! are assigned by the index. A point upserted with an ID keeps it in the
+//! review's `qdrant_id` attribute; points without one are addressed by
+//! their vector ID.
+
+use crate::api::qdrant::models::PointId;
+use crate::storage::ReviewMetadata;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::info;
+
+/// Attribute holding the point ID of an upserted review
+pub const POINT_ID_ATTRIBUTE: &str = "qdrant_id";
+
+/// The point ID a review was upserted with, if any
+pub fn stored_point_id(review: &ReviewMetadata) -> Option<PointId> {
+    let id: PointId = serde_json::from_value(review.attributes.get(POINT_ID_ATTRIBUTE)?.clone()).ok()?;
+    id.normalize().ok()
+}
+
+/// Vector IDs by point ID, built from a store's reviews on first use
+///
+/// Like [`ContentHashes`](crate::content_hash::ContentHashes), entries are
+/// only ever added, so callers check candidates against the store.
+#[derive(Debug, Default)]
+pub struct PointIds {
+    index: RwLock<Option<HashMap<PointId, Vec<usize>>>>,
+}
+
+impl PointIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Vector IDs that were stored under `id`, oldest first
+    ///
+    /// `load` reads every review, to build the index on the first call.
+    pub fn candidates(
+        &self,
+        id: &PointId,
+        load: impl FnOnce() -> anyhow::Result<Vec<ReviewMetadata>>,
+    ) -> anyhow::Result<Vec<usize>> {
+        if let Some(index) = self.index.read().unwrap().as_ref() {
+            return Ok(index.get(id).cloned().unwrap_or_default());
+        }
+
+        let mut slot = self.index.write().unwrap();
+        let index = match &mut *slot {
+            Some(index) => index,
+            None => {
+                let mut index: HashMap<PointId, Vec<usize>> = HashMap::new();
+                for (vector_id, review) in load()?.iter().enumerate() {
+                    if let Some(id) = stored_point_id(review) {
+                        index.entry(id).or_default().push(vector_id);
+                    }
+                }
+                info!(points = index.len(), "Qdrant point IDs loaded");
+                slot.insert(index)
+            }
+        };
+        Ok(index.get(id).cloned().unwrap_or_default())
+    }
+
+    /// Record a stored review; a no-op until the index is built
+    pub fn observe(&self, vector_id: usize, review: &ReviewMetadata) {
+        let Some(id) = stored_point_id(review) else {
+            return;
+        };
+        if let Some(index) = self.index.write().unwrap().as_mut() {
+            index.entry(id).or_default().push(vector_id);
+        }
+    }
+
+    /// Drop the index after the store changed wholesale; the next lookup
+    /// rebuilds it
+    pub fn invalidate(&self) {
+        *self.index.write().unwrap() = None;
+    }
+}
@@ -0,0 +1,11 @@
+//! Qdrant-compatible REST layer over the review index
+//!
+//! Serves the one configured collection so Qdrant clients (and LangChain's
+//! Qdrant vector store) can talk to this server unchanged.
+
+pub mod handlers;
+pub mod ids;
+pub mod models;
+pub mod routes;
+
+pub use routes::routes;
@@ -0,0 +1,347 @@
+//! Request/response shapes of Qdrant's REST API (the subset we serve)
+
+use crate::api::models::{AddReviewRequest, AppError};
+use crate::api::qdrant::ids::POINT_ID_ATTRIBUTE;
+use crate::storage::ReviewMetadata;
+use axum::{
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// `{"result": ..., "status": "ok", "time": seconds}`
+#[derive(Debug, Serialize)]
+pub struct QdrantResponse<T> {
+    pub result: T,
+    pub status: &'static str,
+    pub time: f64,
+}
+
+impl<T> QdrantResponse<T> {
+    pub fn ok(result: T, started: Instant) -> Json<Self> {
+        Json(Self {
+            result,
+            status: "ok",
+            time: started.elapsed().as_secs_f64(),
+        })
+    }
+}
+
+/// Errors in Qdrant's `{"status": {"error": "..."}}` shape
+pub struct QdrantError(pub AppError);
+
+impl From<AppError> for QdrantError {
+    fn from(err: AppError) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for QdrantError {
+    fn into_response(self) -> Response {
+        let status = self.0.status_code();
+        let body = serde_json::json!({
+            "status": { "error": self.0.message() },
+            "time": 0.0,
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+impl QdrantError {
+    pub fn not_found(collection: &str) -> Self {
+        Self(AppError::NotFound(format!(
+            "Collection `{}` doesn't exist!",
+            collection
+        )))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionsList {
+    pub collections: Vec<CollectionDescription>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionDescription {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionInfo {
+    pub status: &'static str,
+    pub optimizer_status: &'static str,
+    pub vectors_count: usize,
+    pub indexed_vectors_count: usize,
+    pub points_count: usize,
+    pub segments_count: usize,
+    pub config: serde_json::Value,
+    pub payload_schema: serde_json::Value,
+}
+
+/// Point IDs are integers or UUID strings in Qdrant
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PointId {
+    Num(u64),
+    Uuid(String),
+}
+
+impl PointId {
+    /// Parse a point ID from a URL path
+    pub fn parse(id: &str) -> Result<Self, String> {
+        match id.parse() {
+            Ok(num) => Ok(PointId::Num(num)),
+            Err(_) => PointId::Uuid(id.to_string()).normalize(),
+        }
+    }
+
+    /// Check a UUID and bring it to the lowercase hyphenated form, so each
+    /// point has one spelling
+    pub fn normalize(self) -> Result<Self, String> {
+        let PointId::Uuid(uuid) = self else {
+            return Ok(self);
+        };
+        let hex: String = uuid.chars().filter(|&c| c != '-').collect();
+        let hyphenated = uuid.len() == 36 && [8, 13, 18, 23].iter().all(|&i| uuid.as_bytes()[i] == b'-');
+        if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) || !(hyphenated || uuid.len() == 32) {
+            return Err(format!(
+                "Point ID `{}` is neither an unsigned integer nor a UUID",
+                uuid
+            ));
+        }
+        let hex = hex.to_ascii_lowercase();
+        Ok(PointId::Uuid(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpsertPoints {
+    pub points: Vec<PointStruct>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PointStruct {
+    /// Kept with the review; a live point with the same ID is replaced
+    pub id: Option<PointId>,
+    /// Embedded from the payload text when omitted
+    pub vector: Option<Vec<f32>>,
+    #[serde(default)]
+    pub payload: PointPayload,
+}
+
+/// Payload accepted on upsert: review fields, or LangChain's `page_content`
+#[derive(Debug, Default, Deserialize)]
+pub struct PointPayload {
+    pub review_title: Option<String>,
+    pub review_body: Option<String>,
+    pub page_content: Option<String>,
+    pub product_id: Option<String>,
+    pub review_rating: Option<u8>,
+    pub language: Option<String>,
+
+    /// Any other payload keys, kept as review attributes
+    #[serde(flatten)]
+    pub attributes: BTreeMap<String, serde_json::Value>,
+}
+
+impl PointPayload {
+    /// The review to add, validated like any other
+    pub fn into_request(self) -> AddReviewRequest {
+        AddReviewRequest {
+            review_title: self.review_title.unwrap_or_default(),
+            review_body: self.review_body.or(self.page_content).unwrap_or_default(),
+            product_id: self.product_id.unwrap_or_default(),
+            // 0 = unrated, accepted only when `validation.min_rating` is 0
+            review_rating: self.review_rating.unwrap_or(0),
+            expires_at: None,
+            ttl_secs: None,
+            language: self.language,
+            attributes: self.attributes,
+            image: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateResult {
+    pub operation_id: u64,
+    pub status: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchPoints {
+    pub vector: NamedOrPlainVector,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub with_payload: Option<serde_json::Value>,
+    pub score_threshold: Option<f32>,
+}
+
+/// Newer `/points/query` request
+#[derive(Debug, Deserialize)]
+pub struct QueryPoints {
+    pub query: Option<NamedOrPlainVector>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub with_payload: Option<serde_json::Value>,
+    pub score_threshold: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryResponse {
+    pub points: Vec<ScoredPoint>,
+}
+
+/// `[0.1, ...]` or `{"name": "...", "vector": [0.1, ...]}`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum NamedOrPlainVector {
+    Plain(Vec<f32>),
+    Named { vector: Vec<f32> },
+}
+
+impl NamedOrPlainVector {
+    pub fn into_vec(self) -> Vec<f32> {
+        match self {
+            NamedOrPlainVector::Plain(v) | NamedOrPlainVector::Named { vector: v } => v,
+        }
+    }
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoredPoint {
+    pub id: PointId,
+    pub version: u64,
+    pub score: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetrievePoints {
+    pub ids: Vec<PointId>,
+    #[serde(default)]
+    pub with_payload: Option<serde_json::Value>,
+}
+
+/// `{"points": [...]}`; Qdrant's `{"filter": ...}` selector isn't served
+#[derive(Debug, Deserialize)]
+pub struct DeletePoints {
+    pub points: Option<Vec<PointId>>,
+    pub filter: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Record {
+    pub id: PointId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+}
+
+/// Payload returned for a stored review: its fields, then any other
+/// payload keys it was stored with
+///
+/// `page_content`/`metadata` mirror what LangChain's Qdrant store reads;
+/// a stored `metadata` object is merged into the latter.
+pub fn payload(review: &ReviewMetadata) -> serde_json::Value {
+    let mut metadata = serde_json::json!({
+        "review_title": review.review_title,
+        "product_id": review.product_id,
+        "review_rating": review.review_rating,
+    });
+    if let (Some(merged), Some(serde_json::Value::Object(stored))) =
+        (metadata.as_object_mut(), review.attributes.get("metadata"))
+    {
+        merged.extend(stored.clone());
+    }
+
+    let mut payload = serde_json::json!({
+        "review_title": review.review_title,
+        "review_body": review.review_body,
+        "product_id": review.product_id,
+        "review_rating": review.review_rating,
+        "page_content": review.review_body,
+        "metadata": metadata,
+    });
+    if let Some(payload) = payload.as_object_mut() {
+        if let Some(language) = &review.language {
+            payload.insert("language".to_string(), language.clone().into());
+        }
+        for (key, value) in &review.attributes {
+            if key != POINT_ID_ATTRIBUTE && !payload.contains_key(key) {
+                payload.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    payload
+}
+
+/// `with_payload` defaults to true for search and false elsewhere in
+/// Qdrant; anything but an explicit `false` counts as true here
+pub fn wants_payload(with_payload: &Option<serde_json::Value>, default: bool) -> bool {
+    match with_payload {
+        None => default,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::ReviewRequestExt;
+
+    #[test]
+    fn test_langchain_point_payload() {
+        let point: PointStruct = serde_json::from_value(serde_json::json!({
+            "id": "5F0C6E1A-0000-4000-8000-000000000000",
+            "payload": { "page_content": "Battery lasts all day", "metadata": { "source": "faq" } }
+        }))
+        .unwrap();
+        assert!(point.vector.is_none());
+        assert_eq!(
+            point.id.unwrap().normalize(),
+            Ok(PointId::Uuid("5f0c6e1a-0000-4000-8000-000000000000".to_string()))
+        );
+
+        let review = point.payload.into_request();
+        assert_eq!(review.review_body, "Battery lasts all day");
+        assert_eq!(review.review_rating, 0);
+
+        // Payload keys outside the review fields come back
+        let payload = payload(&review.into_metadata());
+        assert_eq!(payload["metadata"]["source"], "faq");
+        assert_eq!(payload["page_content"], "Battery lasts all day");
+    }
+
+    #[test]
+    fn test_point_ids() {
+        assert_eq!(PointId::parse("42"), Ok(PointId::Num(42)));
+        assert_eq!(
+            PointId::parse("5f0c6e1a000040008000000000000000"),
+            Ok(PointId::Uuid("5f0c6e1a-0000-4000-8000-000000000000".to_string()))
+        );
+        assert!(PointId::parse("-1").is_err());
+        assert!(PointId::parse("5f0c6e1a-0000-4000-8000").is_err());
+        assert!(PointId::parse("5f0c6e1a0-000-4000-8000-000000000000").is_err());
+    }
+}
@@ -0,0 +1,23 @@
+use crate::api::models::AppState;
+use crate::api::qdrant::handlers::*;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/collections", get(list_collections_handler))
+        .route(
+            "/collections/{name}",
+            get(collection_info_handler).put(create_collection_handler),
+        )
+        .route(
+            "/collections/{name}/points",
+            post(retrieve_points_handler).put(upsert_points_handler),
+        )
+        .route("/collections/{name}/points/{id}", get(get_point_handler))
+        .route("/collections/{name}/points/delete", post(delete_points_handler))
+        .route("/collections/{name}/points/search", post(search_points_handler))
+        .route("/collections/{name}/points/query", post(query_points_handler))
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+pub use vector_search_client::models::ComponentState;
+
+/// Tracks which startup components (model, index, ...) have finished loading
+pub struct Readiness {
+    components: Mutex<BTreeMap<&'static str, ComponentState>>,
+}
+
+impl Readiness {
+    /// Start with every component in `Loading`
+    pub fn new(components: &[&'static str]) -> Self {
+        Self {
+            components: Mutex::new(
+                components
+                    .iter()
+                    .map(|&name| (name, ComponentState::Loading))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn set(&self, component: &'static str, state: ComponentState) {
+        self.lock().insert(component, state);
+    }
+
+    /// True once every component is ready
+    pub fn is_ready(&self) -> bool {
+        self.lock().values().all(|s| *s == ComponentState::Ready)
+    }
+
+    pub fn is_component_ready(&self, component: &str) -> bool {
+        self.lock().get(component) == Some(&ComponentState::Ready)
+    }
+
+    /// Names of components that are not ready yet
+    pub fn pending(&self) -> Vec<&'static str> {
+        self.lock()
+            .iter()
+            .filter(|(_, s)| **s != ComponentState::Ready)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, ComponentState> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<&'static str, ComponentState>> {
+        self.components.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_once_all_components_load() {
+        let readiness = Readiness::new(&["embedding", "index"]);
+        assert!(!readiness.is_ready());
+
+        readiness.set("index", ComponentState::Ready);
+        assert!(readiness.is_component_ready("index"));
+        assert_eq!(readiness.pending(), vec!["embedding"]);
+
+        readiness.set("embedding", ComponentState::Failed("boom".to_string()));
+        assert!(!readiness.is_ready());
+
+        readiness.set("embedding", ComponentState::Ready);
+        assert!(readiness.is_ready());
+    }
+}
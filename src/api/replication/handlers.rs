@@ -0,0 +1,133 @@
+use crate::api::models::*;
+use crate::storage::ReviewMetadata;
+use axum::{
+    body::Body,
+    extract::{Query, Request, State},
+    http::{header, HeaderName},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio_util::io::ReaderStream;
+use tracing::info;
+
+/// Largest metadata page served per request
+const MAX_METADATA_PAGE: usize = 10_000;
+
+/// Response header carrying the vector count of a snapshot
+pub const VECTOR_COUNT_HEADER: &str = "x-vector-count";
+
+static SNAPSHOT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Require `Authorization: Bearer <replication.token>` when a token is set
+pub async fn require_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if let Some(token) = &state.config.current().replication.token {
+        let presented = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if presented != Some(token.as_str()) {
+            return Err(AppError::Unauthorized("Invalid replication token".to_string()));
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+pub async fn status_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ReplicationStatus>, AppError> {
+    let vector_count = state.vector_index.read().await.vector_count();
+    let deleted = tombstoned(state.clone()).await?;
+    let metadata_count = state
+        .metadata_store
+        .count_lines()
+        .map_err(|e| AppError::Internal(format!("Failed to count metadata: {}", e)))?;
+
+    Ok(Json(ReplicationStatus {
+        vector_count,
+        metadata_count,
+        deleted_count: deleted.len(),
+    }))
+}
+
+/// Vector IDs of every tombstoned review: deletes, updates (which
+/// tombstone the old line), bulk deletes and expiries
+///
+/// A tombstone is the only change ever made to a stored line, so these
+/// and the appended lines are all a follower needs.
+pub async fn tombstones_handler(State(state): State<AppState>) -> Result<Json<Vec<usize>>, AppError> {
+    Ok(Json(tombstoned(state).await?))
+}
+
+async fn tombstoned(state: AppState) -> Result<Vec<usize>, AppError> {
+    tokio::task::spawn_blocking(move || {
+        let reviews = state.metadata_store.read_all()?;
+        Ok::<_, anyhow::Error>(
+            reviews
+                .iter()
+                .enumerate()
+                .filter(|(_, review)| review.deleted)
+                .map(|(id, _)| id)
+                .collect(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Metadata task failed: {}", e)))?
+    .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))
+}
+
+/// Stored reviews `from..from + limit`, in vector ID order
+pub async fn metadata_handler(
+    State(state): State<AppState>,
+    Query(query): Query<MetadataRangeQuery>,
+) -> Result<Json<Vec<ReviewMetadata>>, AppError> {
+    let limit = query.limit.min(MAX_METADATA_PAGE);
+    let reviews = tokio::task::spawn_blocking(move || {
+        state.metadata_store.read_range(query.from, limit)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Metadata task failed: {}", e)))?
+    .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+
+    Ok(Json(reviews))
+}
+
+/// A fresh archive of the live index, streamed from a temp file
+pub async fn snapshot_handler(State(state): State<AppState>) -> Result<Response, AppError> {
+    let path = std::env::temp_dir().join(format!(
+        "replica_snapshot_{}_{}.tar.gz",
+        std::process::id(),
+        SNAPSHOT_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let (path, vector_count) = tokio::task::spawn_blocking(move || {
+        let index = state.vector_index.blocking_read();
+        index.export_archive(&path)?;
+        Ok::<_, anyhow::Error>((path, index.vector_count()))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Snapshot task failed: {}", e)))?
+    .map_err(|e| AppError::Internal(format!("Snapshot failed: {}", e)))?;
+
+    let file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to open snapshot: {}", e)))?;
+    // The open handle keeps the data readable after unlinking (unix)
+    let _ = std::fs::remove_file(&path);
+
+    info!(vector_count, "📤 Serving index snapshot to follower");
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (HeaderName::from_static(VECTOR_COUNT_HEADER), vector_count.to_string()),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
@@ -0,0 +1,12 @@
+use crate::api::models::AppState;
+use crate::api::replication::handlers::*;
+use axum::{middleware, routing::get, Router};
+
+pub fn routes(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/replication/status", get(status_handler))
+        .route("/replication/metadata", get(metadata_handler))
+        .route("/replication/snapshot", get(snapshot_handler))
+        .route("/replication/tombstones", get(tombstones_handler))
+        .route_layer(middleware::from_fn_with_state(state, require_token))
+}
@@ -1,61 +1,1167 @@
+use crate::api::collections::handlers::{
+    add_to_collection, language_collection, namespace_collection,
+};
+use crate::api::extract::{ApiJson, CallerKey};
 use crate::api::models::*;
+use crate::api::scoring::similarity;
+use crate::api::tenancy::Namespace;
+use crate::api::timing::{record_if_slow, PhaseTimer};
+use crate::api::usage::count_tokens;
+use crate::config::{AppConfig, DedupAction, DedupConfig, TextNormalization};
+use crate::language;
 use crate::embedding::EmbeddingService;
-use crate::storage::ReviewMetadata;
-use axum::{extract::State, Json};
-use tracing::{error, info};
+use crate::storage::audit::sha256_hex;
+use crate::storage::{tombstone_ids, tombstone_where, AuditEntry, DistanceMetric, ReviewMetadata, VectorIndex};
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use tracing::{error, info, warn};
 
+/// `?durability=async` answers 202 once the review is stored (see
+/// [`accept_review`])
 pub async fn add_review_handler(
     State(state): State<AppState>,
-    Json(request): Json<AddReviewRequest>,
-) -> Result<Json<AddReviewResponse>, AppError> {
+    CallerKey(api_key): CallerKey,
+    namespace: Option<Extension<Namespace>>,
+    Query(query): Query<AddReviewQuery>,
+    ApiJson(request): ApiJson<AddReviewRequest>,
+) -> Result<(StatusCode, Json<AddReviewResponse>), AppError> {
+    if query.durability == Durability::Async {
+        if namespace.is_some() {
+            return Err(AppError::BadRequest(
+                "durability=async only applies to the default collection".to_string(),
+            ));
+        }
+        let response = accept_review(state, api_key, request).await?;
+        return Ok((StatusCode::ACCEPTED, Json(response)));
+    }
+
+    if let Some(Extension(Namespace(namespace))) = namespace {
+        let response = add_to_namespace(state, api_key, &namespace, request).await?;
+        return Ok((StatusCode::OK, Json(response)));
+    }
+    let response = add_review(state, api_key, request).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Add a review to a tenant's collection, within its quotas
+///
+/// Shared by the REST and gRPC front ends.
+pub async fn add_to_namespace(
+    state: AppState,
+    api_key: Option<String>,
+    namespace: &str,
+    request: AddReviewRequest,
+) -> Result<AddReviewResponse, AppError> {
+    let quota = state.config.current().tenancy.quota(namespace).clone();
+    let collection = namespace_collection(&state, namespace)?;
+    if let Some(max) = quota.max_vectors
+        && collection.index.read().await.vector_count() >= max
+    {
+        return Err(AppError::Forbidden(format!(
+            "Namespace {} has reached its quota of {} reviews",
+            namespace, max
+        )));
+    }
+    let text = EmbeddingService::prepare_review_text(&request.review_title, &request.review_body);
+    state
+        .usage
+        .charge_tokens(namespace, count_tokens(&text), quota.max_embedding_tokens)?;
+    add_to_collection(state, api_key, collection, request).await
+}
+
+/// Validate, embed and persist one review
+///
+/// Shared by the REST and gRPC front ends.
+pub async fn add_review(
+    state: AppState,
+    api_key: Option<String>,
+    request: AddReviewRequest,
+) -> Result<AddReviewResponse, AppError> {
+    let mut timer = PhaseTimer::start();
+    let config = state.config.current();
+    state.check_writable()?;
+
     // Validate
-    request.validate().map_err(AppError::BadRequest)?;
+    let mut request = request;
+    request.validate(&config.validation).map_err(AppError::validation)?;
+    tag_language(&config, &mut request);
+    if let Some(collection) = language_collection(&state, request.language.as_deref())? {
+        return add_to_collection(state, api_key, collection, request).await;
+    }
+    if request.image.is_some() {
+        return Err(AppError::BadRequest(
+            "Images can only be added to a collection created with a CLIP model".to_string(),
+        ));
+    }
+    let redactions = redact(&state, &mut request)?;
+    timer.mark("validate");
+
+    state
+        .backpressure
+        .admit_write(state.write_gate.in_flight() + state.ingest.pending(), &config.backpressure)?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
 
     info!(product_id = %request.product_id, "Adding review");
 
-    // Embed
-    let text = EmbeddingService::prepare_review_text(&request.review_title, &request.review_body);
-    let embedding = state
-        .embedding_service
-        .embed(&text)
-        .map_err(|e| AppError::Internal(format!("Embedding failed: {}", e)))?;
-
-    // Add to index & save
-    let vector_id = {
-        let mut index = state.vector_index.write().await;
-        let id = index
-            .add_vector(&embedding)
-            .map_err(|e| AppError::Internal(format!("Add vector failed: {}", e)))?;
-        
-        index
-            .save(&std::path::Path::new("data/reviews.index"))
-            .map_err(|e| AppError::Internal(format!("Save index failed: {}", e)))?;
-        
-        id
-    };
-
-    // Store metadata
-    let metadata = ReviewMetadata {
-        review_title: request.review_title,
-        review_body: request.review_body,
-        product_id: request.product_id,
-        review_rating: request.review_rating,
+    let embedding = {
+        let _permit = state.backpressure.embedding(&config.backpressure)?;
+        embed_review(&state, &request).await?
+    };
+    timer.mark("embed");
+
+    let metadata = request.into_metadata();
+    let product_id = metadata.product_id.clone();
+    let payload_hash = serde_json::to_vec(&metadata)
+        .map(|bytes| sha256_hex(&bytes))
+        .ok();
+
+    // Run the write in a detached task: if the client disconnects or the
+    // request times out, the index and metadata are still updated together
+    let dedup = config.dedup.clone();
+    let latency = state.latency.clone();
+    let (vector_id, duplicate_of) = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let (vector_id, duplicate_of) = persist_review(&state, embedding, metadata, &dedup).await?;
+
+        state.record_mutation(AuditEntry {
+            api_key,
+            payload_hash,
+            ids: vec![vector_id],
+            detail: duplicate_of.map(|id| serde_json::json!({ "duplicate_of": id })),
+            ..AuditEntry::new("add")
+        });
+        Ok::<_, AppError>((vector_id, duplicate_of))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+    timer.mark("write");
+
+    info!(vector_id, "Review added");
+    latency.observe("add_review", &timer);
+    record_if_slow(
+        &config.slow_log,
+        "add_review",
+        &timer,
+        serde_json::json!({ "product_id": product_id, "vector_id": vector_id }),
+    );
+
+    let message = match duplicate_of {
+        Some(id) => format!("Review added with ID {} (duplicate of {})", vector_id, id),
+        None => format!("Review added with ID {}", vector_id),
+    };
+    Ok(AddReviewResponse {
+        vector_id,
+        status: "success".to_string(),
+        message,
+        duplicate_of,
+        redactions,
+    })
+}
+
+/// Validate and store one review, leaving embedding and indexing to the
+/// background indexer (`durability=async`)
+///
+/// The metadata line is the write-ahead record, so the review survives a
+/// crash once this returns. Its vector ID is final, but it only shows up
+/// in searches once indexed. Exact duplicates are still caught; finding
+/// near-duplicates needs the embedding and is skipped.
+pub async fn accept_review(
+    state: AppState,
+    api_key: Option<String>,
+    request: AddReviewRequest,
+) -> Result<AddReviewResponse, AppError> {
+    let config = state.config.current();
+    state.check_writable()?;
+
+    let mut request = request;
+    request.validate(&config.validation).map_err(AppError::validation)?;
+    tag_language(&config, &mut request);
+    if request.image.is_some() || language_collection(&state, request.language.as_deref())?.is_some() {
+        return Err(AppError::BadRequest(
+            "durability=async only applies to text reviews in the default collection".to_string(),
+        ));
+    }
+    let redactions = redact(&state, &mut request)?;
+    // Nothing could index it until the model is loaded
+    state.embedder()?;
+
+    state
+        .backpressure
+        .admit_write(state.write_gate.in_flight() + state.ingest.pending(), &config.backpressure)?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let metadata = request.into_metadata();
+    let payload_hash = serde_json::to_vec(&metadata)
+        .map(|bytes| sha256_hex(&bytes))
+        .ok();
+    let dedup = config.dedup.clone();
+    let ingest = state.ingest.clone();
+    let (vector_id, duplicate_of) = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        // Keeps out writes that need the index and metadata in step
+        let _index = state.vector_index.write().await;
+
+        let exact = if dedup.exact {
+            let stored = state
+                .metadata_store
+                .count_lines()
+                .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+            find_exact_duplicate(&state, &metadata, stored)?
+        } else {
+            None
+        };
+        if let Some(id) = exact
+            && dedup.action == DedupAction::Reject
+        {
+            return Err(AppError::Duplicate {
+                message: format!("Review is an exact duplicate of review {}", id),
+                existing_id: id,
+            });
+        }
+
+        let vector_id = state
+            .metadata_store
+            .append(&metadata)
+            .map_err(|e| AppError::Internal(format!("Store metadata failed: {}", e)))?;
+        state.spelling.observe(&metadata.review_title, &metadata.review_body);
+        state.suggester.observe(&metadata);
+        state.content_hashes.observe(vector_id, &metadata);
+        state.point_ids.observe(vector_id, &metadata);
+        state.ingest.push();
+
+        state.record_mutation(AuditEntry {
+            api_key,
+            payload_hash,
+            ids: vec![vector_id],
+            detail: exact.map(|id| serde_json::json!({ "duplicate_of": id })),
+            ..AuditEntry::new("add")
+        });
+        Ok::<_, AppError>((vector_id, exact))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    info!(vector_id, pending = ingest.pending(), "Review accepted");
+    Ok(AddReviewResponse {
+        vector_id,
+        status: "accepted".to_string(),
+        message: format!("Review accepted with ID {}; it becomes searchable once indexed", vector_id),
+        duplicate_of,
+        redactions,
+    })
+}
+
+/// Normalize the review's language tag, or detect one when
+/// `language.detect` is on
+pub(crate) fn tag_language(config: &AppConfig, request: &mut AddReviewRequest) {
+    request.language = match request.language.take().filter(|tag| !tag.trim().is_empty()) {
+        Some(tag) => Some(language::normalize(&tag)),
+        None if config.language.detect => {
+            let text = EmbeddingService::prepare_review_text(&request.review_title, &request.review_body);
+            language::detect(&text, config.language.min_confidence)
+        }
+        None => None,
+    };
+}
+
+/// Mask personal data in the review per `redaction`, before it is embedded
+///
+/// Returns the report only when something was masked.
+pub(crate) fn redact(
+    state: &AppState,
+    request: &mut AddReviewRequest,
+) -> Result<Option<RedactionReport>, AppError> {
+    let redactor = state
+        .redactor
+        .get(&state.config.current().redaction)
+        .map_err(|e| AppError::Internal(format!("{:#}", e)))?;
+    let Some(redactor) = redactor else {
+        return Ok(None);
+    };
+
+    let report = redactor.redact_review(&mut request.review_title, &mut request.review_body);
+    if report.is_empty() {
+        return Ok(None);
+    }
+    info!(counts = ?report.counts, "Redacted personal data from review");
+    Ok(Some(report))
+}
+
+/// A review's vector and the model setup it was embedded with
+pub(crate) struct ReviewVector {
+    pub values: Vec<f32>,
+    /// Model and normalization; `None` for vectors a client sent
+    embedded_with: Option<(String, TextNormalization)>,
+}
+
+impl ReviewVector {
+    /// A vector embedded under `config`'s model and normalization
+    pub(crate) fn embedded(values: Vec<f32>, config: &AppConfig) -> Self {
+        let embedding = &config.embedding;
+        Self {
+            values,
+            embedded_with: Some((embedding.model_name.clone(), embedding.normalization.clone())),
+        }
+    }
+
+    /// A vector sent by the client, used as is
+    pub(crate) fn given(values: Vec<f32>) -> Self {
+        Self {
+            values,
+            embedded_with: None,
+        }
+    }
+
+    /// Whether a rebuild or cutover switched models since this was embedded
+    fn is_stale(&self, config: &AppConfig) -> bool {
+        self.embedded_with.as_ref().is_some_and(|(model, normalization)| {
+            !EmbeddingService::same_model(model, &config.embedding.model_name)
+                || *normalization != config.embedding.normalization
+        })
+    }
+}
+
+/// Embed a review's text (CPU-bound, keep it off the async workers)
+async fn embed_review(state: &AppState, request: &AddReviewRequest) -> Result<ReviewVector, AppError> {
+    let config = state.config.current();
+    let text = EmbeddingService::review_text(
+        &request.review_title,
+        &request.review_body,
+        &config.embedding.normalization,
+    );
+    let embedding_service = state.embedder_for(&config.embedding.model_name).await?;
+    tokio::task::spawn_blocking(move || embedding_service.embed(&text))
+        .await
+        .map_err(|e| AppError::Internal(format!("Embedding task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Embedding failed: {}", e)))
+        .map(|values| ReviewVector::embedded(values, &config))
+}
+
+/// Append metadata, then add the vector and save the index
+///
+/// The metadata line is written first and serves as the write-ahead record:
+/// if adding the vector fails the line is truncated away, and if the process
+/// dies before the index is saved, startup re-embeds it. Line numbers and
+/// vector IDs therefore never drift apart.
+///
+/// Returns the new vector ID and, with `dedup` in flag mode, the review it
+/// nearly duplicates.
+async fn persist_review(
+    state: &AppState,
+    embedding: ReviewVector,
+    metadata: ReviewMetadata,
+    dedup: &DedupConfig,
+) -> Result<(usize, Option<usize>), AppError> {
+    // Held across both writes so concurrent adds can't interleave (or slip
+    // past each other's duplicate check)
+    let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+    let (task_state, dedup) = (state.clone(), dedup.clone());
+    let (index, stored) = tokio::task::spawn_blocking(move || {
+        let stored = insert_review(&task_state, &mut index, &embedding, metadata, &dedup, None);
+        (index, stored)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))?;
+    let stored = stored?;
+
+    // Both sides now hold the review; the save is shared with adds that
+    // land close together
+    let ticket = state.group_commit.written();
+    drop(index);
+    state.group_commit.commit(ticket, state).await;
+
+    Ok(stored)
+}
+
+/// Check for duplicates, then append the metadata line and add the vector,
+/// under the caller's index write lock; the caller saves the index
+///
+/// `replaces` is tombstoned once the new review is in, and isn't counted
+/// as its duplicate. Shared by every write into the default collection.
+///
+/// Embeds the review again, blocking, if the model changed since
+/// `embedding` was made, so old-model vectors never reach a new index.
+pub(crate) fn insert_review(
+    state: &AppState,
+    index: &mut VectorIndex,
+    embedding: &ReviewVector,
+    mut metadata: ReviewMetadata,
+    dedup: &DedupConfig,
+    replaces: Option<usize>,
+) -> Result<(usize, Option<usize>), AppError> {
+    let config = state.config.current();
+    let reembedded;
+    let embedding = if embedding.is_stale(&config) {
+        info!(model = %config.embedding.model_name, "Model changed while the review was embedded; embedding it again");
+        let text = EmbeddingService::review_text(
+            &metadata.review_title,
+            &metadata.review_body,
+            &config.embedding.normalization,
+        );
+        reembedded = state
+            .embedder()?
+            .embed(&text)
+            .map_err(|e| AppError::Internal(format!("Embedding failed: {}", e)))?;
+        &reembedded
+    } else {
+        &embedding.values
     };
 
+    let vector_id = index.vector_count();
+    let replaced = match replaces {
+        Some(old_id) => {
+            let old = read_current(state, old_id)?;
+            if old.deleted {
+                return Err(deleted(old_id));
+            }
+            metadata.revision = old.revision + 1;
+            Some((old_id, old))
+        }
+        None => None,
+    };
+
+    let exact = if dedup.exact {
+        find_exact_duplicate(state, &metadata, vector_id)?.filter(|&id| Some(id) != replaces)
+    } else {
+        None
+    };
+    if let Some(id) = exact
+        && dedup.action == DedupAction::Reject
+    {
+        return Err(AppError::Duplicate {
+            message: format!("Review is an exact duplicate of review {}", id),
+            existing_id: id,
+        });
+    }
+
+    let duplicate = if dedup.enabled && exact.is_none() {
+        let metric = config.index.metric;
+        find_duplicate(index, embedding, metric, dedup.threshold).filter(|&(id, _)| Some(id) != replaces)
+    } else {
+        None
+    };
+    if let Some((id, score)) = duplicate
+        && dedup.action == DedupAction::Reject
+    {
+        return Err(AppError::Duplicate {
+            message: format!("Review is a near-duplicate of review {} (similarity {:.3})", id, score),
+            existing_id: id,
+        });
+    }
+
+    // Reserve the metadata line
     let stored_id = state
         .metadata_store
         .append(&metadata)
         .map_err(|e| AppError::Internal(format!("Store metadata failed: {}", e)))?;
-
-    if vector_id != stored_id {
+    state.spelling.observe(&metadata.review_title, &metadata.review_body);
+    state.suggester.observe(&metadata);
+    state.content_hashes.observe(stored_id, &metadata);
+    state.point_ids.observe(stored_id, &metadata);
+    if stored_id != vector_id {
+        rollback_metadata(state, stored_id);
         error!(vector_id, stored_id, "ID mismatch");
+        return Err(AppError::Internal(format!(
+            "Metadata ({} reviews) and index ({} vectors) are out of sync",
+            stored_id, vector_id
+        )));
     }
 
-    info!(vector_id, "Review added");
+    if let Err(e) = index.add_vector(embedding) {
+        rollback_metadata(state, stored_id);
+        return Err(AppError::Internal(format!("Add vector failed: {}", e)));
+    }
+
+    if let Some((old_id, mut old)) = replaced {
+        old.revision += 1;
+        old.deleted = true;
+        tombstone(state, index, old_id, &old)?;
+    }
+    Ok((vector_id, exact.or(duplicate.map(|(id, _)| id))))
+}
+
+/// A live review with the same title, body and product ID, among the
+/// first `stored` reviews
+fn find_exact_duplicate(
+    state: &AppState,
+    metadata: &ReviewMetadata,
+    stored: usize,
+) -> Result<Option<usize>, AppError> {
+    state
+        .content_hashes
+        .find(
+            metadata,
+            stored,
+            || state.metadata_store.read_all(),
+            |ids| state.metadata_store.read_batch(ids),
+        )
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))
+}
+
+/// Closest stored review if it is at least `threshold` similar
+pub(crate) fn find_duplicate(
+    index: &VectorIndex,
+    embedding: &[f32],
+    metric: DistanceMetric,
+    threshold: f32,
+) -> Option<(usize, f32)> {
+    if index.vector_count() == 0 {
+        return None;
+    }
+    match index.search(embedding, 1) {
+        Ok(results) => results
+            .first()
+            .map(|r| (r.vector_id, similarity(metric, r.distance)))
+            .filter(|&(_, score)| score >= threshold),
+        Err(e) => {
+            // Best effort: a failed check shouldn't block ingest
+            warn!("Duplicate check failed: {:#}", e);
+            None
+        }
+    }
+}
+
+/// Drop a reserved metadata line whose vector was never added
+fn rollback_metadata(state: &AppState, line_count: usize) {
+    if let Err(e) = state.metadata_store.truncate(line_count) {
+        error!(line_count, "Metadata rollback failed: {:#}", e);
+    }
+}
+
+pub async fn get_review_handler(
+    State(state): State<AppState>,
+    Path(vector_id): Path<usize>,
+) -> Result<Json<ReviewRecord>, AppError> {
+    let metadata = read_current(&state, vector_id)?;
+    if metadata.deleted {
+        return Err(deleted(vector_id));
+    }
+    if metadata.is_expired(now_ms()) {
+        return Err(AppError::NotFound(format!("Review {} has expired", vector_id)));
+    }
+    Ok(Json(ReviewRecord::new(vector_id, metadata)))
+}
+
+/// The embedding stored for a live review
+pub async fn get_vector_handler(
+    State(state): State<AppState>,
+    Path(vector_id): Path<usize>,
+) -> Result<Json<VectorRecord>, AppError> {
+    let metadata = read_current(&state, vector_id)?;
+    if metadata.deleted {
+        return Err(deleted(vector_id));
+    }
+    if metadata.is_expired(now_ms()) {
+        return Err(AppError::NotFound(format!("Review {} has expired", vector_id)));
+    }
 
-    Ok(Json(AddReviewResponse {
+    let index = state.vector_index.read().await;
+    // Accepted with durability=async; the indexer hasn't reached it yet
+    if vector_id >= index.vector_count() {
+        return Err(AppError::Conflict(format!("Review {} is not indexed yet", vector_id)));
+    }
+    let vector = index
+        .get_vector(vector_id)
+        .map_err(|e| AppError::Internal(format!("Read vector failed: {:#}", e)))?;
+    drop(index);
+    let config = state.config.current();
+    Ok(Json(VectorRecord {
         vector_id,
-        status: "success".to_string(),
-        message: format!("Review added with ID {}", vector_id),
+        model: config.embedding.model_name.clone(),
+        metric: config.index.metric,
+        vector,
     }))
 }
+
+/// Replace a review at `expected_revision`
+///
+/// Vectors can't be changed in place, so the new text is stored under a
+/// new vector ID and the old one is tombstoned.
+pub async fn update_review_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(old_id): Path<usize>,
+    ApiJson(mut request): ApiJson<UpdateReviewRequest>,
+) -> Result<Json<ReviewRecord>, AppError> {
+    state.check_writable()?;
+    request
+        .review
+        .validate(&state.config.current().validation)
+        .map_err(AppError::validation)?;
+    redact(&state, &mut request.review)?;
+    tag_language(&state.config.current(), &mut request.review);
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    info!(vector_id = old_id, "Updating review");
+    let embedding = embed_review(&state, &request.review).await?;
+
+    // Detached like adds, so the two writes aren't split by a disconnect
+    let dedup = state.config.current().dedup.clone();
+    let record = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+
+        let task_state = state.clone();
+        let (index, stored) = tokio::task::spawn_blocking(move || {
+            let state = task_state;
+            let stored = read_current(&state, old_id).and_then(|old| {
+                check_revision(old_id, &old, request.expected_revision)?;
+
+                // The same path as an add, with the old review tombstoned
+                // once the new one is in
+                let mut metadata = request.review.into_metadata();
+                let (new_id, _) =
+                    insert_review(&state, &mut index, &embedding, metadata.clone(), &dedup, Some(old_id))?;
+                metadata.revision = old.revision + 1;
+                Ok((new_id, metadata))
+            });
+            (index, stored)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))?;
+        let (new_id, metadata) = stored?;
+        let ticket = state.group_commit.written();
+        drop(index);
+        state.group_commit.commit(ticket, &state).await;
+
+        state.record_mutation(AuditEntry {
+            api_key,
+            ids: vec![old_id, new_id],
+            detail: Some(serde_json::json!({ "replaced": old_id, "revision": metadata.revision })),
+            ..AuditEntry::new("update")
+        });
+        Ok::<_, AppError>(ReviewRecord::new(new_id, metadata))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    info!(old_id, vector_id = record.vector_id, revision = record.revision, "Review updated");
+    Ok(Json(record))
+}
+
+/// Tombstone a review at `expected_revision`
+pub async fn delete_review_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    Path(vector_id): Path<usize>,
+    Query(query): Query<DeleteReviewQuery>,
+) -> Result<StatusCode, AppError> {
+    state.check_deletable()?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = state.ingest.write_index(&state.vector_index).await?;
+
+        let mut metadata = read_current(&state, vector_id)?;
+        check_revision(vector_id, &metadata, query.expected_revision)?;
+        metadata.revision += 1;
+        metadata.deleted = true;
+        tombstone(&state, &mut index, vector_id, &metadata)?;
+        let ticket = state.group_commit.written();
+        drop(index);
+        state.group_commit.commit(ticket, &state).await;
+
+        state.record_mutation(AuditEntry {
+            api_key,
+            ids: vec![vector_id],
+            ..AuditEntry::new("delete")
+        });
+        Ok::<_, AppError>(())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    info!(vector_id, "Review deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Tombstone every live review matching a filter, in one metadata pass
+pub async fn delete_by_filter_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    ApiJson(request): ApiJson<DeleteByFilterRequest>,
+) -> Result<Json<DeleteByFilterResponse>, AppError> {
+    request.filter.validate().map_err(AppError::BadRequest)?;
+    request
+        .filter
+        .check_types(&state.config.current().validation.attributes)
+        .map_err(AppError::BadRequest)?;
+    if request.filter.is_empty() {
+        return Err(AppError::BadRequest(
+            "Filter must have at least one condition".to_string(),
+        ));
+    }
+    state.check_deletable()?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let deleted = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+
+        // Reads and rewrites the whole metadata file
+        let config = state.config.current();
+        let metadata_store = state.metadata_store.clone();
+        let index_path = state.index_path();
+        let filter = request.filter.clone();
+        let ids = tokio::task::spawn_blocking(move || {
+            tombstone_where(
+                metadata_store.as_ref(),
+                &mut index,
+                &index_path,
+                |review| filter.matches(review, &config.validation.attributes),
+            )
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Delete task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Delete by filter failed: {}", e)))?;
+
+        let deleted = ids.len();
+        if deleted > 0 {
+            state.suggester.invalidate();
+            state.spelling.invalidate();
+            state.record_mutation(AuditEntry {
+                api_key,
+                ids,
+                detail: serde_json::to_value(&request.filter).ok(),
+                ..AuditEntry::new("delete_by_filter")
+            });
+        }
+        Ok::<_, AppError>(deleted)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    info!(deleted, "Reviews deleted by filter");
+    Ok(Json(DeleteByFilterResponse { deleted }))
+}
+
+/// Reviews per scroll page unless the request says
+const DEFAULT_SCROLL_LIMIT: usize = 100;
+
+/// Records read from the metadata store at a time while scrolling
+const SCROLL_BATCH: usize = 1000;
+
+/// One page of the live reviews matching a filter, in vector ID order
+///
+/// The cursor is the vector ID to resume from, so pages stay stable while
+/// reviews are added (they show up at the end) or deleted (they are
+/// skipped).
+pub async fn scroll_handler(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<ScrollRequest>,
+) -> Result<Json<ScrollResponse>, AppError> {
+    let limit = request.limit.unwrap_or(DEFAULT_SCROLL_LIMIT);
+    if limit == 0 || limit > default_metadata_range_limit() {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            default_metadata_range_limit()
+        )));
+    }
+    let config = state.config.current();
+    if let Some(filter) = &request.filter {
+        filter.validate().map_err(AppError::BadRequest)?;
+        filter
+            .check_types(&config.validation.attributes)
+            .map_err(AppError::BadRequest)?;
+    }
+    let start = match &request.cursor {
+        Some(cursor) => decode_cursor(cursor)?,
+        None => 0,
+    };
+
+    let metadata_store = state.metadata_store.clone();
+    let (reviews, next) = tokio::task::spawn_blocking(move || {
+        let now = now_ms();
+        let schema = &config.validation.attributes;
+        let mut reviews = Vec::with_capacity(limit);
+        let mut next = start;
+        loop {
+            let batch = metadata_store.read_range(next, SCROLL_BATCH)?;
+            if batch.is_empty() {
+                return Ok((reviews, None));
+            }
+            for review in batch {
+                let vector_id = next;
+                next += 1;
+                if review.is_live(now) && request.filter.as_ref().is_none_or(|f| f.matches(&review, schema)) {
+                    reviews.push(ReviewRecord::new(vector_id, review));
+                    if reviews.len() == limit {
+                        return Ok((reviews, Some(next)));
+                    }
+                }
+            }
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Scroll task failed: {}", e)))?
+    .map_err(|e: anyhow::Error| AppError::Internal(format!("Scroll failed: {:#}", e)))?;
+
+    Ok(Json(ScrollResponse {
+        reviews,
+        next_cursor: next.map(encode_cursor),
+    }))
+}
+
+fn encode_cursor(vector_id: usize) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(vector_id.to_string())
+}
+
+fn decode_cursor(cursor: &str) -> Result<usize, AppError> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| AppError::BadRequest("Invalid cursor".to_string()))
+}
+
+/// Tombstone the listed reviews under one write lock and one metadata pass
+///
+/// Missing and already deleted IDs don't fail the request; every ID gets
+/// its own outcome.
+pub async fn delete_reviews_handler(
+    State(state): State<AppState>,
+    CallerKey(api_key): CallerKey,
+    ApiJson(request): ApiJson<DeleteReviewsRequest>,
+) -> Result<Json<DeleteReviewsResponse>, AppError> {
+    if request.vector_ids.is_empty() {
+        return Err(AppError::BadRequest("vector_ids cannot be empty".to_string()));
+    }
+    if request.vector_ids.len() > default_metadata_range_limit() {
+        return Err(AppError::BadRequest(format!(
+            "At most {} vector_ids per request",
+            default_metadata_range_limit()
+        )));
+    }
+    state.check_deletable()?;
+    let write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    let results = tokio::spawn(async move {
+        let _write_guard = write_guard;
+        let mut index = state.ingest.write_index_owned(&state.vector_index).await?;
+
+        // Rewrites the metadata file
+        let metadata_store = state.metadata_store.clone();
+        let index_path = state.index_path();
+        let results = tokio::task::spawn_blocking(move || {
+            tombstone_ids(metadata_store.as_ref(), &mut index, &index_path, &request.vector_ids)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Delete task failed: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Bulk delete failed: {}", e)))?;
+
+        let ids: Vec<usize> = results
+            .iter()
+            .filter(|outcome| outcome.status == DeleteStatus::Deleted)
+            .map(|outcome| outcome.vector_id)
+            .collect();
+        if !ids.is_empty() {
+            state.suggester.invalidate();
+            state.spelling.invalidate();
+            state.record_mutation(AuditEntry {
+                api_key,
+                ids,
+                ..AuditEntry::new("delete")
+            });
+        }
+        Ok::<_, AppError>(results)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Write task failed: {}", e)))??;
+
+    let deleted = results
+        .iter()
+        .filter(|outcome| outcome.status == DeleteStatus::Deleted)
+        .count();
+    info!(deleted, requested = results.len(), "Reviews deleted by ID");
+    Ok(Json(DeleteReviewsResponse { deleted, results }))
+}
+
+/// Stored metadata for `vector_id`, tombstoned or not
+pub(crate) fn read_current(state: &AppState, vector_id: usize) -> Result<ReviewMetadata, AppError> {
+    let count = state
+        .metadata_store
+        .count_lines()
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+    if vector_id >= count {
+        return Err(AppError::NotFound(format!("Review {} not found", vector_id)));
+    }
+    state
+        .metadata_store
+        .read_by_id(vector_id)
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))
+}
+
+/// 409 unless the caller saw the latest revision; a stale caller learns
+/// about a delete this way too, since deletes bump the revision
+fn check_revision(vector_id: usize, current: &ReviewMetadata, expected: u64) -> Result<(), AppError> {
+    if current.revision != expected {
+        return Err(AppError::Conflict(format!(
+            "Review {} is at revision {}, not {}",
+            vector_id, current.revision, expected
+        )));
+    }
+    if current.deleted {
+        return Err(deleted(vector_id));
+    }
+    Ok(())
+}
+
+fn deleted(vector_id: usize) -> AppError {
+    AppError::NotFound(format!("Review {} was deleted", vector_id))
+}
+
+/// Write the tombstone line, then mask the vector; callers save the index
+/// through [`GroupCommit::commit`](crate::api::group_commit::GroupCommit::commit)
+///
+/// The metadata is authoritative: searches drop tombstoned results even if
+/// masking the vector fails.
+fn tombstone(
+    state: &AppState,
+    index: &mut VectorIndex,
+    vector_id: usize,
+    metadata: &ReviewMetadata,
+) -> Result<(), AppError> {
+    state
+        .metadata_store
+        .replace(vector_id, metadata)
+        .map_err(|e| AppError::Internal(format!("Store metadata failed: {}", e)))?;
+    state.suggester.forget(metadata);
+    state.spelling.invalidate();
+
+    if let Err(e) = index.delete_vector(vector_id) {
+        warn!(vector_id, "Masking deleted vector failed: {:#}", e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_review, ReviewVector};
+    use crate::embedding::EmbeddingService;
+    use crate::test_utils::{review, review_request, TestApp};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_scroll() {
+        let app = TestApp::builder().build().unwrap();
+        for rating in [5, 2, 4, 5, 1, 4] {
+            let review = json!({
+                "review_title": format!("Rated {}", rating),
+                "review_body": "Bought it last week",
+                "product_id": "P1",
+                "review_rating": rating,
+            });
+            let (status, body) = app.post("/reviews", &review).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+        let delete = Request::delete("/reviews/3?expected_revision=1").body(Body::empty()).unwrap();
+        assert_eq!(app.request(delete).await.status(), StatusCode::NO_CONTENT);
+
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let request = json!({ "filter": { "min_rating": 4 }, "limit": 2, "cursor": cursor });
+            let (status, body) = app.post("/reviews/scroll", &request).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            ids.extend(body["reviews"].as_array().unwrap().iter().map(|review| review["vector_id"].as_u64().unwrap()));
+            match body["next_cursor"].as_str() {
+                Some(next) => cursor = Some(next.to_string()),
+                None => break,
+            }
+        }
+        // 3 is deleted, 1 and 4 rated too low
+        assert_eq!(ids, [0, 2, 5]);
+
+        let (status, _) = app.post("/reviews/scroll", &json!({ "cursor": "not a cursor" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_ids() {
+        let app = TestApp::builder().build().unwrap();
+        for title in ["Great battery", "Loud fan", "Broken hinge"] {
+            let (status, body) = app.post("/reviews", &review_request(title)).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+        let (status, body) = app.post("/reviews/delete", &json!({ "vector_ids": [2, 0, 2, 9] })).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["deleted"], 2);
+        let statuses: Vec<&str> = body["results"].as_array().unwrap().iter().map(|r| r["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, ["deleted", "deleted", "already_deleted", "not_found"]);
+        assert_eq!(app.state.vector_index.read().await.live_count(), 1);
+
+        let too_many: Vec<usize> = (0..=1000).collect();
+        let (status, _) = app.post("/reviews/delete", &json!({ "vector_ids": too_many })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_reembed_after_model_switch() {
+        let app = TestApp::builder().build().unwrap();
+        let embedder = app.state.embedder().unwrap();
+        let before = app.state.config.current();
+        let text = |normalization| EmbeddingService::review_text("The battery", "Body", normalization);
+        let stale = ReviewVector::embedded(embedder.embed(&text(&before.embedding.normalization)).unwrap(), &before);
+
+        // A rebuild switches the setup while the add waits for the lock
+        app.state.config.update(|config| config.embedding.normalization.remove_stopwords = true);
+        let after = app.state.config.current();
+        {
+            let mut index = app.state.vector_index.write().await;
+            let metadata = review("The battery", "P1");
+            insert_review(&app.state, &mut index, &stale, metadata, &after.dedup, None).unwrap();
+        }
+
+        let (status, body) = app.get("/vectors/0").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let vector: Vec<f32> = serde_json::from_value(body["vector"].clone()).unwrap();
+        let expected = embedder.embed(&text(&after.embedding.normalization)).unwrap();
+        assert_ne!(stale.values, expected);
+        assert_eq!(vector, expected);
+    }
+
+    #[tokio::test]
+    async fn test_get_vector() {
+        let app = TestApp::builder().seed(3).build().unwrap();
+        let review = review_request("Great battery life");
+        let (status, _) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = app.get("/vectors/0").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let expected = app.state.embedder().unwrap().embed("Great battery life Bought it last week").unwrap();
+        let vector: Vec<f32> = serde_json::from_value(body["vector"].clone()).unwrap();
+        assert_eq!(vector, expected);
+
+        let (status, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "include_vectors": true }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let vector: Vec<f32> = serde_json::from_value(body["results"][0]["vector"].clone()).unwrap();
+        assert_eq!(vector, expected);
+        let (_, body) = app.post("/reviews/search", &json!({ "query": "battery" })).await;
+        assert!(body["results"][0].get("vector").is_none());
+
+        let (status, _) = app.get("/vectors/1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_add_saves_to_configured_index_path() {
+        let app = TestApp::builder().build().unwrap();
+        let index_path = app.state.index_path();
+        assert!(index_path.starts_with(app.dir()));
+        assert!(!index_path.exists());
+
+        let review = review_request("Great battery life");
+        let (status, _) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(index_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_async_durability() {
+        let app = TestApp::builder().build().unwrap();
+        let (status, body) = app.post("/reviews?durability=async", &review_request("Great battery")).await;
+        assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+        assert_eq!(body["vector_id"], 0);
+        assert_eq!(body["status"], "accepted");
+        assert_eq!(app.get("/reviews/0").await.0, StatusCode::OK);
+        assert_eq!(app.get("/vectors/0").await.0, StatusCode::CONFLICT);
+        assert_eq!(app.state.ingest.pending(), 1);
+        assert_eq!(app.state.vector_index.read().await.vector_count(), 0);
+
+        // A synchronous add waits for the backlog, so IDs stay in step
+        let (_shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+        let indexer = crate::ingest::spawn_async_indexer(app.state.clone(), shutdown_rx);
+        let (status, body) = app.post("/reviews", &review_request("Solid screen")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["vector_id"], 1);
+        assert_eq!(app.state.ingest.pending(), 0);
+        assert_eq!(app.get("/vectors/0").await.0, StatusCode::OK);
+
+        let (_, body) = app.post("/reviews/search", &json!({ "query": "Great battery" })).await;
+        let ids: Vec<u64> = body["results"].as_array().unwrap().iter().map(|r| r["vector_id"].as_u64().unwrap()).collect();
+        assert!(ids.contains(&0), "{:?}", ids);
+        indexer.abort();
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_names_existing_id() {
+        let app = TestApp::builder().config(|config| config.dedup.exact = true).build().unwrap();
+        for title in ["Great battery", "Solid screen"] {
+            let (status, body) = app.post("/reviews", &review_request(title)).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let (status, body) = app.post("/reviews", &review_request("Solid screen")).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert_eq!(body["existing_id"], 1);
+        let (status, body) = app.post("/reviews?durability=async", &review_request("Great battery")).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert_eq!(body["existing_id"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_namespace_adds_check_duplicates() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.dedup.enabled = true;
+                config.dedup.exact = true;
+                config.tenancy.enabled = true;
+                config.tenancy.keys = vec![crate::config::TenantKey {
+                    key: "acme-key".to_string(),
+                    namespaces: vec!["acme".to_string()],
+                }];
+            })
+            .build()
+            .unwrap();
+        let add = |title: &str| {
+            Request::post("/reviews")
+                .header("content-type", "application/json")
+                .header("x-api-key", "acme-key")
+                .body(Body::from(review_request(title).to_string()))
+                .unwrap()
+        };
+
+        let (status, body) = app.send(add("Great battery")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let (status, body) = app.send(add("Great battery")).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert_eq!(body["existing_id"], 0);
+        assert!(body["message"].as_str().unwrap().contains("exact duplicate"), "{}", body);
+
+        // Near-duplicates are still caught without the hashes
+        app.state.config.update(|config| config.dedup.exact = false);
+        let (status, body) = app.send(add("Great battery")).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert!(body["message"].as_str().unwrap().contains("near-duplicate"), "{}", body);
+        assert_eq!(app.state.collections.get("acme").unwrap().index.read().await.vector_count(), 1);
+    }
+}
@@ -1,8 +1,24 @@
 use crate::api::models::AppState;
-use crate::api::review::handlers::add_review_handler;
-use axum::{routing::post, Router};
+use crate::api::review::handlers::{
+    add_review_handler, delete_by_filter_handler, delete_review_handler, delete_reviews_handler,
+    get_review_handler, get_vector_handler, scroll_handler, update_review_handler,
+};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/reviews", post(add_review_handler))
+        .route("/reviews/delete", post(delete_reviews_handler))
+        .route("/reviews/delete_by_filter", post(delete_by_filter_handler))
+        .route("/reviews/scroll", post(scroll_handler))
+        .route(
+            "/reviews/{id}",
+            get(get_review_handler)
+                .put(update_review_handler)
+                .delete(delete_review_handler),
+        )
+        .route("/vectors/{id}", get(get_vector_handler))
 }
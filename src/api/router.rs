@@ -0,0 +1,161 @@
+use crate::api::middleware::{log_bodies, require_ready, with_request_id, with_timeout};
+use crate::api::idempotency::idempotent;
+use crate::api::models::AppState;
+use crate::api::tenancy::resolve_tenant;
+use crate::api::{admin, answer, collections, coordinator, health, jobs, qdrant, replication, review, search, ws};
+use crate::config::ReplicationRole;
+use axum::{extract::DefaultBodyLimit, http::Method, middleware, Router};
+use std::time::Duration;
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::trace::TraceLayer;
+
+/// The public HTTP API, ready to serve or nest in a larger app
+///
+/// Limits, timeouts and optional route sets come from the state's config.
+/// Admin routes are included unless `server.admin_addr` moves them to
+/// their own listener (see [`build_admin_router`]).
+pub fn build_router(state: AppState) -> Router {
+    let config = state.config.current();
+
+    let mut data_routes = Router::new()
+        .merge(with_timeout(
+            review::routes()
+                .route_layer(middleware::from_fn_with_state(state.clone(), idempotent))
+                .layer(DefaultBodyLimit::max(config.limits.review_body_bytes)),
+            Duration::from_millis(config.timeouts.ingest_ms),
+        ))
+        .merge(with_timeout(
+            collections::routes().layer(DefaultBodyLimit::max(config.limits.review_body_bytes)),
+            Duration::from_millis(config.timeouts.ingest_ms),
+        ))
+        .merge(with_timeout(
+            jobs::routes().layer(DefaultBodyLimit::max(config.limits.import_body_bytes)),
+            Duration::from_millis(config.timeouts.import_ms),
+        ))
+        // Long-lived; each message gets the search timeout instead
+        .merge(ws::routes());
+    // Under its own prefix: its `/collections` paths clash with ours
+    if config.qdrant.enabled {
+        data_routes = data_routes.nest("/qdrant", with_timeout(
+            qdrant::routes().layer(DefaultBodyLimit::max(config.limits.import_body_bytes)),
+            Duration::from_millis(config.timeouts.ingest_ms),
+        ));
+    }
+    // A coordinator answers searches from its shards, so they don't wait on
+    // the local model and index
+    let search_routes = if config.coordinator.shards.is_empty() {
+        search::routes().route_layer(middleware::from_fn_with_state(state.clone(), require_ready))
+    } else {
+        coordinator::routes()
+    };
+    // Bounded by the LLM call rather than the search
+    if config.rag.enabled {
+        data_routes = data_routes.merge(with_timeout(
+            answer::routes().layer(DefaultBodyLimit::max(config.limits.search_body_bytes)),
+            Duration::from_millis(config.rag.llm.timeout_ms + config.timeouts.search_ms),
+        ));
+    }
+    let data_routes =
+        data_routes.route_layer(middleware::from_fn_with_state(state.clone(), require_ready));
+
+    let mut routes = Router::new()
+        .merge(with_timeout(
+            search_routes.layer(DefaultBodyLimit::max(config.limits.search_body_bytes)),
+            Duration::from_millis(config.timeouts.search_ms),
+        ))
+        .merge(data_routes);
+    if config.server.admin_addr.is_none() {
+        routes = routes.merge(admin::routes());
+    }
+    let mut routes = routes
+        .route_layer(middleware::from_fn_with_state(state.clone(), resolve_tenant))
+        .merge(health::routes());
+    // Snapshots can take a while to stream; no timeout. Followers use the
+    // replication token instead of a tenant key.
+    if config.replication.role == ReplicationRole::Primary {
+        routes = routes.merge(
+            replication::routes(state.clone())
+                .route_layer(middleware::from_fn_with_state(state.clone(), require_ready)),
+        );
+    }
+
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+        .allow_headers(Any);
+
+    // Inside compression, so bodies are logged as the handlers wrote them
+    let routes = routes.layer(middleware::from_fn_with_state(state.clone(), log_bodies));
+
+    // gzip/br/zstd, negotiated via Accept-Encoding; search results compress
+    // well. Snapshot archives are already gzipped and must keep byte ranges.
+    let compress = DefaultPredicate::new().and(NotForContentType::const_new("application/gzip"));
+    with_request_id(routes.with_state(state))
+        .layer(CompressionLayer::new().compress_when(compress))
+        .layer(TraceLayer::new_for_http())
+        .layer(cors)
+}
+
+/// Health and admin routes for the separate admin listener
+pub fn build_admin_router(state: AppState) -> Router {
+    let routes = Router::new()
+        .merge(health::routes())
+        .merge(admin::routes());
+
+    with_request_id(routes.with_state(state)).layer(TraceLayer::new_for_http())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{review_request, TestApp};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+
+    #[tokio::test]
+    async fn test_qdrant_routes_beside_collections() {
+        let app = TestApp::builder()
+            .config(|config| config.qdrant.enabled = true)
+            .build()
+            .unwrap();
+
+        let (status, body) = app.get("/qdrant/collections").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["result"]["collections"][0]["name"], "reviews");
+        let (status, body) = app.get("/collections").await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_body_limits_answer_json() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.limits.review_body_bytes = 16;
+                config.limits.import_body_bytes = 16;
+            })
+            .build()
+            .unwrap();
+
+        let (status, body) = app.post("/reviews", &review_request("Great battery life")).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{}", body);
+        assert_eq!(body["error"], "413 Payload Too Large");
+
+        let import = Request::post("/jobs/import").body(Body::from("x".repeat(64))).unwrap();
+        let (status, body) = app.send(import).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE, "{}", body);
+        assert_eq!(body["error"], "413 Payload Too Large");
+    }
+
+    #[tokio::test]
+    async fn test_timeout_is_gateway_timeout() {
+        let app = TestApp::builder()
+            .config(|config| config.timeouts.ingest_ms = 0)
+            .build()
+            .unwrap();
+
+        let (status, body) = app.post("/reviews", &review_request("Great battery life")).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT, "{}", body);
+        assert_eq!(body["message"], "Request timed out");
+    }
+}
@@ -0,0 +1,61 @@
+//! Turning raw index distances into 0–1 similarity scores
+
+use crate::config::ScoreNormalization;
+use crate::storage::DistanceMetric;
+
+/// Score of a single hit, independent of the rest of its result set
+///
+/// Cosine distances are `1 - cos`, so they pass through (clamped, since
+/// opposite vectors are further than 1); L2 distances are unbounded and
+/// map to `1 / (1 + d)`.
+pub fn similarity(metric: DistanceMetric, distance: f32) -> f32 {
+    match metric {
+        DistanceMetric::Cosine => (1.0 - distance).clamp(0.0, 1.0),
+        DistanceMetric::L2 => 1.0 / (1.0 + distance.max(0.0)),
+    }
+}
+
+/// Scores for a result set, ordered like `distances`
+pub fn similarity_scores(
+    metric: DistanceMetric,
+    normalization: ScoreNormalization,
+    distances: &[f32],
+) -> Vec<f32> {
+    if metric == DistanceMetric::Cosine || normalization == ScoreNormalization::Reciprocal {
+        return distances.iter().map(|&d| similarity(metric, d)).collect();
+    }
+
+    let min = distances.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = distances.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+    distances
+        .iter()
+        .map(|&d| if range > 0.0 { 1.0 - (d - min) / range } else { 1.0 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_stay_in_unit_range() {
+        let distances = [0.0, 0.5, 3.0, 12.0];
+
+        let reciprocal =
+            similarity_scores(DistanceMetric::L2, ScoreNormalization::Reciprocal, &distances);
+        assert_eq!(reciprocal[0], 1.0);
+        assert_eq!(reciprocal[2], 0.25);
+        assert!(reciprocal.windows(2).all(|w| w[0] > w[1]));
+
+        let min_max = similarity_scores(DistanceMetric::L2, ScoreNormalization::MinMax, &distances);
+        assert_eq!(min_max, vec![1.0, 1.0 - 0.5 / 12.0, 0.75, 0.0]);
+        assert_eq!(
+            similarity_scores(DistanceMetric::L2, ScoreNormalization::MinMax, &[4.0]),
+            vec![1.0]
+        );
+
+        assert_eq!(similarity(DistanceMetric::Cosine, 0.25), 0.75);
+        assert_eq!(similarity(DistanceMetric::Cosine, 1.5), 0.0);
+    }
+}
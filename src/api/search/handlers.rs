@@ -1,29 +1,247 @@
+use crate::api::collections::handlers::{find, search_collection};
+use crate::api::experiment::{assign, experiment_unit, record};
+use crate::api::extract::ApiJson;
 use crate::api::models::*;
-use axum::{extract::State, Json};
-use tracing::info;
+use crate::api::scoring::{similarity, similarity_scores};
+use crate::api::search_cache::SearchCache;
+use crate::api::tenancy::Namespace;
+use crate::api::timing::{record_if_slow, PhaseTimer};
+use crate::api::usage::count_tokens;
+use crate::config::{AttributeSpec, ExperimentConfig, QueryExpansion, ScoreNormalization, SearchConfig, TextNormalization};
+use crate::embedding::{normalize_text, EmbeddingService};
+use crate::language;
+use crate::spelling::SpellChecker;
+use crate::synonyms::MAX_QUERY_VARIANTS;
+use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use crate::storage::{DistanceMetric, ReviewMetadata, SearchResult, VectorIndex};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Extension, Json,
+};
+use std::cmp::Ordering;
+use std::time::Instant;
+use tracing::{info, warn};
 
 pub async fn search_handler(
     State(state): State<AppState>,
-    Json(request): Json<SearchRequest>,
+    namespace: Option<Extension<Namespace>>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<SearchRequest>,
 ) -> Result<Json<SearchResponse>, AppError> {
+    let started = Instant::now();
+    let config = state.config.current();
+    let namespace = namespace.map(|Extension(Namespace(namespace))| namespace);
+    // Searches across collections stay out of experiments, which compare
+    // the default collection with a candidate
+    let arm = match namespace {
+        None if request.collections.is_empty() => {
+            assign(&config.experiment, &experiment_unit(&headers))
+        }
+        _ => None,
+    };
+
+    // Experiment searches aren't cached, so each one is logged with its arm
+    let cache = (config.search.cache.enabled && arm.is_none()).then(|| {
+        let key = SearchCache::key(namespace.as_deref(), &request, &config);
+        (key, state.search_cache.version(&state.write_gate, config.clone()))
+    });
+    if let Some(response) = cache
+        .as_ref()
+        .and_then(|(key, version)| state.search_cache.get(key, version))
+    {
+        let response = SearchResponse {
+            query: request.query,
+            ..response
+        };
+        state
+            .analytics
+            .record(&config.analytics, &response, started.elapsed());
+        return Ok(Json(response));
+    }
+
+    let response = if let Some(namespace) = namespace {
+        search_namespace(&state, &namespace, request).await?
+    } else {
+        match arm {
+            Some(arm) => search_experiment(&state, &config.experiment, arm, request).await?,
+            None => search(&state, request).await?,
+        }
+    };
+    if let Some((key, version)) = cache {
+        state.search_cache.insert(key, version, &response);
+    }
+
+    state
+        .analytics
+        .record(&config.analytics, &response, started.elapsed());
+    Ok(Json(response))
+}
+
+/// Search a tenant's collection, charging the query to its quota
+///
+/// Shared by the REST and gRPC front ends.
+pub async fn search_namespace(
+    state: &AppState,
+    namespace: &str,
+    request: SearchRequest,
+) -> Result<SearchResponse, AppError> {
+    let config = state.config.current();
+    state.usage.charge_tokens(
+        namespace,
+        count_tokens(&request.query),
+        config.tenancy.quota(namespace).max_embedding_tokens,
+    )?;
+    match state.collections.get(namespace) {
+        Some(collection) => search_collection(state, &collection, request).await,
+        // Nothing added to the namespace yet
+        None => {
+            request
+                .validate(config.search.max_top_k)
+                .map_err(AppError::BadRequest)?;
+            Ok(SearchResponse {
+                results: Vec::new(),
+                total_found: 0,
+                query: request.query,
+                corrected_query: None,
+                experiment: None,
+                aggregations: None,
+            })
+        }
+    }
+}
+
+/// Serve a search from its experiment arm and log the assignment
+///
+/// Candidate searches fall back to control while the candidate collection
+/// doesn't exist.
+async fn search_experiment(
+    state: &AppState,
+    settings: &ExperimentConfig,
+    arm: ExperimentArm,
+    request: SearchRequest,
+) -> Result<SearchResponse, AppError> {
+    let started = Instant::now();
+    let candidate = match arm {
+        ExperimentArm::Candidate => {
+            let collection = state.collections.get(&settings.candidate_collection);
+            if collection.is_none() {
+                warn!(
+                    collection = %settings.candidate_collection,
+                    "Experiment candidate collection not found; serving control"
+                );
+            }
+            collection
+        }
+        ExperimentArm::Control => None,
+    };
+
+    let (mut response, arm, model) = match candidate {
+        Some(collection) => (
+            search_collection(state, &collection, request).await?,
+            ExperimentArm::Candidate,
+            collection.manifest().model.clone(),
+        ),
+        None => (
+            search(state, request).await?,
+            ExperimentArm::Control,
+            state.config.current().embedding.model_name.clone(),
+        ),
+    };
+    response.experiment = Some(ExperimentAssignment {
+        experiment: settings.name.clone(),
+        arm,
+        model,
+    });
+    record(settings, &response, started.elapsed().as_secs_f64() * 1000.0);
+    Ok(response)
+}
+
+/// Embed the query, search the index and attach metadata
+///
+/// Shared by the REST and gRPC front ends.
+pub async fn search(state: &AppState, mut request: SearchRequest) -> Result<SearchResponse, AppError> {
+    let mut timer = PhaseTimer::start();
+    let config = state.config.current();
+    if let Some(filter) = &mut request.filter {
+        language::normalize_filter(filter);
+    }
+
     // Validate
-    request.validate().map_err(AppError::BadRequest)?;
+    request.validate(config.search.max_top_k).map_err(AppError::BadRequest)?;
+    if let Some(filter) = &request.filter {
+        filter
+            .check_types(&config.validation.attributes)
+            .map_err(AppError::BadRequest)?;
+    }
+    timer.mark("validate");
+
+    if !request.collections.is_empty() {
+        return search_federated(state, request).await;
+    }
+
+    if request.cross_lingual {
+        require_multilingual(&config.embedding.model_name)?;
+        let one_language = request.filter.as_ref().is_some_and(|filter| filter.language.is_some());
+        if !one_language && !config.language.collections.is_empty() {
+            return search_languages(state, request).await;
+        }
+    }
+
+    // Searches for one language go to that language's collection, once it
+    // exists; until then the filter finds the language's reviews, if any,
+    // in the default collection
+    if let Some(collection) = request
+        .filter
+        .as_ref()
+        .and_then(|filter| filter.language.as_deref())
+        .and_then(|language| config.language.collection_for(language))
+        .and_then(|name| state.collections.get(name))
+    {
+        return search_collection(state, &collection, request).await;
+    }
+
+    if request.image.is_some() {
+        return Err(AppError::BadRequest(
+            "Image queries need a collection created with a CLIP model".to_string(),
+        ));
+    }
 
     info!(query = %request.query, k = request.top_k, "Searching");
 
-    // Embed query
-    let embedding = state
-        .embedding_service
-        .embed(&request.query)
-        .map_err(|e| AppError::Internal(format!("Embedding failed: {}", e)))?;
+    let metadata = state.metadata_store.clone();
+    let corrected_query = correct_query(
+        &state.spelling,
+        move || metadata.read_all(),
+        &config.search,
+        &request.query,
+    );
+    timer.mark("spell_correct");
+
+    // Embed query (CPU-bound, keep it off the async workers)
+    let texts = query_texts(
+        state,
+        &config.search,
+        &config.embedding.normalization,
+        corrected_query.as_deref().unwrap_or(&request.query),
+    );
+    let embedder = state.embedder()?;
+    let embeddings = embed_queries(embedder.clone(), texts).await?;
+    timer.mark("embed");
 
     // Search
-    let search_results = state
-        .vector_index
-        .read()
-        .await
-        .search(&embedding, request.top_k)
-        .map_err(|e| AppError::Internal(format!("Search failed: {}", e)))?;
+    let k = candidate_count(&request, &config.search);
+    let field_results = if config.search.multi_vector.enabled {
+        search_fields(state, embedder, embeddings.clone(), k).await?
+    } else {
+        None
+    };
+    let search_results = match field_results {
+        Some(results) => results,
+        None => search_fused(&*state.vector_index.read().await, &embeddings, k)?,
+    };
+    timer.mark("ann_search");
 
     info!(found = search_results.len(), "Search complete");
 
@@ -33,26 +251,669 @@ pub async fn search_handler(
         .metadata_store
         .read_batch(&vector_ids)
         .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+    timer.mark("metadata");
 
-    // Combine results
-    let results: Vec<SearchResultItem> = search_results
-        .iter()
-        .zip(metadata_list.iter())
-        .map(|(sr, meta)| SearchResultItem {
-            review_title: meta.review_title.clone(),
-            review_body: meta.review_body.clone(),
-            product_id: meta.product_id.clone(),
-            review_rating: meta.review_rating,
-            similarity_score: 1.0 - sr.distance,
-            vector_id: sr.vector_id,
-        })
-        .collect();
+    let (mut results, aggregations) = assemble_results(
+        &search_results,
+        metadata_list,
+        &request,
+        config.index.metric,
+        config.search.score_normalization,
+        &config.validation.attributes,
+    );
+    if request.include_vectors {
+        attach_vectors(&*state.vector_index.read().await, &mut results);
+    }
 
     let total = results.len();
+    timer.mark("assemble");
+
+    state.latency.observe("search", &timer);
+    record_if_slow(
+        &config.slow_log,
+        "search",
+        &timer,
+        serde_json::json!({ "query": request.query, "top_k": request.top_k, "results": total }),
+    );
 
-    Ok(Json(SearchResponse {
+    Ok(SearchResponse {
         query: request.query,
         results,
         total_found: total,
+        corrected_query,
+        experiment: None,
+        aggregations,
+    })
+}
+
+/// A `cross_lingual` search: the default collection and every language's
+/// collection, their hits merged by distance
+///
+/// Vector IDs are per collection; each hit's `collection` tells where it
+/// came from (none for the default collection).
+async fn search_languages(state: &AppState, request: SearchRequest) -> Result<SearchResponse, AppError> {
+    let config = state.config.current();
+    // Distances are only comparable under one metric
+    let per_collection = SearchRequest {
+        cross_lingual: false,
+        include_distance: true,
+        ..request.clone()
+    };
+
+    let mut response = Box::pin(search(state, per_collection.clone())).await?;
+    let names: BTreeSet<&String> = config.language.collections.values().collect();
+    for name in names {
+        // Created on the first review in its language
+        let Some(collection) = state.collections.get(name) else {
+            continue;
+        };
+        let manifest = collection.manifest();
+        require_multilingual(&manifest.model)?;
+        if manifest.metric != config.index.metric {
+            return Err(AppError::BadRequest(format!(
+                "cross_lingual needs one metric; collection {} uses {:?}, the default {:?}",
+                name, manifest.metric, config.index.metric
+            )));
+        }
+
+        let other = search_collection(state, &collection, per_collection.clone()).await?;
+        response.results.extend(other.results.into_iter().map(|item| SearchResultItem {
+            collection: Some(name.clone()),
+            ..item
+        }));
+        if let Some(aggregations) = other.aggregations {
+            response.aggregations.get_or_insert_default().merge(aggregations);
+        }
+    }
+
+    response
+        .results
+        .sort_by(|a, b| a.distance.unwrap_or(f32::INFINITY).total_cmp(&b.distance.unwrap_or(f32::INFINITY)));
+    response.results.truncate(request.top_k);
+    sort_results(&mut response.results, &request.sort);
+    if !request.include_distance {
+        for item in &mut response.results {
+            item.distance = None;
+            item.metric = None;
+        }
+    }
+    response.total_found = response.results.len();
+    Ok(response)
+}
+
+/// A search of `collections`: each is searched concurrently with its own
+/// model, and their hits merged by similarity
+///
+/// Each hit is rescored from its raw distance with its collection's metric
+/// ([`similarity`]), so every collection lands on the same 0-1 scale
+/// whatever `search.score_normalization` says; per-result-set min-max
+/// scaling would lift a collection's best hit to 1 however weak it is.
+/// Vector IDs are per collection; each hit's `collection` tells where it
+/// came from.
+async fn search_federated(state: &AppState, request: SearchRequest) -> Result<SearchResponse, AppError> {
+    let per_collection = SearchRequest {
+        collections: Vec::new(),
+        include_distance: true,
+        ..request.clone()
+    };
+
+    let mut searches = tokio::task::JoinSet::new();
+    for (position, name) in request.collections.iter().enumerate() {
+        let collection = find(state, name)?;
+        let state = state.clone();
+        let request = per_collection.clone();
+        searches.spawn(async move {
+            let response = search_collection(&state, &collection, request).await;
+            (position, collection.name().to_string(), response)
+        });
+    }
+    let mut responses = Vec::new();
+    while let Some(joined) = searches.join_next().await {
+        let (position, name, response) =
+            joined.map_err(|e| AppError::Internal(format!("Search task failed: {}", e)))?;
+        responses.push((position, name, response?));
+    }
+    // Ties keep the order the collections were listed in
+    responses.sort_by_key(|(position, _, _)| *position);
+
+    let mut merged = SearchResponse {
+        results: Vec::new(),
+        total_found: 0,
+        query: request.query,
+        corrected_query: None,
+        experiment: None,
+        aggregations: None,
+    };
+    for (_, name, response) in responses {
+        merged.results.extend(response.results.into_iter().map(|item| SearchResultItem {
+            similarity_score: match (item.metric, item.distance) {
+                (Some(metric), Some(distance)) => similarity(metric, distance),
+                _ => item.similarity_score,
+            },
+            distance: item.distance.filter(|_| request.include_distance),
+            metric: item.metric.filter(|_| request.include_distance),
+            collection: Some(name.clone()),
+            ..item
+        }));
+        merged.corrected_query = merged.corrected_query.or(response.corrected_query);
+        if let Some(aggregations) = response.aggregations {
+            merged.aggregations.get_or_insert_default().merge(aggregations);
+        }
+    }
+
+    merged
+        .results
+        .sort_by(|a, b| b.similarity_score.total_cmp(&a.similarity_score));
+    merged.results.truncate(request.top_k);
+    sort_results(&mut merged.results, &request.sort);
+    merged.total_found = merged.results.len();
+    Ok(merged)
+}
+
+/// 400 unless `model_name` is multilingual, for `cross_lingual` searches
+fn require_multilingual(model_name: &str) -> Result<(), AppError> {
+    if EmbeddingService::is_multilingual(model_name) {
+        return Ok(());
+    }
+    Err(AppError::BadRequest(format!(
+        "cross_lingual needs a multilingual embedding model (e.g. paraphrase-multilingual-MiniLM-L12-v2); {} is not",
+        model_name
+    )))
+}
+
+/// Most completions one `/suggest` call may ask for
+const MAX_SUGGESTIONS: usize = 100;
+
+/// Autocomplete a search-box prefix from the default collection's titles
+/// and product IDs
+pub async fn suggest_handler(
+    State(state): State<AppState>,
+    Query(query): Query<SuggestQuery>,
+) -> Result<Json<SuggestResponse>, AppError> {
+    if query.q.trim().is_empty() {
+        return Err(AppError::BadRequest("q cannot be empty".to_string()));
+    }
+    if query.limit == 0 || query.limit > MAX_SUGGESTIONS {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_SUGGESTIONS
+        )));
+    }
+
+    let suggestions = state
+        .suggester
+        .suggest(&query.q, query.limit, || state.metadata_store.read_all())
+        .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+    Ok(Json(SuggestResponse {
+        query: query.q,
+        suggestions,
     }))
 }
+
+/// `query` with misspelled terms corrected against a store's vocabulary,
+/// per `search.spell_correction`
+///
+/// A missing or stale vocabulary is rebuilt from `load` in the background;
+/// until the first build finishes, queries go uncorrected.
+pub(crate) fn correct_query<F>(
+    spelling: &Arc<SpellChecker>,
+    load: F,
+    settings: &SearchConfig,
+    query: &str,
+) -> Option<String>
+where
+    F: FnOnce() -> anyhow::Result<Vec<ReviewMetadata>> + Send + 'static,
+{
+    if !settings.spell_correction.enabled {
+        return None;
+    }
+    spelling.refresh(load);
+    let corrected = spelling.correct(query, &settings.spell_correction);
+    if let Some(corrected) = &corrected {
+        info!(query, corrected = %corrected, "Query spell-corrected");
+    }
+    corrected
+}
+
+/// Texts to embed for `query` per `search.query_expansion`, normalized like
+/// the reviews they are compared with
+pub(crate) fn query_texts(
+    state: &AppState,
+    settings: &SearchConfig,
+    normalization: &TextNormalization,
+    query: &str,
+) -> Vec<String> {
+    let mut texts = vec![query.to_string()];
+    match settings.query_expansion {
+        QueryExpansion::Off => {}
+        QueryExpansion::Append => {
+            if let Some(expanded) = state.synonyms.append(query) {
+                texts = vec![expanded];
+            }
+        }
+        QueryExpansion::Fusion => {
+            texts.extend(state.synonyms.variants(query, MAX_QUERY_VARIANTS - 1));
+        }
+    }
+    texts
+        .iter()
+        .map(|text| normalize_text(text, normalization))
+        .collect()
+}
+
+/// Embed query texts in one batch (CPU-bound, keep it off the async workers)
+pub(crate) async fn embed_queries(
+    embedding_service: Arc<EmbeddingService>,
+    texts: Vec<String>,
+) -> Result<Vec<Vec<f32>>, AppError> {
+    tokio::task::spawn_blocking(move || {
+        embedding_service.embed_batch(texts.iter().map(String::as_str).collect())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Embedding task failed: {}", e)))?
+    .map_err(|e| AppError::Internal(format!("Embedding failed: {}", e)))
+}
+
+/// Search once per embedding, keeping each vector's best distance
+pub(crate) fn search_fused(
+    index: &VectorIndex,
+    embeddings: &[Vec<f32>],
+    k: usize,
+) -> Result<Vec<SearchResult>, AppError> {
+    fuse_queries(embeddings, k, |embedding, k| index.search(embedding, k))
+}
+
+/// Multi-vector search of the default collection, per
+/// `search.multi_vector`: search the title and body vectors once per
+/// embedding; `None` until they are built for the current model
+async fn search_fields(
+    state: &AppState,
+    embedder: Arc<EmbeddingService>,
+    embeddings: Vec<Vec<f32>>,
+    k: usize,
+) -> Result<Option<Vec<SearchResult>>, AppError> {
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let config = state.config.current();
+        let (normalization, metric) = (&config.embedding.normalization, config.index.metric);
+        if !state.field_vectors.is_ready(&embedder, normalization, metric) {
+            return Ok(None);
+        }
+        fuse_queries(&embeddings, k, |embedding, k| {
+            let results = state.field_vectors.search(embedding, k, &config.search.multi_vector, &embedder, normalization, metric)?;
+            Ok(results.unwrap_or_default())
+        })
+        .map(Some)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Search task failed: {}", e)))?
+}
+
+fn fuse_queries(
+    embeddings: &[Vec<f32>],
+    k: usize,
+    search: impl Fn(&[f32], usize) -> anyhow::Result<Vec<SearchResult>>,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut best: BTreeMap<usize, f32> = BTreeMap::new();
+    for embedding in embeddings {
+        let hits = search(embedding, k).map_err(|e| AppError::Internal(format!("Search failed: {}", e)))?;
+        if embeddings.len() == 1 {
+            return Ok(hits);
+        }
+        for hit in hits {
+            best.entry(hit.vector_id)
+                .and_modify(|d| *d = d.min(hit.distance))
+                .or_insert(hit.distance);
+        }
+    }
+
+    let mut fused: Vec<SearchResult> = best
+        .into_iter()
+        .map(|(vector_id, distance)| SearchResult { vector_id, distance })
+        .collect();
+    fused.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    fused.truncate(k);
+    Ok(fused)
+}
+
+/// How many ANN candidates to fetch; filtered searches oversample so
+/// enough survive the filter, and aggregated ones so the aggregates see
+/// more than one page
+pub(crate) fn candidate_count(request: &SearchRequest, settings: &SearchConfig) -> usize {
+    let filtered = match &request.filter {
+        Some(filter) if !filter.is_empty() => settings.filter_oversample.max(1),
+        _ => 1,
+    };
+    let aggregated = if request.aggregate { settings.aggregate_oversample.max(1) } else { 1 };
+    request.top_k * filtered * aggregated
+}
+
+/// Fill in each result's stored embedding, for `include_vectors`
+///
+/// A vector that can't be read is left out rather than failing the search.
+pub(crate) fn attach_vectors(index: &VectorIndex, results: &mut [SearchResultItem]) {
+    for item in results {
+        match index.get_vector(item.vector_id) {
+            Ok(vector) => item.vector = Some(vector),
+            Err(e) => warn!(vector_id = item.vector_id, "Reading result vector failed: {:#}", e),
+        }
+    }
+}
+
+/// Pair hits with their metadata, drop tombstoned, expired and filtered-out
+/// reviews, keep the best `top_k` and score them
+///
+/// Aggregates, when requested, cover every surviving hit.
+pub(crate) fn assemble_results(
+    hits: &[SearchResult],
+    metadata: Vec<ReviewMetadata>,
+    request: &SearchRequest,
+    metric: DistanceMetric,
+    normalization: ScoreNormalization,
+    schema: &BTreeMap<String, AttributeSpec>,
+) -> (Vec<SearchResultItem>, Option<SearchAggregations>) {
+    let now = now_ms();
+    let mut kept: Vec<(&SearchResult, ReviewMetadata)> = hits
+        .iter()
+        .zip(metadata)
+        .filter(|(_, meta)| meta.is_live(now))
+        .filter(|(_, meta)| request.filter.as_ref().is_none_or(|f| f.matches(meta, schema)))
+        .collect();
+    let aggregations = request.aggregate.then(|| {
+        kept.iter()
+            .map(|(_, meta)| (meta.review_rating, meta.product_id.as_str()))
+            .collect()
+    });
+    kept.truncate(request.top_k);
+
+    // Scored after filtering so min-max spans the returned results
+    let distances: Vec<f32> = kept.iter().map(|(hit, _)| hit.distance).collect();
+    let scores = similarity_scores(metric, normalization, &distances);
+    let mut results: Vec<SearchResultItem> = kept
+        .into_iter()
+        .zip(scores)
+        .map(|((hit, meta), score)| SearchResultItem {
+            review_title: meta.review_title,
+            review_body: meta.review_body,
+            product_id: meta.product_id,
+            review_rating: meta.review_rating,
+            similarity_score: score,
+            vector_id: hit.vector_id,
+            shard: None,
+            collection: None,
+            language: meta.language,
+            created_at: meta.created_at,
+            attributes: meta.attributes,
+            vector: None,
+            distance: request.include_distance.then_some(hit.distance),
+            metric: request.include_distance.then_some(metric),
+        })
+        .collect();
+    sort_results(&mut results, &request.sort);
+    (results, aggregations)
+}
+
+/// Reorder `results` by `sort`, applied once the returned page is known
+///
+/// Stable, so full ties keep their similarity order.
+pub(crate) fn sort_results(results: &mut [SearchResultItem], sort: &[SortKey]) {
+    if sort.is_empty() {
+        return;
+    }
+    results.sort_by(|a, b| {
+        sort.iter()
+            .map(|key| compare_by(key, a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Order of two results under one sort key
+fn compare_by(key: &SortKey, a: &SearchResultItem, b: &SearchResultItem) -> Ordering {
+    let ordering = match key.field {
+        SortField::Rating => a.review_rating.cmp(&b.review_rating),
+        SortField::Similarity => a.similarity_score.total_cmp(&b.similarity_score),
+        SortField::CreatedAt => match (a.created_at, b.created_at) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // Unknown dates last, whatever the order
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    };
+    match key.order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{review_request, TestApp};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_cross_lingual_search() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.embedding.model_name = "paraphrase-multilingual-MiniLM-L12-v2".to_string();
+                config.language.collections.insert("deu".to_string(), "reviews-de".to_string());
+            })
+            .build()
+            .unwrap();
+        for (title, language, product, rating) in [("Battery great", "eng", "P1", 5), ("Battery gut", "deu", "P2", 2)] {
+            let review = json!({
+                "review_title": title,
+                "review_body": "Bought it last week",
+                "product_id": product,
+                "review_rating": rating,
+                "language": language,
+            });
+            let (status, body) = app.post("/reviews", &review).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let (_, body) = app.post("/reviews/search", &json!({ "query": "battery" })).await;
+        assert_eq!(body["total_found"], 1);
+
+        let (status, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "cross_lingual": true }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let mut languages: Vec<&str> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["language"].as_str().unwrap())
+            .collect();
+        languages.sort();
+        assert_eq!(languages, ["deu", "eng"]);
+        assert!(body["results"][0].get("distance").is_none());
+
+        // Sorted after merging; hits outside the default collection say
+        // where they came from
+        for (order, first) in [("asc", "deu"), ("desc", "eng")] {
+            let request = json!({
+                "query": "battery",
+                "cross_lingual": true,
+                "sort": [{ "field": "rating", "order": order }],
+            });
+            let (status, body) = app.post("/reviews/search", &request).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+            assert_eq!(body["results"][0]["language"], first, "{}", order);
+            for hit in body["results"].as_array().unwrap() {
+                let collection = if hit["language"] == "deu" { json!("reviews-de") } else { json!(null) };
+                assert_eq!(hit["collection"], collection);
+            }
+        }
+
+        // English-only models can't compare across languages
+        app.state.config.update(|config| config.embedding.model_name = "all-MiniLM-L6-v2".to_string());
+        let (status, _) = app
+            .post("/reviews/search", &json!({ "query": "battery", "cross_lingual": true }))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_language_routing() {
+        let app = TestApp::builder().build().unwrap();
+        for (title, language) in [("Battery great", "en"), ("Battery gut", "de-DE")] {
+            let mut review = review_request(title);
+            review["language"] = json!(language);
+            let (status, body) = app.post("/reviews", &review).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let (_, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "filter": { "language": "ENG" } }))
+            .await;
+        assert_eq!(body["total_found"], 1);
+        assert_eq!(body["results"][0]["language"], "eng");
+
+        // Routed to a collection no review has created yet: the German
+        // review added before the routing is still found
+        app.state.config.update(|config| {
+            config.language.collections.insert("deu".to_string(), "reviews-de".to_string());
+        });
+        let (status, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "filter": { "language": "de" } }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["total_found"], 1);
+        assert_eq!(body["results"][0]["language"], "deu");
+        assert!(app.state.collections.get("reviews-de").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_oversamples() {
+        let app = TestApp::builder().build().unwrap();
+        for n in 0..12 {
+            let (status, body) = app.post("/reviews", &review_request(&format!("Battery {}", n))).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let (status, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "top_k": 2, "aggregate": true }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+        assert_eq!(body["aggregations"]["count"], 12);
+        assert_eq!(body["aggregations"]["product_counts"]["P1"], 12);
+    }
+
+    #[tokio::test]
+    async fn test_federated_search() {
+        let app = TestApp::builder().build().unwrap();
+        for (collection, titles) in [("electronics", ["Battery great", "Screen dim"]), ("books", ["Battery of tests", "Long read"])] {
+            let (status, body) = app.post("/collections", &json!({ "name": collection })).await;
+            assert_eq!(status, StatusCode::CREATED, "{}", body);
+            for title in titles {
+                let review = review_request(title);
+                let (status, body) = app.post(&format!("/collections/{}/reviews", collection), &review).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+            }
+        }
+
+        let request = json!({ "query": "Battery great", "top_k": 4, "collections": ["electronics", "books"] });
+        let (status, body) = app.post("/reviews/search", &request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let hits: Vec<(&str, f64)> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| (hit["collection"].as_str().unwrap(), hit["similarity_score"].as_f64().unwrap()))
+            .collect();
+        assert_eq!(hits.len(), 4);
+        // Scores are compared as they are, so books' best hit isn't raised
+        // to tie with the exact match
+        assert_eq!(hits[0].0, "electronics");
+        assert!(hits.iter().filter(|(collection, _)| *collection == "books").all(|(_, score)| *score < hits[0].1));
+        assert!(body["results"][0].get("distance").is_none(), "{}", body);
+
+        // Per-query min-max scaling doesn't apply across collections
+        app.state
+            .config
+            .update(|c| c.search.score_normalization = crate::config::ScoreNormalization::MinMax);
+        let (status, body) = app.post("/reviews/search", &request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let rescored: Vec<(&str, f64)> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| (hit["collection"].as_str().unwrap(), hit["similarity_score"].as_f64().unwrap()))
+            .collect();
+        assert_eq!(rescored, hits);
+        app.state
+            .config
+            .update(|c| c.search.score_normalization = crate::config::ScoreNormalization::Reciprocal);
+        assert!(hits.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+
+        let request = json!({
+            "query": "Battery great",
+            "top_k": 4,
+            "collections": ["electronics", "books"],
+            "sort": [{ "field": "similarity", "order": "asc" }],
+        });
+        let (status, body) = app.post("/reviews/search", &request).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let sorted: Vec<(&str, f64)> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| (hit["collection"].as_str().unwrap(), hit["similarity_score"].as_f64().unwrap()))
+            .collect();
+        assert_eq!(sorted.last(), Some(&hits[0]));
+        assert!(sorted.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        let request = json!({ "query": "Battery", "collections": ["electronics", "movies"] });
+        let (status, _) = app.post("/reviews/search", &request).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let request = json!({ "query": "Battery", "collections": ["books", "books"] });
+        let (status, _) = app.post("/reviews/search", &request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_sorted_search() {
+        let app = TestApp::builder().build().unwrap();
+        for (title, rating) in [("Battery great", 3), ("Battery fine", 5), ("Battery ok", 4), ("Screen dim", 5)] {
+            let review = json!({
+                "review_title": title,
+                "review_body": "Bought it last week",
+                "product_id": "P1",
+                "review_rating": rating,
+            });
+            let (status, body) = app.post("/reviews", &review).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+        let search = |sort: serde_json::Value| {
+            let app = &app;
+            async move {
+                let request = json!({ "query": "Battery great", "top_k": 4, "sort": sort });
+                let (status, body) = app.post("/reviews/search", &request).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+                body["results"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|hit| (hit["review_rating"].as_u64().unwrap(), hit["similarity_score"].as_f64().unwrap()))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let by_rating = search(json!([{ "field": "rating" }, { "field": "similarity" }])).await;
+        let ratings: Vec<u64> = by_rating.iter().map(|(rating, _)| *rating).collect();
+        assert_eq!(ratings, [5, 5, 4, 3]);
+        // Ties on rating go to the more similar review
+        assert!(by_rating[0].1 >= by_rating[1].1);
+
+        let ascending = search(json!([{ "field": "rating", "order": "asc" }])).await;
+        let ratings: Vec<u64> = ascending.iter().map(|(rating, _)| *rating).collect();
+        assert_eq!(ratings, [3, 4, 5, 5]);
+
+        let by_similarity = search(json!([])).await;
+        assert!(by_similarity.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+}
@@ -1,8 +1,12 @@
 use crate::api::models::AppState;
-use crate::api::search::handlers::search_handler;
-use axum::{routing::post, Router};
+use crate::api::search::handlers::{search_handler, suggest_handler};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/reviews/search", post(search_handler))
+        .route("/suggest", get(suggest_handler))
 }
@@ -0,0 +1,181 @@
+//! Search response cache (`search.cache`): a search repeating the query,
+//! filters and parameters of a recent one is answered from memory
+//!
+//! A response stays valid for `ttl_secs` or until anything is written, the
+//! config is reloaded or a follower syncs, whichever comes first. The cache
+//! lives in memory and starts empty with the server.
+
+use crate::api::write_gate::WriteGate;
+use crate::config::AppConfig;
+use crate::embedding::normalize_text;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use vector_search_client::models::{SearchRequest, SearchResponse};
+
+/// The state a response was computed from; it is served only while that
+/// state still holds
+#[derive(Debug, Clone)]
+pub struct CacheVersion {
+    writes: u64,
+    epoch: u64,
+    config: Arc<AppConfig>,
+}
+
+impl PartialEq for CacheVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.writes == other.writes && self.epoch == other.epoch && Arc::ptr_eq(&self.config, &other.config)
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    response: SearchResponse,
+    created: Instant,
+    version: CacheVersion,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    /// Bumped by [`invalidate`](Self::invalidate)
+    epoch: AtomicU64,
+}
+
+impl SearchCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current version, to take before searching
+    pub fn version(&self, write_gate: &WriteGate, config: Arc<AppConfig>) -> CacheVersion {
+        CacheVersion {
+            writes: write_gate.version(),
+            epoch: self.epoch.load(Ordering::SeqCst),
+            config,
+        }
+    }
+
+    /// Cache key of `request` in `namespace` (the default collection when
+    /// `None`): the request with its query normalized like the text that
+    /// gets embedded
+    pub fn key(namespace: Option<&str>, request: &SearchRequest, config: &AppConfig) -> String {
+        let request = SearchRequest {
+            query: normalize_text(&request.query, &config.embedding.normalization),
+            ..request.clone()
+        };
+        format!(
+            "{}\n{}",
+            namespace.unwrap_or_default(),
+            serde_json::to_string(&request).unwrap_or_default()
+        )
+    }
+
+    /// The response cached under `key`, if it was computed at `version` and
+    /// hasn't expired
+    pub fn get(&self, key: &str, version: &CacheVersion) -> Option<SearchResponse> {
+        let settings = &version.config.search.cache;
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+        if entry.version != *version || entry.created.elapsed() >= Duration::from_secs(settings.ttl_secs) {
+            entries.remove(key);
+            return None;
+        }
+        Some(entry.response.clone())
+    }
+
+    /// Cache `response`, computed at `version`, dropping the oldest entries
+    /// past `max_entries`
+    pub fn insert(&self, key: String, version: CacheVersion, response: &SearchResponse) {
+        let settings = &version.config.search.cache;
+        let ttl = Duration::from_secs(settings.ttl_secs);
+        let max_entries = settings.max_entries;
+        if max_entries == 0 || ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.created.elapsed() < ttl);
+        while entries.len() >= max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+        entries.insert(
+            key,
+            Entry {
+                response: response.clone(),
+                created: Instant::now(),
+                version,
+            },
+        );
+    }
+
+    /// Drop every cached response, for changes made outside the write gate
+    /// (e.g. a follower swapping in the primary's index)
+    pub fn invalidate(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestApp;
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_cached_until_write() {
+        let app = TestApp::builder()
+            .config(|config| {
+                config.search.cache.enabled = true;
+                config.embedding.normalization.lowercase = true;
+            })
+            .build()
+            .unwrap();
+        let add = |product: &'static str| {
+            let review = json!({
+                "review_title": "Great battery",
+                "review_body": "Lasts two days",
+                "product_id": product,
+                "review_rating": 5,
+            });
+            let app = &app;
+            async move {
+                let (status, body) = app.post("/reviews", &review).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+            }
+        };
+        let state = &app.state;
+        let cached = |query: &str| {
+            let config = state.config.current();
+            let key = SearchCache::key(None, &SearchRequest::new(query), &config);
+            state.search_cache.get(&key, &state.search_cache.version(&state.write_gate, config))
+        };
+
+        add("P1").await;
+        let (_, body) = app.post("/reviews/search", &json!({ "query": "Great battery" })).await;
+        assert_eq!(body["total_found"], 1);
+        // Same query once normalized; answered with the caller's query
+        assert!(cached("GREAT BATTERY").is_some());
+        let (_, body) = app.post("/reviews/search", &json!({ "query": "GREAT BATTERY" })).await;
+        assert_eq!(body["query"], "GREAT BATTERY");
+        assert!(cached("great battery, please").is_none());
+
+        add("P2").await;
+        assert!(cached("Great battery").is_none());
+        let (_, body) = app.post("/reviews/search", &json!({ "query": "Great battery" })).await;
+        assert_eq!(body["total_found"], 2);
+
+        state.search_cache.invalidate();
+        assert!(cached("Great battery").is_none());
+    }
+}
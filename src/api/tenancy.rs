@@ -0,0 +1,132 @@
+//! API-key tenancy: each namespaced key adds to and searches only its own
+//! collection
+
+use crate::api::models::{AppError, AppState};
+use crate::storage::audit::sha256_hex;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+
+/// Namespace the request is confined to, set by [`resolve_tenant`]
+///
+/// The namespace is also the name of the collection holding its reviews.
+#[derive(Debug, Clone)]
+pub struct Namespace(pub String);
+
+/// Header choosing among a key's namespaces
+const NAMESPACE_HEADER: &str = "x-namespace";
+
+/// The raw API key sent as `X-API-Key` or `Authorization: Bearer`
+pub fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        })
+}
+
+/// Stable, non-secret identifier for an API key, used in audit entries
+/// and usage accounts
+pub fn key_fingerprint(key: &str) -> String {
+    format!("key:{}", &sha256_hex(key.as_bytes())[..12])
+}
+
+/// With `tenancy.enabled`, require a configured key and attach the
+/// caller's [`Namespace`]
+///
+/// Every request is counted towards its account's usage. Operator keys (no
+/// namespaces) pass through unrestricted. Namespaced keys may only add and
+/// search reviews, within their `requests_per_minute` quota; `X-Namespace`
+/// picks one when a key has several.
+pub async fn resolve_tenant(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let partitioned = is_partitioned(request.method(), request.uri().path());
+    if let Some(namespace) = authorize(&state, request.headers(), partitioned)? {
+        request.extensions_mut().insert(namespace);
+    }
+    Ok(next.run(request).await)
+}
+
+/// The tenancy checks behind [`resolve_tenant`], for any front end
+///
+/// `partitioned` says whether the call adds or searches reviews, the only
+/// calls namespaced keys may make. Gives the caller's namespace, if any.
+pub fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+    partitioned: bool,
+) -> Result<Option<Namespace>, AppError> {
+    let config = state.config.current();
+    if !config.tenancy.enabled {
+        return Ok(None);
+    }
+
+    let key = presented_key(headers)
+        .ok_or_else(|| AppError::Unauthorized("API key required".to_string()))?;
+    let tenant = config
+        .tenancy
+        .find(key)
+        .ok_or_else(|| AppError::Unauthorized("Unknown API key".to_string()))?;
+    if tenant.namespaces.is_empty() {
+        state.usage.record_request(&key_fingerprint(key), None)?;
+        return Ok(None);
+    }
+
+    let requested = headers.get(NAMESPACE_HEADER).and_then(|v| v.to_str().ok());
+    let namespace = pick_namespace(&tenant.namespaces, requested)?;
+
+    if !partitioned {
+        return Err(AppError::Forbidden(
+            "Namespaced keys can only add and search reviews".to_string(),
+        ));
+    }
+
+    let quota = config.tenancy.quota(&namespace);
+    state.usage.record_request(&namespace, quota.requests_per_minute)?;
+    Ok(Some(Namespace(namespace)))
+}
+
+fn pick_namespace(allowed: &[String], requested: Option<&str>) -> Result<String, AppError> {
+    match requested {
+        Some(name) if allowed.iter().any(|n| n == name) => Ok(name.to_string()),
+        Some(name) => Err(AppError::Forbidden(format!(
+            "API key has no access to namespace {}",
+            name
+        ))),
+        None if allowed.len() == 1 => Ok(allowed[0].clone()),
+        None => Err(AppError::BadRequest(
+            "API key has several namespaces; choose one with X-Namespace".to_string(),
+        )),
+    }
+}
+
+/// Routes that serve a namespace from its collection
+fn is_partitioned(method: &Method, path: &str) -> bool {
+    method == Method::POST && matches!(path, "/reviews" | "/reviews/search")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_namespace() {
+        let one = vec!["acme".to_string()];
+        let two = vec!["acme".to_string(), "globex".to_string()];
+
+        assert_eq!(pick_namespace(&one, None).unwrap(), "acme");
+        assert_eq!(pick_namespace(&two, Some("globex")).unwrap(), "globex");
+        assert!(matches!(pick_namespace(&two, None), Err(AppError::BadRequest(_))));
+        assert!(matches!(pick_namespace(&one, Some("globex")), Err(AppError::Forbidden(_))));
+    }
+}
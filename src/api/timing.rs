@@ -0,0 +1,126 @@
+use crate::api::metrics::Histogram;
+use crate::config::SlowLogConfig;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Records how long each phase of a request took
+pub struct PhaseTimer {
+    started: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl PhaseTimer {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last: now,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Close the current phase under `name`
+    pub fn mark(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.last));
+        self.last = now;
+    }
+
+    /// Total time since the timer started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+}
+
+/// Per-endpoint latency histograms for `/metrics`, one per phase plus the
+/// request as a whole (`total`)
+#[derive(Debug, Default)]
+pub struct LatencyHistograms {
+    histograms: Mutex<BTreeMap<(&'static str, &'static str), Histogram>>,
+}
+
+impl LatencyHistograms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a finished request's phases
+    pub fn observe(&self, endpoint: &'static str, timer: &PhaseTimer) {
+        let mut histograms = self.histograms.lock().unwrap();
+        for &(phase, took) in timer.phases() {
+            histograms.entry((endpoint, phase)).or_default().observe(took.as_secs_f64());
+        }
+        histograms
+            .entry((endpoint, "total"))
+            .or_default()
+            .observe(timer.elapsed().as_secs_f64());
+    }
+
+    /// Every histogram, ordered by endpoint and phase
+    pub fn snapshot(&self) -> Vec<((&'static str, &'static str), Histogram)> {
+        let histograms = self.histograms.lock().unwrap();
+        histograms.iter().map(|(key, histogram)| (*key, histogram.clone())).collect()
+    }
+}
+
+/// One slow-query log entry
+#[derive(Debug, Serialize)]
+struct SlowQueryEntry<'a> {
+    timestamp_ms: u64,
+    endpoint: &'a str,
+    total_ms: f64,
+    phases_ms: BTreeMap<&'static str, f64>,
+    detail: serde_json::Value,
+}
+
+/// Log the request if it exceeded the slow-query threshold
+pub fn record_if_slow(
+    config: &SlowLogConfig,
+    endpoint: &str,
+    timer: &PhaseTimer,
+    detail: serde_json::Value,
+) {
+    let total = timer.elapsed();
+    if config.threshold_ms == 0 || total < Duration::from_millis(config.threshold_ms) {
+        return;
+    }
+
+    let entry = SlowQueryEntry {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        endpoint,
+        total_ms: as_ms(total),
+        phases_ms: timer.phases().iter().map(|(name, d)| (*name, as_ms(*d))).collect(),
+        detail,
+    };
+
+    let line = serde_json::to_string(&entry).unwrap_or_default();
+    warn!(endpoint, total_ms = entry.total_ms, "🐢 Slow request: {}", line);
+
+    if let Some(path) = &config.path {
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            warn!("Failed to write slow-query log {:?}: {}", path, e);
+        }
+    }
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
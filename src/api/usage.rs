@@ -0,0 +1,139 @@
+//! Per-account usage counters and tenant quota enforcement
+//!
+//! Counters live in memory and restart from zero with the server; stored
+//! vectors are always counted from the namespace's collection.
+
+use crate::api::models::AppError;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub requests: u64,
+    pub embedding_tokens: u64,
+}
+
+struct Account {
+    usage: Usage,
+    window_start: Instant,
+    window_requests: u32,
+}
+
+impl Account {
+    fn new(now: Instant) -> Self {
+        Self {
+            usage: Usage::default(),
+            window_start: now,
+            window_requests: 0,
+        }
+    }
+}
+
+/// Usage keyed by namespace (or operator key fingerprint)
+#[derive(Default)]
+pub struct UsageTracker {
+    accounts: Mutex<BTreeMap<String, Account>>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request, or 429 if it exceeds `per_minute` in the current
+    /// one-minute window (rejected requests aren't counted)
+    pub fn record_request(&self, account: &str, per_minute: Option<u32>) -> Result<(), AppError> {
+        let now = Instant::now();
+        let mut accounts = self.accounts.lock().unwrap();
+        let entry = accounts
+            .entry(account.to_string())
+            .or_insert_with(|| Account::new(now));
+
+        if now.duration_since(entry.window_start) >= RATE_WINDOW {
+            entry.window_start = now;
+            entry.window_requests = 0;
+        }
+        if let Some(limit) = per_minute
+            && entry.window_requests >= limit
+        {
+            return Err(AppError::TooManyRequests(format!(
+                "Rate limit of {} requests per minute exceeded for {}",
+                limit, account
+            )));
+        }
+
+        entry.window_requests += 1;
+        entry.usage.requests += 1;
+        Ok(())
+    }
+
+    /// Charge embedding tokens, or 403 if they would exceed `limit`
+    pub fn charge_tokens(&self, account: &str, tokens: u64, limit: Option<u64>) -> Result<(), AppError> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let entry = accounts
+            .entry(account.to_string())
+            .or_insert_with(|| Account::new(Instant::now()));
+
+        if let Some(limit) = limit
+            && entry.usage.embedding_tokens + tokens > limit
+        {
+            return Err(AppError::Forbidden(format!(
+                "Embedding token quota of {} exhausted for {}",
+                limit, account
+            )));
+        }
+
+        entry.usage.embedding_tokens += tokens;
+        Ok(())
+    }
+
+    /// Every account seen so far
+    pub fn snapshot(&self) -> BTreeMap<String, Usage> {
+        self.accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, account)| (name.clone(), account.usage))
+            .collect()
+    }
+}
+
+/// Tokens charged for embedding `text`
+///
+/// The model's tokenizer isn't exposed, so whitespace-separated words stand
+/// in for tokens; subword tokenizers produce somewhat more.
+pub fn count_tokens(text: &str) -> u64 {
+    text.split_whitespace().count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotas() {
+        let tracker = UsageTracker::new();
+
+        assert!(tracker.record_request("acme", Some(2)).is_ok());
+        assert!(tracker.record_request("acme", Some(2)).is_ok());
+        assert!(matches!(
+            tracker.record_request("acme", Some(2)),
+            Err(AppError::TooManyRequests(_))
+        ));
+        assert!(tracker.record_request("globex", Some(2)).is_ok());
+
+        assert!(tracker.charge_tokens("acme", 8, Some(10)).is_ok());
+        assert!(matches!(
+            tracker.charge_tokens("acme", 3, Some(10)),
+            Err(AppError::Forbidden(_))
+        ));
+
+        let usage = tracker.snapshot();
+        assert_eq!(usage["acme"], Usage { requests: 2, embedding_tokens: 8 });
+        assert_eq!(usage["globex"].requests, 1);
+        assert_eq!(count_tokens("  great battery\tlife "), 3);
+    }
+}
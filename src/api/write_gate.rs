@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Tracks in-flight writes and stops admitting new ones during shutdown
+#[derive(Default)]
+pub struct WriteGate {
+    closed: AtomicBool,
+    in_flight: AtomicUsize,
+    /// Bumped when a write starts and when it ends
+    writes: AtomicU64,
+    idle: Notify,
+}
+
+/// Held for the duration of a write; releases its slot on drop
+pub struct WriteGuard {
+    gate: Arc<WriteGate>,
+}
+
+impl WriteGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a write, or `None` if the gate has been closed
+    pub fn enter(self: &Arc<Self>) -> Option<WriteGuard> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        let guard = WriteGuard { gate: self.clone() };
+        if self.closed.load(Ordering::SeqCst) {
+            return None; // guard drop releases the slot
+        }
+        Some(guard)
+    }
+
+    /// Stop admitting new writes
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Number of writes currently in progress
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Changes whenever a write starts or ends, so anything read while it
+    /// stays the same saw no write in between
+    pub fn version(&self) -> u64 {
+        self.writes.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no writes are in progress
+    pub async fn drained(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        self.gate.writes.fetch_add(1, Ordering::SeqCst);
+        if self.gate.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.gate.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_close_rejects_and_drains() {
+        let gate = Arc::new(WriteGate::new());
+        let guard = gate.enter().unwrap();
+        assert_eq!(gate.in_flight(), 1);
+
+        gate.close();
+        assert!(gate.enter().is_none());
+        assert_eq!(gate.in_flight(), 1);
+
+        let waiter = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.drained().await }
+        });
+        drop(guard);
+        waiter.await.unwrap();
+        assert_eq!(gate.in_flight(), 0);
+    }
+}
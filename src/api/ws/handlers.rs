@@ -0,0 +1,76 @@
+use crate::api::models::*;
+use crate::api::search::handlers::search;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Upgrade to a WebSocket that answers one search per text message
+pub async fn ws_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| search_session(state, socket))
+}
+
+async fn search_session(state: AppState, mut socket: WebSocket) {
+    debug!("WebSocket search session opened");
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            // Pings are answered by axum; binary frames aren't supported
+            _ => continue,
+        };
+
+        let reply = answer(&state, &text).await;
+        let Ok(json) = serde_json::to_string(&reply) else {
+            continue;
+        };
+        if socket.send(Message::Text(json.into())).await.is_err() {
+            break;
+        }
+    }
+
+    debug!("WebSocket search session closed");
+}
+
+async fn answer(state: &AppState, text: &str) -> WsReply {
+    let request: WsSearchRequest = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => {
+            return WsReply::Error {
+                id: None,
+                message: format!("Invalid request: {}", e),
+            };
+        }
+    };
+
+    // Same budget as POST /reviews/search, applied per message
+    let config = state.config.current();
+    let timeout = Duration::from_millis(config.timeouts.search_ms);
+    let started = Instant::now();
+    let result = match tokio::time::timeout(timeout, search(state, request.search)).await {
+        Ok(result) => result,
+        Err(_) => Err(AppError::Timeout("Request timed out".to_string())),
+    };
+
+    match result {
+        Ok(response) => {
+            state
+                .analytics
+                .record(&config.analytics, &response, started.elapsed());
+            WsReply::Results {
+                id: request.id,
+                response,
+            }
+        }
+        Err(e) => WsReply::Error {
+            id: request.id,
+            message: e.message().to_string(),
+        },
+    }
+}
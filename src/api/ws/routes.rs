@@ -0,0 +1,8 @@
+use crate::api::models::AppState;
+use crate::api::ws::handlers::ws_handler;
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/ws", get(ws_handler))
+}
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use arrow_array::builder::{FixedSizeListBuilder, Float32Builder};
+use arrow_array::{ArrayRef, RecordBatch, UInt64Array};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Rows per record batch; vectors are read from the index one batch at a time
+const WRITE_BATCH_ROWS: usize = 4096;
+
+/// Write `count` vectors as an Arrow IPC file (Feather v2)
+///
+/// Columns are `vector_id` (UInt64) and `vector` (FixedSizeList<Float32>).
+/// `get_vector` is called once per ID in order.
+pub fn write_vectors<W: Write>(
+    output: W,
+    count: usize,
+    dim: usize,
+    mut get_vector: impl FnMut(usize) -> Result<Vec<f32>>,
+) -> Result<()> {
+    let item = Arc::new(Field::new("item", DataType::Float32, false));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("vector_id", DataType::UInt64, false),
+        Field::new("vector", DataType::FixedSizeList(item.clone(), dim as i32), false),
+    ]));
+    let mut writer = FileWriter::try_new(output, &schema)?;
+
+    for start in (0..count).step_by(WRITE_BATCH_ROWS) {
+        let end = (start + WRITE_BATCH_ROWS).min(count);
+        let mut vectors = FixedSizeListBuilder::with_capacity(
+            Float32Builder::with_capacity((end - start) * dim),
+            dim as i32,
+            end - start,
+        )
+        .with_field(item.clone());
+        for id in start..end {
+            let vector = get_vector(id)?;
+            if vector.len() != dim {
+                anyhow::bail!("Vector {} has dimension {}, expected {}", id, vector.len(), dim);
+            }
+            vectors.values().append_slice(&vector);
+            vectors.append(true);
+        }
+
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values((start..end).map(|id| id as u64))),
+            Arc::new(vectors.finish()),
+        ];
+        writer.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+    }
+
+    writer.finish().context("Failed to finish Arrow output")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, FixedSizeListArray, Float32Array};
+    use arrow_ipc::reader::FileReader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_write_vectors_round_trip() {
+        let vectors = [vec![1.0_f32, 0.0], vec![0.0, 1.0], vec![0.6, 0.8]];
+
+        let mut buffer = Vec::new();
+        write_vectors(&mut buffer, vectors.len(), 2, |id| Ok(vectors[id].clone())).unwrap();
+
+        let batches: Vec<RecordBatch> = FileReader::try_new(Cursor::new(buffer), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        let ids = batch.column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(ids.values(), &[0, 1, 2]);
+
+        let list = batch.column(1).as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+        let last = list.value(2);
+        let last = last.as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(last.values(), &[0.6, 0.8]);
+    }
+}
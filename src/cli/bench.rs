@@ -0,0 +1,296 @@
+use crate::api::timing::PhaseTimer;
+use crate::cli::{open_index, LatencySummary};
+use crate::config::AppConfig;
+use crate::embedding::{normalize_text, EmbeddingService};
+use crate::storage::{now_ms, JsonlStorage, MetadataStore};
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use vector_search_client::models::SearchRequest;
+use vector_search_client::Client;
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Server to load, e.g. http://localhost:8000; without it the configured
+    /// index is searched in-process
+    #[arg(long)]
+    pub url: Option<String>,
+
+    /// API key sent to `--url`
+    #[arg(long)]
+    pub api_key: Option<String>,
+
+    /// Queries to replay, one per line (plain text or `{"query": "..."}`);
+    /// stored review titles are used when omitted
+    #[arg(long)]
+    pub queries: Option<PathBuf>,
+
+    /// Searches to run in total, cycling through the queries
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    pub requests: usize,
+
+    /// Searches in flight at once
+    #[arg(short, long, default_value_t = 8)]
+    pub concurrency: usize,
+
+    /// Results per search
+    #[arg(short, long, default_value_t = 10)]
+    pub k: usize,
+
+    /// Print the report as JSON on stdout
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    /// The server URL, or "in-process"
+    target: String,
+    requests: usize,
+    errors: usize,
+    concurrency: usize,
+    elapsed_secs: f64,
+    qps: f64,
+    latency_ms: LatencySummary,
+    /// Per-phase costs (in-process runs only)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    phases_ms: BTreeMap<&'static str, LatencySummary>,
+}
+
+/// Samples collected by the workers
+#[derive(Default)]
+struct Samples {
+    latencies: Vec<f64>,
+    phases: BTreeMap<&'static str, Vec<f64>>,
+    errors: usize,
+}
+
+/// Run `--requests` searches, `--concurrency` at a time, and report
+/// throughput and latency percentiles
+pub async fn run(config: &AppConfig, args: BenchArgs) -> Result<()> {
+    if args.requests == 0 || args.concurrency == 0 || args.k == 0 {
+        anyhow::bail!("--requests, --concurrency and -k must be at least 1");
+    }
+    let queries = match &args.queries {
+        Some(path) => read_queries(path)?,
+        None => sample_titles(config, args.requests)?,
+    };
+    if queries.is_empty() {
+        anyhow::bail!("No queries to run; pass --queries or store some reviews first");
+    }
+    info!(
+        queries = queries.len(),
+        requests = args.requests,
+        concurrency = args.concurrency,
+        target = args.url.as_deref().unwrap_or("in-process"),
+        "🏋️ Benchmarking"
+    );
+
+    let started = Instant::now();
+    let samples = match &args.url {
+        Some(url) => bench_server(url, &args, queries).await?,
+        None => {
+            let config = config.clone();
+            let (requests, concurrency, k) = (args.requests, args.concurrency, args.k);
+            tokio::task::spawn_blocking(move || {
+                bench_in_process(&config, queries, requests, concurrency, k)
+            })
+            .await??
+        }
+    };
+    let elapsed = started.elapsed();
+
+    let report = BenchReport {
+        target: args.url.clone().unwrap_or_else(|| "in-process".to_string()),
+        requests: args.requests,
+        errors: samples.errors,
+        concurrency: args.concurrency,
+        elapsed_secs: elapsed.as_secs_f64(),
+        qps: samples.latencies.len() as f64 / elapsed.as_secs_f64(),
+        latency_ms: LatencySummary::from_samples(samples.latencies),
+        phases_ms: samples
+            .phases
+            .into_iter()
+            .map(|(phase, samples)| (phase, LatencySummary::from_samples(samples)))
+            .collect(),
+    };
+    info!(
+        qps = format!("{:.1}", report.qps),
+        errors = report.errors,
+        p50_ms = format!("{:.2}", report.latency_ms.p50),
+        p95_ms = format!("{:.2}", report.latency_ms.p95),
+        p99_ms = format!("{:.2}", report.latency_ms.p99),
+        "📈 Benchmark complete"
+    );
+    for (phase, latency) in &report.phases_ms {
+        info!(
+            phase,
+            mean_ms = format!("{:.2}", latency.mean),
+            p95_ms = format!("{:.2}", latency.p95),
+            "Phase cost"
+        );
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// Search a running server over HTTP; failed searches count as errors
+async fn bench_server(url: &str, args: &BenchArgs, queries: Vec<String>) -> Result<Samples> {
+    let mut builder = Client::builder(url).max_retries(0);
+    if let Some(key) = &args.api_key {
+        builder = builder.api_key(key);
+    }
+    let client = builder.build()?;
+
+    let queries = Arc::new(queries);
+    let next = Arc::new(AtomicUsize::new(0));
+    let samples = Arc::new(Mutex::new(Samples::default()));
+    let mut workers = tokio::task::JoinSet::new();
+    for _ in 0..args.concurrency {
+        let (client, queries, next, samples) =
+            (client.clone(), queries.clone(), next.clone(), samples.clone());
+        let (requests, k) = (args.requests, args.k);
+        workers.spawn(async move {
+            loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= requests {
+                    break;
+                }
+                let request = SearchRequest {
+                    top_k: k,
+                    ..SearchRequest::new(&queries[i % queries.len()])
+                };
+                let started = Instant::now();
+                let result = client.search(&request).await;
+                let mut samples = samples.lock().unwrap();
+                match result {
+                    Ok(_) => samples.latencies.push(ms(started.elapsed())),
+                    Err(e) => {
+                        if samples.errors == 0 {
+                            warn!("Search failed: {}", e);
+                        }
+                        samples.errors += 1;
+                    }
+                }
+            }
+        });
+    }
+    workers.join_all().await;
+
+    Ok(Arc::try_unwrap(samples)
+        .map_err(|_| anyhow::anyhow!("Benchmark workers still running"))?
+        .into_inner()
+        .unwrap())
+}
+
+/// Search the configured index directly, timing the same phases as
+/// `POST /reviews/search`
+fn bench_in_process(
+    config: &AppConfig,
+    queries: Vec<String>,
+    requests: usize,
+    concurrency: usize,
+    k: usize,
+) -> Result<Samples> {
+    let index = open_index(config)?;
+    let metadata_store = JsonlStorage::new(&config.storage.metadata_path);
+    let service = EmbeddingService::with_instances(
+        &config.embedding.model_name,
+        config.embedding.max_length,
+        config.embedding.instances,
+    )?;
+
+    let next = AtomicUsize::new(0);
+    let samples = Mutex::new(Samples::default());
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= requests {
+                    break;
+                }
+                let query = &queries[i % queries.len()];
+                let mut timer = PhaseTimer::start();
+                let result = (|| {
+                    let embedding =
+                        service.embed(&normalize_text(query, &config.embedding.normalization))?;
+                    timer.mark("embed");
+                    let hits = index.search(&embedding, k)?;
+                    timer.mark("ann_search");
+                    let ids: Vec<usize> = hits.iter().map(|hit| hit.vector_id).collect();
+                    metadata_store.read_batch(&ids)?;
+                    timer.mark("metadata");
+                    anyhow::Ok(())
+                })();
+
+                let mut samples = samples.lock().unwrap();
+                match result {
+                    Ok(()) => {
+                        samples.latencies.push(ms(timer.elapsed()));
+                        for &(phase, took) in timer.phases() {
+                            samples.phases.entry(phase).or_default().push(ms(took));
+                        }
+                    }
+                    Err(e) => {
+                        if samples.errors == 0 {
+                            warn!("Search failed: {:#}", e);
+                        }
+                        samples.errors += 1;
+                    }
+                }
+            });
+        }
+    });
+    Ok(samples.into_inner().unwrap())
+}
+
+/// One query per line: plain text, or a JSON object with a `query` field
+/// (so `evaluate` files can be replayed)
+fn read_queries(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read queries file {}", path.display()))?;
+    let mut queries = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('{') {
+            let value: serde_json::Value = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid JSON", path.display(), number + 1))?;
+            let query = value["query"].as_str().with_context(|| {
+                format!("{}:{}: missing \"query\"", path.display(), number + 1)
+            })?;
+            queries.push(query.to_string());
+        } else {
+            queries.push(line.to_string());
+        }
+    }
+    Ok(queries)
+}
+
+/// Up to `limit` distinct titles of live stored reviews
+fn sample_titles(config: &AppConfig, limit: usize) -> Result<Vec<String>> {
+    let now = now_ms();
+    let mut seen = HashSet::new();
+    Ok(JsonlStorage::new(&config.storage.metadata_path)
+        .read_all()?
+        .into_iter()
+        .filter(|review| review.is_live(now) && !review.review_title.trim().is_empty())
+        .map(|review| review.review_title)
+        .filter(|title| seen.insert(title.clone()))
+        .take(limit)
+        .collect())
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,157 @@
+use crate::cli::{open_index, LatencySummary};
+use crate::config::AppConfig;
+use crate::embedding::{normalize_text, EmbeddingService};
+use crate::storage::{now_ms, JsonlStorage, MetadataStore};
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+#[derive(Debug, Args)]
+pub struct EvaluateArgs {
+    /// JSONL file of `{"query": "...", "expected_ids": [3, 17]}` lines
+    pub queries: PathBuf,
+
+    /// Results per search that recall and MRR are measured over
+    #[arg(short, long, default_value_t = 10)]
+    pub k: usize,
+
+    /// Print the report as JSON on stdout
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// A query and the vector IDs a good search should return for it
+#[derive(Debug, Deserialize)]
+struct EvalCase {
+    query: String,
+    expected_ids: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct EvaluationReport {
+    queries: usize,
+    k: usize,
+    recall_at_k: f64,
+    mrr: f64,
+    latency_ms: LatencySummary,
+}
+
+/// Search the configured index for every query and score the results
+/// against the expected IDs
+///
+/// Queries are embedded and searched the way an unfiltered search is, with
+/// the configured model, normalization and index settings.
+pub fn run(config: &AppConfig, args: EvaluateArgs) -> Result<()> {
+    if args.k == 0 {
+        anyhow::bail!("k must be at least 1");
+    }
+    let cases = read_cases(&args.queries)?;
+    if cases.is_empty() {
+        warn!("No queries to evaluate");
+        return Ok(());
+    }
+
+    let index = open_index(config)?;
+    let metadata_store = JsonlStorage::new(&config.storage.metadata_path);
+    let service = EmbeddingService::new(&config.embedding.model_name, config.embedding.max_length)?;
+    info!(queries = cases.len(), k = args.k, "Evaluating");
+
+    let mut recall = 0.0;
+    let mut reciprocal_ranks = 0.0;
+    let mut latencies = Vec::with_capacity(cases.len());
+    for case in &cases {
+        let started = Instant::now();
+        let embedding = service.embed(&normalize_text(&case.query, &config.embedding.normalization))?;
+        let hits = index.search(&embedding, args.k)?;
+        let ids: Vec<usize> = hits.iter().map(|hit| hit.vector_id).collect();
+        let now = now_ms();
+        let retrieved: Vec<usize> = metadata_store
+            .read_batch(&ids)?
+            .iter()
+            .zip(&ids)
+            .filter(|(review, _)| review.is_live(now))
+            .map(|(_, &id)| id)
+            .collect();
+        latencies.push(started.elapsed().as_secs_f64() * 1000.0);
+
+        recall += recall_at_k(&retrieved, &case.expected_ids);
+        reciprocal_ranks += reciprocal_rank(&retrieved, &case.expected_ids);
+    }
+
+    let report = EvaluationReport {
+        queries: cases.len(),
+        k: args.k,
+        recall_at_k: recall / cases.len() as f64,
+        mrr: reciprocal_ranks / cases.len() as f64,
+        latency_ms: LatencySummary::from_samples(latencies),
+    };
+    info!(
+        queries = report.queries,
+        recall_at_k = format!("{:.4}", report.recall_at_k),
+        mrr = format!("{:.4}", report.mrr),
+        p50_ms = format!("{:.2}", report.latency_ms.p50),
+        p95_ms = format!("{:.2}", report.latency_ms.p95),
+        p99_ms = format!("{:.2}", report.latency_ms.p99),
+        "📏 Evaluation complete"
+    );
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+fn read_cases(path: &Path) -> Result<Vec<EvalCase>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open queries file {}", path.display()))?;
+    let mut cases = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let case: EvalCase = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid query line", path.display(), number + 1))?;
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// Share of `expected` found in `retrieved`; 1 when nothing is expected
+fn recall_at_k(retrieved: &[usize], expected: &[usize]) -> f64 {
+    let expected: HashSet<usize> = expected.iter().copied().collect();
+    if expected.is_empty() {
+        return 1.0;
+    }
+    let found = retrieved.iter().filter(|id| expected.contains(id)).count();
+    found as f64 / expected.len() as f64
+}
+
+/// 1 / rank of the first expected ID in `retrieved`, 0 if none is there
+fn reciprocal_rank(retrieved: &[usize], expected: &[usize]) -> f64 {
+    retrieved
+        .iter()
+        .position(|id| expected.contains(id))
+        .map_or(0.0, |rank| 1.0 / (rank + 1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranking_metrics() {
+        assert_eq!(recall_at_k(&[4, 1, 9], &[1, 2]), 0.5);
+        assert_eq!(recall_at_k(&[4, 1, 9], &[]), 1.0);
+        assert_eq!(reciprocal_rank(&[4, 1, 9], &[1, 9]), 0.5);
+        assert_eq!(reciprocal_rank(&[4, 1, 9], &[7]), 0.0);
+
+        let latency = LatencySummary::from_samples((1..=100).rev().map(f64::from).collect());
+        assert_eq!((latency.p50, latency.p95, latency.p99, latency.max), (50.0, 95.0, 99.0, 100.0));
+        assert_eq!(latency.mean, 50.5);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::cli::{arrow_ipc, embed_reviews, open_index, parquet};
+use crate::config::AppConfig;
+use crate::embedding::EmbeddingService;
+use crate::storage::{JsonlStorage, MetadataStore, ReviewMetadata};
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use tracing::info;
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// Output file (stdout if omitted)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Output format (detected from the extension by default, else JSONL)
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// Add an `embedding` column, computed with the configured model (Parquet only)
+    #[arg(long)]
+    pub with_embeddings: bool,
+
+    /// Number of reviews embedded per batch
+    #[arg(long, default_value_t = 64)]
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Jsonl,
+    Parquet,
+    /// Raw index vectors with their IDs as an Arrow IPC (Feather) file
+    Arrow,
+}
+
+impl ExportFormat {
+    /// Guess the format from a file extension
+    fn detect(path: &std::path::Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("parquet") => ExportFormat::Parquet,
+            Some("arrow" | "feather" | "ipc") => ExportFormat::Arrow,
+            _ => ExportFormat::Jsonl,
+        }
+    }
+}
+
+/// One exported line: the stored review plus its vector ID
+#[derive(Serialize)]
+struct ExportRecord<'a> {
+    vector_id: usize,
+    #[serde(flatten)]
+    review: &'a ReviewMetadata,
+}
+
+/// Write every stored review as JSONL or Parquet, or the index vectors as Arrow
+pub fn run(config: &AppConfig, args: ExportArgs) -> Result<()> {
+    let format = args.format.unwrap_or_else(|| match &args.output {
+        Some(path) => ExportFormat::detect(path),
+        None => ExportFormat::Jsonl,
+    });
+    if args.with_embeddings && format != ExportFormat::Parquet {
+        anyhow::bail!("--with-embeddings requires Parquet output");
+    }
+
+    let writer: Box<dyn Write + Send> = match &args.output {
+        Some(path) => Box::new(
+            File::create(path)
+                .with_context(|| format!("Failed to create output file: {}", path.display()))?,
+        ),
+        None => Box::new(std::io::stdout()),
+    };
+    let mut writer = BufWriter::new(writer);
+
+    // Vectors come straight from the index; no model needed
+    if format == ExportFormat::Arrow {
+        let index = open_index(config)?;
+        arrow_ipc::write_vectors(writer, index.vector_count(), index.dimension(), |id| {
+            index.get_vector(id)
+        })?;
+        info!(exported = index.vector_count(), "✅ Vector export complete");
+        return Ok(());
+    }
+
+    let reviews = JsonlStorage::new(&config.storage.metadata_path).read_all()?;
+
+    if format == ExportFormat::Parquet {
+        let embeddings = if args.with_embeddings {
+            let service = EmbeddingService::with_instances(
+                &config.embedding.model_name,
+                config.embedding.max_length,
+                config.embedding.instances,
+            )?;
+            let embeddings =
+                embed_reviews(&service, &reviews, &config.embedding.normalization, args.batch_size)?;
+            Some((embeddings, service.dimension()))
+        } else {
+            None
+        };
+        parquet::write_reviews(
+            writer,
+            &reviews,
+            embeddings.as_ref().map(|(e, dim)| (e.as_slice(), *dim)),
+        )?;
+        info!(exported = reviews.len(), "✅ Export complete");
+        return Ok(());
+    }
+
+    for (vector_id, review) in reviews.iter().enumerate() {
+        if review.deleted {
+            continue;
+        }
+        let json = serde_json::to_string(&ExportRecord { vector_id, review })?;
+        writeln!(writer, "{}", json).context("Failed to write export")?;
+    }
+    writer.flush().context("Failed to write export")?;
+
+    info!(exported = reviews.len(), "✅ Export complete");
+    Ok(())
+}
@@ -0,0 +1,447 @@
+use crate::api::models::{AddReviewRequest, ReviewRequestExt};
+use crate::api::review::handlers::tag_language;
+use crate::cli::{embed_reviews, open_index, parquet};
+use crate::config::{AppConfig, DedupAction, DedupConfig};
+use crate::content_hash::{content_hash, ContentHashes};
+use crate::embedding::EmbeddingService;
+use crate::redaction::Redactor;
+use crate::storage::{AuditEntry, AuditLog, JsonlStorage, MetadataStore, ReviewMetadata};
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// Input file (JSONL, CSV or Parquet)
+    pub input: PathBuf,
+
+    /// Input format (detected from the extension by default)
+    #[arg(long, value_enum)]
+    pub format: Option<ImportFormat>,
+
+    /// Number of reviews embedded per batch
+    #[arg(long, default_value_t = 64)]
+    pub batch_size: usize,
+
+    /// Import the valid rows even if some rows are invalid
+    #[arg(long)]
+    pub skip_invalid: bool,
+
+    #[command(flatten)]
+    pub columns: ColumnMapping,
+}
+
+/// Which CSV/Parquet column holds each review field
+///
+/// Columns are header names or 0-based indexes. Without a CSV header row,
+/// the default names map to positions 0-3 (title, body, product_id, rating).
+#[derive(Debug, Clone, Args)]
+pub struct ColumnMapping {
+    /// Column holding the review title
+    #[arg(long = "title-column", default_value = "review_title")]
+    pub title: String,
+
+    /// Column holding the review body
+    #[arg(long = "body-column", default_value = "review_body")]
+    pub body: String,
+
+    /// Column holding the product ID
+    #[arg(long = "product-id-column", default_value = "product_id")]
+    pub product_id: String,
+
+    /// Column holding the 1-5 rating
+    #[arg(long = "rating-column", default_value = "review_rating")]
+    pub rating: String,
+
+    /// Whether the first CSV row is a header (detected by default)
+    #[arg(long)]
+    pub header: Option<bool>,
+}
+
+const DEFAULT_COLUMNS: [&str; 4] = ["review_title", "review_body", "product_id", "review_rating"];
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        let [title, body, product_id, rating] = DEFAULT_COLUMNS.map(String::from);
+        Self {
+            title,
+            body,
+            product_id,
+            rating,
+            header: None,
+        }
+    }
+}
+
+impl ColumnMapping {
+    fn specs(&self) -> [&str; 4] {
+        [&self.title, &self.body, &self.product_id, &self.rating]
+    }
+
+    /// A first row is a header if any cell names one of the mapped columns
+    fn detect_header(&self, first: &csv::StringRecord) -> bool {
+        self.header.unwrap_or_else(|| {
+            first.iter().any(|cell| {
+                self.specs()
+                    .iter()
+                    .any(|spec| cell.trim().eq_ignore_ascii_case(spec))
+            })
+        })
+    }
+
+    /// Positions of title, body, product_id and rating
+    pub(crate) fn resolve(&self, headers: Option<&[&str]>) -> Result<[usize; 4]> {
+        let mut positions = [0; 4];
+        for (field, spec) in self.specs().into_iter().enumerate() {
+            positions[field] = if let Ok(index) = spec.parse::<usize>() {
+                index
+            } else if let Some(headers) = headers {
+                headers
+                    .iter()
+                    .position(|h| h.trim().eq_ignore_ascii_case(spec))
+                    .ok_or_else(|| anyhow::anyhow!("Input has no column '{}'", spec))?
+            } else if spec == DEFAULT_COLUMNS[field] {
+                field
+            } else {
+                anyhow::bail!("Column '{}' needs a header row; use a 0-based index instead", spec);
+            };
+        }
+        Ok(positions)
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl ImportFormat {
+    pub(crate) fn detect(path: &Path) -> Self {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ImportFormat::Csv,
+            Some(ext) if ext.eq_ignore_ascii_case("parquet") => ImportFormat::Parquet,
+            _ => ImportFormat::Jsonl,
+        }
+    }
+}
+
+/// Embed and index every review in the input file
+pub fn run(config: &AppConfig, args: ImportArgs) -> Result<()> {
+    let format = args.format.unwrap_or_else(|| ImportFormat::detect(&args.input));
+    let file = File::open(&args.input)
+        .with_context(|| format!("Failed to open input file: {}", args.input.display()))?;
+
+    let mut reviews = Vec::new();
+    let mut invalid = 0;
+    for row in parse_reviews(file, format, &args.columns, config)? {
+        match row {
+            Ok(review) => reviews.push(review),
+            Err(e) => {
+                warn!("{:#}", e);
+                invalid += 1;
+            }
+        }
+    }
+    if invalid > 0 && !args.skip_invalid {
+        anyhow::bail!("{} invalid row(s); fix them or pass --skip-invalid", invalid);
+    }
+    info!(count = reviews.len(), invalid, input = %args.input.display(), "Read reviews for import");
+
+    if reviews.is_empty() {
+        info!("Nothing to import");
+        return Ok(());
+    }
+
+    let metadata_store = JsonlStorage::new(&config.storage.metadata_path);
+    metadata_store.initialize()?;
+    let mut index = open_index(config)?;
+
+    let existing = metadata_store.count_lines()?;
+    if existing != index.vector_count() {
+        anyhow::bail!(
+            "Index ({} vectors) and metadata ({} reviews) are out of sync; run `verify` / `rebuild` first",
+            index.vector_count(),
+            existing
+        );
+    }
+
+    let (reviews, duplicates) =
+        drop_exact_duplicates(reviews, &config.dedup, &ContentHashes::new(), &metadata_store, existing)?;
+    for duplicate in &duplicates {
+        warn!("{}", duplicate);
+    }
+    if reviews.is_empty() {
+        info!("Nothing to import");
+        return Ok(());
+    }
+
+    let service = EmbeddingService::with_instances(
+        &config.embedding.model_name,
+        config.embedding.max_length,
+        config.embedding.instances,
+    )?;
+    let embeddings = embed_reviews(&service, &reviews, &config.embedding.normalization, args.batch_size)?;
+
+    // A fresh index is built in one pass; otherwise append to the existing one
+    if existing == 0 {
+        index.build_from_vectors(&embeddings)?;
+    } else {
+        for embedding in &embeddings {
+            index.add_vector(embedding)?;
+        }
+    }
+
+    let first_id = metadata_store.append_batch(&reviews)?;
+    metadata_store.flush()?;
+    index.save(&config.storage.index_path)?;
+
+    AuditLog::new(&config.storage.audit_path).record(AuditEntry {
+        ids: (first_id..first_id + reviews.len()).collect(),
+        detail: Some(serde_json::json!({ "source": "cli", "input": args.input })),
+        ..AuditEntry::new("import")
+    });
+
+    info!(
+        imported = reviews.len(),
+        first_id = first_id,
+        total = index.vector_count(),
+        "✅ Import complete"
+    );
+    Ok(())
+}
+
+/// Parse and validate every review in the input, failing on the first bad row
+pub(crate) fn read_reviews(
+    path: &Path,
+    format: ImportFormat,
+    config: &AppConfig,
+) -> Result<Vec<ReviewMetadata>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open input file: {}", path.display()))?;
+
+    parse_reviews(file, format, &ColumnMapping::default(), config)?
+        .into_iter()
+        .collect()
+}
+
+/// Parse each row and give it the checks of `POST /reviews`: validation,
+/// language tagging and redaction, keeping per-row errors
+///
+/// Errors name the 1-based line of the input. Only an unusable CSV column
+/// mapping fails the whole input.
+pub(crate) fn parse_reviews<R: Read>(
+    mut input: R,
+    format: ImportFormat,
+    columns: &ColumnMapping,
+    config: &AppConfig,
+) -> Result<Vec<Result<ReviewMetadata>>> {
+    let redactor = if config.redaction.enabled {
+        Some(Redactor::new(&config.redaction)?)
+    } else {
+        None
+    };
+    let requests: Vec<(usize, Result<AddReviewRequest>)> = match format {
+        ImportFormat::Jsonl => BufReader::new(input)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+            .map(|(idx, line)| {
+                let request = line.context("Failed to read input").and_then(|line| {
+                    serde_json::from_str(&line)
+                        .with_context(|| format!("Line {}: invalid review", idx + 1))
+                });
+                (idx + 1, request)
+            })
+            .collect(),
+        ImportFormat::Csv => parse_csv(input, columns)?,
+        ImportFormat::Parquet => {
+            // Parquet needs random access, so buffer the whole input
+            let mut buffer = Vec::new();
+            input.read_to_end(&mut buffer).context("Failed to read input")?;
+            parquet::read_reviews(Bytes::from(buffer), columns)?
+        }
+    };
+
+    Ok(requests
+        .into_iter()
+        .map(|(line, request)| {
+            let mut request = request?;
+            request
+                .validate(&config.validation)
+                .map_err(|violations| anyhow::anyhow!("Line {}: {}", line, violations.join("; ")))?;
+            tag_language(config, &mut request);
+            if let Some(redactor) = &redactor {
+                redactor.redact_review(&mut request.review_title, &mut request.review_body);
+            }
+            Ok(request.into_metadata())
+        })
+        .collect())
+}
+
+/// With `dedup.exact` rejecting duplicates, drop reviews with the content
+/// of a live stored review or of an earlier row, describing each
+///
+/// Imports have no per-review response to flag duplicates in, so the flag
+/// action keeps them all.
+pub(crate) fn drop_exact_duplicates(
+    reviews: Vec<ReviewMetadata>,
+    dedup: &DedupConfig,
+    hashes: &ContentHashes,
+    store: &dyn MetadataStore,
+    stored: usize,
+) -> Result<(Vec<ReviewMetadata>, Vec<String>)> {
+    if !dedup.exact || dedup.action != DedupAction::Reject {
+        return Ok((reviews, Vec::new()));
+    }
+
+    let mut kept = Vec::with_capacity(reviews.len());
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates = Vec::new();
+    for review in reviews {
+        if let Some(id) = hashes.find(&review, stored, || store.read_all(), |ids| store.read_batch(ids))? {
+            duplicates.push(format!("{}: exact duplicate of review {}; skipped", describe(&review), id));
+        } else if !seen.insert(content_hash(&review)) {
+            duplicates.push(format!("{}: exact duplicate of an earlier row; skipped", describe(&review)));
+        } else {
+            kept.push(review);
+        }
+    }
+    Ok((kept, duplicates))
+}
+
+/// Names a review in import messages, since rows are counted before
+/// invalid ones are dropped
+pub(crate) fn describe(review: &ReviewMetadata) -> String {
+    format!("Review {:?} ({})", review.review_title, review.product_id)
+}
+
+fn parse_csv<R: Read>(input: R, columns: &ColumnMapping) -> Result<Vec<(usize, Result<AddReviewRequest>)>> {
+    let mut records = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input)
+        .into_records()
+        .peekable();
+
+    let headers = match records.peek() {
+        Some(Ok(first)) if columns.detect_header(first) => records.next().and_then(|r| r.ok()),
+        _ => None,
+    };
+    let header_names: Option<Vec<&str>> = headers.as_ref().map(|h| h.iter().collect());
+    let [title, body, product_id, rating] = columns.resolve(header_names.as_deref())?;
+
+    Ok(records
+        .enumerate()
+        .map(|(idx, record)| {
+            let fallback_line = idx + 1 + usize::from(headers.is_some());
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => return (fallback_line, Err(anyhow::anyhow!("Line {}: {}", fallback_line, e))),
+            };
+            let line = record
+                .position()
+                .map_or(fallback_line, |p| p.line() as usize);
+
+            let field = |position: usize, name: &str| {
+                record
+                    .get(position)
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| anyhow::anyhow!("Line {}: missing {} (column {})", line, name, position))
+            };
+            let request = (|| {
+                let raw_rating = field(rating, "rating")?;
+                Ok(AddReviewRequest {
+                    review_title: field(title, "title")?,
+                    review_body: field(body, "body")?,
+                    product_id: field(product_id, "product_id")?,
+                    review_rating: raw_rating.trim().parse().map_err(|_| {
+                        anyhow::anyhow!("Line {}: rating '{}' is not a number", line, raw_rating)
+                    })?,
+                    expires_at: None,
+                    ttl_secs: None,
+                    language: None,
+                    attributes: Default::default(),
+                    image: None,
+                })
+            })();
+            (line, request)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_column_mapping() {
+        let columns = ColumnMapping {
+            title: "Title".to_string(),
+            body: "Text".to_string(),
+            product_id: "asin".to_string(),
+            rating: "stars".to_string(),
+            header: None,
+        };
+        let input = "asin,stars,Title,Text\nB01,5,Great,Works well\nB02,five,Bad,Broke\n";
+
+        let rows = parse_reviews(input.as_bytes(), ImportFormat::Csv, &columns, &AppConfig::default()).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.product_id, "B01");
+        assert_eq!(first.review_title, "Great");
+        assert_eq!(first.review_rating, 5);
+
+        let err = rows[1].as_ref().unwrap_err().to_string();
+        assert!(err.starts_with("Line 3:"), "{}", err);
+    }
+
+    #[test]
+    fn test_csv_without_header_uses_positions() {
+        let input = "Great,Works well,B01,4\n";
+        let rows = parse_reviews(
+            input.as_bytes(),
+            ImportFormat::Csv,
+            &ColumnMapping::default(),
+            &AppConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(rows[0].as_ref().unwrap().review_rating, 4);
+    }
+
+    #[test]
+    fn test_import_gets_the_add_checks() {
+        let mut config = AppConfig::default();
+        config.redaction.enabled = true;
+        config.dedup.exact = true;
+        config.dedup.action = DedupAction::Reject;
+        let input = [
+            r#"{"review_title":"Great","review_body":"Mail jane@example.com","product_id":"P1","review_rating":5}"#,
+            r#"{"review_title":"Great","review_body":"Mail jane@example.com","product_id":"P1","review_rating":4}"#,
+            r#"{"review_title":"Stored","review_body":"Already here","product_id":"P2","review_rating":3}"#,
+        ]
+        .join("\n");
+
+        let reviews: Vec<ReviewMetadata> = parse_reviews(input.as_bytes(), ImportFormat::Jsonl, &ColumnMapping::default(), &config)
+            .unwrap()
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(reviews[0].review_body, "Mail [EMAIL]");
+
+        let store = crate::test_utils::MemoryStore::new();
+        store.append(&reviews[2]).unwrap();
+        let (kept, duplicates) = drop_exact_duplicates(reviews, &config.dedup, &ContentHashes::new(), &store, 1).unwrap();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(duplicates.len(), 2, "{:?}", duplicates);
+        assert!(duplicates[1].contains("exact duplicate of review 0"), "{:?}", duplicates);
+    }
+}
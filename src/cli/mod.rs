@@ -0,0 +1,182 @@
+pub mod arrow_ipc;
+pub mod bench;
+pub mod evaluate;
+pub mod export;
+pub mod import;
+pub mod parquet;
+pub mod rebuild;
+pub mod repair;
+pub mod serve;
+pub mod verify;
+
+use crate::config::{AppConfig, TextNormalization};
+use crate::embedding::EmbeddingService;
+use crate::logging::LogControl;
+use crate::storage::{ReviewMetadata, VectorIndex};
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::info;
+
+/// Vector search API over product reviews
+///
+/// Offline commands (import, rebuild, repair) write the data files directly; run
+/// them while the server is stopped.
+#[derive(Debug, Parser)]
+#[command(name = "vector-search-api", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+
+    /// Bulk-load reviews from a JSONL or CSV file
+    Import(import::ImportArgs),
+
+    /// Dump stored reviews (JSONL/Parquet) or raw vectors (Arrow IPC)
+    Export(export::ExportArgs),
+
+    /// Regenerate the vector index from stored metadata
+    Rebuild(rebuild::RebuildArgs),
+
+    /// Check that the index and metadata are consistent
+    Verify,
+
+    /// Recreate a lost or corrupted index from the metadata file
+    Repair(repair::RepairArgs),
+
+    /// Measure recall@k, MRR and latency over queries with known answers
+    Evaluate(evaluate::EvaluateArgs),
+
+    /// Load a running server, or the index in-process, and report QPS and
+    /// latency percentiles
+    Bench(bench::BenchArgs),
+}
+
+/// Dispatch the selected command
+pub async fn run(cli: Cli, config: AppConfig, log_control: Arc<LogControl>) -> Result<()> {
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve::run(config, log_control).await,
+        Command::Import(args) => import::run(&config, args),
+        Command::Export(args) => export::run(&config, args),
+        Command::Rebuild(args) => rebuild::run(&config, args),
+        Command::Verify => verify::run(&config),
+        Command::Repair(args) => repair::run(&config, args),
+        Command::Evaluate(args) => evaluate::run(&config, args),
+        Command::Bench(args) => bench::run(&config, args).await,
+    }
+}
+
+/// Load the configured index, or initialize an empty one
+pub fn open_index(config: &AppConfig) -> Result<VectorIndex> {
+    let mut index = VectorIndex::new(&config.index);
+
+    if config.storage.index_path.exists() {
+        index.load(&config.storage.index_path)?;
+    } else {
+        index.initialize()?;
+    }
+    Ok(index)
+}
+
+/// Mask the vectors of tombstoned reviews in a freshly built index
+///
+/// `reviews[i]` must hold vector `first_id + i`.
+pub fn delete_tombstoned(
+    index: &mut VectorIndex,
+    reviews: &[ReviewMetadata],
+    first_id: usize,
+) -> Result<()> {
+    for (offset, review) in reviews.iter().enumerate() {
+        if review.deleted {
+            index.delete_vector(first_id + offset)?;
+        }
+    }
+    Ok(())
+}
+
+/// Embed reviews in batches, logging progress
+pub fn embed_reviews(
+    service: &EmbeddingService,
+    reviews: &[ReviewMetadata],
+    normalization: &TextNormalization,
+    batch_size: usize,
+) -> Result<Vec<Vec<f32>>> {
+    embed_reviews_with_progress(service, reviews, normalization, batch_size, |_| {})
+}
+
+/// Like [`embed_reviews`], also reporting the running count after each batch
+///
+/// With several model instances, that many batches are embedded at once.
+pub fn embed_reviews_with_progress(
+    service: &EmbeddingService,
+    reviews: &[ReviewMetadata],
+    normalization: &TextNormalization,
+    batch_size: usize,
+    mut on_progress: impl FnMut(usize),
+) -> Result<Vec<Vec<f32>>> {
+    let mut embeddings = Vec::with_capacity(reviews.len());
+
+    let batch_size = batch_size.max(1);
+    for group in reviews.chunks(batch_size * service.instances()) {
+        let batches: Vec<Result<Vec<Vec<f32>>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = group
+                .chunks(batch_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let texts: Vec<String> = chunk
+                            .iter()
+                            .map(|r| EmbeddingService::review_text(&r.review_title, &r.review_body, normalization))
+                            .collect();
+                        service.embed_batch(texts.iter().map(String::as_str).collect())
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        for batch in batches {
+            embeddings.extend(batch?);
+
+            info!(embedded = embeddings.len(), total = reviews.len(), "Embedding progress");
+            on_progress(embeddings.len());
+        }
+    }
+
+    Ok(embeddings)
+}
+
+/// Latency percentiles over a run, in milliseconds
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LatencySummary {
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl LatencySummary {
+    /// Nearest-rank percentiles of `samples`
+    pub fn from_samples(mut samples: Vec<f64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_by(f64::total_cmp);
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1]
+        };
+        Self {
+            mean: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
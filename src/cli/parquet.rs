@@ -0,0 +1,163 @@
+use crate::api::models::AddReviewRequest;
+use crate::cli::import::ColumnMapping;
+use crate::storage::ReviewMetadata;
+use anyhow::{Context, Result};
+use arrow_array::builder::{FixedSizeListBuilder, Float32Builder};
+use arrow_array::{Array, ArrayRef, Int64Array, RecordBatch, StringArray, UInt64Array, UInt8Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::ChunkReader;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Rows per record batch when writing
+const WRITE_BATCH_ROWS: usize = 8192;
+
+/// Read review rows from a Parquet file
+///
+/// Columns are found by name (or 0-based index) through `columns`; text
+/// columns are cast to strings and the rating to an integer. Row numbers in
+/// errors are 1-based.
+pub fn read_reviews<R: ChunkReader + 'static>(
+    input: R,
+    columns: &ColumnMapping,
+) -> Result<Vec<(usize, Result<AddReviewRequest>)>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(input)
+        .context("Failed to open Parquet input")?;
+    let schema = builder.schema().clone();
+    let headers: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    let [title, body, product_id, rating] = columns.resolve(Some(&headers))?;
+
+    let mut rows = Vec::new();
+    for batch in builder.build().context("Failed to read Parquet input")? {
+        let batch = batch.context("Failed to read Parquet record batch")?;
+        let text = |position: usize| -> Result<StringArray> {
+            let array = arrow_cast::cast(batch.column(position), &DataType::Utf8)?;
+            Ok(array.as_any().downcast_ref::<StringArray>().cloned().expect("cast to Utf8"))
+        };
+        let (titles, bodies, product_ids) = (text(title)?, text(body)?, text(product_id)?);
+        let ratings = arrow_cast::cast(batch.column(rating), &DataType::Int64)
+            .context("Rating column is not numeric")?;
+        let ratings = ratings.as_any().downcast_ref::<Int64Array>().expect("cast to Int64");
+
+        for i in 0..batch.num_rows() {
+            let line = rows.len() + 1;
+            let string = |array: &StringArray, name: &str| {
+                if array.is_null(i) {
+                    anyhow::bail!("Line {}: missing {}", line, name);
+                }
+                Ok(array.value(i).to_string())
+            };
+            let request = (|| {
+                if ratings.is_null(i) {
+                    anyhow::bail!("Line {}: missing rating", line);
+                }
+                Ok(AddReviewRequest {
+                    review_title: string(&titles, "title")?,
+                    review_body: string(&bodies, "body")?,
+                    product_id: string(&product_ids, "product_id")?,
+                    review_rating: u8::try_from(ratings.value(i)).map_err(|_| {
+                        anyhow::anyhow!("Line {}: rating {} is out of range", line, ratings.value(i))
+                    })?,
+                    expires_at: None,
+                    ttl_secs: None,
+                    language: None,
+                    attributes: Default::default(),
+                    image: None,
+                })
+            })();
+            rows.push((line, request));
+        }
+    }
+    Ok(rows)
+}
+
+/// Write reviews (and optionally their embeddings) as Snappy-compressed Parquet
+///
+/// The vector ID of each review is its position in `reviews`.
+pub fn write_reviews<W: Write + Send>(
+    output: W,
+    reviews: &[ReviewMetadata],
+    embeddings: Option<(&[Vec<f32>], usize)>,
+) -> Result<()> {
+    let mut fields = vec![
+        Field::new("vector_id", DataType::UInt64, false),
+        Field::new("review_title", DataType::Utf8, false),
+        Field::new("review_body", DataType::Utf8, false),
+        Field::new("product_id", DataType::Utf8, false),
+        Field::new("review_rating", DataType::UInt8, false),
+    ];
+    if let Some((_, dim)) = embeddings {
+        fields.push(Field::new(
+            "embedding",
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, false)), dim as i32),
+            false,
+        ));
+    }
+    let schema = Arc::new(Schema::new(fields));
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(output, schema.clone(), Some(properties))?;
+
+    for start in (0..reviews.len()).step_by(WRITE_BATCH_ROWS) {
+        let chunk = &reviews[start..(start + WRITE_BATCH_ROWS).min(reviews.len())];
+        let mut arrays: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from_iter_values(
+                (start..start + chunk.len()).map(|id| id as u64),
+            )),
+            Arc::new(StringArray::from_iter_values(chunk.iter().map(|r| &r.review_title))),
+            Arc::new(StringArray::from_iter_values(chunk.iter().map(|r| &r.review_body))),
+            Arc::new(StringArray::from_iter_values(chunk.iter().map(|r| &r.product_id))),
+            Arc::new(UInt8Array::from_iter_values(chunk.iter().map(|r| r.review_rating))),
+        ];
+        if let Some((embeddings, dim)) = embeddings {
+            let mut builder = FixedSizeListBuilder::with_capacity(
+                Float32Builder::with_capacity(chunk.len() * dim),
+                dim as i32,
+                chunk.len(),
+            )
+            .with_field(Arc::new(Field::new("item", DataType::Float32, false)));
+            for embedding in &embeddings[start..start + chunk.len()] {
+                builder.values().append_slice(embedding);
+                builder.append(true);
+            }
+            arrays.push(Arc::new(builder.finish()));
+        }
+
+        writer.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+    }
+
+    writer.close().context("Failed to finish Parquet output")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::review;
+    use axum::body::Bytes;
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let reviews = vec![ReviewMetadata {
+            review_body: "Works well".to_string(),
+            review_rating: 5,
+            ..review("Great", "B01")
+        }];
+        let embeddings = vec![vec![0.5_f32, -0.5]];
+
+        let mut buffer = Vec::new();
+        write_reviews(&mut buffer, &reviews, Some((&embeddings, 2))).unwrap();
+
+        let rows = read_reviews(Bytes::from(buffer), &ColumnMapping::default()).unwrap();
+        assert_eq!(rows.len(), 1);
+        let request = rows[0].1.as_ref().unwrap();
+        assert_eq!(request.product_id, "B01");
+        assert_eq!(request.review_rating, 5);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::cli::{delete_tombstoned, embed_reviews};
+use crate::config::AppConfig;
+use crate::embedding::EmbeddingService;
+use crate::storage::{AuditEntry, AuditLog, JsonlStorage, MetadataStore, ReviewMetadata, VectorIndex};
+use anyhow::{Context, Result};
+use clap::Args;
+use tracing::{info, warn};
+
+#[derive(Debug, Args)]
+pub struct RebuildArgs {
+    /// Number of reviews embedded per batch
+    #[arg(long, default_value_t = 64)]
+    pub batch_size: usize,
+}
+
+/// Re-embed all stored reviews and replace the index archive
+pub fn run(config: &AppConfig, args: RebuildArgs) -> Result<()> {
+    let reviews = JsonlStorage::new(&config.storage.metadata_path).read_all()?;
+    if reviews.is_empty() {
+        warn!("No stored reviews; nothing to rebuild");
+        return Ok(());
+    }
+    info!(count = reviews.len(), "Rebuilding index from metadata");
+
+    let service = EmbeddingService::with_instances(
+        &config.embedding.model_name,
+        config.embedding.max_length,
+        config.embedding.instances,
+    )?;
+    let embeddings = embed_reviews(&service, &reviews, &config.embedding.normalization, args.batch_size)?;
+    let index = replace_index(config, &reviews, &embeddings)?;
+
+    AuditLog::new(&config.storage.audit_path).record(AuditEntry {
+        detail: Some(serde_json::json!({ "source": "cli", "vectors": index.vector_count() })),
+        ..AuditEntry::new("rebuild")
+    });
+
+    info!(vectors = index.vector_count(), "✅ Rebuild complete");
+    Ok(())
+}
+
+/// Build an index from `embeddings` and swap it in for the configured archive
+pub(crate) fn replace_index(
+    config: &AppConfig,
+    reviews: &[ReviewMetadata],
+    embeddings: &[Vec<f32>],
+) -> Result<VectorIndex> {
+    let mut index = VectorIndex::new(&config.index);
+    index.initialize()?;
+    index.build_from_vectors(embeddings)?;
+    delete_tombstoned(&mut index, reviews, 0)?;
+
+    // Write next to the live archive, then swap it in
+    let index_path = &config.storage.index_path;
+    let tmp_path = index_path.with_extension("rebuild.tmp");
+    index.save(&tmp_path)?;
+    std::fs::rename(&tmp_path, index_path)
+        .with_context(|| format!("Failed to replace index: {}", index_path.display()))?;
+
+    Ok(index)
+}
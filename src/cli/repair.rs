@@ -0,0 +1,145 @@
+use crate::cli::embed_reviews;
+use crate::cli::rebuild::replace_index;
+use crate::config::AppConfig;
+use crate::embedding::EmbeddingService;
+use crate::storage::{AuditEntry, AuditLog, JsonlStorage, MetadataStore, ReviewMetadata};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Args)]
+pub struct RepairArgs {
+    /// Number of reviews embedded per batch
+    #[arg(long, default_value_t = 64)]
+    pub batch_size: usize,
+
+    /// Remove metadata lines that don't parse instead of stopping (the
+    /// original file is kept as `<metadata_path>.bak`). Vector IDs after a
+    /// removed line shift down.
+    #[arg(long)]
+    pub drop_invalid: bool,
+}
+
+/// Recover a lost or corrupted index from the metadata file
+///
+/// Unlike `rebuild`, every metadata line is checked first, and the old
+/// index is never opened.
+pub fn run(config: &AppConfig, args: RepairArgs) -> Result<()> {
+    let metadata_path = &config.storage.metadata_path;
+    if !metadata_path.exists() {
+        anyhow::bail!("Metadata file {} is missing; nothing to repair from", metadata_path.display());
+    }
+
+    info!("🩺 Scanning {}", metadata_path.display());
+    let MetadataScan { reviews, invalid } = scan_metadata(metadata_path)?;
+    for (line, reason) in &invalid {
+        error!("❌ line {}: {}", line, reason);
+    }
+
+    if !invalid.is_empty() {
+        if !args.drop_invalid {
+            anyhow::bail!(
+                "{} unreadable metadata line(s); rerun with --drop-invalid to remove them",
+                invalid.len()
+            );
+        }
+        rewrite_metadata(metadata_path, &reviews)?;
+        warn!(dropped = invalid.len(), "Removed unreadable metadata lines");
+    }
+
+    if reviews.is_empty() {
+        warn!("No stored reviews; nothing to repair");
+        return Ok(());
+    }
+    info!(count = reviews.len(), "Re-embedding metadata");
+
+    let service = EmbeddingService::with_instances(
+        &config.embedding.model_name,
+        config.embedding.max_length,
+        config.embedding.instances,
+    )?;
+    let embeddings = embed_reviews(&service, &reviews, &config.embedding.normalization, args.batch_size)?;
+    let index = replace_index(config, &reviews, &embeddings)?;
+
+    AuditLog::new(&config.storage.audit_path).record(AuditEntry {
+        detail: Some(serde_json::json!({
+            "source": "cli",
+            "vectors": index.vector_count(),
+            "dropped_lines": invalid.len(),
+        })),
+        ..AuditEntry::new("repair")
+    });
+
+    info!(vectors = index.vector_count(), "✅ Repair complete");
+    Ok(())
+}
+
+/// Readable reviews, plus the 1-based line number and error of the rest
+struct MetadataScan {
+    reviews: Vec<ReviewMetadata>,
+    invalid: Vec<(usize, String)>,
+}
+
+/// Parse every line of the metadata file
+fn scan_metadata(path: &Path) -> Result<MetadataScan> {
+    let file = File::open(path).context("Failed to open metadata file")?;
+    let mut reviews = Vec::new();
+    let mut invalid = Vec::new();
+
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let parsed = line
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(serde_json::from_str::<ReviewMetadata>(&line)?));
+        match parsed {
+            Ok(review) => reviews.push(review),
+            Err(e) => invalid.push((idx + 1, e.to_string())),
+        }
+    }
+
+    Ok(MetadataScan { reviews, invalid })
+}
+
+/// Replace the metadata file with `reviews`, keeping a `.bak` of the original
+fn rewrite_metadata(path: &Path, reviews: &[ReviewMetadata]) -> Result<()> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    std::fs::copy(path, &backup)
+        .with_context(|| format!("Failed to back up {}", path.display()))?;
+
+    let tmp_path = path.with_extension("repair.tmp");
+    let _ = std::fs::remove_file(&tmp_path);
+    let tmp = JsonlStorage::new(&tmp_path);
+    tmp.append_batch(reviews)?;
+    tmp.flush()?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+    info!(backup = %Path::new(&backup).display(), "Original metadata backed up");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_and_rewrite_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("metadata.jsonl");
+        let good = r#"{"review_title":"T","review_body":"B","product_id":"P","review_rating":4}"#;
+        std::fs::write(&path, format!("{good}\n{{\"review_title\":\n{good}\n")).unwrap();
+
+        let MetadataScan { reviews, invalid } = scan_metadata(&path).unwrap();
+        assert_eq!(reviews.len(), 2);
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0, 2);
+
+        rewrite_metadata(&path, &reviews).unwrap();
+        assert_eq!(JsonlStorage::new(&path).count_lines().unwrap(), 2);
+        assert!(temp_dir.path().join("metadata.jsonl.bak").exists());
+    }
+}
@@ -0,0 +1,666 @@
+use crate::api::readiness::{ComponentState, Readiness};
+use crate::api::write_gate::WriteGate;
+use crate::api::analytics::SearchAnalytics;
+use crate::api::backpressure::Backpressure;
+use crate::api::group_commit::GroupCommit;
+use crate::api::idempotency::IdempotencyCache;
+use crate::api::search_cache::SearchCache;
+use crate::api::timing::LatencyHistograms;
+use crate::api::jobs::JobRegistry;
+use crate::api::usage::UsageTracker;
+use crate::api::{build_admin_router, build_router, AppState};
+use crate::cli::{delete_tombstoned, embed_reviews};
+use crate::cli::import::{read_reviews, ImportFormat};
+use crate::config::{AppConfig, ConfigHandle, QueryExpansion, ReplicationRole, TextNormalization};
+use crate::content_hash::ContentHashes;
+use crate::api::qdrant::ids::PointIds;
+use crate::field_vectors::{spawn_field_vectors_task, FieldVectors};
+use crate::ingest::{spawn_async_indexer, IngestQueue};
+use crate::embedding::{EmbeddingService, ModelCache};
+use crate::grpc::GrpcService;
+use crate::listener::ListenerSource;
+use crate::logging::LogControl;
+use crate::memory::{spawn_memory_watchdog, MemoryWatchdog};
+use crate::redaction::RedactorCache;
+use crate::replication::spawn_follower_task;
+use crate::snapshot::{self, spawn_snapshot_task};
+use crate::spelling::SpellChecker;
+use crate::suggest::Suggester;
+use crate::synonyms::Synonyms;
+use crate::shadow::spawn_shadow_sync;
+use crate::ttl::spawn_ttl_sweeper;
+use crate::storage::{AuditLog, CollectionManager, JsonlStorage, MetadataStore, VectorIndex};
+use crate::webhooks::WebhookDispatcher;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+/// Reviews embedded per batch when seeding an empty index
+const SEED_BATCH_SIZE: usize = 64;
+
+/// Run the HTTP server until a shutdown signal arrives
+pub async fn run(config: AppConfig, log_control: Arc<LogControl>) -> anyhow::Result<()> {
+    info!("🚀 Starting Vector Search API Server");
+
+    let config_handle = Arc::new(ConfigHandle::new(config.clone()));
+    info!("📋 Configuration loaded");
+    info!("   - Index Type: {}", config.index.index_type);
+    info!("   - Vector Dim: {}", config.index.vector_dim);
+    info!("   - Server: {}:{}", config.server.host, config.server.port);
+    if config.is_read_only() {
+        info!("   - Read-only: mutations are rejected and the index is never saved");
+    }
+
+    // Initialize metadata storage
+    info!("💾 Initializing metadata storage...");
+    let metadata_store: Arc<dyn MetadataStore> =
+        Arc::new(JsonlStorage::new(&config.storage.metadata_path));
+    metadata_store.initialize()?;
+    let review_count = metadata_store.count_lines()?;
+    info!("✅ Metadata storage ready ({} reviews)", review_count);
+
+    let collections = Arc::new(CollectionManager::open(&config.storage.collections_dir, &config)?);
+
+    // The model and index load in the background; listeners start right away
+    // and /readyz reports progress
+    let vector_index = Arc::new(RwLock::new(VectorIndex::new(&config.index)));
+    let index_path = config.storage.index_path.clone(); // Clone for shutdown handler
+    let embedding_service = Arc::new(OnceLock::new());
+    let readiness = Arc::new(Readiness::new(&["embedding", "index"]));
+
+    let synonyms = match &config.storage.synonyms_path {
+        Some(path) => Synonyms::load(path)?,
+        None => Synonyms::default(),
+    };
+    if config.search.query_expansion != QueryExpansion::Off && synonyms.is_empty() {
+        warn!("search.query_expansion is set but no synonyms are loaded");
+    }
+
+    // Create application state
+    let write_gate = Arc::new(WriteGate::new());
+    let state = AppState {
+        vector_index: vector_index.clone(),
+        metadata_store: metadata_store.clone(),
+        collections,
+        embedding_service: embedding_service.clone(),
+        models: Arc::new(ModelCache::new(config.embedding.max_length, config.embedding.instances)),
+        readiness: readiness.clone(),
+        write_gate: write_gate.clone(),
+        config: config_handle.clone(),
+        log_control: log_control.clone(),
+        audit_log: Arc::new(AuditLog::new(&config.storage.audit_path)),
+        webhooks: Arc::new(WebhookDispatcher::new(config_handle.clone())),
+        jobs: Arc::new(JobRegistry::new()),
+        usage: Arc::new(UsageTracker::new()),
+        redactor: Arc::new(RedactorCache::new()),
+        synonyms: Arc::new(synonyms),
+        spelling: Arc::new(SpellChecker::new()),
+        suggester: Arc::new(Suggester::new()),
+        content_hashes: Arc::new(ContentHashes::new()),
+        point_ids: Arc::new(PointIds::new()),
+        field_vectors: Arc::new(FieldVectors::new()),
+        analytics: Arc::new(SearchAnalytics::new()),
+        idempotency: Arc::new(IdempotencyCache::new()),
+        search_cache: Arc::new(SearchCache::new()),
+        memory: Arc::new(MemoryWatchdog::new()),
+        backpressure: Arc::new(Backpressure::new()),
+        ingest: Arc::new(IngestQueue::new()),
+        group_commit: Arc::new(GroupCommit::new()),
+        latency: Arc::new(LatencyHistograms::new()),
+    };
+
+    let app = build_router(state.clone());
+    let admin_app = config
+        .server
+        .admin_addr
+        .is_some()
+        .then(|| build_admin_router(state.clone()));
+
+    // Broadcast shutdown to every listener
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    {
+        let write_gate = write_gate.clone();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            // Reject new writes before the listeners stop
+            write_gate.close();
+            let _ = shutdown_tx.send(true);
+        });
+    }
+
+    // Config reloads (SIGHUP or POST /admin/config/reload)
+    spawn_log_level_watcher(config_handle.clone(), log_control.clone());
+    #[cfg(unix)]
+    spawn_sighup_reloader(config_handle.clone());
+
+    // Periodic snapshots
+    let read_only = config.is_read_only();
+    let snapshot_task = (!read_only).then(|| {
+        spawn_snapshot_task(
+            config_handle.subscribe(),
+            vector_index.clone(),
+            metadata_store.clone(),
+            index_path.clone(),
+            shutdown_rx.clone(),
+        )
+    });
+
+    // Expired reviews age out; read-only nodes still hide them from searches
+    let ttl_task = (!read_only).then(|| spawn_ttl_sweeper(state.clone(), shutdown_rx.clone()));
+
+    // A shadow index follows the default collection until cut over
+    let shadow_task = (!read_only).then(|| spawn_shadow_sync(state.clone(), shutdown_rx.clone()));
+
+    // Writes pause while memory is over `memory` limits
+    let memory_task = spawn_memory_watchdog(state.clone(), shutdown_rx.clone());
+
+    // Title and body vectors for `search.multi_vector` follow the store
+    let field_vectors_task = spawn_field_vectors_task(state.clone(), shutdown_rx.clone());
+
+    // The spell-correction vocabulary is ready before the first query
+    if config.search.spell_correction.enabled {
+        let metadata = state.metadata_store.clone();
+        state.spelling.refresh(move || metadata.read_all());
+    }
+    // So are the exact-duplicate hashes before the first write
+    if config.dedup.exact {
+        let metadata = state.metadata_store.clone();
+        state.content_hashes.refresh(move || metadata.read_all());
+    }
+
+    // Adds acknowledged with `durability=async` are indexed in the background
+    let ingest_task = (!read_only).then(|| spawn_async_indexer(state.clone(), shutdown_rx.clone()));
+
+    // Followers pull from the primary instead of taking writes
+    let follower_task = (config.replication.role == ReplicationRole::Follower)
+        .then(|| spawn_follower_task(state.clone(), shutdown_rx.clone()));
+
+    // Start servers
+    let mut servers: JoinSet<anyhow::Result<()>> = JoinSet::new();
+
+    if config.server.tcp_enabled {
+        let addr = format!("{}:{}", config.server.host, config.server.port);
+        let source = ListenerSource::from_config(&config.server)?;
+        // Take an inherited socket over right away; a shared port is only
+        // bound once this instance can serve it
+        let inherited = match source {
+            ListenerSource::Inherited(_) => Some(source.listen(&addr).await?),
+            _ => None,
+        };
+        let (app, readiness, shutdown_rx) = (app.clone(), readiness.clone(), shutdown_rx.clone());
+        servers.spawn(async move {
+            if source.is_handoff() {
+                info!(?source, "Waiting for the model and index before accepting on {}", addr);
+                if !wait_until_ready(&readiness, shutdown_rx.clone()).await {
+                    return Ok(());
+                }
+            }
+            let listener = match inherited {
+                Some(listener) => listener,
+                None => source.listen(&addr).await?,
+            };
+            info!("🌐 Server listening on http://{}", listener.local_addr()?);
+
+            axum::serve(listener, app)
+                .with_graceful_shutdown(wait_for_shutdown(shutdown_rx))
+                .await?;
+            Ok(())
+        });
+    }
+
+    #[cfg(unix)]
+    if let Some(socket_path) = config.server.unix_socket.clone() {
+        // Remove a stale socket left behind by a previous run
+        if socket_path.exists() {
+            std::fs::remove_file(&socket_path)?;
+        }
+        let listener = tokio::net::UnixListener::bind(&socket_path)?;
+        info!("🔌 Server listening on unix:{}", socket_path.display());
+
+        let server = axum::serve(listener, app.clone())
+            .with_graceful_shutdown(wait_for_shutdown(shutdown_rx.clone()));
+        servers.spawn(async move {
+            let result = server.await;
+            let _ = std::fs::remove_file(&socket_path);
+            Ok(result?)
+        });
+    }
+
+    #[cfg(not(unix))]
+    if config.server.unix_socket.is_some() {
+        anyhow::bail!("server.unix_socket is only supported on unix platforms");
+    }
+
+    if servers.is_empty() {
+        anyhow::bail!("No listener configured: enable server.tcp_enabled or set server.unix_socket");
+    }
+
+    if let (Some(addr), Some(admin_app)) = (&config.server.admin_addr, admin_app) {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        info!("🛠️  Admin listening on http://{}", addr);
+
+        let server = axum::serve(listener, admin_app)
+            .with_graceful_shutdown(wait_for_shutdown(shutdown_rx.clone()));
+        servers.spawn(async move { Ok(server.await?) });
+    }
+
+    if let Some(addr) = &config.server.grpc_addr {
+        let addr = addr
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid server.grpc_addr '{}': {}", addr, e))?;
+        info!("📡 gRPC listening on {}", addr);
+
+        let server = tonic::transport::Server::builder()
+            .add_service(GrpcService::new(state.clone()))
+            .serve_with_shutdown(addr, wait_for_shutdown(shutdown_rx.clone()));
+        servers.spawn(async move { Ok(server.await?) });
+    }
+
+    // Background loader; a load failure stops the process
+    servers.spawn(load_components(
+        config.clone(),
+        embedding_service,
+        metadata_store.clone(),
+        vector_index.clone(),
+        readiness.clone(),
+    ));
+
+    info!("");
+    info!("📡 Available endpoints:");
+    info!("   GET  /health           - Health check (?deep=true exercises the pipeline)");
+    info!("   GET  /readyz           - Readiness (model and index loaded)");
+    info!("   GET  /metrics          - Prometheus metrics");
+    info!("   POST /reviews      - Add new review");
+    info!("   POST /reviews/search   - Search reviews");
+    if !config.coordinator.shards.is_empty() {
+        info!("   (coordinator: searches fan out to {} shards)", config.coordinator.shards.len());
+    }
+    info!("   GET  /ws               - WebSocket search channel");
+    info!("   POST /jobs/import      - Background bulk import (JSONL/CSV body)");
+    info!("   POST /jobs/rebuild     - Background index rebuild");
+    info!("   GET  /jobs/{{id}}/events - Job progress (SSE)");
+    if config.qdrant.enabled {
+        info!("   *    /qdrant/collections/{} - Qdrant-compatible points API", config.qdrant.collection);
+    }
+    if config.replication.role == ReplicationRole::Primary {
+        info!("   GET  /replication/*    - Status, metadata and snapshots for followers");
+    }
+    info!("   POST /admin/config/reload - Reload configuration");
+    info!("   POST /admin/reembed    - Re-embed all reviews (?model= switches models)");
+    info!("   PUT  /admin/log-level  - Change log filter");
+    info!("   GET  /admin/audit      - Audit log of mutations");
+    info!("");
+    info!("✨ Server is accepting connections (loading model and index...)");
+
+    while let Some(result) = servers.join_next().await {
+        result??;
+    }
+
+    if let Some(task) = snapshot_task {
+        let _ = task.await;
+    }
+    if let Some(task) = ttl_task {
+        let _ = task.await;
+    }
+    if let Some(task) = shadow_task {
+        let _ = task.await;
+    }
+    let _ = memory_task.await;
+    let _ = field_vectors_task.await;
+    if let Some(task) = ingest_task {
+        let _ = task.await;
+    }
+    if let Some(task) = follower_task {
+        let _ = task.await;
+    }
+
+    // Wait for detached writes to finish before persisting
+    info!("⏳ Waiting for {} in-flight write(s) to finish...", write_gate.in_flight());
+    let drain_timeout = Duration::from_millis(config.server.shutdown_timeout_ms);
+    if tokio::time::timeout(drain_timeout, write_gate.drained()).await.is_err() {
+        warn!(
+            in_flight = write_gate.in_flight(),
+            "Timed out waiting for in-flight writes"
+        );
+    }
+
+    // Save index on graceful shutdown (the write lock waits out any straggler).
+    // An index that never finished loading must not overwrite the archive.
+    if read_only {
+        info!("Read-only; leaving the index archive untouched");
+    } else if readiness.is_component_ready("index") {
+        info!("💾 Saving vector index before shutdown...");
+        match vector_index.write().await.save(&index_path) {
+            Ok(()) => info!("✅ Index saved successfully"),
+            Err(e) => error!("⚠️  Failed to save index: {}", e),
+        }
+    } else {
+        warn!("Index never finished loading; skipping save");
+    }
+
+    // Flush metadata
+    match metadata_store.flush() {
+        Ok(()) => info!("✅ Metadata flushed"),
+        Err(e) => error!("⚠️  Failed to flush metadata: {}", e),
+    }
+
+    info!("👋 Server shutting down gracefully");
+    
+    Ok(())
+}
+
+/// Wait until the model and index are loaded; false if shutdown came first
+async fn wait_until_ready(readiness: &Readiness, mut shutdown_rx: watch::Receiver<bool>) -> bool {
+    while !readiness.is_ready() {
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {}
+            _ = shutdown_rx.wait_for(|&stop| stop) => return false,
+        }
+    }
+    true
+}
+
+/// Load the embedding model and vector index concurrently, updating readiness
+///
+/// Resolves once both are loaded; on failure the component is marked failed
+/// and the error is returned so the server exits.
+async fn load_components(
+    config: AppConfig,
+    embedding_service: Arc<OnceLock<Arc<EmbeddingService>>>,
+    metadata_store: Arc<dyn MetadataStore>,
+    vector_index: Arc<RwLock<VectorIndex>>,
+    readiness: Arc<Readiness>,
+) -> anyhow::Result<()> {
+    let read_only = config.is_read_only();
+    let embedding = {
+        let config = config.clone();
+        let embedding_service = embedding_service.clone();
+        let readiness = readiness.clone();
+        async move {
+            info!("🧠 Initializing embedding model...");
+            let result = tokio::task::spawn_blocking(move || {
+                EmbeddingService::with_instances(
+                    &config.embedding.model_name,
+                    config.embedding.max_length,
+                    config.embedding.instances,
+                )
+            })
+            .await?;
+            match result {
+                Ok(service) => {
+                    info!("✅ Embedding model ready (dim: {}, instances: {})", service.dimension(), service.instances());
+                    let _ = embedding_service.set(Arc::new(service));
+                    readiness.set("embedding", ComponentState::Ready);
+                    Ok(())
+                }
+                Err(e) => {
+                    readiness.set("embedding", ComponentState::Failed(format!("{:#}", e)));
+                    Err(e.context("Failed to load embedding model"))
+                }
+            }
+        }
+    };
+
+    let index = {
+        let vector_index = vector_index.clone();
+        let metadata_store = metadata_store.clone();
+        let readiness = readiness.clone();
+        let index_path = config.storage.index_path.clone();
+        async move {
+            info!("🔍 Initializing vector index...");
+            let result = tokio::task::spawn_blocking(move || {
+                // A snapshot restore the last run committed but didn't finish
+                snapshot::finish_restore(metadata_store.as_ref(), &index_path)
+                    .map_err(|e| e.context("Failed to finish an interrupted snapshot restore"))?;
+                let mut vector_index = vector_index.blocking_write();
+                // Load existing index or initialize new one
+                if index_path.exists() {
+                    info!("📂 Loading existing index from {:?}", index_path);
+                    vector_index.load(&index_path)
+                } else {
+                    info!("🆕 Creating new index");
+                    vector_index.initialize()
+                }
+            })
+            .await?;
+            result.inspect_err(|e| {
+                readiness.set("index", ComponentState::Failed(format!("{:#}", e)));
+            })
+        }
+    };
+
+    tokio::try_join!(embedding, index)?;
+
+    // Metadata lines past the end of the index are adds that never reached
+    // a saved index (the metadata line is written first); embed them again
+    if !read_only {
+        let embedder = embedding_service.get().cloned().expect("embedding model loaded");
+        let metadata_store = metadata_store.clone();
+        let vector_index = vector_index.clone();
+        let index_path = config.storage.index_path.clone();
+        let normalization = config.embedding.normalization.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            replay_pending_writes(&embedder, &normalization, metadata_store.as_ref(), &vector_index, &index_path)
+        })
+        .await?;
+        if let Err(e) = result {
+            readiness.set("index", ComponentState::Failed(format!("{:#}", e)));
+            return Err(e.context("Failed to replay pending writes"));
+        }
+    }
+
+    // Seeding needs the model, so the index only becomes ready afterwards
+    if config.storage.seed_path.is_some() && read_only {
+        warn!("Read-only; ignoring storage.seed_path");
+    } else if let Some(seed_path) = config.storage.seed_path.clone() {
+        let embedder = embedding_service.get().cloned().expect("embedding model loaded");
+        let seed_config = config.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            seed_index(&seed_path, &seed_config, &embedder, metadata_store.as_ref(), &vector_index)
+        })
+        .await?;
+        if let Err(e) = result {
+            readiness.set("index", ComponentState::Failed(format!("{:#}", e)));
+            return Err(e.context("Failed to seed index"));
+        }
+    }
+
+    info!("✅ Vector index ready");
+    readiness.set("index", ComponentState::Ready);
+    info!("✨ Server is ready to accept requests!");
+    Ok(())
+}
+
+/// Add the vectors for metadata lines the index doesn't have yet
+fn replay_pending_writes(
+    embedding_service: &EmbeddingService,
+    normalization: &TextNormalization,
+    metadata_store: &dyn MetadataStore,
+    vector_index: &RwLock<VectorIndex>,
+    index_path: &Path,
+) -> anyhow::Result<()> {
+    let mut index = vector_index.blocking_write();
+    let vector_count = index.vector_count();
+    let metadata_count = metadata_store.count_lines()?;
+    if vector_count > metadata_count {
+        warn!(vector_count, metadata_count, "Index has vectors without metadata (run `rebuild`)");
+        return Ok(());
+    }
+    // From zero too: a crash before the first index save loses every vector
+    if vector_count == metadata_count {
+        return Ok(());
+    }
+
+    let pending = metadata_store.read_range(vector_count, metadata_count - vector_count)?;
+    info!(count = pending.len(), "🩹 Replaying writes missing from the index");
+    for embedding in embed_reviews(embedding_service, &pending, normalization, SEED_BATCH_SIZE)? {
+        index.add_vector(&embedding)?;
+    }
+    delete_tombstoned(&mut index, &pending, vector_count)?;
+    index.save(index_path)?;
+    Ok(())
+}
+
+/// Bulk-load the seed dataset into an empty index
+fn seed_index(
+    seed_path: &Path,
+    config: &AppConfig,
+    embedding_service: &EmbeddingService,
+    metadata_store: &dyn MetadataStore,
+    vector_index: &RwLock<VectorIndex>,
+) -> anyhow::Result<()> {
+    let mut index = vector_index.blocking_write();
+    if index.vector_count() > 0 {
+        return Ok(());
+    }
+    if metadata_store.count_lines()? > 0 {
+        warn!("Index is empty but metadata is not; skipping seed (run `rebuild` instead)");
+        return Ok(());
+    }
+
+    info!("🌱 Seeding index from {}", seed_path.display());
+    let reviews = read_reviews(seed_path, ImportFormat::detect(seed_path), config)?;
+    if reviews.is_empty() {
+        warn!("Seed file is empty");
+        return Ok(());
+    }
+
+    let embeddings = embed_reviews(embedding_service, &reviews, &config.embedding.normalization, SEED_BATCH_SIZE)?;
+    index.build_from_vectors(&embeddings)?;
+    // Metadata first: if the save below never happens, the next start
+    // replays the seed from it instead of seeding twice
+    metadata_store.append_batch(&reviews)?;
+    metadata_store.flush()?;
+    index.save(&config.storage.index_path)?;
+
+    info!(count = reviews.len(), "✅ Seeded index");
+    Ok(())
+}
+
+/// Apply `logging.level` whenever the configuration is reloaded
+fn spawn_log_level_watcher(config: Arc<ConfigHandle>, log_control: Arc<LogControl>) {
+    let mut config_rx = config.subscribe();
+    tokio::spawn(async move {
+        while config_rx.changed().await.is_ok() {
+            let level = config_rx.borrow_and_update().logging.level.clone();
+            if let Some(level) = level
+                && let Err(e) = log_control.set_level(&level)
+            {
+                warn!("Failed to apply reloaded log level: {:#}", e);
+            }
+        }
+    });
+}
+
+/// Reload configuration on SIGHUP
+#[cfg(unix)]
+fn spawn_sighup_reloader(config: Arc<ConfigHandle>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    tokio::spawn(async move {
+        let mut hangup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+        while hangup.recv().await.is_some() {
+            match config.reload() {
+                Ok(changed) => info!(?changed, "🔄 Configuration reloaded (SIGHUP)"),
+                Err(e) => warn!("Configuration reload rejected: {}", e),
+            }
+        }
+    });
+}
+
+/// Resolves once the shutdown flag has been raised
+async fn wait_for_shutdown(mut shutdown_rx: watch::Receiver<bool>) {
+    let _ = shutdown_rx.wait_for(|&stop| stop).await;
+}
+
+/// Graceful shutdown handler
+async fn shutdown_signal() {
+    use tokio::signal;
+
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    // Closing the console window, logoff/shutdown, or Ctrl+Break. Windows
+    // ends the process a few seconds after a close event, so the final save
+    // has to fit in that window.
+    #[cfg(windows)]
+    let terminate = async {
+        let install = "Failed to install console event handler";
+        let mut close = signal::windows::ctrl_close().expect(install);
+        let mut shutdown = signal::windows::ctrl_shutdown().expect(install);
+        let mut ctrl_break = signal::windows::ctrl_break().expect(install);
+        tokio::select! {
+            _ = close.recv() => {},
+            _ = shutdown.recv() => {},
+            _ = ctrl_break.recv() => {},
+        }
+    };
+
+    #[cfg(not(any(unix, windows)))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("🛑 Shutdown signal received");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{archive, DistanceMetric};
+    use crate::test_utils::{review, HashEmbedder, MemoryIndex, MemoryStore};
+
+    #[test]
+    fn test_replay_into_empty_index() {
+        let embedder = EmbeddingService::from_provider("hash", 8, HashEmbedder::new(8, 0));
+        let store = MemoryStore::new();
+        for title in ["First", "Second"] {
+            store.append(&review(title, "P1")).unwrap();
+        }
+        let index = RwLock::new(VectorIndex::from_index(MemoryIndex::new(8, DistanceMetric::Cosine)));
+        let dir = archive::temp_dir("replay").unwrap();
+
+        replay_pending_writes(&embedder, &TextNormalization::default(), &store, &index, &dir.join("index")).unwrap();
+        assert_eq!(index.blocking_read().vector_count(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_seed_saves_index() {
+        let embedder = EmbeddingService::from_provider("hash", 8, HashEmbedder::new(8, 0));
+        let store = MemoryStore::new();
+        let index = RwLock::new(VectorIndex::from_index(MemoryIndex::new(8, DistanceMetric::Cosine)));
+        let dir = archive::temp_dir("seed").unwrap();
+        let seed_path = dir.join("seed.jsonl");
+        let lines: Vec<String> = ["First", "Second"]
+            .iter()
+            .map(|title| serde_json::to_string(&review(title, "P1")).unwrap())
+            .collect();
+        std::fs::write(&seed_path, lines.join("\n")).unwrap();
+        let mut config = AppConfig::default();
+        config.storage.index_path = dir.join("index");
+
+        seed_index(&seed_path, &config, &embedder, &store, &index).unwrap();
+        assert_eq!(index.blocking_read().vector_count(), 2);
+        assert_eq!(store.count_lines().unwrap(), 2);
+        assert!(config.storage.index_path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::config::AppConfig;
+use crate::storage::{verify, JsonlStorage, MetadataStore, VectorIndex};
+use anyhow::Result;
+use tracing::{error, info};
+
+/// Check the index archive and metadata file against each other
+///
+/// Exits with an error if any problem is found.
+pub fn run(config: &AppConfig) -> Result<()> {
+    let mut problems = Vec::new();
+
+    let metadata_store = JsonlStorage::new(&config.storage.metadata_path);
+
+    // With a loadable index, check it against the metadata; otherwise the
+    // metadata can still be checked on its own
+    let index_count = if config.storage.index_path.exists() {
+        let mut index = VectorIndex::new(&config.index);
+        match index.load(&config.storage.index_path) {
+            Ok(()) => {
+                let report = verify::verify(&*index, &metadata_store)?;
+                problems.extend(report.problems(config.index.vector_dim));
+                Some(report.index_vectors)
+            }
+            Err(e) => {
+                problems.push(format!("index failed to load: {:#}", e));
+                problems.extend(metadata_store.verify()?.problems());
+                None
+            }
+        }
+    } else {
+        let report = metadata_store.verify()?;
+        problems.extend(report.problems());
+        if report.records > 0 {
+            problems.push(format!(
+                "index file {} is missing",
+                config.storage.index_path.display()
+            ));
+            None
+        } else {
+            Some(0)
+        }
+    };
+    let metadata_count = metadata_store.count_lines()?;
+
+    info!(metadata = metadata_count, index = ?index_count, "Verification finished");
+
+    if problems.is_empty() {
+        info!("✅ Index and metadata are consistent");
+        return Ok(());
+    }
+    for problem in &problems {
+        error!("❌ {}", problem);
+    }
+    anyhow::bail!("{} consistency problem(s) found", problems.len())
+}
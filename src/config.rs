@@ -1,6 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
+use std::sync::Arc;
 use anyhow::Context;
+use tokio::sync::watch;
+
+use crate::redaction::Redactor;
+use crate::storage::{CollectionManifest, DistanceMetric, IndexBackend};
+use vector_search_client::models::{NormalizationSettings, UsageQuota};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +23,97 @@ pub struct AppConfig {
     
     /// Storage paths
     pub storage: StorageConfig,
+
+    /// Request body size limits
+    #[serde(default)]
+    pub limits: LimitsConfig,
+
+    /// Per-route request timeouts
+    #[serde(default)]
+    pub timeouts: TimeoutsConfig,
+
+    /// Background snapshot settings
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+
+    /// Search tuning
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// Logging settings
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// Slow-query logging
+    #[serde(default)]
+    pub slow_log: SlowLogConfig,
+
+    /// Qdrant-compatible REST routes
+    #[serde(default)]
+    pub qdrant: QdrantConfig,
+
+    /// LLM answers grounded in retrieved reviews
+    #[serde(default)]
+    pub rag: RagConfig,
+
+    /// Notifications sent on data mutations
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+
+    /// Leader-follower replication
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+
+    /// Scatter-gather search over shard servers
+    #[serde(default)]
+    pub coordinator: CoordinatorConfig,
+
+    /// Near-duplicate check on ingest
+    #[serde(default)]
+    pub dedup: DedupConfig,
+
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
+
+    #[serde(default)]
+    pub ttl: TtlConfig,
+
+    /// Masking personal data in review text on ingest
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// Length, rating and required-field rules for added reviews
+    #[serde(default)]
+    pub validation: ValidationRules,
+
+    /// Review language detection and per-language collections
+    #[serde(default)]
+    pub language: LanguageConfig,
+
+    /// A/B split of searches between the server's model and a candidate
+    #[serde(default)]
+    pub experiment: ExperimentConfig,
+
+    /// Collection following the default one with a new model, for a
+    /// zero-downtime migration
+    #[serde(default)]
+    pub shadow: ShadowConfig,
+
+    /// Recent searches kept for the /admin/analytics reports
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+
+    /// Replaying write responses for retried `Idempotency-Key`s
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+
+    /// Pausing writes when the process uses too much memory
+    #[serde(default)]
+    pub memory: MemoryConfig,
+
+    /// Rejecting writes instead of queueing them without bound
+    #[serde(default)]
+    pub backpressure: BackpressureConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,11 +125,65 @@ pub struct ServerConfig {
     /// Server port
     #[serde(default = "default_port")]
     pub port: u16,
+
+    /// Serve over TCP (disable to listen only on the unix socket)
+    #[serde(default = "default_tcp_enabled")]
+    pub tcp_enabled: bool,
+
+    /// Optional unix domain socket path (e.g., for a sidecar behind nginx)
+    #[serde(default)]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Bind the TCP listener with SO_REUSEPORT so a new instance can share
+    /// the port with the old one during a deploy; it only binds once the
+    /// model and index are loaded (unix only)
+    #[serde(default)]
+    pub reuse_port: bool,
+
+    /// Serve on an already-listening TCP socket inherited as this file
+    /// descriptor instead of binding `host:port`; systemd socket activation
+    /// (`LISTEN_FDS`) is picked up without it (unix only)
+    #[serde(default)]
+    pub listen_fd: Option<i32>,
+
+    /// Separate bind address (e.g., "127.0.0.1:9000") for /admin/* routes;
+    /// when unset they are served on the public listener
+    #[serde(default)]
+    pub admin_addr: Option<String>,
+
+    /// Bind address for the gRPC service (e.g., "0.0.0.0:50051");
+    /// disabled when unset
+    #[serde(default)]
+    pub grpc_addr: Option<String>,
+
+    /// Max time to wait for in-flight writes on shutdown (milliseconds)
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+
+    /// Tokio worker threads for HTTP handling (default: one per CPU core)
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+
+    /// Max threads in the blocking pool used for embedding inference and
+    /// index I/O (default: tokio's 512)
+    #[serde(default)]
+    pub max_blocking_threads: Option<usize>,
+
+    /// Reject mutations and skip index saves (replicas serving a snapshot
+    /// produced elsewhere); implied by `replication.role = "follower"`
+    #[serde(default)]
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexConfig {
-    /// Index type: "BKT" (default) or "KDT"
+    /// Index library: "spfresh" (default), "faiss" (`faiss` feature) or
+    /// "usearch" (`usearch` feature); fixed once the index is built
+    #[serde(default)]
+    pub backend: IndexBackend,
+
+    /// Index type: "BKT" (default) or "KDT"; for FAISS, an index factory
+    /// string such as "HNSW32" or "IVF1024,Flat"; unused by USearch
     #[serde(default = "default_index_type")]
     pub index_type: String,
     
@@ -42,32 +194,1221 @@ pub struct IndexConfig {
     /// Number of trees (for BKT/KDT)
     #[serde(default = "default_num_trees")]
     pub num_trees: usize,
+
+    /// Distance function: "l2" (default) or "cosine"; fixed once the
+    /// index is built, so run `rebuild` after changing it
+    #[serde(default)]
+    pub metric: DistanceMetric,
+
+    /// Backend search parameters: FAISS's, e.g. "nprobe=16" or
+    /// "efSearch=128", or USearch's "expansion_search=128"
+    #[serde(default)]
+    pub search_params: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    /// Model name (e.g., "all-MiniLM-L6-v2")
+    #[serde(default = "default_model_name")]
+    pub model_name: String,
+    
+    /// Maximum sequence length
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+
+    /// Copies of the model the server loads and uses in turn, so that
+    /// several requests are embedded in parallel; each costs the model's
+    /// memory again
+    #[serde(default = "default_embedding_instances")]
+    pub instances: usize,
+
+    /// Preprocessing for the default collection's reviews and queries, and
+    /// for new collections that don't set their own (changing it needs a
+    /// `rebuild`)
+    #[serde(default)]
+    pub normalization: TextNormalization,
+}
+
+/// Preprocessing applied to text before it is embedded, identically for
+/// reviews and queries; stored text is left as sent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextNormalization {
+    #[serde(default)]
+    pub lowercase: bool,
+
+    /// Drop HTML tags and decode common entities
+    #[serde(default)]
+    pub strip_html: bool,
+
+    /// Trim and turn every whitespace run into one space
+    #[serde(default)]
+    pub collapse_whitespace: bool,
+
+    /// Unicode NFC normalization
+    #[serde(default)]
+    pub unicode_nfc: bool,
+
+    /// Drop common English stopwords
+    #[serde(default)]
+    pub remove_stopwords: bool,
+}
+
+impl TextNormalization {
+    /// No step enabled
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl From<NormalizationSettings> for TextNormalization {
+    fn from(settings: NormalizationSettings) -> Self {
+        Self {
+            lowercase: settings.lowercase,
+            strip_html: settings.strip_html,
+            collapse_whitespace: settings.collapse_whitespace,
+            unicode_nfc: settings.unicode_nfc,
+            remove_stopwords: settings.remove_stopwords,
+        }
+    }
+}
+
+impl From<&TextNormalization> for NormalizationSettings {
+    fn from(normalization: &TextNormalization) -> Self {
+        Self {
+            lowercase: normalization.lowercase,
+            strip_html: normalization.strip_html,
+            collapse_whitespace: normalization.collapse_whitespace,
+            unicode_nfc: normalization.unicode_nfc,
+            remove_stopwords: normalization.remove_stopwords,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Data directory
+    #[serde(default = "default_data_dir")]
+    pub data_dir: PathBuf,
+    
+    /// Vector index file path
+    #[serde(default = "default_index_path")]
+    pub index_path: PathBuf,
+    
+    /// Metadata JSONL file path
+    #[serde(default = "default_metadata_path")]
+    pub metadata_path: PathBuf,
+
+    /// Append-only audit log of mutating operations
+    #[serde(default = "default_audit_path")]
+    pub audit_path: PathBuf,
+
+    /// One subdirectory per named collection
+    #[serde(default = "default_collections_dir")]
+    pub collections_dir: PathBuf,
+
+    /// Index files of named collections kept somewhere other than
+    /// `<collections_dir>/<name>/reviews.index`, by collection name
+    #[serde(default)]
+    pub collection_index_paths: BTreeMap<String, PathBuf>,
+
+    /// Named snapshots taken through `/admin/snapshots`, one tar.gz each
+    #[serde(default = "default_snapshots_dir")]
+    pub snapshots_dir: PathBuf,
+
+    /// Async adds the background indexer gave up on, one JSON line each
+    #[serde(default = "default_dead_letter_path")]
+    pub dead_letter_path: PathBuf,
+
+    /// JSONL or CSV dataset loaded into an empty index at startup
+    #[serde(default)]
+    pub seed_path: Option<PathBuf>,
+
+    /// Synonym groups for `search.query_expansion`, read at startup
+    #[serde(default)]
+    pub synonyms_path: Option<PathBuf>,
+}
+
+impl StorageConfig {
+    /// Replace `{data_dir}` in the other paths, so a layout (snapshots
+    /// included) moves with `data_dir` alone
+    pub fn expand_templates(&mut self) {
+        let data_dir = self.data_dir.to_string_lossy().into_owned();
+        let expand = |path: &mut PathBuf| {
+            if let Some(template) = path.to_str().filter(|p| p.contains("{data_dir}")) {
+                *path = PathBuf::from(template.replace("{data_dir}", &data_dir));
+            }
+        };
+        for path in [
+            &mut self.index_path,
+            &mut self.metadata_path,
+            &mut self.audit_path,
+            &mut self.collections_dir,
+            &mut self.snapshots_dir,
+            &mut self.dead_letter_path,
+        ] {
+            expand(path);
+        }
+        for path in [&mut self.seed_path, &mut self.synonyms_path].into_iter().flatten() {
+            expand(path);
+        }
+        self.collection_index_paths.values_mut().for_each(expand);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Max body size for search requests (bytes)
+    #[serde(default = "default_search_body_bytes")]
+    pub search_body_bytes: usize,
+
+    /// Max body size for add-review requests (bytes)
+    #[serde(default = "default_review_body_bytes")]
+    pub review_body_bytes: usize,
+
+    /// Max body size for bulk import requests (bytes)
+    #[serde(default = "default_import_body_bytes")]
+    pub import_body_bytes: usize,
+
+    /// Max size of an image to embed, inline or downloaded (bytes)
+    #[serde(default = "default_image_bytes")]
+    pub image_bytes: usize,
+
+    /// Max size of a snapshot archive uploaded for restore (bytes)
+    #[serde(default = "default_restore_body_bytes")]
+    pub restore_body_bytes: u64,
+
+    /// Whether images may be given as URLs for the server to download
+    #[serde(default)]
+    pub image_urls: bool,
+
+    /// Hosts image URLs may point at (empty: any public host)
+    #[serde(default)]
+    pub image_url_hosts: Vec<String>,
+
+    /// Max queries in one gRPC `BatchSearch` call
+    #[serde(default = "default_batch_search_queries")]
+    pub batch_search_queries: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            search_body_bytes: default_search_body_bytes(),
+            review_body_bytes: default_review_body_bytes(),
+            import_body_bytes: default_import_body_bytes(),
+            image_bytes: default_image_bytes(),
+            restore_body_bytes: default_restore_body_bytes(),
+            image_urls: false,
+            image_url_hosts: Vec::new(),
+            batch_search_queries: default_batch_search_queries(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeoutsConfig {
+    /// Search request timeout (milliseconds)
+    #[serde(default = "default_search_timeout_ms")]
+    pub search_ms: u64,
+
+    /// Add-review request timeout (milliseconds)
+    #[serde(default = "default_ingest_timeout_ms")]
+    pub ingest_ms: u64,
+
+    /// Bulk import request timeout (milliseconds)
+    #[serde(default = "default_import_timeout_ms")]
+    pub import_ms: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            search_ms: default_search_timeout_ms(),
+            ingest_ms: default_ingest_timeout_ms(),
+            import_ms: default_import_timeout_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Interval between background snapshots in seconds (0 disables)
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Max random delay added to each interval (seconds)
+    #[serde(default = "default_snapshot_jitter_secs")]
+    pub jitter_secs: u64,
+
+    /// How long the first of several close writes waits for others before
+    /// saving the index once for all of them (milliseconds; 0 saves right
+    /// away, still sharing the save with writes that are already waiting)
+    #[serde(default = "default_group_commit_ms")]
+    pub group_commit_ms: u64,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_snapshot_interval_secs(),
+            jitter_secs: default_snapshot_jitter_secs(),
+            group_commit_ms: default_group_commit_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// Resident set size in bytes above which writes get 503 (0: no limit)
+    #[serde(default)]
+    pub max_rss_bytes: u64,
+
+    /// Estimated size of the default index's vectors in bytes above which
+    /// writes get 503 (0: no limit)
+    #[serde(default)]
+    pub max_index_bytes: u64,
+
+    /// Writes resume once usage is back under this fraction of the limits,
+    /// so they don't flap around the threshold
+    #[serde(default = "default_memory_resume_ratio")]
+    pub resume_ratio: f64,
+
+    /// Save the index when a limit is crossed, in case the process is
+    /// killed next
+    #[serde(default = "default_memory_emergency_snapshot")]
+    pub emergency_snapshot: bool,
+
+    /// How often usage is sampled, in seconds
+    #[serde(default = "default_memory_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+impl MemoryConfig {
+    /// Whether any limit is set
+    pub fn enabled(&self) -> bool {
+        self.max_rss_bytes > 0 || self.max_index_bytes > 0
+    }
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            max_rss_bytes: 0,
+            max_index_bytes: 0,
+            resume_ratio: default_memory_resume_ratio(),
+            emergency_snapshot: default_memory_emergency_snapshot(),
+            check_interval_secs: default_memory_check_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackpressureConfig {
+    /// Writes in progress (waiting on embedding or the index lock) above
+    /// which new adds get 429 (0: no limit)
+    #[serde(default = "default_max_pending_writes")]
+    pub max_pending_writes: usize,
+
+    /// Reviews being embedded at once above which new adds get 503
+    /// (0: no limit)
+    #[serde(default = "default_max_concurrent_embeddings")]
+    pub max_concurrent_embeddings: usize,
+
+    /// `Retry-After` sent with those rejections, in seconds
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+}
+
+impl Default for BackpressureConfig {
+    fn default() -> Self {
+        Self {
+            max_pending_writes: default_max_pending_writes(),
+            max_concurrent_embeddings: default_max_concurrent_embeddings(),
+            retry_after_secs: default_retry_after_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtlConfig {
+    /// How often expired reviews are tombstoned, in seconds (0 disables;
+    /// searches skip expired reviews either way)
+    #[serde(default = "default_ttl_sweep_interval_secs")]
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for TtlConfig {
+    fn default() -> Self {
+        Self {
+            sweep_interval_secs: default_ttl_sweep_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    /// Tag reviews added without a `language` with the detected one
+    #[serde(default)]
+    pub detect: bool,
+
+    /// Detector confidence (0-1) below which a review stays untagged
+    #[serde(default = "default_language_min_confidence")]
+    pub min_confidence: f64,
+
+    /// Collection per language tag, e.g. {"deu": "reviews-de"}: adds in
+    /// that language and searches filtered to it go there
+    #[serde(default)]
+    pub collections: BTreeMap<String, String>,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self {
+            detect: false,
+            min_confidence: default_language_min_confidence(),
+            collections: BTreeMap::new(),
+        }
+    }
+}
+
+impl LanguageConfig {
+    /// Collection configured for `language`, if any; tags are compared in
+    /// their [normalized](crate::language::normalize) form
+    pub fn collection_for(&self, language: &str) -> Option<&str> {
+        let language = crate::language::normalize(language);
+        self.collections
+            .iter()
+            .find(|(tag, _)| crate::language::normalize(tag) == language)
+            .map(|(_, collection)| collection.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Reported with every assignment and mixed into the split, so
+    /// renaming an experiment reshuffles who sees which arm
+    #[serde(default = "default_experiment_name")]
+    pub name: String,
+
+    /// Collection holding the same reviews embedded with the candidate
+    /// model; searches fall back to control while it doesn't exist
+    #[serde(default)]
+    pub candidate_collection: String,
+
+    /// Share of searches (0-1) served by the candidate
+    #[serde(default = "default_candidate_share")]
+    pub candidate_share: f64,
+
+    /// Optional JSONL file every assigned search is appended to
+    #[serde(default)]
+    pub log_path: Option<PathBuf>,
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: default_experiment_name(),
+            candidate_collection: String::new(),
+            candidate_share: default_candidate_share(),
+            log_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowConfig {
+    /// Collection that every review added to the default collection is
+    /// also embedded into, with the collection's own model, until
+    /// `POST /admin/shadow/cutover`
+    #[serde(default)]
+    pub collection: Option<String>,
+
+    /// Seconds between catch-up rounds
+    #[serde(default = "default_shadow_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            collection: None,
+            sync_interval_secs: default_shadow_sync_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Searches kept; the oldest roll off first (0 disables recording)
+    #[serde(default = "default_analytics_capacity")]
+    pub capacity: usize,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_analytics_capacity(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyConfig {
+    /// How long a key's response is kept for replay (0 disables)
+    #[serde(default = "default_idempotency_window_secs")]
+    pub window_secs: u64,
+
+    /// Keys remembered at once; the oldest are forgotten first
+    #[serde(default = "default_idempotency_max_keys")]
+    pub max_keys: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: default_idempotency_window_secs(),
+            max_keys: default_idempotency_max_keys(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Mask matches in titles and bodies before they are embedded and stored
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_redact_builtin")]
+    pub emails: bool,
+
+    #[serde(default = "default_redact_builtin")]
+    pub phones: bool,
+
+    /// Names after an honorific ("Dr. Smith") or "my name is"
+    #[serde(default = "default_redact_builtin")]
+    pub names: bool,
+
+    /// Extra patterns, applied after the built-in ones
+    #[serde(default)]
+    pub patterns: Vec<RedactionPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: default_redact_builtin(),
+            phones: default_redact_builtin(),
+            names: default_redact_builtin(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionPattern {
+    /// Reported as the redaction kind, e.g. "order_number"
+    pub name: String,
+
+    /// Regular expression (Rust `regex` syntax)
+    pub pattern: String,
+
+    /// Replacement text, may use `$1`-style groups (default: `[NAME]` with
+    /// the pattern name upper-cased)
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+/// Rules every added or updated review must pass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationRules {
+    /// Longest accepted title, in characters
+    #[serde(default = "default_max_title_chars")]
+    pub max_title_chars: usize,
+
+    /// Longest accepted body, in characters
+    #[serde(default = "default_max_body_chars")]
+    pub max_body_chars: usize,
+
+    #[serde(default = "default_min_rating")]
+    pub min_rating: u8,
+
+    #[serde(default = "default_max_rating")]
+    pub max_rating: u8,
+
+    /// Fields that must not be blank; a review always needs a title or a
+    /// body to embed
+    #[serde(default = "default_required_fields")]
+    pub required_fields: Vec<ReviewField>,
+
+    /// Declared `attributes`, by name
+    #[serde(default)]
+    pub attributes: BTreeMap<String, AttributeSpec>,
+
+    /// Accept attributes that aren't declared in `attributes`
+    #[serde(default = "default_allow_unknown_attributes")]
+    pub allow_unknown_attributes: bool,
+}
+
+/// Declared type of one attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeSpec {
+    #[serde(rename = "type")]
+    pub kind: AttributeType,
+
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+impl AttributeType {
+    pub fn accepts(self, value: &serde_json::Value) -> bool {
+        match self {
+            AttributeType::String => value.is_string(),
+            AttributeType::Number => value.is_number(),
+            AttributeType::Integer => value.is_i64() || value.is_u64(),
+            AttributeType::Boolean => value.is_boolean(),
+        }
+    }
+
+    pub(crate) fn is_numeric(self) -> bool {
+        matches!(self, AttributeType::Number | AttributeType::Integer)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            AttributeType::String => "string",
+            AttributeType::Number => "number",
+            AttributeType::Integer => "integer",
+            AttributeType::Boolean => "boolean",
+        }
+    }
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        Self {
+            max_title_chars: default_max_title_chars(),
+            max_body_chars: default_max_body_chars(),
+            min_rating: default_min_rating(),
+            max_rating: default_max_rating(),
+            required_fields: default_required_fields(),
+            attributes: BTreeMap::new(),
+            allow_unknown_attributes: default_allow_unknown_attributes(),
+        }
+    }
+}
+
+/// A text field of a review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewField {
+    ReviewTitle,
+    ReviewBody,
+    ProductId,
+}
+
+impl ReviewField {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ReviewField::ReviewTitle => "review_title",
+            ReviewField::ReviewBody => "review_body",
+            ReviewField::ProductId => "product_id",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    /// Largest `top_k` a search request may ask for
+    #[serde(default = "default_max_top_k")]
+    pub max_top_k: usize,
+
+    /// How L2 distances become `similarity_score`s; cosine scores are
+    /// always `1 - distance`
+    #[serde(default)]
+    pub score_normalization: ScoreNormalization,
+
+    /// Filtered searches fetch `top_k` times this many candidates before
+    /// filtering
+    #[serde(default = "default_filter_oversample")]
+    pub filter_oversample: usize,
+
+    /// Searches with `aggregate` fetch `top_k` times this many candidates
+    /// (on top of `filter_oversample`), so the aggregates cover more than
+    /// the returned page
+    #[serde(default = "default_aggregate_oversample")]
+    pub aggregate_oversample: usize,
+
+    /// How queries are expanded with `storage.synonyms_path`
+    #[serde(default)]
+    pub query_expansion: QueryExpansion,
+
+    /// Correct misspelled query terms against the indexed reviews' words
+    #[serde(default)]
+    pub spell_correction: SpellCorrectionConfig,
+
+    /// Search separate title and body embeddings and fuse their distances
+    #[serde(default)]
+    pub multi_vector: MultiVectorConfig,
+
+    /// Serve repeated searches from memory
+    #[serde(default)]
+    pub cache: SearchCacheConfig,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            max_top_k: default_max_top_k(),
+            score_normalization: ScoreNormalization::Reciprocal,
+            filter_oversample: default_filter_oversample(),
+            aggregate_oversample: default_aggregate_oversample(),
+            query_expansion: QueryExpansion::Off,
+            spell_correction: SpellCorrectionConfig::default(),
+            multi_vector: MultiVectorConfig::default(),
+            cache: SearchCacheConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreNormalization {
+    /// `1 / (1 + distance)`: comparable across queries
+    #[default]
+    Reciprocal,
+    /// Scale each result set so the closest hit scores 1 and the furthest 0
+    MinMax,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryExpansion {
+    #[default]
+    Off,
+    /// Embed the query with its terms' synonyms appended
+    Append,
+    /// Search the query and each synonym rewrite of it, keeping every
+    /// review's best distance
+    Fusion,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpellCorrectionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Edits allowed between a term and its correction (at most 2)
+    #[serde(default = "default_max_edit_distance")]
+    pub max_edit_distance: usize,
+
+    /// Shorter terms are never corrected
+    #[serde(default = "default_min_word_len")]
+    pub min_word_len: usize,
+}
+
+impl Default for SpellCorrectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_edit_distance: default_max_edit_distance(),
+            min_word_len: default_min_word_len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiVectorConfig {
+    /// Rank the default collection by title and body embeddings instead of
+    /// the combined one
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub fusion: FieldFusion,
+
+    /// Share of the title distance under `weighted_sum`; the body gets the
+    /// rest
+    #[serde(default = "default_title_weight")]
+    pub title_weight: f32,
+}
+
+impl Default for MultiVectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fusion: FieldFusion::Max,
+            title_weight: default_title_weight(),
+        }
+    }
+}
+
+/// How a review's title and body distances become one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldFusion {
+    /// Whichever field is closer
+    #[default]
+    Max,
+    /// `title_weight * title + (1 - title_weight) * body`
+    WeightedSum,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How long a response is served from the cache; any write or config
+    /// reload drops it sooner
+    #[serde(default = "default_search_cache_ttl_secs")]
+    pub ttl_secs: u64,
+
+    /// Responses kept at once; the oldest are dropped first
+    #[serde(default = "default_search_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for SearchCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_search_cache_ttl_secs(),
+            max_entries: default_search_cache_max_entries(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Log filter directive (e.g., "info" or "vector_search_api=debug");
+    /// falls back to RUST_LOG, then "info"
+    #[serde(default)]
+    pub level: Option<String>,
+
+    /// Log request and response bodies of selected routes
+    #[serde(default)]
+    pub bodies: BodyLogConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyLogConfig {
+    /// Path prefixes whose bodies are logged, e.g. ["/reviews/search"];
+    /// empty disables body logging
+    #[serde(default)]
+    pub routes: Vec<String>,
+
+    /// JSON fields whose values are replaced before logging, at any depth
+    #[serde(default = "default_body_log_redact_fields")]
+    pub redact_fields: Vec<String>,
+
+    /// Longest body logged; longer ones are cut off
+    #[serde(default = "default_body_log_max_bytes")]
+    pub max_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowLogConfig {
+    /// Log requests slower than this (milliseconds, 0 disables)
+    #[serde(default = "default_slow_threshold_ms")]
+    pub threshold_ms: u64,
+
+    /// Optional JSONL file that slow entries are also appended to
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QdrantConfig {
+    /// Serve /collections/* routes shaped like Qdrant's REST API
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Name the index is exposed under
+    #[serde(default = "default_qdrant_collection")]
+    pub collection: String,
+}
+
+impl Default for QdrantConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            collection: default_qdrant_collection(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RagConfig {
+    /// Serve POST /reviews/answer
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Reviews put in the prompt when the request doesn't say
+    #[serde(default = "default_rag_top_k")]
+    pub top_k: usize,
+
+    /// Prompt templates by name; a request picks one with `template`
+    #[serde(default = "default_rag_templates")]
+    pub templates: BTreeMap<String, PromptTemplate>,
+
+    /// Template used when the request doesn't name one
+    #[serde(default = "default_rag_template")]
+    pub default_template: String,
+
+    #[serde(default)]
+    pub llm: LlmConfig,
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            top_k: default_rag_top_k(),
+            templates: default_rag_templates(),
+            default_template: default_rag_template(),
+            llm: LlmConfig::default(),
+        }
+    }
+}
+
+impl RagConfig {
+    /// The template called `name`, or the default one
+    pub fn template(&self, name: Option<&str>) -> Option<&PromptTemplate> {
+        self.templates.get(name.unwrap_or(&self.default_template))
+    }
+}
+
+/// How the prompt for a generated answer is put together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    /// Sent as the system message
+    #[serde(default = "default_rag_system_prompt")]
+    pub system_prompt: String,
+
+    /// The user message: `{context}` becomes the retrieved reviews, one
+    /// `context_line` each, and `{question}` the query
+    #[serde(default = "default_rag_prompt")]
+    pub prompt: String,
+
+    /// One review in `{context}`, with `{vector_id}`, `{title}`, `{body}`,
+    /// `{rating}` and `{product_id}` filled in
+    #[serde(default = "default_rag_context_line")]
+    pub context_line: String,
+
+    /// Reviews that would take `{context}` past this many tokens (counted
+    /// as words) are left out, best match first; 0 for no limit
+    #[serde(default = "default_rag_max_context_tokens")]
+    pub max_context_tokens: usize,
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        Self {
+            system_prompt: default_rag_system_prompt(),
+            prompt: default_rag_prompt(),
+            context_line: default_rag_context_line(),
+            max_context_tokens: default_rag_max_context_tokens(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    #[serde(default)]
+    pub api: LlmApi,
+
+    /// Base URL, e.g. `https://api.openai.com/v1` or
+    /// `http://localhost:11434` for Ollama
+    #[serde(default = "default_llm_url")]
+    pub url: String,
+
+    #[serde(default = "default_llm_model")]
+    pub model: String,
+
+    /// Sent as a bearer token when set
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    #[serde(default = "default_llm_temperature")]
+    pub temperature: f32,
+
+    /// Limit on one LLM call, which is also the answer route's timeout;
+    /// streamed answers apply it to each wait for the next piece instead
+    #[serde(default = "default_llm_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            api: LlmApi::Ollama,
+            url: default_llm_url(),
+            model: default_llm_model(),
+            api_key: None,
+            temperature: default_llm_temperature(),
+            timeout_ms: default_llm_timeout_ms(),
+        }
+    }
+}
+
+/// Wire format of the LLM endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmApi {
+    /// `POST {url}/api/chat`
+    #[default]
+    Ollama,
+    /// `POST {url}/chat/completions` (OpenAI, vLLM, llama.cpp, LM Studio, ...)
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhooksConfig {
+    /// Endpoints notified after each mutation
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+
+    /// Retries after the first failed delivery
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry (milliseconds, doubled per attempt)
+    #[serde(default = "default_webhook_backoff_ms")]
+    pub backoff_ms: u64,
+
+    /// Per-attempt request timeout (milliseconds)
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            max_retries: default_webhook_max_retries(),
+            backoff_ms: default_webhook_backoff_ms(),
+            timeout_ms: default_webhook_timeout_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicationRole {
+    #[default]
+    Standalone,
+    /// Serves /replication/* to followers
+    Primary,
+    /// Pulls snapshots and metadata from `primary_url`
+    Follower,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationConfig {
+    #[serde(default)]
+    pub role: ReplicationRole,
+
+    /// Base URL of the primary, e.g. "http://primary:8000" (followers only)
+    #[serde(default)]
+    pub primary_url: Option<String>,
+
+    /// Shared secret sent as a bearer token; required by the primary when set
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// How often followers check the primary for new data
+    #[serde(default = "default_replication_poll_secs")]
+    pub poll_interval_secs: u64,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            role: ReplicationRole::Standalone,
+            primary_url: None,
+            token: None,
+            poll_interval_secs: default_replication_poll_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoordinatorConfig {
+    /// Base URLs of shard servers; when set, `/reviews/search` fans out to
+    /// them instead of searching locally
+    #[serde(default)]
+    pub shards: Vec<String>,
+
+    /// Per-shard request timeout (milliseconds)
+    #[serde(default = "default_shard_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Answer from the shards that responded when some fail
+    #[serde(default = "default_allow_partial")]
+    pub allow_partial: bool,
+}
+
+impl Default for CoordinatorConfig {
+    fn default() -> Self {
+        Self {
+            shards: Vec::new(),
+            timeout_ms: default_shard_timeout_ms(),
+            allow_partial: default_allow_partial(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupAction {
+    /// Refuse the review with 409
+    #[default]
+    Reject,
+    /// Store it anyway and report `duplicate_of` in the response
+    Flag,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupConfig {
+    /// Search each new review against the index before storing it
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Similarity at or above which a review counts as a duplicate
+    #[serde(default = "default_dedup_threshold")]
+    pub threshold: f32,
+
+    /// Check new reviews for an identical title, body and product ID
+    /// (independent of `enabled`)
+    #[serde(default)]
+    pub exact: bool,
+
+    #[serde(default)]
+    pub action: DedupAction,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: default_dedup_threshold(),
+            exact: false,
+            action: DedupAction::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenancyConfig {
+    /// Require one of `keys` on every data and admin route, and keep each
+    /// namespaced key's reviews in its own collection
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub keys: Vec<TenantKey>,
+
+    /// Per-namespace limits, overriding `default_quota`
+    #[serde(default)]
+    pub quotas: BTreeMap<String, TenantQuota>,
+
+    /// Limits for namespaces without an entry in `quotas`
+    #[serde(default)]
+    pub default_quota: TenantQuota,
+}
+
+impl TenancyConfig {
+    pub fn find(&self, key: &str) -> Option<&TenantKey> {
+        self.keys.iter().find(|k| k.key == key)
+    }
+
+    pub fn quota(&self, namespace: &str) -> &TenantQuota {
+        self.quotas.get(namespace).unwrap_or(&self.default_quota)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantKey {
+    /// Sent as `X-API-Key` or `Authorization: Bearer`
+    pub key: String,
+
+    /// Namespaces the key may add to and search; empty for an operator key
+    /// with unrestricted access to the default collection
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+}
+
+/// Limits for one tenant namespace; unset fields are unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenantQuota {
+    /// Reviews stored in the namespace (403 once reached)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_vectors: Option<usize>,
+
+    /// Requests per minute (429 once exceeded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+
+    /// Embedding tokens since the server started (403 once exceeded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_embedding_tokens: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EmbeddingConfig {
-    /// Model name (e.g., "all-MiniLM-L6-v2")
-    #[serde(default = "default_model_name")]
-    pub model_name: String,
-    
-    /// Maximum sequence length
-    #[serde(default = "default_max_length")]
-    pub max_length: usize,
+impl From<&TenantQuota> for UsageQuota {
+    fn from(quota: &TenantQuota) -> Self {
+        Self {
+            max_vectors: quota.max_vectors,
+            requests_per_minute: quota.requests_per_minute,
+            max_embedding_tokens: quota.max_embedding_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StorageConfig {
-    /// Data directory
-    #[serde(default = "default_data_dir")]
-    pub data_dir: PathBuf,
-    
-    /// Vector index file path
-    #[serde(default = "default_index_path")]
-    pub index_path: PathBuf,
-    
-    /// Metadata JSONL file path
-    #[serde(default = "default_metadata_path")]
-    pub metadata_path: PathBuf,
+pub struct WebhookEndpoint {
+    pub url: String,
+
+    /// Signs each body with HMAC-SHA256 (`X-Signature-256` header)
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Actions to send, e.g. ["add", "import"]; empty means all
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl Default for BodyLogConfig {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            redact_fields: default_body_log_redact_fields(),
+            max_bytes: default_body_log_max_bytes(),
+        }
+    }
+}
+
+impl Default for SlowLogConfig {
+    fn default() -> Self {
+        Self {
+            threshold_ms: default_slow_threshold_ms(),
+            path: None,
+        }
+    }
 }
 
 // Default values
@@ -79,6 +1420,38 @@ fn default_port() -> u16 {
     3000
 }
 
+fn default_idempotency_window_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_idempotency_max_keys() -> usize {
+    10_000
+}
+
+fn default_body_log_redact_fields() -> Vec<String> {
+    vec!["review_body".to_string()]
+}
+
+fn default_body_log_max_bytes() -> usize {
+    4096
+}
+
+fn default_language_min_confidence() -> f64 {
+    0.5
+}
+
+fn default_redact_builtin() -> bool {
+    true
+}
+
+fn default_tcp_enabled() -> bool {
+    true
+}
+
+fn default_shutdown_timeout_ms() -> u64 {
+    30_000
+}
+
 fn default_index_type() -> String {
     "BKT".to_string()
 }
@@ -99,6 +1472,10 @@ fn default_max_length() -> usize {
     512
 }
 
+fn default_embedding_instances() -> usize {
+    1
+}
+
 fn default_data_dir() -> PathBuf {
     PathBuf::from("data")
 }
@@ -111,34 +1488,386 @@ fn default_metadata_path() -> PathBuf {
     PathBuf::from("data/reviews.jsonl")
 }
 
+fn default_audit_path() -> PathBuf {
+    PathBuf::from("data/audit.jsonl")
+}
+
+fn default_collections_dir() -> PathBuf {
+    PathBuf::from("data/collections")
+}
+
+fn default_snapshots_dir() -> PathBuf {
+    PathBuf::from("data/snapshots")
+}
+
+fn default_dead_letter_path() -> PathBuf {
+    PathBuf::from("data/dead_letter.jsonl")
+}
+
+fn default_search_body_bytes() -> usize {
+    16 * 1024
+}
+
+fn default_review_body_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_image_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_restore_body_bytes() -> u64 {
+    4 * 1024 * 1024 * 1024
+}
+
+fn default_import_body_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_batch_search_queries() -> usize {
+    100
+}
+
+fn default_search_timeout_ms() -> u64 {
+    2_000
+}
+
+fn default_ingest_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_import_timeout_ms() -> u64 {
+    600_000
+}
+
+fn default_snapshot_interval_secs() -> u64 {
+    300
+}
+
+fn default_snapshot_jitter_secs() -> u64 {
+    30
+}
+
+fn default_ttl_sweep_interval_secs() -> u64 {
+    60
+}
+
+fn default_group_commit_ms() -> u64 {
+    5
+}
+
+fn default_memory_resume_ratio() -> f64 {
+    0.9
+}
+
+fn default_memory_emergency_snapshot() -> bool {
+    true
+}
+
+fn default_memory_check_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_pending_writes() -> usize {
+    256
+}
+
+fn default_max_concurrent_embeddings() -> usize {
+    64
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+fn default_max_top_k() -> usize {
+    100
+}
+
+fn default_filter_oversample() -> usize {
+    4
+}
+
+fn default_aggregate_oversample() -> usize {
+    10
+}
+
+fn default_max_edit_distance() -> usize {
+    2
+}
+
+fn default_min_word_len() -> usize {
+    4
+}
+
+fn default_title_weight() -> f32 {
+    0.5
+}
+
+fn default_search_cache_ttl_secs() -> u64 {
+    60
+}
+
+fn default_search_cache_max_entries() -> usize {
+    1000
+}
+
+fn default_rag_top_k() -> usize {
+    5
+}
+
+fn default_rag_templates() -> BTreeMap<String, PromptTemplate> {
+    BTreeMap::from([(default_rag_template(), PromptTemplate::default())])
+}
+
+fn default_rag_template() -> String {
+    "default".to_string()
+}
+
+fn default_rag_system_prompt() -> String {
+    "You answer questions about products using only the customer reviews provided. \
+     Cite the reviews you rely on by their ID in square brackets, e.g. [12]. \
+     If the reviews don't answer the question, say so."
+        .to_string()
+}
+
+fn default_rag_prompt() -> String {
+    "Reviews:\n{context}\n\nQuestion: {question}".to_string()
+}
+
+fn default_rag_context_line() -> String {
+    "[{vector_id}] {title}: {body} (rating {rating}/5, product {product_id})".to_string()
+}
+
+fn default_rag_max_context_tokens() -> usize {
+    2000
+}
+
+fn default_llm_url() -> String {
+    "http://localhost:11434".to_string()
+}
+
+fn default_llm_model() -> String {
+    "llama3.1".to_string()
+}
+
+fn default_llm_temperature() -> f32 {
+    0.2
+}
+
+fn default_llm_timeout_ms() -> u64 {
+    60_000
+}
+
+fn default_experiment_name() -> String {
+    "experiment".to_string()
+}
+
+fn default_candidate_share() -> f64 {
+    0.5
+}
+
+fn default_shadow_sync_interval_secs() -> u64 {
+    5
+}
+
+fn default_analytics_capacity() -> usize {
+    10_000
+}
+
+fn default_slow_threshold_ms() -> u64 {
+    500
+}
+
+fn default_qdrant_collection() -> String {
+    "reviews".to_string()
+}
+
+fn default_webhook_max_retries() -> u32 {
+    5
+}
+
+fn default_webhook_backoff_ms() -> u64 {
+    500
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_replication_poll_secs() -> u64 {
+    10
+}
+
+fn default_shard_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_allow_partial() -> bool {
+    true
+}
+
+fn default_dedup_threshold() -> f32 {
+    0.95
+}
+
+fn default_max_title_chars() -> usize {
+    500
+}
+
+fn default_max_body_chars() -> usize {
+    10_000
+}
+
+fn default_min_rating() -> u8 {
+    1
+}
+
+fn default_max_rating() -> u8 {
+    5
+}
+
+fn default_required_fields() -> Vec<ReviewField> {
+    vec![ReviewField::ReviewTitle, ReviewField::ReviewBody, ReviewField::ProductId]
+}
+
+fn default_allow_unknown_attributes() -> bool {
+    true
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             server: ServerConfig {
                 host: default_host(),
                 port: default_port(),
+                tcp_enabled: default_tcp_enabled(),
+                unix_socket: None,
+                reuse_port: false,
+                listen_fd: None,
+                admin_addr: None,
+                shutdown_timeout_ms: default_shutdown_timeout_ms(),
+                grpc_addr: None,
+                worker_threads: None,
+                max_blocking_threads: None,
+                read_only: false,
             },
             index: IndexConfig {
+                backend: IndexBackend::default(),
                 index_type: default_index_type(),
                 vector_dim: default_vector_dim(),
                 num_trees: default_num_trees(),
+                metric: DistanceMetric::L2,
+                search_params: None,
             },
             embedding: EmbeddingConfig {
                 model_name: default_model_name(),
                 max_length: default_max_length(),
+                instances: default_embedding_instances(),
+                normalization: TextNormalization::default(),
             },
             storage: StorageConfig {
                 data_dir: default_data_dir(),
                 index_path: default_index_path(),
                 metadata_path: default_metadata_path(),
+                audit_path: default_audit_path(),
+                collections_dir: default_collections_dir(),
+                collection_index_paths: BTreeMap::new(),
+                snapshots_dir: default_snapshots_dir(),
+                dead_letter_path: default_dead_letter_path(),
+                seed_path: None,
+                synonyms_path: None,
             },
+            limits: LimitsConfig::default(),
+            timeouts: TimeoutsConfig::default(),
+            snapshot: SnapshotConfig::default(),
+            search: SearchConfig::default(),
+            logging: LoggingConfig::default(),
+            slow_log: SlowLogConfig::default(),
+            qdrant: QdrantConfig::default(),
+            rag: RagConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            replication: ReplicationConfig::default(),
+            coordinator: CoordinatorConfig::default(),
+            dedup: DedupConfig::default(),
+            tenancy: TenancyConfig::default(),
+            ttl: TtlConfig::default(),
+            redaction: RedactionConfig::default(),
+            validation: ValidationRules::default(),
+            language: LanguageConfig::default(),
+            experiment: ExperimentConfig::default(),
+            shadow: ShadowConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            idempotency: IdempotencyConfig::default(),
+            memory: MemoryConfig::default(),
+            backpressure: BackpressureConfig::default(),
         }
     }
 }
 
+/// Prefix for environment overrides, e.g. `APP__INDEX__VECTOR_DIM=768`
+const ENV_PREFIX: &str = "APP__";
+
 impl AppConfig {
-    /// Load configuration from file, or use defaults
+    /// Load configuration from file (or defaults), then apply `APP__*`
+    /// environment overrides
     pub fn load() -> anyhow::Result<Self> {
+        let mut vars: Vec<(String, String)> = std::env::vars().collect();
+
+        // Legacy: PORT predates the APP__ overrides
+        let has_port_override = vars.iter().any(|(key, _)| key == "APP__SERVER__PORT");
+        if !has_port_override && let Ok(port) = std::env::var("PORT") {
+            vars.push(("APP__SERVER__PORT".to_string(), port));
+        }
+
+        let mut config = Self::load_file()?.with_env_overrides(vars)?;
+        config.storage.expand_templates();
+
+        // A shadow cutover records the default collection's new model next
+        // to its index, ahead of the config file
+        if let Some(manifest) = CollectionManifest::read_default(&config.storage.index_path)? {
+            manifest.apply(&mut config);
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Reject settings that parse but can't be used, so startup fails and
+    /// a reload keeps the running configuration
+    pub fn check(&self) -> anyhow::Result<()> {
+        Redactor::new(&self.redaction).context("Invalid redaction settings")?;
+        Ok(())
+    }
+
+    /// Whether this node must not change its data
+    pub fn is_read_only(&self) -> bool {
+        self.server.read_only || self.replication.role == ReplicationRole::Follower
+    }
+
+    /// Overlay `APP__SECTION__KEY` variables onto this configuration
+    ///
+    /// Keys are case-insensitive and nested with `__`. Values are coerced to
+    /// the type of the setting they replace; unknown keys are an error.
+    pub fn with_env_overrides<I>(self, vars: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut value = serde_json::to_value(&self)?;
+
+        for (key, raw) in vars {
+            let Some(path) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let segments: Vec<String> = path.split("__").map(|s| s.to_lowercase()).collect();
+            set_override(&mut value, &segments, &raw)
+                .with_context(|| format!("Invalid environment override {}", key))?;
+        }
+
+        serde_json::from_value(value).context("Invalid value in environment override")
+    }
+
+    /// Load configuration from file, or use defaults
+    fn load_file() -> anyhow::Result<Self> {
         use std::env;
         use std::fs;
 
@@ -203,3 +1932,210 @@ impl AppConfig {
         Ok(Self::default())
     }
 }
+
+/// Set the setting at `path` inside the serialized config
+fn set_override(value: &mut serde_json::Value, path: &[String], raw: &str) -> anyhow::Result<()> {
+    let (key, rest) = path
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("empty key"))?;
+
+    let slot = value
+        .as_object_mut()
+        .and_then(|map| map.get_mut(key))
+        .ok_or_else(|| anyhow::anyhow!("unknown config key '{}'", key))?;
+
+    if !rest.is_empty() {
+        return set_override(slot, rest, raw);
+    }
+
+    *slot = match slot {
+        serde_json::Value::String(_) => serde_json::Value::String(raw.to_string()),
+        serde_json::Value::Object(_) => anyhow::bail!("'{}' is a section, not a setting", key),
+        // Numbers, bools, unset optionals: parse as JSON, fall back to a string
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+    };
+    Ok(())
+}
+
+/// Top-level sections that may change without a restart
+const RELOADABLE_SECTIONS: &[&str] = &[
+    "search", "logging", "snapshot", "slow_log", "webhooks", "dedup", "tenancy", "ttl",
+    "redaction", "validation", "language", "experiment", "shadow", "analytics",
+    "idempotency", "memory", "backpressure",
+];
+
+/// Error returned by [`ConfigHandle::reload`]
+#[derive(Debug, thiserror::Error)]
+pub enum ReloadError {
+    #[error("failed to load config: {0:#}")]
+    Load(#[from] anyhow::Error),
+
+    #[error("settings require a restart: {}", .0.join(", "))]
+    NotReloadable(Vec<String>),
+}
+
+/// Shared, reloadable view of the configuration
+///
+/// Subscribers get notified whenever a reload changes a reloadable setting.
+pub struct ConfigHandle {
+    tx: watch::Sender<Arc<AppConfig>>,
+}
+
+impl ConfigHandle {
+    pub fn new(config: AppConfig) -> Self {
+        let (tx, _) = watch::channel(Arc::new(config));
+        Self { tx }
+    }
+
+    /// Current configuration
+    pub fn current(&self) -> Arc<AppConfig> {
+        self.tx.borrow().clone()
+    }
+
+    /// Apply a change the server made itself, such as a shadow cutover,
+    /// notifying subscribers
+    pub fn update(&self, change: impl FnOnce(&mut AppConfig)) {
+        let mut config = (*self.current()).clone();
+        change(&mut config);
+        self.tx.send_replace(Arc::new(config));
+    }
+
+    /// Watch for configuration changes
+    pub fn subscribe(&self) -> watch::Receiver<Arc<AppConfig>> {
+        self.tx.subscribe()
+    }
+
+    /// Re-read the config file and apply reloadable changes
+    ///
+    /// Returns the changed keys (dotted paths). Nothing is applied if any
+    /// non-reloadable key changed.
+    pub fn reload(&self) -> Result<Vec<String>, ReloadError> {
+        let new_config = AppConfig::load()?;
+        let changed = changed_keys(&self.current(), &new_config)?;
+
+        let rejected: Vec<String> = changed
+            .iter()
+            .filter(|key| !is_reloadable(key))
+            .cloned()
+            .collect();
+        if !rejected.is_empty() {
+            return Err(ReloadError::NotReloadable(rejected));
+        }
+
+        if !changed.is_empty() {
+            self.tx.send_replace(Arc::new(new_config));
+        }
+        Ok(changed)
+    }
+}
+
+fn is_reloadable(key: &str) -> bool {
+    let section = key.split('.').next().unwrap_or(key);
+    RELOADABLE_SECTIONS.contains(&section)
+}
+
+/// Dotted paths of every setting that differs between two configs
+fn changed_keys(old: &AppConfig, new: &AppConfig) -> anyhow::Result<Vec<String>> {
+    let mut old_flat = BTreeMap::new();
+    let mut new_flat = BTreeMap::new();
+    flatten("", &serde_json::to_value(old)?, &mut old_flat);
+    flatten("", &serde_json::to_value(new)?, &mut new_flat);
+
+    let keys: BTreeSet<&String> = old_flat.keys().chain(new_flat.keys()).collect();
+    Ok(keys
+        .into_iter()
+        .filter(|key| old_flat.get(*key) != new_flat.get(*key))
+        .cloned()
+        .collect())
+}
+
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, serde_json::Value>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, child, out);
+            }
+        }
+        leaf => {
+            out.insert(prefix.to_string(), leaf.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides() {
+        let vars = vec![
+            ("APP__INDEX__VECTOR_DIM".to_string(), "768".to_string()),
+            ("APP__SERVER__HOST".to_string(), "0.0.0.0".to_string()),
+            ("APP__SERVER__UNIX_SOCKET".to_string(), "/run/api.sock".to_string()),
+            ("UNRELATED".to_string(), "x".to_string()),
+        ];
+        let config = AppConfig::default().with_env_overrides(vars).unwrap();
+        assert_eq!(config.index.vector_dim, 768);
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.unix_socket, Some(PathBuf::from("/run/api.sock")));
+
+        let unknown = vec![("APP__INDEX__NOPE".to_string(), "1".to_string())];
+        assert!(AppConfig::default().with_env_overrides(unknown).is_err());
+    }
+
+    #[test]
+    fn test_storage_templates() {
+        let vars = vec![
+            ("APP__STORAGE__DATA_DIR".to_string(), "/srv/reviews".to_string()),
+            ("APP__STORAGE__SNAPSHOTS_DIR".to_string(), "{data_dir}/backups".to_string()),
+        ];
+        let mut config = AppConfig::default().with_env_overrides(vars).unwrap();
+        config.storage.expand_templates();
+        assert_eq!(config.storage.snapshots_dir, PathBuf::from("/srv/reviews/backups"));
+        assert_eq!(config.storage.index_path, PathBuf::from("data/reviews.index"));
+    }
+
+    #[test]
+    fn test_follower_is_read_only() {
+        assert!(!AppConfig::default().is_read_only());
+
+        let vars = vec![("APP__REPLICATION__ROLE".to_string(), "follower".to_string())];
+        let config = AppConfig::default().with_env_overrides(vars).unwrap();
+        assert_eq!(config.replication.role, ReplicationRole::Follower);
+        assert!(config.is_read_only());
+    }
+
+    #[test]
+    fn test_changed_keys_and_reloadability() {
+        let old = AppConfig::default();
+        let mut new = AppConfig::default();
+        new.snapshot.interval_secs = 60;
+        new.index.vector_dim = 768;
+
+        let changed = changed_keys(&old, &new).unwrap();
+        assert_eq!(changed, vec!["index.vector_dim", "snapshot.interval_secs"]);
+        assert!(!is_reloadable(&changed[0]));
+        assert!(is_reloadable(&changed[1]));
+    }
+
+    #[test]
+    fn test_check_redaction_patterns() {
+        let mut config = AppConfig::default();
+        config.redaction.patterns.push(RedactionPattern {
+            name: "order_number".to_string(),
+            pattern: r"\bORD-(\d+\b".to_string(),
+            replacement: None,
+        });
+        // Checked even while redaction is off, so turning it on can't fail
+        let err = config.check().unwrap_err();
+        assert!(format!("{:#}", err).contains("order_number"), "{:#}", err);
+
+        config.redaction.patterns[0].pattern = r"\bORD-\d+\b".to_string();
+        config.check().unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+//! Exact-duplicate detection: reviews by a hash of their title, body and
+//! product ID
+//!
+//! The hashes aren't stored separately; they are computed from the metadata
+//! in the background at startup (or on first use), so they survive restarts
+//! along with the reviews.
+
+use crate::storage::audit::sha256_hex;
+use crate::storage::{now_ms, ReviewMetadata};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// Hash of the fields that make two reviews the same review
+pub fn content_hash(review: &ReviewMetadata) -> String {
+    let content = [
+        review.review_title.as_str(),
+        review.review_body.as_str(),
+        review.product_id.as_str(),
+    ]
+    .join("\0");
+    sha256_hex(content.as_bytes())
+}
+
+/// Vector IDs by content hash, built from a store's reviews by
+/// [`refresh`](ContentHashes::refresh) or on first use
+///
+/// Entries are only ever added; a hit may point at a review that has since
+/// been deleted or updated, so callers check candidates against the store
+/// (see [`ContentHashes::find`]).
+#[derive(Debug, Default)]
+pub struct ContentHashes {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    index: Option<HashMap<String, Vec<usize>>>,
+    /// A background build is running; reviews observed meanwhile are kept
+    /// here and added to its result
+    building: Option<Vec<(usize, String)>>,
+    /// Bumped by [`ContentHashes::invalidate`], so a build started before
+    /// is thrown away
+    generation: u64,
+}
+
+impl ContentHashes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the hashes from `load` on a blocking thread, unless they are
+    /// built or being built already, so the first write doesn't pay for it
+    pub fn refresh<F>(self: &Arc<Self>, load: F)
+    where
+        F: FnOnce() -> anyhow::Result<Vec<ReviewMetadata>> + Send + 'static,
+    {
+        let generation = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.index.is_some() || inner.building.is_some() {
+                return;
+            }
+            inner.building = Some(Vec::new());
+            inner.generation
+        };
+        let hashes = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let built = load().map(|reviews| build(&reviews));
+            let mut inner = hashes.inner.lock().unwrap();
+            if inner.generation != generation || inner.index.is_some() {
+                return;
+            }
+            let Some(observed) = inner.building.take() else {
+                return;
+            };
+            match built {
+                Ok(mut index) => {
+                    for (vector_id, hash) in observed {
+                        index.entry(hash).or_default().push(vector_id);
+                    }
+                    info!(hashes = index.len(), "#️⃣ Content hashes built");
+                    inner.index = Some(index);
+                }
+                Err(e) => warn!("Content hashes not built: {:#}", e),
+            }
+        });
+    }
+
+    /// The oldest live review with the same content as `review`, among the
+    /// first `stored` reviews of the store
+    ///
+    /// `load` reads every review, to build the hashes on the first call;
+    /// `read` reads the candidates by vector ID.
+    pub fn find(
+        &self,
+        review: &ReviewMetadata,
+        stored: usize,
+        load: impl FnOnce() -> anyhow::Result<Vec<ReviewMetadata>>,
+        read: impl FnOnce(&[usize]) -> anyhow::Result<Vec<ReviewMetadata>>,
+    ) -> anyhow::Result<Option<usize>> {
+        let hash = content_hash(review);
+        // IDs past the end belong to appends that were rolled back
+        let candidates: Vec<usize> = self
+            .candidates(&hash, load)?
+            .into_iter()
+            .filter(|&id| id < stored)
+            .collect();
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let now = now_ms();
+        let found = read(&candidates)?
+            .iter()
+            .zip(&candidates)
+            .find(|(stored, _)| stored.is_live(now) && content_hash(stored) == hash)
+            .map(|(_, &id)| id);
+        Ok(found)
+    }
+
+    fn candidates(
+        &self,
+        hash: &str,
+        load: impl FnOnce() -> anyhow::Result<Vec<ReviewMetadata>>,
+    ) -> anyhow::Result<Vec<usize>> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(index) = &inner.index {
+            return Ok(index.get(hash).cloned().unwrap_or_default());
+        }
+
+        // Not waiting for a background build; this one reads the same
+        // reviews and any added since
+        let index = build(&load()?);
+        info!(hashes = index.len(), "#️⃣ Content hashes built");
+        let candidates = index.get(hash).cloned().unwrap_or_default();
+        inner.building = None;
+        inner.index = Some(index);
+        Ok(candidates)
+    }
+
+    /// Record a stored review; a no-op until the hashes are built or being
+    /// built
+    pub fn observe(&self, vector_id: usize, review: &ReviewMetadata) {
+        let mut inner = self.inner.lock().unwrap();
+        let hash = content_hash(review);
+        if let Some(observed) = inner.building.as_mut() {
+            observed.push((vector_id, hash.clone()));
+        }
+        if let Some(index) = inner.index.as_mut() {
+            index.entry(hash).or_default().push(vector_id);
+        }
+    }
+
+    /// Drop the hashes after the store changed wholesale; the next refresh
+    /// or lookup rebuilds them
+    pub fn invalidate(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.index = None;
+        inner.building = None;
+        inner.generation += 1;
+    }
+}
+
+fn build(reviews: &[ReviewMetadata]) -> HashMap<String, Vec<usize>> {
+    let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+    for (vector_id, review) in reviews.iter().enumerate() {
+        index.entry(content_hash(review)).or_default().push(vector_id);
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn review(title: &str, product_id: &str) -> ReviewMetadata {
+        ReviewMetadata {
+            review_rating: 5,
+            ..test_utils::review(title, product_id)
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let mut stored = vec![review("Great", "P1"), review("Great", "P2")];
+        let hashes = ContentHashes::new();
+        let find = |hashes: &ContentHashes, stored: &[ReviewMetadata], review: &ReviewMetadata| {
+            hashes
+                .find(
+                    review,
+                    stored.len(),
+                    || Ok(stored.to_vec()),
+                    |ids| Ok(ids.iter().map(|&id| stored[id].clone()).collect()),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(find(&hashes, &stored, &review("Great", "P2")), Some(1));
+        assert_eq!(find(&hashes, &stored, &review("Great", "P3")), None);
+
+        // Ratings and other fields don't count
+        let mut other_rating = review("Great", "P1");
+        other_rating.review_rating = 1;
+        assert_eq!(find(&hashes, &stored, &other_rating), Some(0));
+
+        // A deleted review no longer matches; a newer copy does
+        stored[0].deleted = true;
+        assert_eq!(find(&hashes, &stored, &review("Great", "P1")), None);
+        stored.push(review("Great", "P1"));
+        hashes.observe(2, &stored[2]);
+        assert_eq!(find(&hashes, &stored, &review("Great", "P1")), Some(2));
+
+        // Rolled back
+        hashes.observe(3, &review("Great", "P3"));
+        assert_eq!(find(&hashes, &stored, &review("Great", "P3")), None);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_reviews_added_meanwhile() {
+        let stored = vec![review("Great", "P1"), review("Great", "P2")];
+        let hashes = Arc::new(ContentHashes::new());
+        let (start, started) = std::sync::mpsc::channel::<()>();
+        let loaded = stored.clone();
+        hashes.refresh(move || {
+            let _ = started.recv();
+            Ok(loaded)
+        });
+
+        let added = review("Fine", "P1");
+        hashes.observe(2, &added);
+        start.send(()).unwrap();
+        while hashes.inner.lock().unwrap().index.is_none() {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let index = hashes.inner.lock().unwrap().index.clone().unwrap();
+        assert_eq!(index[&content_hash(&stored[1])], [1]);
+        assert_eq!(index[&content_hash(&added)], [2]);
+    }
+}
@@ -0,0 +1,93 @@
+//! Image embeddings for collections created with a CLIP model: photos go
+//! through the model's vision encoder, into the space its text encoder puts
+//! reviews and queries in, so either can be searched with the other
+
+use super::EmbeddingService;
+use anyhow::{Context, Result};
+use fastembed::{EmbeddingModel, ImageEmbedding, ImageEmbeddingModel, ImageInitOptions};
+use tracing::info;
+
+/// Turns encoded images (PNG, JPEG, ...) into embeddings; fastembed in
+/// production
+pub trait ImageEmbeddingProvider: Send + Sync {
+    fn embed_images(&self, images: &[&[u8]]) -> Result<Vec<Vec<f32>>>;
+}
+
+impl ImageEmbeddingProvider for ImageEmbedding {
+    fn embed_images(&self, images: &[&[u8]]) -> Result<Vec<Vec<f32>>> {
+        self.embed_bytes(images, None)
+    }
+}
+
+/// The vision encoder paired with a text model
+pub struct ImageEmbeddingService {
+    model: Box<dyn ImageEmbeddingProvider>,
+    model_name: String,
+    dimension: usize,
+}
+
+impl ImageEmbeddingService {
+    /// Load the vision encoder paired with `text_model`
+    pub fn new(text_model: &str) -> Result<Self> {
+        let (model_type, model_name) = Self::vision_model(text_model)
+            .with_context(|| format!("Model {} has no image encoder", text_model))?;
+        info!(model_name, "Initializing image embedding model");
+
+        let model = ImageEmbedding::try_new(
+            ImageInitOptions::new(model_type).with_show_download_progress(true),
+        )
+        .context("Failed to initialize image embedding model")?;
+
+        Ok(Self {
+            model: Box::new(model),
+            model_name: model_name.to_string(),
+            dimension: EmbeddingService::model_dimension(text_model).unwrap_or_default(),
+        })
+    }
+
+    /// Serve image embeddings from `provider` under `model_name` (e.g. a
+    /// test double standing in for a real model)
+    pub fn from_provider(
+        model_name: &str,
+        dimension: usize,
+        provider: impl ImageEmbeddingProvider + 'static,
+    ) -> Self {
+        Self {
+            model: Box::new(provider),
+            model_name: model_name.to_string(),
+            dimension,
+        }
+    }
+
+    /// Whether `text_model` has a vision encoder, i.e. is a CLIP text model
+    pub fn supports(text_model: &str) -> bool {
+        Self::vision_model(text_model).is_some()
+    }
+
+    fn vision_model(text_model: &str) -> Option<(ImageEmbeddingModel, &'static str)> {
+        match EmbeddingService::lookup_model(text_model)? {
+            EmbeddingModel::ClipVitB32 => {
+                Some((ImageEmbeddingModel::ClipVitB32, "Qdrant/clip-ViT-B-32-vision"))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Embed one encoded image
+    pub fn embed(&self, image: &[u8]) -> Result<Vec<f32>> {
+        self.model
+            .embed_images(&[image])
+            .context("Failed to embed image")?
+            .into_iter()
+            .next()
+            .context("No embedding returned")
+    }
+}
@@ -1,19 +1,51 @@
+pub mod image;
+pub mod normalize;
+
+pub use image::ImageEmbeddingService;
+pub use normalize::normalize_text;
+
 use anyhow::{Context, Result};
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
+use crate::config::TextNormalization;
+
+/// Turns texts into embeddings; fastembed in production
+pub trait EmbeddingProvider: Send + Sync {
+    fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>>;
+}
+
+impl EmbeddingProvider for TextEmbedding {
+    fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        self.embed(texts, None)
+    }
+}
 
 /// Embedding service using fastembed-rs
 pub struct EmbeddingService {
-    model: TextEmbedding,
+    /// Independent instances of the model, used in turn so concurrent
+    /// requests don't queue on one session
+    models: Vec<Box<dyn EmbeddingProvider>>,
+    next: AtomicUsize,
+    model_name: String,
     dimension: usize,
 }
 
 impl EmbeddingService {
     /// Create a new embedding service
     pub fn new(model_name: &str, max_length: usize) -> Result<Self> {
+        Self::with_instances(model_name, max_length, 1)
+    }
+
+    /// Create a service backed by `instances` copies of the model (at
+    /// least one), each holding its own weights in memory
+    pub fn with_instances(model_name: &str, max_length: usize, instances: usize) -> Result<Self> {
         info!(
             model_name = %model_name,
             max_length = max_length,
+            instances = instances,
             "Initializing embedding model"
         );
 
@@ -21,55 +53,137 @@ impl EmbeddingService {
         let model_type = Self::parse_model_name(model_name);
 
         // Get model dimension first (before moving model_type)
-        let dimension = match model_type {
-            EmbeddingModel::AllMiniLML6V2 => 384,
-            EmbeddingModel::BGESmallENV15 => 384,
-            EmbeddingModel::AllMiniLML12V2 => 384,
-            _ => {
-                warn!("Unknown model dimension, defaulting to 384");
-                384
-            }
-        };
+        let dimension = Self::dimension_of(&model_type);
 
         // Initialize the model
-        let model = TextEmbedding::try_new(
-            InitOptions::new(model_type).with_show_download_progress(true)
-        )
-        .context("Failed to initialize embedding model")?;
+        let mut models: Vec<Box<dyn EmbeddingProvider>> = Vec::new();
+        for _ in 0..instances.max(1) {
+            let model = TextEmbedding::try_new(
+                InitOptions::new(model_type.clone()).with_show_download_progress(true)
+            )
+            .context("Failed to initialize embedding model")?;
+            models.push(Box::new(model));
+        }
 
         info!(dimension = dimension, "Embedding model ready");
 
-        Ok(Self { model, dimension })
+        Ok(Self::from_models(model_name, dimension, models))
+    }
+
+    /// Serve embeddings from `provider` under `model_name` (e.g. a test
+    /// double standing in for a real model)
+    pub fn from_provider(
+        model_name: &str,
+        dimension: usize,
+        provider: impl EmbeddingProvider + 'static,
+    ) -> Self {
+        Self::from_models(model_name, dimension, vec![Box::new(provider)])
+    }
+
+    fn from_models(model_name: &str, dimension: usize, models: Vec<Box<dyn EmbeddingProvider>>) -> Self {
+        Self {
+            models,
+            next: AtomicUsize::new(0),
+            model_name: model_name.to_string(),
+            dimension,
+        }
+    }
+
+    /// Model instances serving requests
+    pub fn instances(&self) -> usize {
+        self.models.len()
+    }
+
+    /// The next instance in round-robin order
+    fn model(&self) -> &dyn EmbeddingProvider {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        self.models[next % self.models.len()].as_ref()
     }
 
     /// Parse model name string to EmbeddingModel enum
     fn parse_model_name(name: &str) -> EmbeddingModel {
+        Self::lookup_model(name).unwrap_or_else(|| {
+            warn!(
+                "Unknown model '{}', defaulting to AllMiniLML6V2",
+                name
+            );
+            EmbeddingModel::AllMiniLML6V2
+        })
+    }
+
+    fn lookup_model(name: &str) -> Option<EmbeddingModel> {
         match name.to_lowercase().as_str() {
             "sentence-transformers/all-minilm-l6-v2" | "all-minilm-l6-v2" => {
-                EmbeddingModel::AllMiniLML6V2
+                Some(EmbeddingModel::AllMiniLML6V2)
             }
             "baai/bge-small-en-v1.5" | "bge-small-en-v1.5" => {
-                EmbeddingModel::BGESmallENV15
+                Some(EmbeddingModel::BGESmallENV15)
             }
             "sentence-transformers/all-minilm-l12-v2" | "all-minilm-l12-v2" => {
-                EmbeddingModel::AllMiniLML12V2
+                Some(EmbeddingModel::AllMiniLML12V2)
+            }
+            // Multilingual: texts in different languages land close together
+            "sentence-transformers/paraphrase-multilingual-minilm-l12-v2"
+            | "paraphrase-multilingual-minilm-l12-v2" => Some(EmbeddingModel::ParaphraseMLMiniLML12V2),
+            "intfloat/multilingual-e5-small" | "multilingual-e5-small" => {
+                Some(EmbeddingModel::MultilingualE5Small)
+            }
+            // Text half of CLIP; see `image` for the vision half
+            "qdrant/clip-vit-b-32-text" | "clip-vit-b-32-text" | "clip-vit-b-32" => {
+                Some(EmbeddingModel::ClipVitB32)
             }
+            _ => None,
+        }
+    }
+
+    fn dimension_of(model: &EmbeddingModel) -> usize {
+        match model {
+            EmbeddingModel::AllMiniLML6V2 => 384,
+            EmbeddingModel::BGESmallENV15 => 384,
+            EmbeddingModel::AllMiniLML12V2 => 384,
+            EmbeddingModel::ClipVitB32 => 512,
+            EmbeddingModel::ParaphraseMLMiniLML12V2 => 384,
+            EmbeddingModel::MultilingualE5Small => 384,
             _ => {
-                warn!(
-                    "Unknown model '{}', defaulting to AllMiniLML6V2",
-                    name
-                );
-                EmbeddingModel::AllMiniLML6V2
+                warn!("Unknown model dimension, defaulting to 384");
+                384
             }
         }
     }
 
+    /// Output dimension of a supported model, `None` for unknown names
+    pub fn model_dimension(model_name: &str) -> Option<usize> {
+        Self::lookup_model(model_name).map(|model| Self::dimension_of(&model))
+    }
+
+    /// Whether a supported model maps different languages into one space,
+    /// so queries in one find reviews in another
+    pub fn is_multilingual(model_name: &str) -> bool {
+        matches!(
+            Self::lookup_model(model_name),
+            Some(EmbeddingModel::ParaphraseMLMiniLML12V2 | EmbeddingModel::MultilingualE5Small)
+        )
+    }
+
+    /// Name the model was loaded by
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// Whether two names refer to the same model
+    pub fn same_model(a: &str, b: &str) -> bool {
+        match (Self::lookup_model(a), Self::lookup_model(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a.eq_ignore_ascii_case(b),
+        }
+    }
+
     /// Generate embedding for a single text
     pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
         let documents = vec![text];
         let embeddings = self
-            .model
-            .embed(documents, None)
+            .model()
+            .embed_batch(documents)
             .context("Failed to generate embedding")?;
 
         embeddings
@@ -80,8 +194,8 @@ impl EmbeddingService {
 
     /// Generate embeddings for multiple texts (batch)
     pub fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
-        self.model
-            .embed(texts, None)
+        self.model()
+            .embed_batch(texts)
             .context("Failed to generate embeddings")
     }
 
@@ -94,6 +208,95 @@ impl EmbeddingService {
     pub fn prepare_review_text(title: &str, body: &str) -> String {
         format!("{} {}", title, body)
     }
+
+    /// The text embedded for a review, after `normalization`
+    pub fn review_text(title: &str, body: &str, normalization: &TextNormalization) -> String {
+        normalize_text(&Self::prepare_review_text(title, body), normalization)
+    }
+}
+
+/// One model's entry in a [`ModelCache`]; held while it loads
+type Slot<T> = Arc<Mutex<Option<Arc<T>>>>;
+
+/// Models other than the configured one, loaded on first use
+///
+/// Each model loads under its own lock, so a slow load (or download) never
+/// holds up lookups of models that are already loaded.
+pub struct ModelCache {
+    max_length: usize,
+    /// `embedding.instances`, for every model loaded here
+    instances: usize,
+    models: Mutex<HashMap<String, Slot<EmbeddingService>>>,
+    /// Image encoders by the text model they are paired with
+    vision: Mutex<HashMap<String, Slot<ImageEmbeddingService>>>,
+}
+
+impl ModelCache {
+    pub fn new(max_length: usize, instances: usize) -> Self {
+        Self {
+            max_length,
+            instances,
+            models: Mutex::new(HashMap::new()),
+            vision: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The loaded model, loading (and possibly downloading) it if needed
+    ///
+    /// Blocking; concurrent first uses wait for a single load.
+    pub fn get_or_load(&self, model_name: &str) -> Result<Arc<EmbeddingService>> {
+        let slot = slot(&self.models, model_name);
+        let mut slot = slot.lock().unwrap();
+        if let Some(service) = slot.as_ref() {
+            return Ok(service.clone());
+        }
+
+        let service = Arc::new(EmbeddingService::with_instances(
+            model_name,
+            self.max_length,
+            self.instances,
+        )?);
+        *slot = Some(service.clone());
+        Ok(service)
+    }
+
+    /// The model if it is loaded; `None` while it is missing or loading
+    pub fn get(&self, model_name: &str) -> Option<Arc<EmbeddingService>> {
+        let slot = self.models.lock().unwrap().get(&model_name.to_lowercase())?.clone();
+        slot.try_lock().ok()?.clone()
+    }
+
+    /// The image encoder paired with `text_model`, loading it if needed
+    ///
+    /// Blocking, like [`get_or_load`](Self::get_or_load).
+    pub fn get_or_load_vision(&self, text_model: &str) -> Result<Arc<ImageEmbeddingService>> {
+        let slot = slot(&self.vision, text_model);
+        let mut slot = slot.lock().unwrap();
+        if let Some(service) = slot.as_ref() {
+            return Ok(service.clone());
+        }
+
+        let service = Arc::new(ImageEmbeddingService::new(text_model)?);
+        *slot = Some(service.clone());
+        Ok(service)
+    }
+
+    /// Use `service` for `model_name` instead of loading the real model
+    /// (e.g. a test double)
+    pub fn insert(&self, model_name: &str, service: EmbeddingService) {
+        *slot(&self.models, model_name).lock().unwrap() = Some(Arc::new(service));
+    }
+
+    /// Use `service` for `text_model`'s images instead of loading the real
+    /// encoder (e.g. a test double)
+    pub fn insert_vision(&self, text_model: &str, service: ImageEmbeddingService) {
+        *slot(&self.vision, text_model).lock().unwrap() = Some(Arc::new(service));
+    }
+}
+
+/// `name`'s slot, created empty on first use
+fn slot<T>(slots: &Mutex<HashMap<String, Slot<T>>>, name: &str) -> Slot<T> {
+    slots.lock().unwrap().entry(name.to_lowercase()).or_default().clone()
 }
 
 #[cfg(test)]
@@ -116,6 +319,46 @@ mod tests {
         assert!(embedding.iter().any(|&v| v != 0.0));
     }
 
+    #[test]
+    fn test_instances_take_turns() {
+        struct Counting(Arc<AtomicUsize>);
+        impl EmbeddingProvider for Counting {
+            fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Ok(texts.iter().map(|_| vec![0.0; 4]).collect())
+            }
+        }
+
+        let calls: Vec<Arc<AtomicUsize>> = (0..3).map(|_| Arc::default()).collect();
+        let models = calls
+            .iter()
+            .map(|calls| Box::new(Counting(calls.clone())) as Box<dyn EmbeddingProvider>)
+            .collect();
+        let service = EmbeddingService::from_models("test", 4, models);
+        for _ in 0..5 {
+            service.embed("text").unwrap();
+        }
+        service.embed_batch(vec!["a", "b"]).unwrap();
+
+        assert_eq!(service.instances(), 3);
+        let calls: Vec<usize> = calls.iter().map(|calls| calls.load(Ordering::Relaxed)).collect();
+        assert_eq!(calls, vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn test_cache_lookups_skip_loading_models() {
+        let cache = ModelCache::new(512, 1);
+        cache.insert("Loaded", EmbeddingService::from_models("Loaded", 4, Vec::new()));
+
+        // Another model loading holds only its own slot
+        let loading = slot(&cache.models, "loading");
+        let _held = loading.lock().unwrap();
+        assert!(cache.get("loading").is_none());
+        assert_eq!(cache.get("LOADED").unwrap().model_name(), "Loaded");
+        assert_eq!(cache.get_or_load("loaded").unwrap().model_name(), "Loaded");
+        assert!(cache.get("missing").is_none());
+    }
+
     #[test]
     fn test_prepare_review_text() {
         let title = "Great product";
@@ -0,0 +1,90 @@
+//! Text preprocessing applied before embedding (see [`TextNormalization`])
+
+use unicode_normalization::UnicodeNormalization;
+use crate::config::TextNormalization;
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "i",
+    "in", "is", "it", "its", "of", "on", "or", "so", "that", "the", "this", "to", "was", "were",
+    "will", "with",
+];
+
+/// Run the enabled steps over `text`: HTML stripping, NFC, lowercasing,
+/// stopword removal, then whitespace collapsing
+pub fn normalize_text(text: &str, steps: &TextNormalization) -> String {
+    if steps.is_noop() {
+        return text.to_string();
+    }
+
+    let mut text = if steps.strip_html {
+        strip_html(text)
+    } else {
+        text.to_string()
+    };
+    if steps.unicode_nfc {
+        text = text.nfc().collect();
+    }
+    if steps.lowercase {
+        text = text.to_lowercase();
+    }
+    if steps.remove_stopwords {
+        text = text
+            .split_whitespace()
+            .filter(|word| {
+                let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+                !STOPWORDS.iter().any(|stop| stop.eq_ignore_ascii_case(bare))
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    if steps.collapse_whitespace {
+        text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    text
+}
+
+/// Replace tags with spaces and decode the common entities
+fn strip_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_text() {
+        let all = TextNormalization {
+            lowercase: true,
+            strip_html: true,
+            collapse_whitespace: true,
+            unicode_nfc: true,
+            remove_stopwords: true,
+        };
+        assert_eq!(
+            normalize_text("<p>The  Cafe\u{301} is <b>great</b> &amp; cozy</p>", &all),
+            "caf\u{e9} great & cozy"
+        );
+
+        let untouched = "  <b>The</b> Cafe  ";
+        assert_eq!(normalize_text(untouched, &TextNormalization::default()), untouched);
+    }
+}
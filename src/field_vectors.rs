@@ -0,0 +1,584 @@
+//! Multi-vector search (`search.multi_vector`): a title and a body embedding
+//! per review of the default collection, searched side by side and fused
+//! into one distance per review
+//!
+//! The field vectors live in two indexes whose vector IDs match the main
+//! one, saved next to it. A background task embeds newly stored reviews
+//! and masks deleted ones; searches only read them, and use the main index
+//! until they are caught up with the current model.
+
+use crate::api::models::AppState;
+use crate::config::{FieldFusion, IndexConfig, MultiVectorConfig, TextNormalization};
+use crate::embedding::{normalize_text, EmbeddingService};
+use crate::storage::{now_ms, DistanceMetric, MetadataStore, ReviewMetadata, SearchResult, VectorIndex};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Reviews embedded per batch while catching up
+const BATCH_SIZE: usize = 64;
+
+/// How often the background task catches up with the store
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Least time between saves after the first build
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Candidates searched per field for each result; fusion reorders them
+const OVERSAMPLE: usize = 4;
+
+/// `<index>.title`, `<index>.body` and `<index>.fields.json`
+fn field_paths(index_path: &Path) -> (PathBuf, PathBuf, PathBuf) {
+    (
+        index_path.with_extension("title"),
+        index_path.with_extension("body"),
+        index_path.with_extension("fields.json"),
+    )
+}
+
+/// Written after both indexes, describing what they hold
+#[derive(Serialize, Deserialize)]
+struct FieldManifest {
+    model: String,
+    normalization: TextNormalization,
+    metric: DistanceMetric,
+    len: usize,
+    deleted: BTreeSet<usize>,
+}
+
+/// Title and body vectors of the first `len()` reviews
+struct FieldIndexes {
+    /// What the vectors were embedded with; either changing starts over
+    model: String,
+    normalization: TextNormalization,
+    metric: DistanceMetric,
+    title: VectorIndex,
+    body: VectorIndex,
+    /// Vector IDs masked in both
+    deleted: BTreeSet<usize>,
+}
+
+impl FieldIndexes {
+    fn new(embedder: &EmbeddingService, config: &IndexConfig, normalization: &TextNormalization) -> Result<Self> {
+        let config = IndexConfig {
+            vector_dim: embedder.dimension(),
+            ..config.clone()
+        };
+        let mut title = VectorIndex::new(&config);
+        title.initialize()?;
+        let mut body = VectorIndex::new(&config);
+        body.initialize()?;
+        Ok(Self {
+            model: embedder.model_name().to_string(),
+            normalization: normalization.clone(),
+            metric: config.metric,
+            title,
+            body,
+            deleted: BTreeSet::new(),
+        })
+    }
+
+    /// The copy saved next to `index_path`, if there is one for this model
+    fn load(
+        embedder: &EmbeddingService,
+        config: &IndexConfig,
+        normalization: &TextNormalization,
+        index_path: &Path,
+    ) -> Result<Option<Self>> {
+        let (title_path, body_path, manifest_path) = field_paths(index_path);
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+        let manifest: FieldManifest = serde_json::from_slice(&std::fs::read(&manifest_path)?)
+            .with_context(|| format!("{} is not a field vector manifest", manifest_path.display()))?;
+
+        let mut indexes = Self::new(embedder, config, normalization)?;
+        if !EmbeddingService::same_model(&manifest.model, &indexes.model)
+            || manifest.normalization != indexes.normalization
+            || manifest.metric != indexes.metric
+        {
+            return Ok(None);
+        }
+        indexes.title.load(&title_path)?;
+        indexes.body.load(&body_path)?;
+        // A save that stopped before its manifest
+        if indexes.title.vector_count() != manifest.len || indexes.body.vector_count() != manifest.len {
+            return Ok(None);
+        }
+        indexes.deleted = manifest.deleted;
+        Ok(Some(indexes))
+    }
+
+    /// Save both indexes, then the manifest that vouches for them
+    fn save(&self, index_path: &Path) -> Result<()> {
+        let (title_path, body_path, manifest_path) = field_paths(index_path);
+        self.title.save(&title_path)?;
+        self.body.save(&body_path)?;
+
+        let manifest = FieldManifest {
+            model: self.model.clone(),
+            normalization: self.normalization.clone(),
+            metric: self.metric,
+            len: self.len(),
+            deleted: self.deleted.clone(),
+        };
+        let staged = manifest_path.with_extension("json.tmp");
+        std::fs::write(&staged, serde_json::to_vec(&manifest)?)?;
+        std::fs::rename(&staged, &manifest_path)?;
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.title.vector_count()
+    }
+
+    fn is_current(&self, embedder: &EmbeddingService, normalization: &TextNormalization, metric: DistanceMetric) -> bool {
+        EmbeddingService::same_model(&self.model, embedder.model_name())
+            && &self.normalization == normalization
+            && self.metric == metric
+    }
+
+    /// Add the embedded `reviews`, the next ones after `len()`
+    ///
+    /// Reviews that are no longer live still take their slot, deleted.
+    fn append(&mut self, fields: FieldEmbeddings, reviews: &[ReviewMetadata]) -> Result<()> {
+        let first_id = self.len();
+        for (index, vectors) in [(&mut self.title, fields.titles), (&mut self.body, fields.bodies)] {
+            if first_id == 0 {
+                index.build_from_vectors(&vectors)?;
+            } else {
+                for vector in &vectors {
+                    index.add_vector(vector)?;
+                }
+            }
+        }
+
+        let now = now_ms();
+        let gone: Vec<usize> = reviews
+            .iter()
+            .enumerate()
+            .filter(|(_, review)| !review.is_live(now))
+            .map(|(offset, _)| first_id + offset)
+            .collect();
+        self.mask(&gone)
+    }
+
+    /// Delete the listed vectors from both indexes, skipping masked ones
+    fn mask(&mut self, vector_ids: &[usize]) -> Result<()> {
+        for &vector_id in vector_ids {
+            if vector_id < self.len() && self.deleted.insert(vector_id) {
+                self.title.delete_vector(vector_id)?;
+                self.body.delete_vector(vector_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn search(&self, query: &[f32], k: usize, settings: &MultiVectorConfig) -> Result<Vec<SearchResult>> {
+        let fields = [&self.title, &self.body];
+        let mut found: BTreeMap<usize, [Option<f32>; 2]> = BTreeMap::new();
+        for (field, index) in fields.iter().enumerate() {
+            for hit in index.search(query, k.saturating_mul(OVERSAMPLE))? {
+                found.entry(hit.vector_id).or_default()[field] = Some(hit.distance);
+            }
+        }
+
+        let mut fused = Vec::with_capacity(found.len());
+        for (vector_id, distances) in found {
+            // A review found through one field is scored on both
+            let mut both = [0.0; 2];
+            for (field, index) in fields.iter().enumerate() {
+                both[field] = match distances[field] {
+                    Some(distance) => distance,
+                    None => distance(self.metric, query, &index.get_vector(vector_id)?),
+                };
+            }
+            fused.push(SearchResult {
+                vector_id,
+                distance: fuse(settings, both[0], both[1]),
+            });
+        }
+        fused.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        fused.truncate(k);
+        Ok(fused)
+    }
+}
+
+/// Title and body vectors of a batch of reviews
+struct FieldEmbeddings {
+    titles: Vec<Vec<f32>>,
+    bodies: Vec<Vec<f32>>,
+}
+
+fn embed_fields(
+    embedder: &EmbeddingService,
+    normalization: &TextNormalization,
+    reviews: &[ReviewMetadata],
+) -> Result<FieldEmbeddings> {
+    let embed = |field: fn(&ReviewMetadata) -> &str| {
+        let texts: Vec<String> = reviews
+            .iter()
+            .map(|review| normalize_text(field(review), normalization))
+            .collect();
+        embedder.embed_batch(texts.iter().map(String::as_str).collect())
+    };
+    Ok(FieldEmbeddings {
+        titles: embed(|review| &review.review_title)?,
+        bodies: embed(|review| &review.review_body)?,
+    })
+}
+
+/// Vector IDs of stored reviews that are no longer live
+fn tombstoned(metadata: &dyn MetadataStore) -> Result<Vec<usize>> {
+    let now = now_ms();
+    Ok(metadata
+        .read_all()?
+        .iter()
+        .enumerate()
+        .filter(|(_, review)| !review.is_live(now))
+        .map(|(vector_id, _)| vector_id)
+        .collect())
+}
+
+/// Bookkeeping of the one catch-up that runs at a time
+#[derive(Default)]
+struct SyncState {
+    /// Whether the saved copy was tried; only the first build may use it
+    tried_saved: bool,
+    /// Deleted vectors of the main index when tombstones were last applied
+    main_deleted: Option<usize>,
+    dirty: bool,
+    saved_at: Option<Instant>,
+}
+
+/// The title and body indexes of the default collection
+///
+/// Embedding happens in [`sync`](Self::sync), outside the lock searches
+/// take, which is only held to add the embedded vectors.
+#[derive(Default)]
+pub struct FieldVectors {
+    indexes: RwLock<Option<FieldIndexes>>,
+    sync: Mutex<SyncState>,
+    /// Bumped by [`invalidate`](Self::invalidate), so a catch-up that
+    /// started before it doesn't install stale vectors
+    generation: AtomicU64,
+}
+
+impl FieldVectors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Embed the titles and bodies of reviews stored since the last call,
+    /// mask reviews deleted since then and save now and then; blocking
+    ///
+    /// The first call loads the copy saved next to `index_path` or embeds
+    /// every review, as does the first after a change of model or
+    /// normalization (e.g. a shadow cutover). `main_deleted` is the main
+    /// index's count of deleted vectors; tombstones are only looked for
+    /// when it changes. Returns how many reviews were embedded.
+    pub fn sync(
+        &self,
+        embedder: &EmbeddingService,
+        metadata: &dyn MetadataStore,
+        config: &IndexConfig,
+        normalization: &TextNormalization,
+        index_path: &Path,
+        main_deleted: usize,
+    ) -> Result<usize> {
+        let mut sync = self.sync.lock().unwrap();
+        let generation = self.generation.load(Ordering::SeqCst);
+        let stored = metadata.count_lines()?;
+        // Fewer lines than vectors: the store was truncated under us
+        let covered = self
+            .indexes
+            .read()
+            .unwrap()
+            .as_ref()
+            .filter(|indexes| indexes.is_current(embedder, normalization, config.metric) && indexes.len() <= stored)
+            .map(FieldIndexes::len);
+
+        let mut embedded = 0;
+        match covered {
+            None => {
+                let saved = if sync.tried_saved || generation > 0 {
+                    None
+                } else {
+                    sync.tried_saved = true;
+                    FieldIndexes::load(embedder, config, normalization, index_path)
+                        .unwrap_or_else(|e| {
+                            warn!("Ignoring saved field vectors: {:#}", e);
+                            None
+                        })
+                        .filter(|indexes| indexes.len() <= stored)
+                };
+                // A saved copy may miss deletes made after it was written
+                sync.main_deleted = saved.is_none().then_some(main_deleted);
+                let mut fresh = match saved {
+                    Some(indexes) => indexes,
+                    None => FieldIndexes::new(embedder, config, normalization)?,
+                };
+                while fresh.len() < stored {
+                    let reviews = metadata.read_range(fresh.len(), BATCH_SIZE.min(stored - fresh.len()))?;
+                    fresh.append(embed_fields(embedder, normalization, &reviews)?, &reviews)?;
+                    embedded += reviews.len();
+                }
+
+                let mut slot = self.indexes.write().unwrap();
+                if self.generation.load(Ordering::SeqCst) != generation {
+                    return Ok(0);
+                }
+                *slot = Some(fresh);
+                info!(reviews = stored, embedded, "🧩 Field vectors ready");
+                sync.dirty = true;
+                sync.saved_at = None;
+            }
+            Some(mut covered) => {
+                while covered < stored {
+                    let reviews = metadata.read_range(covered, BATCH_SIZE.min(stored - covered))?;
+                    let fields = embed_fields(embedder, normalization, &reviews)?;
+                    let mut slot = self.indexes.write().unwrap();
+                    match slot.as_mut() {
+                        Some(indexes) if self.generation.load(Ordering::SeqCst) == generation && indexes.len() == covered => {
+                            indexes.append(fields, &reviews)?;
+                        }
+                        _ => return Ok(embedded),
+                    }
+                    covered += reviews.len();
+                    embedded += reviews.len();
+                    sync.dirty = true;
+                }
+            }
+        }
+
+        if sync.main_deleted != Some(main_deleted) {
+            let gone = tombstoned(metadata)?;
+            if let Some(indexes) = self.indexes.write().unwrap().as_mut() {
+                indexes.mask(&gone)?;
+            }
+            sync.main_deleted = Some(main_deleted);
+            sync.dirty = true;
+        }
+
+        if sync.dirty && sync.saved_at.is_none_or(|at| at.elapsed() >= SAVE_INTERVAL) {
+            self.save(&mut sync, index_path)?;
+        }
+        Ok(embedded)
+    }
+
+    /// Save unsaved changes now, e.g. at shutdown; blocking
+    pub fn flush(&self, index_path: &Path) -> Result<()> {
+        let mut sync = self.sync.lock().unwrap();
+        if sync.dirty {
+            self.save(&mut sync, index_path)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, sync: &mut SyncState, index_path: &Path) -> Result<()> {
+        if let Some(indexes) = self.indexes.read().unwrap().as_ref() {
+            indexes.save(index_path)?;
+        }
+        sync.dirty = false;
+        sync.saved_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Drop both indexes after the store changed wholesale; the next
+    /// [`sync`](Self::sync) re-embeds every review
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.indexes.write().unwrap() = None;
+    }
+
+    /// Whether the field vectors were built for `embedder`'s model
+    pub fn is_ready(&self, embedder: &EmbeddingService, normalization: &TextNormalization, metric: DistanceMetric) -> bool {
+        self.indexes
+            .read()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|indexes| indexes.is_current(embedder, normalization, metric))
+    }
+
+    /// The `k` reviews with the smallest fused distance to `query`, closest
+    /// first; `None` until the field vectors match `embedder`'s model
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        settings: &MultiVectorConfig,
+        embedder: &EmbeddingService,
+        normalization: &TextNormalization,
+        metric: DistanceMetric,
+    ) -> Result<Option<Vec<SearchResult>>> {
+        match self.indexes.read().unwrap().as_ref() {
+            Some(indexes) if indexes.is_current(embedder, normalization, metric) => {
+                indexes.search(query, k, settings).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Keep the field vectors caught up while `search.multi_vector` is on,
+/// saving them at shutdown
+pub fn spawn_field_vectors_task(state: AppState, mut shutdown_rx: watch::Receiver<bool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(SYNC_INTERVAL) => {}
+                _ = shutdown_rx.wait_for(|&stop| stop) => break,
+            }
+            if !state.config.current().search.multi_vector.enabled || !state.readiness.is_component_ready("index") {
+                continue;
+            }
+            if let Err(e) = catch_up(&state).await {
+                error!("Field vector sync failed: {:#}", e);
+            }
+        }
+
+        let task_state = state.clone();
+        let flushed = tokio::task::spawn_blocking(move || task_state.field_vectors.flush(&task_state.index_path())).await;
+        if let Ok(Err(e)) = flushed {
+            error!("Saving field vectors failed: {:#}", e);
+        }
+    })
+}
+
+/// One [`FieldVectors::sync`] round for the default collection
+pub async fn catch_up(state: &AppState) -> Result<usize> {
+    let embedder = state.embedder().map_err(|e| anyhow::anyhow!("Model unavailable: {:?}", e))?;
+    let main_deleted = {
+        let index = state.vector_index.read().await;
+        index.vector_count().saturating_sub(index.live_count())
+    };
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let config = state.config.current();
+        state.field_vectors.sync(
+            &embedder,
+            state.metadata_store.as_ref(),
+            &config.index,
+            &config.embedding.normalization,
+            &state.index_path(),
+            main_deleted,
+        )
+    })
+    .await?
+}
+
+/// One distance from a review's title and body distances
+pub fn fuse(settings: &MultiVectorConfig, title: f32, body: f32) -> f32 {
+    match settings.fusion {
+        FieldFusion::Max => title.min(body),
+        FieldFusion::WeightedSum => {
+            let weight = settings.title_weight.clamp(0.0, 1.0);
+            weight * title + (1.0 - weight) * body
+        }
+    }
+}
+
+/// Exact distance, in the units the index backends report
+fn distance(metric: DistanceMetric, a: &[f32], b: &[f32]) -> f32 {
+    match metric {
+        DistanceMetric::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+        DistanceMetric::Cosine => {
+            let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+            let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt()
+                * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norms > 0.0 { 1.0 - dot / norms } else { 1.0 }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestApp;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::json;
+
+    #[test]
+    fn test_fuse() {
+        let mut settings = MultiVectorConfig::default();
+        assert_eq!(fuse(&settings, 0.2, 0.8), 0.2);
+
+        settings.fusion = FieldFusion::WeightedSum;
+        settings.title_weight = 0.25;
+        assert_eq!(fuse(&settings, 0.5, 1.0), 0.875);
+    }
+
+    #[tokio::test]
+    async fn test_title_match_ranks_first() {
+        let app = TestApp::builder()
+            .config(|config| config.search.multi_vector.enabled = true)
+            .build()
+            .unwrap();
+        let add = |title: &'static str, body: &'static str, product: &'static str| {
+            let review = json!({
+                "review_title": title,
+                "review_body": body,
+                "product_id": product,
+                "review_rating": 4,
+            });
+            let app = &app;
+            async move {
+                let (status, body) = app.post("/reviews", &review).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+            }
+        };
+        let top = |query: &'static str| {
+            let app = &app;
+            async move {
+                let request = json!({ "query": query, "top_k": 1, "include_distance": true });
+                let (status, body) = app.post("/reviews/search", &request).await;
+                assert_eq!(status, StatusCode::OK, "{}", body);
+                body["results"][0].clone()
+            }
+        };
+
+        add("Great battery", "Arrived a day late", "P1").await;
+        add("Meh", "Terrible screen", "P2").await;
+        assert_eq!(catch_up(&app.state).await.unwrap(), 2);
+
+        // An exact field match is distance 0, whatever the other field says
+        let hit = top("Great battery").await;
+        assert_eq!(hit["product_id"], "P1");
+        assert_eq!(hit["distance"], 0.0);
+        assert_eq!(top("Terrible screen").await["product_id"], "P2");
+
+        // Later reviews are embedded on their own, deleted ones masked
+        add("Broken hinge", "Returned it", "P3").await;
+        let delete = Request::delete("/reviews/0?expected_revision=1").body(Body::empty()).unwrap();
+        assert_eq!(app.request(delete).await.status(), StatusCode::NO_CONTENT);
+        assert_eq!(catch_up(&app.state).await.unwrap(), 1);
+        assert_eq!(top("Returned it").await["product_id"], "P3");
+        assert_ne!(top("Great battery").await["product_id"], "P1");
+
+        // A restart picks the saved copy up instead of embedding again
+        let index_path = app.state.index_path();
+        app.state.field_vectors.flush(&index_path).unwrap();
+        let restarted = FieldVectors::new();
+        let config = app.state.config.current();
+        let embedder = app.state.embedder().unwrap();
+        let embedded = restarted
+            .sync(&embedder, app.state.metadata_store.as_ref(), &config.index, &config.embedding.normalization, &index_path, 1)
+            .unwrap();
+        assert_eq!(embedded, 0);
+        let normalization = &config.embedding.normalization;
+        let query = embedder.embed("Great battery").unwrap();
+        let hits = restarted
+            .search(&query, 3, &config.search.multi_vector, &embedder, normalization, config.index.metric)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hits.iter().map(|hit| hit.vector_id).collect::<BTreeSet<_>>(), BTreeSet::from([1, 2]));
+    }
+}
@@ -0,0 +1,10 @@
+//! gRPC front end, sharing `AppState` and the handler logic with REST
+
+mod service;
+
+pub use service::GrpcService;
+
+/// Generated from proto/vector_search.proto
+pub mod proto {
+    tonic::include_proto!("vectorsearch.v1");
+}
@@ -0,0 +1,310 @@
+use crate::api::models::{self, default_top_k, AppError, AppState};
+use crate::api::review::handlers::{add_review, add_to_namespace};
+use crate::api::search::handlers::{search, search_namespace};
+use crate::api::tenancy::{authorize, key_fingerprint, presented_key, Namespace};
+use crate::grpc::proto::vector_search_server::{VectorSearch, VectorSearchServer};
+use crate::grpc::proto::{
+    AddReviewRequest, AddReviewResponse, BatchSearchRequest, BatchSearchResponse, HealthRequest,
+    HealthResponse, SearchRequest, SearchResponse, SearchResult,
+};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::{interceptor::InterceptedService, Interceptor};
+use tonic::{Request, Response, Status};
+
+/// Health stream interval when the client doesn't pick one
+const DEFAULT_HEALTH_INTERVAL_MS: u64 = 5_000;
+
+pub struct GrpcService {
+    state: AppState,
+}
+
+/// Fingerprint of the caller's API key, for audit entries
+#[derive(Clone)]
+struct CallerKey(Option<String>);
+
+/// The REST tenancy checks, applied to every call
+///
+/// Reads the key from `x-api-key` or `authorization: Bearer` metadata and
+/// the namespace from `x-namespace`, as REST does from headers.
+#[derive(Clone)]
+pub struct TenantInterceptor {
+    state: AppState,
+}
+
+impl Interceptor for TenantInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        // Every call adds or searches reviews, bar the health stream, which
+        // REST serves to anyone
+        let namespace = authorize(&self.state, &headers, true)?;
+        let caller = CallerKey(presented_key(&headers).map(key_fingerprint));
+        request.extensions_mut().insert(caller);
+        if let Some(namespace) = namespace {
+            request.extensions_mut().insert(namespace);
+        }
+        Ok(request)
+    }
+}
+
+impl GrpcService {
+    pub fn new(state: AppState) -> InterceptedService<VectorSearchServer<Self>, TenantInterceptor> {
+        let interceptor = TenantInterceptor { state: state.clone() };
+        VectorSearchServer::with_interceptor(Self { state }, interceptor)
+    }
+
+    /// Same gate as `require_ready` on the REST routes
+    fn require_ready(&self) -> Result<(), Status> {
+        if self.state.readiness.is_ready() {
+            return Ok(());
+        }
+        Err(Status::unavailable(format!(
+            "Service is starting up (waiting for: {})",
+            self.state.readiness.pending().join(", ")
+        )))
+    }
+
+    /// Search the caller's namespace, or the default collection, recording
+    /// the search for the analytics reports
+    async fn search_recorded(
+        &self,
+        namespace: Option<&Namespace>,
+        request: models::SearchRequest,
+    ) -> Result<models::SearchResponse, AppError> {
+        let started = Instant::now();
+        let response = match namespace {
+            Some(Namespace(namespace)) => search_namespace(&self.state, namespace, request).await?,
+            None => search(&self.state, request).await?,
+        };
+        self.state.analytics.record(
+            &self.state.config.current().analytics,
+            &response,
+            started.elapsed(),
+        );
+        Ok(response)
+    }
+
+    fn health(&self) -> HealthResponse {
+        HealthResponse {
+            status: "healthy".to_string(),
+            ready: self.state.readiness.is_ready(),
+            total_reviews: self.state.metadata_store.count_lines().unwrap_or(0) as u64,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl VectorSearch for GrpcService {
+    async fn add_review(
+        &self,
+        request: Request<AddReviewRequest>,
+    ) -> Result<Response<AddReviewResponse>, Status> {
+        self.require_ready()?;
+        let api_key = request.extensions().get::<CallerKey>().and_then(|caller| caller.0.clone());
+        let namespace = request.extensions().get::<Namespace>().cloned();
+        let request = request.into_inner();
+        let request = models::AddReviewRequest {
+            review_title: request.review_title,
+            review_body: request.review_body,
+            product_id: request.product_id,
+            review_rating: request.review_rating.try_into().unwrap_or(u8::MAX),
+            expires_at: None,
+            ttl_secs: None,
+            language: None,
+            attributes: Default::default(),
+            image: None,
+        };
+
+        let response = match namespace {
+            Some(Namespace(namespace)) => {
+                add_to_namespace(self.state.clone(), api_key, &namespace, request).await?
+            }
+            None => add_review(self.state.clone(), api_key, request).await?,
+        };
+        Ok(Response::new(AddReviewResponse {
+            vector_id: response.vector_id as u64,
+            duplicate_of: response.duplicate_of.map(|id| id as u64),
+        }))
+    }
+
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchResponse>, Status> {
+        self.require_ready()?;
+        let namespace = request.extensions().get::<Namespace>().cloned();
+        let response = self
+            .search_recorded(namespace.as_ref(), search_request(request.into_inner()))
+            .await?;
+        Ok(Response::new(search_response(response)))
+    }
+
+    async fn batch_search(
+        &self,
+        request: Request<BatchSearchRequest>,
+    ) -> Result<Response<BatchSearchResponse>, Status> {
+        self.require_ready()?;
+        let namespace = request.extensions().get::<Namespace>().cloned();
+        let queries = request.into_inner().queries;
+        let max_queries = self.state.config.current().limits.batch_search_queries;
+        if queries.len() > max_queries {
+            return Err(AppError::BadRequest(format!("At most {} queries per batch", max_queries)).into());
+        }
+        let mut responses = Vec::new();
+        for query in queries {
+            let response = self.search_recorded(namespace.as_ref(), search_request(query)).await?;
+            responses.push(search_response(response));
+        }
+        Ok(Response::new(BatchSearchResponse { responses }))
+    }
+
+    type WatchHealthStream = ReceiverStream<Result<HealthResponse, Status>>;
+
+    async fn watch_health(
+        &self,
+        request: Request<HealthRequest>,
+    ) -> Result<Response<Self::WatchHealthStream>, Status> {
+        let interval_ms = match request.into_inner().interval_ms {
+            0 => DEFAULT_HEALTH_INTERVAL_MS,
+            ms => ms as u64,
+        };
+        let service = Self {
+            state: self.state.clone(),
+        };
+
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms));
+            loop {
+                ticker.tick().await;
+                // Stop once the client goes away
+                if tx.send(Ok(service.health())).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+fn search_request(request: SearchRequest) -> models::SearchRequest {
+    models::SearchRequest {
+        query: request.query,
+        top_k: match request.top_k {
+            0 => default_top_k(),
+            k => k as usize,
+        },
+        filter: None,
+        aggregate: false,
+        include_vectors: false,
+        include_distance: false,
+        image: None,
+        cross_lingual: false,
+        sort: Vec::new(),
+        collections: Vec::new(),
+    }
+}
+
+fn search_response(response: models::SearchResponse) -> SearchResponse {
+    SearchResponse {
+        query: response.query,
+        corrected_query: response.corrected_query.unwrap_or_default(),
+        results: response
+            .results
+            .into_iter()
+            .map(|r| SearchResult {
+                review_title: r.review_title,
+                review_body: r.review_body,
+                product_id: r.product_id,
+                review_rating: r.review_rating.into(),
+                similarity_score: r.similarity_score,
+                vector_id: r.vector_id as u64,
+            })
+            .collect(),
+    }
+}
+
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        match err {
+            AppError::BadRequest(msg) => Status::invalid_argument(msg),
+            AppError::Unauthorized(msg) => Status::unauthenticated(msg),
+            AppError::Forbidden(msg) => Status::permission_denied(msg),
+            AppError::NotFound(msg) => Status::not_found(msg),
+            AppError::Conflict(msg) | AppError::Duplicate { message: msg, .. } => Status::already_exists(msg),
+            AppError::PayloadTooLarge(msg) => Status::resource_exhausted(msg),
+            AppError::TooManyRequests(msg) => Status::resource_exhausted(msg),
+            AppError::Unprocessable { message, .. } => Status::invalid_argument(message),
+            AppError::Timeout(msg) => Status::deadline_exceeded(msg),
+            AppError::ServiceUnavailable(msg) => Status::unavailable(msg),
+            AppError::Overloaded { status, message, .. } if status == axum::http::StatusCode::TOO_MANY_REQUESTS => {
+                Status::resource_exhausted(message)
+            }
+            AppError::Overloaded { message, .. } => Status::unavailable(message),
+            AppError::Internal(msg) => Status::internal(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TenantKey;
+    use crate::test_utils::TestApp;
+
+    fn call(app: &TestApp, metadata: &[(&'static str, &'static str)]) -> Result<Request<()>, Status> {
+        let mut request = Request::new(());
+        for (key, value) in metadata {
+            request.metadata_mut().insert(*key, value.parse().unwrap());
+        }
+        TenantInterceptor { state: app.state.clone() }.call(request)
+    }
+
+    #[tokio::test]
+    async fn test_calls_need_a_tenant_key() {
+        let app = TestApp::builder()
+            .config(|c| {
+                c.tenancy.enabled = true;
+                c.tenancy.keys = vec![
+                    TenantKey { key: "operator".to_string(), namespaces: Vec::new() },
+                    TenantKey { key: "acme-key".to_string(), namespaces: vec!["acme".to_string()] },
+                ];
+            })
+            .build()
+            .unwrap();
+
+        let status = call(&app, &[]).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        let status = call(&app, &[("x-api-key", "guess")]).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        let status = call(&app, &[("x-api-key", "acme-key"), ("x-namespace", "globex")]).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        let request = call(&app, &[("authorization", "Bearer operator")]).unwrap();
+        assert!(request.extensions().get::<Namespace>().is_none());
+        let request = call(&app, &[("x-api-key", "acme-key")]).unwrap();
+        assert_eq!(request.extensions().get::<Namespace>().unwrap().0, "acme");
+    }
+
+    #[tokio::test]
+    async fn test_batch_search_is_capped() {
+        let app = TestApp::builder()
+            .config(|c| c.limits.batch_search_queries = 2)
+            .build()
+            .unwrap();
+        let service = GrpcService { state: app.state.clone() };
+        let batch = |n: usize| {
+            Request::new(BatchSearchRequest {
+                queries: vec![SearchRequest { query: "battery".to_string(), ..Default::default() }; n],
+            })
+        };
+
+        let status = service.batch_search(batch(3)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let response = service.batch_search(batch(2)).await.unwrap();
+        assert_eq!(response.into_inner().responses.len(), 2);
+    }
+}
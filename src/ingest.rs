@@ -0,0 +1,409 @@
+use crate::api::models::{AppError, AppState};
+use crate::cli::{delete_tombstoned, embed_reviews};
+use crate::embedding::EmbeddingService;
+use crate::storage::{now_ms, ReviewMetadata, VectorIndex};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use std::sync::Arc;
+use tokio::sync::{watch, Notify, OwnedRwLockWriteGuard, RwLock, RwLockWriteGuard};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Reviews embedded and indexed per round of the background indexer
+const BATCH_SIZE: usize = 64;
+
+/// How long a write waits for the backlog to drain before giving up
+const BACKLOG_WAIT: Duration = Duration::from_secs(30);
+
+/// Failed rounds in a row before the backlog is embedded one review at a
+/// time, dead-lettering the ones that still fail
+const MAX_ATTEMPTS: u32 = 3;
+
+/// How long shutdown keeps indexing; startup replays whatever is left
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reviews accepted with `durability=async` whose vectors aren't indexed yet
+///
+/// Their metadata lines are already written, so until the backlog drains
+/// the metadata file runs ahead of the index. Writes that assume the two
+/// line up take the index lock through [`IngestQueue::write_index`].
+#[derive(Debug, Default)]
+pub struct IngestQueue {
+    pending: AtomicUsize,
+    accepted: AtomicU64,
+    dead_lettered: AtomicU64,
+    /// Wakes the indexer
+    work: Notify,
+    /// Wakes writers waiting for the backlog to drain
+    drained: Notify,
+}
+
+impl IngestQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reviews waiting to be indexed
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Reviews accepted with `durability=async` since startup
+    pub fn accepted(&self) -> u64 {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    /// Reviews given up on and moved to the dead-letter file since startup
+    pub fn dead_lettered(&self) -> u64 {
+        self.dead_lettered.load(Ordering::Relaxed)
+    }
+
+    /// Count a review whose metadata line was just written; call under the
+    /// index write lock
+    pub fn push(&self) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+        self.work.notify_one();
+    }
+
+    /// The index write lock, once every accepted review is indexed
+    ///
+    /// 503 if the backlog doesn't drain within [`BACKLOG_WAIT`].
+    pub async fn write_index<'a>(
+        &self,
+        index: &'a RwLock<VectorIndex>,
+    ) -> Result<RwLockWriteGuard<'a, VectorIndex>, AppError> {
+        self.when_drained(BACKLOG_WAIT, || index.write()).await
+    }
+
+    /// [`write_index`](Self::write_index), as a guard a blocking task can own
+    pub async fn write_index_owned(
+        &self,
+        index: &Arc<RwLock<VectorIndex>>,
+    ) -> Result<OwnedRwLockWriteGuard<VectorIndex>, AppError> {
+        self.when_drained(BACKLOG_WAIT, || index.clone().write_owned()).await
+    }
+
+    async fn when_drained<G, F>(&self, wait: Duration, lock: impl Fn() -> F) -> Result<G, AppError>
+    where
+        F: Future<Output = G>,
+    {
+        let acquire = async {
+            loop {
+                let drained = self.drained.notified();
+                let guard = lock().await;
+                if self.pending() == 0 {
+                    return guard;
+                }
+                drop(guard);
+                drained.await;
+            }
+        };
+        tokio::time::timeout(wait, acquire).await.map_err(|_| {
+            AppError::ServiceUnavailable(format!(
+                "{} accepted reviews are still waiting to be indexed",
+                self.pending()
+            ))
+        })
+    }
+
+    /// Record how many metadata lines the index is still missing
+    fn set_backlog(&self, backlog: usize) {
+        self.pending.store(backlog, Ordering::SeqCst);
+        if backlog == 0 {
+            self.drained.notify_waiters();
+        }
+    }
+}
+
+/// Spawn the background indexer for `durability=async` adds
+///
+/// Embeds and indexes metadata lines past the end of the index in batches,
+/// saving the index after each. After [`MAX_ATTEMPTS`] failed rounds in a
+/// row, reviews are embedded one at a time and those that can't be are
+/// tombstoned and written to `storage.dead_letter_path`, so one bad review
+/// doesn't hold up the rest. On shutdown it keeps going for up to
+/// [`DRAIN_TIMEOUT`], so the final save covers every accepted review; after
+/// a crash, or if time runs out, startup replays whatever is left.
+pub fn spawn_async_indexer(
+    state: AppState,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut drain_deadline = None;
+        let mut failures = 0;
+        loop {
+            let work = state.ingest.work.notified();
+            if state.ingest.pending() == 0 {
+                if drain_deadline.is_some() {
+                    break;
+                }
+                tokio::select! {
+                    _ = work => {}
+                    _ = shutdown_rx.wait_for(|&stop| stop) => {
+                        drain_deadline = Some(tokio::time::Instant::now() + DRAIN_TIMEOUT);
+                        continue;
+                    }
+                }
+            }
+
+            let round = index_batch(&state, failures >= MAX_ATTEMPTS);
+            let result = match drain_deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, round).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        warn!(pending = state.ingest.pending(), "Async backlog not indexed before shutdown; startup will replay it");
+                        break;
+                    }
+                },
+                None => round.await,
+            };
+            match result {
+                Ok(indexed) => {
+                    failures = 0;
+                    if indexed > 0 {
+                        info!(indexed, pending = state.ingest.pending(), "Indexed accepted reviews");
+                    }
+                }
+                Err(e) if drain_deadline.is_some() => {
+                    error!(pending = state.ingest.pending(), "Async indexing failed during shutdown; startup will replay: {:#}", e);
+                    break;
+                }
+                Err(e) => {
+                    failures += 1;
+                    error!(failures, "Async indexing failed, retrying: {:#}", e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                        _ = shutdown_rx.wait_for(|&stop| stop) => {
+                            drain_deadline = Some(tokio::time::Instant::now() + DRAIN_TIMEOUT);
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// A review the background indexer gave up on
+#[derive(Serialize)]
+struct DeadLetter<'a> {
+    vector_id: usize,
+    failed_at: u64,
+    error: &'a str,
+    review: &'a ReviewMetadata,
+}
+
+fn write_dead_letters(path: &Path, letters: &[DeadLetter]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    for letter in letters {
+        serde_json::to_writer(&mut file, letter)?;
+        file.write_all(b"\n")?;
+    }
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Embed and index the next batch of lines the index is missing
+///
+/// With `isolate`, reviews are embedded one at a time; each that fails
+/// gets a placeholder vector, masked right away, and a dead letter.
+async fn index_batch(state: &AppState, isolate: bool) -> Result<usize> {
+    // Under the lock, so an accept can't land between the two counts
+    let (from, reviews) = {
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || {
+            let index = state.vector_index.blocking_read();
+            let from = index.vector_count();
+            let backlog = state.metadata_store.count_lines()?.saturating_sub(from);
+            if backlog == 0 {
+                state.ingest.set_backlog(0);
+                return Ok::<_, anyhow::Error>((from, Vec::new()));
+            }
+            Ok((from, state.metadata_store.read_range(from, backlog.min(BATCH_SIZE))?))
+        })
+        .await??
+    };
+    if reviews.is_empty() {
+        return Ok(0);
+    }
+
+    let embedded_with = state.config.current();
+    let embedder = state
+        .embedder_for(&embedded_with.embedding.model_name)
+        .await
+        .map_err(|e| anyhow::anyhow!(e.message().to_string()))?;
+    let normalization = embedded_with.embedding.normalization.clone();
+    let (embeddings, failed) = tokio::task::spawn_blocking(move || {
+        if !isolate {
+            let embeddings = embed_reviews(&embedder, &reviews, &normalization, BATCH_SIZE)?;
+            return Ok::<_, anyhow::Error>((embeddings, Vec::new()));
+        }
+        let mut embeddings = Vec::with_capacity(reviews.len());
+        let mut failed = Vec::new();
+        for (offset, review) in reviews.iter().enumerate() {
+            match embed_reviews(&embedder, std::slice::from_ref(review), &normalization, 1) {
+                Ok(mut embedding) => embeddings.push(embedding.remove(0)),
+                Err(e) => {
+                    let mut placeholder = vec![0.0; embedder.dimension()];
+                    placeholder[0] = 1.0;
+                    embeddings.push(placeholder);
+                    failed.push((offset, format!("{:#}", e)));
+                }
+            }
+        }
+        Ok((embeddings, failed))
+    })
+    .await??;
+
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut index = state.vector_index.blocking_write();
+        let current = state.config.current();
+        if index.vector_count() != from
+            || !EmbeddingService::same_model(&current.embedding.model_name, &embedded_with.embedding.model_name)
+            || current.embedding.normalization != embedded_with.embedding.normalization
+        {
+            // Replaced underneath us (restore, rebuild, cutover); start over
+            return Ok(0);
+        }
+        for embedding in &embeddings {
+            index.add_vector(embedding)?;
+        }
+        let mut reviews = state.metadata_store.read_range(from, embeddings.len())?;
+        // Reviews deleted while they waited need no dead letter
+        let failed: Vec<_> = failed.iter().filter(|(offset, _)| !reviews[*offset].deleted).collect();
+        if !failed.is_empty() {
+            let failed_at = now_ms();
+            let letters: Vec<DeadLetter> = failed
+                .iter()
+                .map(|(offset, error)| DeadLetter {
+                    vector_id: from + offset,
+                    failed_at,
+                    error,
+                    review: &reviews[*offset],
+                })
+                .collect();
+            write_dead_letters(&state.config.current().storage.dead_letter_path, &letters)?;
+            let updates: Vec<(usize, ReviewMetadata)> = failed
+                .iter()
+                .map(|(offset, _)| {
+                    let mut review = reviews[*offset].clone();
+                    review.revision += 1;
+                    review.deleted = true;
+                    (from + offset, review)
+                })
+                .collect();
+            state.metadata_store.replace_many(&updates)?;
+            for (vector_id, review) in updates {
+                warn!(vector_id, "Accepted review can't be embedded; moved to the dead-letter file");
+                reviews[vector_id - from] = review;
+            }
+            state.ingest.dead_lettered.fetch_add(failed.len() as u64, Ordering::Relaxed);
+            state.suggester.invalidate();
+            state.spelling.invalidate();
+        }
+        // Tombstoned while they waited, or just now
+        delete_tombstoned(&mut index, &reviews, from)?;
+        if let Err(e) = index.save(&state.index_path()) {
+            warn!("Save index failed, retrying at the next snapshot: {:#}", e);
+        }
+        state.search_cache.invalidate();
+
+        let backlog = state.metadata_store.count_lines()?.saturating_sub(index.vector_count());
+        state.ingest.set_backlog(backlog);
+        Ok(embeddings.len())
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::embedding::{EmbeddingProvider, EmbeddingService};
+    use crate::storage::IndexBackend;
+    use crate::test_utils::{review, HashEmbedder, TestApp};
+    use std::sync::OnceLock;
+
+    #[tokio::test]
+    async fn test_write_index_gives_up() {
+        let queue = IngestQueue::new();
+        let index = RwLock::new(());
+        queue.push();
+        let result = queue.when_drained(Duration::from_millis(20), || index.write()).await;
+        assert!(matches!(result, Err(AppError::ServiceUnavailable(_))));
+    }
+
+    /// Fails any batch with a review mentioning "poison"
+    struct Poisoned(HashEmbedder);
+
+    impl EmbeddingProvider for Poisoned {
+        fn embed_batch(&self, texts: Vec<&str>) -> anyhow::Result<Vec<Vec<f32>>> {
+            if texts.iter().any(|text| text.contains("poison")) {
+                anyhow::bail!("can't embed");
+            }
+            self.0.embed_batch(texts)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_reviews_that_never_embed() {
+        let app = TestApp::builder().build().unwrap();
+        let mut state = app.state.clone();
+        let config = state.config.current();
+        let dimension = config.index.vector_dim;
+        let poisoned = Poisoned(HashEmbedder::new(dimension, 0));
+        let poisoned = EmbeddingService::from_provider(&config.embedding.model_name, dimension, poisoned);
+        state.embedding_service = Arc::new(OnceLock::from(Arc::new(poisoned)));
+        for title in ["Fine", "poison", "Also fine"] {
+            state.metadata_store.append(&review(title, "P1")).unwrap();
+            state.ingest.push();
+        }
+
+        assert!(index_batch(&state, false).await.is_err());
+        assert_eq!(index_batch(&state, true).await.unwrap(), 3);
+        assert_eq!(state.ingest.pending(), 0);
+        assert_eq!(state.ingest.dead_lettered(), 1);
+        assert!(state.metadata_store.read_by_id(1).unwrap().deleted);
+        assert!(!state.metadata_store.read_by_id(2).unwrap().deleted);
+
+        let letters = std::fs::read_to_string(&state.config.current().storage.dead_letter_path).unwrap();
+        let letter: serde_json::Value = serde_json::from_str(letters.lines().next().unwrap()).unwrap();
+        assert_eq!(letter["vector_id"], 1);
+        assert_eq!(letter["review"]["review_title"], "poison");
+    }
+
+    #[tokio::test]
+    async fn test_write_index_waits_for_backlog() {
+        let queue = Arc::new(IngestQueue::new());
+        let mut config = AppConfig::default().index;
+        config.backend = IndexBackend::Memory;
+        let index = Arc::new(RwLock::new(VectorIndex::new(&config)));
+        queue.push();
+
+        let writer = tokio::spawn({
+            let (queue, index) = (queue.clone(), index.clone());
+            async move {
+                let _guard = queue.write_index(&index).await.unwrap();
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!writer.is_finished());
+
+        queue.set_backlog(0);
+        writer.await.unwrap();
+        assert_eq!(queue.accepted(), 1);
+    }
+}
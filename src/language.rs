@@ -0,0 +1,77 @@
+//! Review language tags
+
+use crate::api::models::ReviewFilter;
+
+/// ISO 639-1 codes of the languages the detector knows, with the ISO 639-3
+/// code it reports for them
+const TWO_LETTER_CODES: &[(&str, &str)] = &[
+    ("af", "afr"), ("ak", "aka"), ("am", "amh"), ("ar", "ara"), ("az", "aze"),
+    ("be", "bel"), ("bg", "bul"), ("bn", "ben"), ("cs", "ces"), ("da", "dan"),
+    ("de", "deu"), ("el", "ell"), ("en", "eng"), ("eo", "epo"), ("es", "spa"),
+    ("et", "est"), ("fa", "pes"), ("fi", "fin"), ("fr", "fra"), ("gu", "guj"),
+    ("he", "heb"), ("hi", "hin"), ("hr", "hrv"), ("hu", "hun"), ("hy", "hye"),
+    ("id", "ind"), ("it", "ita"), ("ja", "jpn"), ("jv", "jav"), ("ka", "kat"),
+    ("km", "khm"), ("kn", "kan"), ("ko", "kor"), ("lt", "lit"), ("lv", "lav"),
+    ("mk", "mkd"), ("ml", "mal"), ("mr", "mar"), ("my", "mya"), ("nb", "nob"),
+    ("ne", "nep"), ("nl", "nld"), ("no", "nob"), ("or", "ori"), ("pa", "pan"),
+    ("pl", "pol"), ("pt", "por"), ("ro", "ron"), ("ru", "rus"), ("si", "sin"),
+    ("sl", "slv"), ("sn", "sna"), ("sr", "srp"), ("sv", "swe"), ("ta", "tam"),
+    ("te", "tel"), ("th", "tha"), ("tk", "tuk"), ("tr", "tur"), ("uk", "ukr"),
+    ("ur", "urd"), ("uz", "uzb"), ("vi", "vie"), ("yi", "yid"), ("zh", "cmn"),
+    ("zu", "zul"),
+];
+
+/// Canonical form of a language tag: trimmed, lower-case, without a region
+/// (`en-US`), and with two-letter codes mapped to the three-letter ones the
+/// detector reports, so "en", "EN" and "eng" are the same language
+pub fn normalize(tag: &str) -> String {
+    let tag = tag.trim().to_ascii_lowercase();
+    let primary = tag.split(['-', '_']).next().unwrap_or_default();
+    match TWO_LETTER_CODES.iter().find(|(short, _)| *short == primary) {
+        Some((_, code)) => code.to_string(),
+        None => primary.to_string(),
+    }
+}
+
+/// Normalize every `language` condition in `filter`, nested ones included
+pub fn normalize_filter(filter: &mut ReviewFilter) {
+    if let Some(language) = &mut filter.language {
+        *language = normalize(language);
+    }
+    filter
+        .and
+        .iter_mut()
+        .chain(&mut filter.or)
+        .chain(filter.not.as_deref_mut())
+        .for_each(normalize_filter);
+}
+
+/// ISO 639-3 code of the language `text` is written in, if the detector is
+/// at least `min_confidence` sure
+pub fn detect(text: &str, min_confidence: f64) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    (info.confidence() >= min_confidence).then(|| info.lang().code().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect("The battery lasts all day and the screen is bright", 0.5).as_deref(),
+            Some("eng")
+        );
+        assert_eq!(
+            detect("Die Batterie hält den ganzen Tag und der Bildschirm ist hell", 0.5).as_deref(),
+            Some("deu")
+        );
+        assert_eq!(detect("ok", 0.99), None);
+        assert_eq!(normalize(" EN "), "eng");
+        assert_eq!(normalize("en-US"), "eng");
+        assert_eq!(normalize("eng"), "eng");
+        assert_eq!(normalize("de"), "deu");
+        assert_eq!(normalize("tlh"), "tlh");
+    }
+}
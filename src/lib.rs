@@ -0,0 +1,46 @@
+//! Vector search over product reviews: SPFresh ANN index, fastembed
+//! embeddings and an axum HTTP API
+//!
+//! The `vector-search-api` binary is a thin wrapper around [`cli::run`].
+//! To embed the engine elsewhere, build an [`AppState`] and serve (or nest)
+//! [`build_router`]:
+//!
+//! ```no_run
+//! # async fn mount(state: vector_search_api::AppState) -> anyhow::Result<()> {
+//! let app = axum::Router::new().nest("/search-api", vector_search_api::build_router(state));
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8000").await?;
+//! axum::serve(listener, app).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod api;
+pub mod cli;
+pub mod config;
+pub mod content_hash;
+pub mod embedding;
+pub mod field_vectors;
+pub mod grpc;
+pub mod ingest;
+pub mod language;
+pub mod listener;
+pub mod llm;
+pub mod logging;
+pub mod memory;
+pub mod redaction;
+pub mod replication;
+pub mod shadow;
+pub mod snapshot;
+pub mod spelling;
+pub mod storage;
+pub mod suggest;
+pub mod synonyms;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod ttl;
+pub mod webhooks;
+
+pub use api::{build_admin_router, build_router, AppState};
+pub use config::AppConfig;
+pub use embedding::EmbeddingService;
+pub use storage::{AnnIndex, JsonlStorage, ReviewMetadata, SearchResult, VectorIndex};
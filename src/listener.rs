@@ -0,0 +1,148 @@
+//! The public TCP listener: bound as usual, bound with SO_REUSEPORT, or
+//! inherited from a supervisor, for restarts without downtime
+
+use crate::config::ServerConfig;
+use anyhow::{Context, Result};
+use tokio::net::TcpListener;
+
+/// First descriptor systemd passes with socket activation
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Connections the kernel queues before they're accepted
+#[cfg(unix)]
+const BACKLOG: u32 = 1024;
+
+/// Where the public TCP listener comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerSource {
+    /// Bind `host:port`
+    Bind,
+    /// Bind `host:port` with SO_REUSEPORT, alongside another instance
+    ReusePort,
+    /// A listening socket handed over as this descriptor
+    Inherited(i32),
+}
+
+impl ListenerSource {
+    /// `server.listen_fd`, then systemd socket activation, then a bind
+    pub fn from_config(settings: &ServerConfig) -> Result<Self> {
+        if let Some(fd) = settings.listen_fd {
+            return Ok(Self::Inherited(fd));
+        }
+        if let Some(fd) = systemd_listen_fd() {
+            return Ok(Self::Inherited(fd));
+        }
+        Ok(if settings.reuse_port { Self::ReusePort } else { Self::Bind })
+    }
+
+    /// Whether to accept only once the model and index are loaded
+    ///
+    /// Until then, connections go to the old instance sharing the port, or
+    /// wait in the inherited socket's queue, instead of getting 503s.
+    pub fn is_handoff(&self) -> bool {
+        *self != Self::Bind
+    }
+
+    /// Open the listener for `addr` (`host:port`, unused when inherited)
+    pub async fn listen(self, addr: &str) -> Result<TcpListener> {
+        match self {
+            Self::Bind => Ok(TcpListener::bind(addr).await?),
+            Self::ReusePort => bind_reuse_port(addr).await,
+            Self::Inherited(fd) => inherit(fd),
+        }
+    }
+}
+
+/// The first socket systemd passed, if it started us with socket activation
+#[cfg(unix)]
+fn systemd_listen_fd() -> Option<i32> {
+    activation_fd(
+        std::env::var("LISTEN_PID").ok().as_deref(),
+        std::env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+#[cfg(not(unix))]
+fn systemd_listen_fd() -> Option<i32> {
+    None
+}
+
+/// `LISTEN_PID` guards against variables inherited by a child process
+#[cfg(unix)]
+fn activation_fd(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> Option<i32> {
+    let listen_pid: u32 = listen_pid?.trim().parse().ok()?;
+    let listen_fds: u32 = listen_fds?.trim().parse().ok()?;
+    (listen_pid == pid && listen_fds >= 1).then_some(SD_LISTEN_FDS_START)
+}
+
+#[cfg(unix)]
+async fn bind_reuse_port(addr: &str) -> Result<TcpListener> {
+    let resolved = tokio::net::lookup_host(addr)
+        .await?
+        .next()
+        .with_context(|| format!("{} did not resolve to an address", addr))?;
+    let socket = if resolved.is_ipv4() {
+        tokio::net::TcpSocket::new_v4()?
+    } else {
+        tokio::net::TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    socket.set_reuseport(true)?;
+    socket.bind(resolved)?;
+    Ok(socket.listen(BACKLOG)?)
+}
+
+#[cfg(not(unix))]
+async fn bind_reuse_port(_addr: &str) -> Result<TcpListener> {
+    anyhow::bail!("server.reuse_port is only supported on unix platforms")
+}
+
+#[cfg(unix)]
+fn inherit(fd: i32) -> Result<TcpListener> {
+    use std::os::unix::io::FromRawFd;
+
+    // The descriptor is ours from here on; it's closed with the listener
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    listener
+        .local_addr()
+        .with_context(|| format!("Inherited descriptor {} is not a TCP socket", fd))?;
+    listener.set_nonblocking(true)?;
+    Ok(TcpListener::from_std(listener)?)
+}
+
+#[cfg(not(unix))]
+fn inherit(_fd: i32) -> Result<TcpListener> {
+    anyhow::bail!("server.listen_fd is only supported on unix platforms")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::io::IntoRawFd;
+
+    #[test]
+    fn test_activation_fd() {
+        assert_eq!(activation_fd(Some("42"), Some("1"), 42), Some(3));
+        assert_eq!(activation_fd(Some("42"), Some("2"), 42), Some(3));
+        // Meant for another process, or nothing passed
+        assert_eq!(activation_fd(Some("41"), Some("1"), 42), None);
+        assert_eq!(activation_fd(Some("42"), Some("0"), 42), None);
+        assert_eq!(activation_fd(None, Some("1"), 42), None);
+    }
+
+    #[tokio::test]
+    async fn test_reuse_port_and_inherited_listeners() {
+        let first = ListenerSource::ReusePort.listen("127.0.0.1:0").await.unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = ListenerSource::ReusePort.listen(&addr.to_string()).await.unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+        assert!(TcpListener::bind(addr).await.is_err());
+
+        let fd = std::net::TcpListener::bind("127.0.0.1:0").unwrap().into_raw_fd();
+        let inherited = ListenerSource::Inherited(fd).listen("unused:0").await.unwrap();
+        tokio::net::TcpStream::connect(inherited.local_addr().unwrap()).await.unwrap();
+        inherited.accept().await.unwrap();
+    }
+}
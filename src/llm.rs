@@ -0,0 +1,270 @@
+//! Chat completions from an OpenAI-compatible endpoint or Ollama
+//! (`rag.llm`), for answers generated from retrieved reviews
+
+use crate::config::{LlmApi, LlmConfig};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Shared across calls for connection reuse
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Limit on opening a connection to the LLM endpoint
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatMessage {
+    pub role: &'static str,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system",
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user",
+            content: content.into(),
+        }
+    }
+}
+
+/// The model's reply to `messages`
+pub async fn complete(settings: &LlmConfig, messages: &[ChatMessage]) -> Result<String> {
+    let (url, request) = chat_request(settings, messages, false);
+    let response: Value = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach {}", url))?
+        .error_for_status()?
+        .json()
+        .await
+        .context("LLM response is not JSON")?;
+
+    let content = match settings.api {
+        LlmApi::Ollama => &response["message"]["content"],
+        LlmApi::OpenAi => &response["choices"][0]["message"]["content"],
+    };
+    content
+        .as_str()
+        .map(str::to_string)
+        .context("LLM response has no message content")
+}
+
+/// The model's reply to `messages`, piece by piece as it is generated
+///
+/// Fails up front if the endpoint can't be reached or rejects the request;
+/// errors after that arrive on the channel, which closes after the last
+/// piece.
+///
+/// A long answer may take longer than `timeout_ms` in all; the limit
+/// applies to the response starting and to each wait for the next piece.
+pub async fn stream(settings: &LlmConfig, messages: &[ChatMessage]) -> Result<mpsc::Receiver<Result<String>>> {
+    let (url, request) = chat_request(settings, messages, true);
+    let idle = Duration::from_millis(settings.timeout_ms);
+    let mut response = tokio::time::timeout(idle, request.send())
+        .await
+        .with_context(|| format!("{} did not answer within {:?}", url, idle))?
+        .with_context(|| format!("Failed to reach {}", url))?
+        .error_for_status()?;
+
+    let api = settings.api;
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        // Ollama sends one JSON object per line, OpenAI server-sent events
+        let mut buffer = Vec::new();
+        loop {
+            let chunk = match tokio::time::timeout(idle, response.chunk()).await {
+                Ok(Ok(Some(chunk))) => chunk,
+                Ok(Ok(None)) => break,
+                Ok(Err(e)) => {
+                    let _ = tx.send(Err(e.into())).await;
+                    return;
+                }
+                Err(_) => {
+                    let _ = tx.send(Err(anyhow::anyhow!("LLM sent nothing for {:?}", idle))).await;
+                    return;
+                }
+            };
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                match parse_stream_line(api, line.trim()) {
+                    StreamLine::Piece(piece) => {
+                        if tx.send(Ok(piece)).await.is_err() {
+                            return;
+                        }
+                    }
+                    StreamLine::Skip => {}
+                    StreamLine::Done => return,
+                }
+            }
+        }
+    });
+    Ok(rx)
+}
+
+fn chat_request(settings: &LlmConfig, messages: &[ChatMessage], stream: bool) -> (String, reqwest::RequestBuilder) {
+    let (path, body) = match settings.api {
+        LlmApi::Ollama => (
+            "/api/chat",
+            json!({
+                "model": settings.model,
+                "messages": messages,
+                "stream": stream,
+                "options": { "temperature": settings.temperature },
+            }),
+        ),
+        LlmApi::OpenAi => (
+            "/chat/completions",
+            json!({
+                "model": settings.model,
+                "messages": messages,
+                "temperature": settings.temperature,
+                "stream": stream,
+            }),
+        ),
+    };
+
+    let url = format!("{}{}", settings.url.trim_end_matches('/'), path);
+    let client = CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("HTTP client")
+    });
+    let mut request = client.post(&url).json(&body);
+    // A streamed response is bounded per piece instead (see `stream`)
+    if !stream {
+        request = request.timeout(Duration::from_millis(settings.timeout_ms));
+    }
+    if let Some(api_key) = &settings.api_key {
+        request = request.bearer_auth(api_key);
+    }
+    (url, request)
+}
+
+#[derive(Debug, PartialEq)]
+enum StreamLine {
+    Piece(String),
+    /// Blank lines, comments, role-only deltas
+    Skip,
+    Done,
+}
+
+fn parse_stream_line(api: LlmApi, line: &str) -> StreamLine {
+    let json = match api {
+        LlmApi::Ollama => line,
+        LlmApi::OpenAi => match line.strip_prefix("data:").map(str::trim) {
+            Some("[DONE]") => return StreamLine::Done,
+            Some(data) => data,
+            None => return StreamLine::Skip,
+        },
+    };
+    let Ok(value) = serde_json::from_str::<Value>(json) else {
+        return StreamLine::Skip;
+    };
+
+    let piece = match api {
+        LlmApi::Ollama => &value["message"]["content"],
+        LlmApi::OpenAi => &value["choices"][0]["delta"]["content"],
+    };
+    match piece.as_str() {
+        Some(piece) if !piece.is_empty() => StreamLine::Piece(piece.to_string()),
+        _ if api == LlmApi::Ollama && value["done"] == true => StreamLine::Done,
+        _ => StreamLine::Skip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_line() {
+        let piece = |text: &str| StreamLine::Piece(text.to_string());
+        assert_eq!(
+            parse_stream_line(LlmApi::Ollama, r#"{"message":{"content":"Hel"},"done":false}"#),
+            piece("Hel")
+        );
+        assert_eq!(
+            parse_stream_line(LlmApi::Ollama, r#"{"message":{"content":""},"done":true}"#),
+            StreamLine::Done
+        );
+        assert_eq!(
+            parse_stream_line(LlmApi::OpenAi, r#"data: {"choices":[{"delta":{"content":"lo"}}]}"#),
+            piece("lo")
+        );
+        assert_eq!(
+            parse_stream_line(LlmApi::OpenAi, r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#),
+            StreamLine::Skip
+        );
+        assert_eq!(parse_stream_line(LlmApi::OpenAi, ": keep-alive"), StreamLine::Skip);
+        assert_eq!(parse_stream_line(LlmApi::OpenAi, "data: [DONE]"), StreamLine::Done);
+    }
+
+    /// An Ollama endpoint that streams `pieces` `gap` apart, then stalls
+    /// for `stall` before ending the response
+    async fn slow_ollama(pieces: &'static [&'static str], gap: Duration, stall: Duration) -> LlmConfig {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let _ = socket.read(&mut request).await;
+            let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for piece in pieces {
+                tokio::time::sleep(gap).await;
+                let line = json!({ "message": { "content": piece }, "done": false }).to_string() + "\n";
+                let chunk = format!("{:x}\r\n{}\r\n", line.len(), line);
+                socket.write_all(chunk.as_bytes()).await.unwrap();
+            }
+            tokio::time::sleep(stall).await;
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+        LlmConfig {
+            url,
+            timeout_ms: 400,
+            ..LlmConfig::default()
+        }
+    }
+
+    async fn collect(mut rx: mpsc::Receiver<Result<String>>) -> (String, Option<String>) {
+        let mut answer = String::new();
+        while let Some(piece) = rx.recv().await {
+            match piece {
+                Ok(piece) => answer.push_str(&piece),
+                Err(e) => return (answer, Some(e.to_string())),
+            }
+        }
+        (answer, None)
+    }
+
+    #[tokio::test]
+    async fn test_stream_timeout_is_per_piece() {
+        // Longer than `timeout_ms` in all, but never idle that long
+        let settings = slow_ollama(&["a", "b", "c", "d"], Duration::from_millis(150), Duration::ZERO).await;
+        let started = std::time::Instant::now();
+        let rx = stream(&settings, &[ChatMessage::user("hi")]).await.unwrap();
+        assert_eq!(collect(rx).await, ("abcd".to_string(), None));
+        assert!(started.elapsed() > Duration::from_millis(settings.timeout_ms));
+
+        let settings = slow_ollama(&["a"], Duration::ZERO, Duration::from_secs(5)).await;
+        let rx = stream(&settings, &[ChatMessage::user("hi")]).await.unwrap();
+        let (answer, error) = collect(rx).await;
+        assert_eq!(answer, "a");
+        assert!(error.unwrap().contains("sent nothing"));
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::Context;
+use std::backtrace::Backtrace;
+use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter, Registry};
+
+/// Runtime handle for changing the log filter
+pub struct LogControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+/// Install the global subscriber (RUST_LOG or "info" until config is applied)
+pub fn init() -> LogControl {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_target(false)
+                .with_thread_ids(false)
+                .compact(),
+        )
+        .init();
+
+    // Route panics (including ones caught by the HTTP layer) through tracing
+    std::panic::set_hook(Box::new(|info| {
+        let backtrace = Backtrace::force_capture();
+        tracing::error!("{}\n{}", info, backtrace);
+    }));
+
+    LogControl { handle }
+}
+
+impl LogControl {
+    /// A handle not attached to the global subscriber (tests); changing the
+    /// level through it fails
+    pub fn detached() -> Self {
+        let (_, handle) = reload::Layer::<EnvFilter, Registry>::new(EnvFilter::new("info"));
+        LogControl { handle }
+    }
+
+    /// Replace the active filter (e.g., "debug" or "info,tower_http=debug")
+    pub fn set_level(&self, directive: &str) -> anyhow::Result<()> {
+        let filter = EnvFilter::try_new(directive)
+            .with_context(|| format!("Invalid log filter: {}", directive))?;
+        self.handle
+            .reload(filter)
+            .context("Failed to apply log filter")
+    }
+
+    /// The active filter, formatted as a directive string
+    pub fn current(&self) -> String {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .unwrap_or_default()
+    }
+}
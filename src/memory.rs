@@ -0,0 +1,224 @@
+use crate::api::models::{AppState, MemoryStatus};
+use crate::config::MemoryConfig;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Latest memory sample and whether writes are paused because of it
+#[derive(Debug, Default)]
+pub struct MemoryWatchdog {
+    /// 0 until sampled, or where RSS can't be read
+    rss_bytes: AtomicU64,
+    index_bytes: AtomicU64,
+    over_limit: AtomicBool,
+    /// Times writes were paused since startup
+    trips: AtomicU64,
+}
+
+/// What a sample changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Unchanged,
+    Paused,
+    Resumed,
+}
+
+impl MemoryWatchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether writes are paused
+    pub fn over_limit(&self) -> bool {
+        self.over_limit.load(Ordering::Relaxed)
+    }
+
+    pub fn trips(&self) -> u64 {
+        self.trips.load(Ordering::Relaxed)
+    }
+
+    /// Store a sample and pause or resume writes
+    ///
+    /// While paused, a limit still counts as exceeded until usage drops
+    /// under `resume_ratio` of it.
+    pub fn record(&self, rss_bytes: Option<u64>, index_bytes: u64, settings: &MemoryConfig) -> Transition {
+        self.rss_bytes.store(rss_bytes.unwrap_or(0), Ordering::Relaxed);
+        self.index_bytes.store(index_bytes, Ordering::Relaxed);
+
+        let paused = self.over_limit();
+        let ratio = if paused { settings.resume_ratio.clamp(0.0, 1.0) } else { 1.0 };
+        let exceeds = |used: u64, limit: u64| limit > 0 && used as f64 > limit as f64 * ratio;
+        let over = exceeds(rss_bytes.unwrap_or(0), settings.max_rss_bytes)
+            || exceeds(index_bytes, settings.max_index_bytes);
+
+        self.over_limit.store(over, Ordering::Relaxed);
+        match (paused, over) {
+            (false, true) => {
+                self.trips.fetch_add(1, Ordering::Relaxed);
+                Transition::Paused
+            }
+            (true, false) => Transition::Resumed,
+            _ => Transition::Unchanged,
+        }
+    }
+
+    pub fn status(&self, settings: &MemoryConfig) -> MemoryStatus {
+        let rss_bytes = self.rss_bytes.load(Ordering::Relaxed);
+        MemoryStatus {
+            rss_bytes: (rss_bytes > 0).then_some(rss_bytes),
+            index_bytes: self.index_bytes.load(Ordering::Relaxed),
+            max_rss_bytes: settings.max_rss_bytes,
+            max_index_bytes: settings.max_index_bytes,
+            writes_paused: self.over_limit(),
+        }
+    }
+}
+
+/// Resident set size of this process, where the platform reports it
+#[cfg(target_os = "linux")]
+pub fn process_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kb: u64 = line.trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Resident set size of this process, where the platform reports it
+#[cfg(not(target_os = "linux"))]
+pub fn process_rss() -> Option<u64> {
+    None
+}
+
+/// Spawn the memory watchdog
+///
+/// Every `memory.check_interval_secs` (re-read each round) the process RSS
+/// and the default index's live vector bytes are sampled. Crossing a limit
+/// pauses writes with 503 and, with `memory.emergency_snapshot`, saves the
+/// index. Samples are taken without limits too, for `/metrics`. Stops when
+/// `shutdown_rx` flips.
+pub fn spawn_memory_watchdog(
+    state: AppState,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let settings = state.config.current().memory.clone();
+            let interval = Duration::from_secs(settings.check_interval_secs.max(1));
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown_rx.wait_for(|&stop| stop) => break,
+            }
+
+            let index_bytes = {
+                let index = state.vector_index.read().await;
+                (index.live_count() * index.dimension() * std::mem::size_of::<f32>()) as u64
+            };
+            let rss_bytes = process_rss();
+            match state.memory.record(rss_bytes, index_bytes, &settings) {
+                Transition::Paused => {
+                    warn!(?rss_bytes, index_bytes, "⚠️  Memory over its limit; pausing writes");
+                    if settings.emergency_snapshot && !state.config.current().is_read_only() {
+                        emergency_snapshot(&state).await;
+                    }
+                }
+                Transition::Resumed => info!(?rss_bytes, index_bytes, "Memory back under its limit; writes resumed"),
+                Transition::Unchanged => {}
+            }
+        }
+    })
+}
+
+/// Sync metadata and save the index if it changed since the last save
+async fn emergency_snapshot(state: &AppState) {
+    let state = state.clone();
+    let saved = tokio::task::spawn_blocking(move || {
+        let index = state.vector_index.blocking_read();
+        if !index.is_dirty() {
+            return Ok(None);
+        }
+        // Metadata first so a saved index never references unsynced lines
+        state.metadata_store.flush()?;
+        index.save(&state.index_path())?;
+        Ok::<_, anyhow::Error>(Some(index.vector_count()))
+    })
+    .await;
+
+    match saved {
+        Ok(Ok(Some(vectors))) => info!(vectors, "📸 Emergency snapshot saved"),
+        Ok(Ok(None)) => {}
+        Ok(Err(e)) => error!("Emergency snapshot failed: {:#}", e),
+        Err(e) => error!("Emergency snapshot task failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{review_request, TestApp};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::json;
+
+    #[test]
+    fn test_record_pauses_with_hysteresis() {
+        let settings = MemoryConfig {
+            max_index_bytes: 1000,
+            resume_ratio: 0.5,
+            ..MemoryConfig::default()
+        };
+        let watchdog = MemoryWatchdog::new();
+
+        assert_eq!(watchdog.record(Some(1 << 30), 1000, &settings), Transition::Unchanged);
+        assert_eq!(watchdog.record(None, 1001, &settings), Transition::Paused);
+        assert!(watchdog.over_limit());
+        // Under the limit but not yet under half of it
+        assert_eq!(watchdog.record(None, 800, &settings), Transition::Unchanged);
+        assert!(watchdog.over_limit());
+        assert_eq!(watchdog.record(None, 500, &settings), Transition::Resumed);
+        assert!(!watchdog.over_limit());
+        assert_eq!(watchdog.trips(), 1);
+
+        let status = watchdog.status(&settings);
+        assert_eq!(status.rss_bytes, None);
+        assert_eq!(status.index_bytes, 500);
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_pauses_writes() {
+        let app = TestApp::builder()
+            .config(|config| config.memory.max_index_bytes = 1)
+            .build()
+            .unwrap();
+        let review = review_request("Great battery life");
+        let (status, _) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // What the watchdog records on its next round
+        let settings = app.state.config.current().memory.clone();
+        app.state.memory.record(None, 1024, &settings);
+        let (status, body) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE, "{}", body);
+        let (status, _) = app.post("/reviews/search", &json!({ "query": "battery" })).await;
+        assert_eq!(status, StatusCode::OK);
+        // Deletes still go through, and only live vectors count
+        let delete = Request::delete("/reviews/0?expected_revision=1").body(Body::empty()).unwrap();
+        assert_eq!(app.request(delete).await.status(), StatusCode::NO_CONTENT);
+        let index = app.state.vector_index.read().await;
+        assert_eq!((index.vector_count(), index.live_count()), (1, 0));
+        drop(index);
+
+        let (status, body) = app.get("/readyz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["memory"]["writes_paused"], true);
+        assert_eq!(body["memory"]["index_bytes"], 1024);
+        let metrics = app.metrics().await;
+        assert!(metrics.contains("\nvector_search_writes_paused 1\n"), "{}", metrics);
+        assert!(metrics.contains("\nvector_search_memory_pauses_total 1\n"), "{}", metrics);
+
+        app.state.memory.record(None, 0, &settings);
+        let (status, _) = app.post("/reviews", &review).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,158 @@
+//! Masking emails, phone numbers and names in review text before it is
+//! embedded and stored
+
+use crate::config::RedactionConfig;
+use anyhow::Context;
+use regex::Regex;
+use std::sync::{Arc, Mutex};
+use vector_search_client::models::RedactionReport;
+
+const EMAIL_PATTERN: &str = r"(?i)\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b";
+
+/// Needs a separator or parentheses after the area code, so plain numbers
+/// (model numbers, prices) are left alone
+const PHONE_PATTERN: &str =
+    r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)[\s.-]?|\b\d{2,4}[\s.-])\d{3,4}[\s.-]?\d{3,4}\b";
+
+/// Keeps the honorific or introduction and masks the name after it
+const NAME_PATTERN: &str =
+    r"\b((?:Mr|Mrs|Ms|Miss|Dr|Prof)\.?\s+|(?i:my name is)\s+)[A-Z][a-z]+(?:\s+[A-Z][a-z]+)?";
+
+struct Rule {
+    kind: String,
+    regex: Regex,
+    replacement: String,
+}
+
+/// Compiled redaction rules
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    pub fn new(config: &RedactionConfig) -> anyhow::Result<Self> {
+        let builtin = [
+            (config.emails, "email", EMAIL_PATTERN, "[EMAIL]"),
+            (config.phones, "phone", PHONE_PATTERN, "[PHONE]"),
+            (config.names, "name", NAME_PATTERN, "${1}[NAME]"),
+        ];
+        let mut rules: Vec<Rule> = builtin
+            .into_iter()
+            .filter(|(enabled, ..)| *enabled)
+            .map(|(_, kind, pattern, replacement)| Rule {
+                kind: kind.to_string(),
+                regex: Regex::new(pattern).expect("built-in redaction pattern"),
+                replacement: replacement.to_string(),
+            })
+            .collect();
+
+        for custom in &config.patterns {
+            let regex = Regex::new(&custom.pattern)
+                .with_context(|| format!("Invalid redaction pattern {:?}", custom.name))?;
+            let replacement = custom
+                .replacement
+                .clone()
+                .unwrap_or_else(|| format!("[{}]", custom.name.to_uppercase()));
+            rules.push(Rule {
+                kind: custom.name.clone(),
+                regex,
+                replacement,
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Mask a review's title and body in place, reporting what changed
+    pub fn redact_review(&self, title: &mut String, body: &mut String) -> RedactionReport {
+        let mut report = RedactionReport::default();
+        for (field, text) in [("review_title", title), ("review_body", body)] {
+            if self.redact(text, &mut report) {
+                report.fields.push(field.to_string());
+            }
+        }
+        report
+    }
+
+    /// Apply every rule to `text`; returns whether anything matched
+    fn redact(&self, text: &mut String, report: &mut RedactionReport) -> bool {
+        let mut changed = false;
+        for rule in &self.rules {
+            let count = rule.regex.find_iter(text).count();
+            if count == 0 {
+                continue;
+            }
+            *text = rule.regex.replace_all(text, rule.replacement.as_str()).into_owned();
+            *report.counts.entry(rule.kind.clone()).or_default() += count;
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// The [`Redactor`] for the current `redaction` settings, recompiled only
+/// when they change
+#[derive(Default)]
+pub struct RedactorCache {
+    current: Mutex<Option<(RedactionConfig, Arc<Redactor>)>>,
+}
+
+impl RedactorCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `None` while redaction is disabled
+    pub fn get(&self, config: &RedactionConfig) -> anyhow::Result<Option<Arc<Redactor>>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let mut current = self.current.lock().unwrap();
+        if let Some((compiled_for, redactor)) = current.as_ref()
+            && compiled_for == config
+        {
+            return Ok(Some(redactor.clone()));
+        }
+        let redactor = Arc::new(Redactor::new(config)?);
+        *current = Some((config.clone(), redactor.clone()));
+        Ok(Some(redactor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RedactionPattern;
+
+    #[test]
+    fn test_redact_review() {
+        let config = RedactionConfig {
+            enabled: true,
+            patterns: vec![RedactionPattern {
+                name: "order_number".to_string(),
+                pattern: r"\bORD-\d+\b".to_string(),
+                replacement: None,
+            }],
+            ..RedactionConfig::default()
+        };
+        let redactor = Redactor::new(&config).unwrap();
+
+        let mut title = "Great kettle, model 1500".to_string();
+        let mut body = "Dr. Jane Doe here, reach me at jane.doe@example.com or (555) 123-4567. \
+                        My name is Sam. Order ORD-991."
+            .to_string();
+        let report = redactor.redact_review(&mut title, &mut body);
+
+        assert_eq!(title, "Great kettle, model 1500");
+        assert_eq!(
+            body,
+            "Dr. [NAME] here, reach me at [EMAIL] or [PHONE]. My name is [NAME]. Order [ORDER_NUMBER]."
+        );
+        assert_eq!(report.fields, vec!["review_body"]);
+        assert_eq!(report.counts["name"], 2);
+        assert_eq!(report.counts["email"], 1);
+        assert_eq!(report.counts["phone"], 1);
+        assert_eq!(report.counts["order_number"], 1);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::api::models::{default_metadata_range_limit, AppState, DeleteStatus, ReplicationStatus};
+use crate::api::replication::handlers::VECTOR_COUNT_HEADER;
+use crate::config::ReplicationConfig;
+use crate::storage::purge::tombstone_ids;
+use crate::storage::{ReviewMetadata, VectorIndex};
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+/// Spawn the follower loop that keeps this node in sync with the primary
+///
+/// Every poll interval the primary's status is checked. When it holds more
+/// vectors, a fresh index snapshot is downloaded, the missing metadata lines
+/// are appended, and the loaded snapshot replaces the live index. When it
+/// has tombstoned more reviews, the same reviews are tombstoned here. The
+/// interval is re-read on every poll. Stops when `shutdown_rx` flips.
+pub fn spawn_follower_task(
+    state: AppState,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+
+        loop {
+            let settings = state.config.current().replication.clone();
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(settings.poll_interval_secs.max(1))) => {}
+                _ = shutdown_rx.wait_for(|&stop| stop) => break,
+            }
+
+            // Nothing to compare against until the local index is loaded
+            if !state.readiness.is_component_ready("index") {
+                continue;
+            }
+
+            match sync_once(&state, &client, &settings).await {
+                Ok(Some(Synced { vectors, tombstoned })) => {
+                    info!(vectors, tombstoned, "🔁 Replicated index from primary")
+                }
+                Ok(None) => {}
+                Err(e) => error!("Replication: sync with primary failed: {:#}", e),
+            }
+        }
+    })
+}
+
+/// What one sync brought over from the primary
+struct Synced {
+    vectors: usize,
+    tombstoned: usize,
+}
+
+/// Pull anything new from the primary: appended reviews with a fresh index
+/// snapshot, then tombstones for reviews deleted or replaced since
+async fn sync_once(
+    state: &AppState,
+    client: &reqwest::Client,
+    settings: &ReplicationConfig,
+) -> Result<Option<Synced>> {
+    let primary = settings
+        .primary_url
+        .as_deref()
+        .context("replication.primary_url is required for followers")?
+        .trim_end_matches('/');
+    let get = |path: &str| {
+        let request = client.get(format!("{}{}", primary, path));
+        match &settings.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    };
+
+    let status: ReplicationStatus = get("/replication/status")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let local = state.vector_index.read().await.vector_count();
+    let vectors = if status.vector_count > local {
+        Some(pull_snapshot(state, &get).await?)
+    } else {
+        None
+    };
+
+    // Appended lines arrive with their tombstones; older lines need theirs
+    // applied here
+    let have = local_tombstones(state).await?;
+    let mut tombstoned = 0;
+    if status.deleted_count > have.len() {
+        let deleted: Vec<usize> = get("/replication/tombstones")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let missing: Vec<usize> = deleted.into_iter().filter(|id| !have.contains(id)).collect();
+        tombstoned = apply_tombstones(state, missing).await?;
+    }
+
+    if vectors.is_none() && tombstoned == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Synced {
+        vectors: vectors.unwrap_or(local),
+        tombstoned,
+    }))
+}
+
+/// Download the primary's index snapshot and the metadata lines it covers,
+/// then swap the snapshot in; returns the new vector count
+async fn pull_snapshot(
+    state: &AppState,
+    get: &impl Fn(&str) -> reqwest::RequestBuilder,
+) -> Result<usize> {
+    // Index snapshot, written next to the live archive
+    let config = state.config.current();
+    let index_path = state.index_path();
+    let tmp_path = index_path.with_extension("replica.tmp");
+    let mut response = get("/replication/snapshot").send().await?.error_for_status()?;
+    let snapshot_count: usize = response
+        .headers()
+        .get(VECTOR_COUNT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .context("Snapshot response is missing its vector count")?;
+    let mut file = tokio::fs::File::create(&tmp_path)
+        .await
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+
+    // Metadata lines the snapshot covers but we don't have yet. Appending
+    // ahead of the index is safe: searches only return IDs the index has.
+    let metadata_store = state.metadata_store.clone();
+    let mut have = tokio::task::spawn_blocking(move || metadata_store.count_lines()).await??;
+    if have > snapshot_count {
+        anyhow::bail!(
+            "Local metadata ({} reviews) is ahead of the primary's snapshot ({} vectors)",
+            have,
+            snapshot_count
+        );
+    }
+    let appending = have < snapshot_count;
+    while have < snapshot_count {
+        let limit = (snapshot_count - have).min(default_metadata_range_limit());
+        let path = format!("/replication/metadata?from={}&limit={}", have, limit);
+        let page: Vec<ReviewMetadata> = get(&path)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if page.is_empty() {
+            anyhow::bail!("Primary returned no metadata from line {}", have);
+        }
+
+        let metadata_store = state.metadata_store.clone();
+        let count = page.len();
+        tokio::task::spawn_blocking(move || {
+            metadata_store.append_batch(&page)?;
+            metadata_store.flush()
+        })
+        .await??;
+        have += count;
+    }
+    // Rebuilt with the new lines on the next lookup
+    if appending {
+        state.suggester.invalidate();
+        state.spelling.invalidate();
+        state.content_hashes.invalidate();
+        state.point_ids.invalidate();
+    }
+
+    // Load the snapshot off to the side, then swap it in
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut replica = VectorIndex::new(&config.index);
+        replica.load(&tmp_path)?;
+        if replica.vector_count() != snapshot_count {
+            anyhow::bail!(
+                "Snapshot holds {} vectors, primary announced {}",
+                replica.vector_count(),
+                snapshot_count
+            );
+        }
+
+        let mut index = state.vector_index.blocking_write();
+        std::fs::rename(&tmp_path, &index_path)
+            .with_context(|| format!("Failed to replace index: {}", index_path.display()))?;
+        *index = replica;
+        state.search_cache.invalidate();
+        Ok(index.vector_count())
+    })
+    .await?
+}
+
+/// Vector IDs of the reviews tombstoned locally
+async fn local_tombstones(state: &AppState) -> Result<BTreeSet<usize>> {
+    let metadata_store = state.metadata_store.clone();
+    let reviews = tokio::task::spawn_blocking(move || metadata_store.read_all()).await??;
+    Ok(reviews
+        .iter()
+        .enumerate()
+        .filter(|(_, review)| review.deleted)
+        .map(|(id, _)| id)
+        .collect())
+}
+
+/// Tombstone `vector_ids` as the primary did, masking their vectors;
+/// returns how many were tombstoned
+async fn apply_tombstones(state: &AppState, vector_ids: Vec<usize>) -> Result<usize> {
+    if vector_ids.is_empty() {
+        return Ok(0);
+    }
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut index = state.vector_index.blocking_write();
+        let outcomes = tombstone_ids(
+            state.metadata_store.as_ref(),
+            &mut index,
+            &state.index_path(),
+            &vector_ids,
+        )?;
+        let tombstoned = outcomes
+            .iter()
+            .filter(|outcome| outcome.status == DeleteStatus::Deleted)
+            .count();
+        if tombstoned > 0 {
+            state.suggester.invalidate();
+            state.spelling.invalidate();
+            state.search_cache.invalidate();
+        }
+        Ok(tombstoned)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReplicationRole;
+    use crate::test_utils::{review, TestApp};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+
+    /// The same reviews, with the same vectors, on every node
+    async fn add_reviews(app: &TestApp, count: usize) {
+        let mut index = app.state.vector_index.write().await;
+        let dimension = index.dimension();
+        for i in 0..count {
+            app.state
+                .metadata_store
+                .append(&review(&format!("Review {}", i), "P1"))
+                .unwrap();
+            index.add_vector(&vec![i as f32; dimension]).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follower_applies_deletes() {
+        let primary = TestApp::builder()
+            .config(|c| c.replication.role = ReplicationRole::Primary)
+            .build()
+            .unwrap();
+        let follower = TestApp::builder().build().unwrap();
+        add_reviews(&primary, 3).await;
+        add_reviews(&follower, 3).await;
+
+        let delete = Request::delete("/reviews/1?expected_revision=1").body(Body::empty()).unwrap();
+        assert_eq!(primary.request(delete).await.status(), StatusCode::NO_CONTENT);
+
+        let settings = ReplicationConfig {
+            role: ReplicationRole::Follower,
+            primary_url: Some(format!("http://{}", primary.serve().await)),
+            ..follower.state.config.current().replication.clone()
+        };
+        let client = reqwest::Client::new();
+        let synced = sync_once(&follower.state, &client, &settings).await.unwrap().unwrap();
+        assert_eq!(synced.tombstoned, 1);
+        let replica = follower.state.metadata_store.read_by_id(1).unwrap();
+        assert!(replica.deleted);
+        assert_eq!(replica.revision, primary.state.metadata_store.read_by_id(1).unwrap().revision);
+        assert!(!follower.state.metadata_store.read_by_id(0).unwrap().deleted);
+
+        // Nothing new the second time
+        assert!(sync_once(&follower.state, &client, &settings).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,229 @@
+//! Shadow indexing: a collection embedded with a new model that follows the
+//! default collection until it is cut over
+//!
+//! The shadow holds a copy of the default metadata, line for line, so vector
+//! IDs match and the cutover is an index swap. Reads stay on the default
+//! index until then.
+
+use crate::api::collections::handlers::collection_error;
+use crate::api::models::{AppError, AppState, ShadowCutoverResponse, ShadowStatus};
+use crate::cli::embed_reviews;
+use crate::storage::{AuditEntry, Collection, MetadataStore};
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Reviews copied per catch-up step
+const SYNC_BATCH_SIZE: usize = 256;
+
+/// Spawn the task keeping `shadow.collection` in step with the default
+/// collection
+///
+/// Every `shadow.sync_interval_secs` (re-read each round), reviews added to
+/// the default collection since the last round are embedded with the
+/// shadow's model. Does nothing while no shadow is configured.
+pub fn spawn_shadow_sync(
+    state: AppState,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let interval = state.config.current().shadow.sync_interval_secs.max(1);
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(interval)) => {}
+                _ = shutdown_rx.wait_for(|&stop| stop) => break,
+            }
+
+            let Some(name) = state.config.current().shadow.collection.clone() else {
+                continue;
+            };
+            if !state.readiness.is_component_ready("index") {
+                continue;
+            }
+            let Some(shadow) = state.collections.get(&name) else {
+                warn!(collection = %name, "Shadow collection not found");
+                continue;
+            };
+
+            match catch_up(&state, &shadow).await {
+                Ok(0) => {}
+                Ok(count) => info!(collection = %name, count, "👥 Shadow index caught up"),
+                Err(e) => error!(collection = %name, "Shadow sync failed: {:#}", e),
+            }
+        }
+    })
+}
+
+/// Embed the default collection's reviews the shadow doesn't have yet
+///
+/// Tombstoned reviews are copied too (and masked) so IDs stay aligned.
+/// Only indexed reviews are copied: a metadata line past the end of the
+/// default index may still be rolled back. Returns how many were added.
+pub async fn catch_up(state: &AppState, shadow: &Arc<Collection>) -> Result<usize> {
+    let service = state
+        .embedder_for(&shadow.manifest().model)
+        .await
+        .map_err(|e| anyhow::anyhow!("Shadow model unavailable: {:?}", e))?;
+
+    let mut added = 0;
+    loop {
+        let (start, batch) = {
+            let (state, shadow) = (state.clone(), shadow.clone());
+            tokio::task::spawn_blocking(move || {
+                let indexed = state.vector_index.blocking_read().vector_count();
+                let start = shadow.metadata.count_lines()?;
+                let limit = indexed.saturating_sub(start).min(SYNC_BATCH_SIZE);
+                Ok::<_, anyhow::Error>((start, state.metadata_store.read_range(start, limit)?))
+            })
+            .await??
+        };
+        if batch.is_empty() {
+            return Ok(added);
+        }
+
+        let (batch, embeddings) = {
+            let service = service.clone();
+            let normalization = shadow.manifest().normalization.clone();
+            tokio::task::spawn_blocking(move || {
+                let embeddings = embed_reviews(&service, &batch, &normalization, SYNC_BATCH_SIZE)?;
+                Ok::<_, anyhow::Error>((batch, embeddings))
+            })
+            .await??
+        };
+
+        // The metadata and index writes below must not be cut short by shutdown
+        let Some(_write_guard) = state.write_gate.enter() else {
+            return Ok(added);
+        };
+        let mut index = shadow.index.write().await;
+        // Cut over, or no longer the shadow, while this batch was embedding
+        let still_shadow = state.config.current().shadow.collection.as_deref() == Some(shadow.name());
+        if !still_shadow || state.collections.get(shadow.name()).is_none() {
+            return Ok(added);
+        }
+        if shadow.metadata.count_lines()? != start || index.vector_count() != start {
+            anyhow::bail!(
+                "Shadow collection {} was written to outside the sync ({} vectors, expected {})",
+                shadow.name(),
+                index.vector_count(),
+                start
+            );
+        }
+
+        for (offset, (review, embedding)) in batch.iter().zip(&embeddings).enumerate() {
+            index.add_vector(embedding)?;
+            if review.deleted {
+                index.delete_vector(start + offset)?;
+            }
+        }
+        shadow.metadata.append_batch(&batch)?;
+        shadow.metadata.flush()?;
+        index.save(&shadow.index_path())?;
+        added += batch.len();
+    }
+}
+
+/// How far `shadow` has caught up with the default collection
+pub async fn status(state: &AppState, shadow: &Collection) -> Result<ShadowStatus, AppError> {
+    let shadowed = shadow.index.read().await.vector_count();
+    let total = state.vector_index.read().await.vector_count();
+    Ok(ShadowStatus {
+        collection: shadow.name().to_string(),
+        model: shadow.manifest().model.clone(),
+        shadowed,
+        total,
+        backfilled: shadowed == total,
+    })
+}
+
+/// Swap the shadow index in for the default one and switch the server to
+/// the shadow's model
+///
+/// 409 unless the shadow holds every review. The new settings are written
+/// next to the index so they survive a restart; the shadow collection is
+/// dropped, as its index now serves the default collection.
+pub async fn cut_over(
+    state: &AppState,
+    shadow: Arc<Collection>,
+    api_key: Option<String>,
+) -> Result<ShadowCutoverResponse, AppError> {
+    state.check_writable()?;
+    let _write_guard = state
+        .write_gate
+        .enter()
+        .ok_or_else(|| AppError::ServiceUnavailable("Server is shutting down".to_string()))?;
+
+    // The shadow is dropped at the end, which an alias would block
+    if let Some(alias) = state.collections.alias_of(shadow.name()) {
+        return Err(AppError::Conflict(format!(
+            "Shadow collection {} is the target of alias {}",
+            shadow.name(),
+            alias
+        )));
+    }
+
+    let index_path = state.index_path();
+    let manifest = shadow.manifest().clone();
+    let vector_count = {
+        let mut index = state.ingest.write_index(&state.vector_index).await?;
+        let mut shadow_index = shadow.index.write().await;
+
+        let total = index.vector_count();
+        if shadow_index.vector_count() != total {
+            return Err(AppError::Conflict(format!(
+                "Shadow index is not fully backfilled ({} of {} reviews)",
+                shadow_index.vector_count(),
+                total
+            )));
+        }
+
+        // Reviews tombstoned after the shadow copied them
+        let reviews = state
+            .metadata_store
+            .read_all()
+            .map_err(|e| AppError::Internal(format!("Metadata read failed: {}", e)))?;
+        for (vector_id, _) in reviews.iter().enumerate().filter(|(_, r)| r.deleted) {
+            if let Err(e) = shadow_index.delete_vector(vector_id) {
+                warn!(vector_id, "Masking tombstoned vector failed: {:#}", e);
+            }
+        }
+
+        shadow_index
+            .save(&index_path)
+            .map_err(|e| AppError::Internal(format!("Save index failed: {:#}", e)))?;
+        if let Err(e) = manifest.write_default(&index_path) {
+            let _ = index.save(&index_path);
+            return Err(AppError::Internal(format!("Write manifest failed: {:#}", e)));
+        }
+
+        std::mem::swap(&mut *index, &mut *shadow_index);
+        state.config.update(|config| {
+            manifest.apply(config);
+            config.shadow.collection = None;
+        });
+        state
+            .collections
+            .drop_collection(shadow.name())
+            .map_err(collection_error)?;
+        total
+    };
+
+    state.record_mutation(AuditEntry {
+        api_key,
+        detail: Some(serde_json::json!({
+            "collection": shadow.name(),
+            "model": manifest.model,
+        })),
+        ..AuditEntry::new("shadow_cutover")
+    });
+    info!(model = %manifest.model, vector_count, "🔀 Cut over to the shadow index");
+
+    Ok(ShadowCutoverResponse {
+        status: "success".to_string(),
+        model: manifest.model,
+        vector_count,
+    })
+}
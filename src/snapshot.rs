@@ -0,0 +1,434 @@
+use crate::api::models::{SnapshotInfo, SnapshotManifest};
+use crate::config::AppConfig;
+use crate::embedding::EmbeddingService;
+use crate::storage::collections::is_valid_name;
+use crate::storage::{archive, now_ms, DistanceMetric, MetadataStore, ReviewMetadata, VectorIndex};
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tar::{Archive, Builder};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// First entry of a named snapshot, so listing doesn't read the rest
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The index as written by `export_archive`
+pub const INDEX_FILE: &str = "index.tar.gz";
+
+/// Every metadata line, tombstones included, in vector ID order
+pub const METADATA_FILE: &str = "reviews.jsonl";
+
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+
+/// Next to the index archive while a restore's staged files are moved in
+fn restore_marker(index_path: &Path) -> PathBuf {
+    index_path.with_extension("restore.pending")
+}
+
+fn staged_index(index_path: &Path) -> PathBuf {
+    index_path.with_extension("restore.tmp")
+}
+
+fn staged_metadata(index_path: &Path) -> PathBuf {
+    index_path.with_extension("restore.jsonl")
+}
+
+/// Spawn the periodic snapshot task
+///
+/// Every interval (plus jitter) the index is saved if it changed since the
+/// last save and the metadata file is synced. The interval is re-read on
+/// config reload; 0 pauses snapshots. Stops when `shutdown_rx` flips.
+pub fn spawn_snapshot_task(
+    mut config_rx: watch::Receiver<Arc<AppConfig>>,
+    vector_index: Arc<RwLock<VectorIndex>>,
+    metadata_store: Arc<dyn MetadataStore>,
+    index_path: PathBuf,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let settings = config_rx.borrow_and_update().snapshot.clone();
+
+            if settings.interval_secs == 0 {
+                info!("Background snapshots disabled");
+                tokio::select! {
+                    changed = config_rx.changed() => if changed.is_err() { break } else { continue },
+                    _ = shutdown_rx.wait_for(|&stop| stop) => break,
+                }
+            }
+
+            info!(
+                interval_secs = settings.interval_secs,
+                jitter_secs = settings.jitter_secs,
+                "Next background snapshot scheduled"
+            );
+            let delay = Duration::from_secs(settings.interval_secs + jitter(settings.jitter_secs));
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                // Reschedule with the new interval
+                changed = config_rx.changed() => if changed.is_err() { break } else { continue },
+                _ = shutdown_rx.wait_for(|&stop| stop) => break,
+            }
+
+            let index = vector_index.read().await;
+            if !index.is_dirty() {
+                continue;
+            }
+
+            // Metadata first so a saved index never references unsynced lines
+            if let Err(e) = metadata_store.flush() {
+                error!("Snapshot: failed to flush metadata: {}", e);
+                continue;
+            }
+            match index.save(&index_path) {
+                Ok(()) => info!(vectors = index.vector_count(), "📸 Snapshot saved"),
+                Err(e) => error!("Snapshot: failed to save index: {}", e),
+            }
+        }
+    })
+}
+
+/// Random-ish delay in `0..=max_secs`, so replicas don't snapshot in lockstep
+fn jitter(max_secs: u64) -> u64 {
+    if max_secs == 0 {
+        return 0;
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    nanos % (max_secs + 1)
+}
+
+/// Name given to snapshots created without one
+pub fn default_name() -> String {
+    format!("snapshot-{}", now_ms())
+}
+
+/// Archive of the snapshot called `name`; None unless the name is path-safe
+pub fn snapshot_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    is_valid_name(name).then(|| dir.join(format!("{}{}", name, ARCHIVE_EXTENSION)))
+}
+
+/// Archive the index and every metadata line as `<dir>/<name>.tar.gz`
+///
+/// Blocking. Callers hold the index lock so no write lands between the two
+/// copies. The archive is written under a temp name and renamed into place,
+/// so a download never sees half of it.
+pub fn create_named(
+    index: &VectorIndex,
+    metadata_store: &dyn MetadataStore,
+    model: &str,
+    metric: DistanceMetric,
+    dir: &Path,
+    name: &str,
+) -> Result<SnapshotInfo> {
+    let path = snapshot_path(dir, name).with_context(|| format!("Invalid snapshot name: {}", name))?;
+    metadata_store.flush()?;
+    let reviews = metadata_store.read_all()?;
+
+    let work = archive::temp_dir("named_snapshot")?;
+    let result = (|| -> Result<SnapshotInfo> {
+        index.export_archive(&work.join(INDEX_FILE))?;
+
+        let mut lines = BufWriter::new(File::create(work.join(METADATA_FILE))?);
+        for review in &reviews {
+            serde_json::to_writer(&mut lines, review)?;
+            lines.write_all(b"\n")?;
+        }
+        lines.flush()?;
+
+        let manifest = SnapshotManifest {
+            created_at: now_ms(),
+            vector_count: index.vector_count(),
+            metadata_count: reviews.len(),
+            model: model.to_string(),
+            metric,
+            dimension: index.dimension(),
+        };
+        std::fs::write(work.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let tmp_path = path.with_extension("tmp");
+        let encoder = GzEncoder::new(File::create(&tmp_path)?, Compression::default());
+        let mut tar = Builder::new(encoder);
+        for file in [MANIFEST_FILE, INDEX_FILE, METADATA_FILE] {
+            tar.append_path_with_name(work.join(file), file)?;
+        }
+        tar.into_inner()?.finish()?.sync_all()?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        Ok(SnapshotInfo {
+            name: name.to_string(),
+            size_bytes: std::fs::metadata(&path)?.len(),
+            manifest,
+        })
+    })();
+    let _ = std::fs::remove_dir_all(&work);
+    result
+}
+
+/// The manifest of the snapshot archive at `path`
+pub fn read_manifest(path: &Path) -> Result<SnapshotManifest> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST_FILE) {
+            return serde_json::from_reader(entry).context("Invalid snapshot manifest");
+        }
+    }
+    bail!("{} has no {}", path.display(), MANIFEST_FILE)
+}
+
+/// Snapshots under `dir`, ordered by name; unreadable archives are skipped
+pub fn list_named(dir: &Path) -> Result<Vec<SnapshotInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(name) = file_name.strip_suffix(ARCHIVE_EXTENSION) else {
+            continue;
+        };
+        if !is_valid_name(name) {
+            continue;
+        }
+        match read_manifest(&entry.path()) {
+            Ok(manifest) => snapshots.push(SnapshotInfo {
+                name: name.to_string(),
+                size_bytes: entry.metadata()?.len(),
+                manifest,
+            }),
+            Err(e) => warn!("Skipping snapshot {}: {:#}", file_name, e),
+        }
+    }
+    snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(snapshots)
+}
+
+/// Why an uploaded snapshot can't be restored
+#[derive(Debug, thiserror::Error)]
+pub enum RestoreError {
+    /// The upload isn't a snapshot this server can serve
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Failed(#[from] anyhow::Error),
+}
+
+/// An uploaded snapshot, unpacked and checked, ready to replace the live data
+pub struct Restore {
+    pub manifest: SnapshotManifest,
+    index: VectorIndex,
+    reviews: Vec<ReviewMetadata>,
+    work: WorkDir,
+}
+
+/// Temp folder removed when dropped
+struct WorkDir(PathBuf);
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Unpack the snapshot archive at `path` and check it against `config`:
+/// same model, metric and dimension, and the counts its manifest claims
+///
+/// Blocking; the live index and metadata aren't touched.
+pub fn prepare_restore(path: &Path, config: &AppConfig) -> Result<Restore, RestoreError> {
+    let invalid = |message: String| RestoreError::Invalid(message);
+    let work = WorkDir(archive::temp_dir("snapshot_restore")?);
+    archive::unpack(path, &work.0).map_err(|e| invalid(format!("Not a snapshot archive: {:#}", e)))?;
+    let member = |file: &str| {
+        let path = work.0.join(file);
+        match path.is_file() {
+            true => Ok(path),
+            false => Err(invalid(format!("Snapshot archive has no {}", file))),
+        }
+    };
+
+    let manifest: SnapshotManifest = serde_json::from_slice(
+        &std::fs::read(member(MANIFEST_FILE)?).context("Failed to read snapshot manifest")?,
+    )
+    .map_err(|e| invalid(format!("Invalid snapshot manifest: {}", e)))?;
+    let model = &config.embedding.model_name;
+    if !EmbeddingService::same_model(&manifest.model, model) {
+        return Err(invalid(format!(
+            "Snapshot was embedded with {}, this server uses {}",
+            manifest.model, model
+        )));
+    }
+    if manifest.metric != config.index.metric || manifest.dimension != config.index.vector_dim {
+        return Err(invalid(format!(
+            "Snapshot index is {:?} with {} dimensions, this server's is {:?} with {}",
+            manifest.metric, manifest.dimension, config.index.metric, config.index.vector_dim
+        )));
+    }
+
+    let mut index = VectorIndex::new(&config.index);
+    index
+        .load(&member(INDEX_FILE)?)
+        .map_err(|e| invalid(format!("Snapshot index can't be loaded: {:#}", e)))?;
+    if index.vector_count() != manifest.vector_count {
+        return Err(invalid(format!(
+            "Snapshot index holds {} vectors, its manifest claims {}",
+            index.vector_count(),
+            manifest.vector_count
+        )));
+    }
+
+    let file = File::open(member(METADATA_FILE)?).context("Failed to open snapshot metadata")?;
+    let mut reviews = Vec::new();
+    for (line_number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.context("Failed to read snapshot metadata")?;
+        let review = serde_json::from_str(&line)
+            .map_err(|e| invalid(format!("Invalid metadata on line {}: {}", line_number + 1, e)))?;
+        reviews.push(review);
+    }
+    if reviews.len() != manifest.metadata_count || reviews.len() < index.vector_count() {
+        return Err(invalid(format!(
+            "Snapshot holds {} metadata lines for {} vectors, its manifest claims {}",
+            reviews.len(),
+            index.vector_count(),
+            manifest.metadata_count
+        )));
+    }
+    // Appends that never made it into the index, as at startup
+    reviews.truncate(index.vector_count());
+
+    Ok(Restore {
+        manifest,
+        index,
+        reviews,
+        work,
+    })
+}
+
+impl Restore {
+    /// Replace `live` and the metadata with the snapshot's, and its index
+    /// archive with the one at `index_path`
+    ///
+    /// Both files are staged next to the index archive first, then a marker
+    /// commits the restore and they are moved into place. A crash before
+    /// the marker keeps the old data; after it, [`finish_restore`] at
+    /// startup completes the move, so the two never end up mismatched.
+    ///
+    /// Blocking; callers hold the index write lock so nothing reads the two
+    /// halfway.
+    pub fn swap_in(
+        self,
+        live: &mut VectorIndex,
+        metadata_store: &dyn MetadataStore,
+        index_path: &Path,
+    ) -> Result<()> {
+        let staged = staged_index(index_path);
+        let metadata = staged_metadata(index_path);
+        let stage = || -> Result<()> {
+            std::fs::copy(self.work.0.join(INDEX_FILE), &staged)
+                .with_context(|| format!("Failed to stage index at {}", staged.display()))?;
+            File::open(&staged)?.sync_all()?;
+            let mut lines = BufWriter::new(File::create(&metadata)?);
+            for review in &self.reviews {
+                serde_json::to_writer(&mut lines, review)?;
+                lines.write_all(b"\n")?;
+            }
+            lines.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            File::create(restore_marker(index_path))?.sync_all()?;
+            Ok(())
+        };
+        if let Err(e) = stage() {
+            let _ = std::fs::remove_file(&staged);
+            let _ = std::fs::remove_file(&metadata);
+            return Err(e.context("Failed to stage snapshot"));
+        }
+
+        *live = self.index;
+        finish_restore(metadata_store, index_path)
+            .context("Restore is committed and will be finished at the next startup")
+    }
+}
+
+/// Move a committed restore's staged metadata and index into place
+///
+/// Run by the restore itself and at startup, before the index loads, in
+/// case the server stopped halfway. Each step can be repeated, and staged
+/// files without the marker are a restore that never committed.
+pub fn finish_restore(metadata_store: &dyn MetadataStore, index_path: &Path) -> Result<()> {
+    let marker = restore_marker(index_path);
+    let staged = staged_index(index_path);
+    let metadata = staged_metadata(index_path);
+    if !marker.exists() {
+        let _ = std::fs::remove_file(&staged);
+        let _ = std::fs::remove_file(&metadata);
+        return Ok(());
+    }
+
+    if metadata.exists() {
+        let file = File::open(&metadata).context("Failed to open staged metadata")?;
+        let reviews = BufReader::new(file)
+            .lines()
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect::<Result<Vec<ReviewMetadata>>>()
+            .context("Failed to read staged metadata")?;
+        metadata_store.replace_all(&reviews)?;
+        std::fs::remove_file(&metadata)?;
+    }
+    if staged.exists() {
+        std::fs::rename(&staged, index_path)
+            .with_context(|| format!("Failed to replace index: {}", index_path.display()))?;
+    }
+    std::fs::remove_file(&marker)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{review, MemoryStore};
+
+    #[test]
+    fn test_finish_restore() {
+        let dir = archive::temp_dir("finish_restore").unwrap();
+        let index_path = dir.join("index.tar.gz");
+        std::fs::write(&index_path, "old index").unwrap();
+        let store = MemoryStore::new();
+        store.append(&review("Old", "P1")).unwrap();
+
+        // Staged but never committed: the old data stays
+        std::fs::write(staged_index(&index_path), "new index").unwrap();
+        finish_restore(&store, &index_path).unwrap();
+        assert_eq!(std::fs::read_to_string(&index_path).unwrap(), "old index");
+        assert!(!staged_index(&index_path).exists());
+
+        // Committed, then stopped before anything moved
+        std::fs::write(staged_index(&index_path), "new index").unwrap();
+        let line = serde_json::to_string(&review("New", "P2")).unwrap();
+        std::fs::write(staged_metadata(&index_path), format!("{}\n{}\n", line, line)).unwrap();
+        File::create(restore_marker(&index_path)).unwrap();
+        finish_restore(&store, &index_path).unwrap();
+        assert_eq!(std::fs::read_to_string(&index_path).unwrap(), "new index");
+        let reviews = store.read_all().unwrap();
+        assert_eq!(reviews.len(), 2);
+        assert_eq!(reviews[0].review_title, "New");
+        assert!(!restore_marker(&index_path).exists());
+        assert!(!staged_metadata(&index_path).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
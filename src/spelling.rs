@@ -0,0 +1,327 @@
+//! Typo-tolerant queries: SymSpell-style correction of query terms against
+//! the words of the indexed reviews
+//!
+//! Every known word is indexed under each string reachable by deleting up to
+//! [`MAX_EDIT_DISTANCE`] characters from its prefix. A misspelled term's own
+//! deletes then lead straight to its candidates, which are checked with a
+//! real edit distance.
+
+use crate::config::SpellCorrectionConfig;
+use crate::storage::{now_ms, ReviewMetadata};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+/// Largest edit distance the delete index is built for
+pub const MAX_EDIT_DISTANCE: usize = 2;
+
+/// Deletes are generated from this many leading characters only, which keeps
+/// the index small; candidates are still compared over the whole word
+const PREFIX_LEN: usize = 7;
+
+/// Word frequencies of a corpus and their delete index
+#[derive(Debug, Default, Clone)]
+pub struct Vocabulary {
+    counts: HashMap<String, u64>,
+    /// Prefix with characters deleted -> known words it came from
+    deletes: HashMap<String, Vec<String>>,
+}
+
+impl Vocabulary {
+    /// Vocabulary of the live reviews' titles and bodies
+    pub fn from_reviews(reviews: &[ReviewMetadata]) -> Self {
+        let now = now_ms();
+        let mut vocabulary = Self::default();
+        for review in reviews.iter().filter(|review| review.is_live(now)) {
+            vocabulary.add_text(&review.review_title);
+            vocabulary.add_text(&review.review_body);
+        }
+        vocabulary
+    }
+
+    pub fn add_text(&mut self, text: &str) {
+        for (_, word) in word_spans(text) {
+            self.add_word(word.to_lowercase());
+        }
+    }
+
+    fn add_word(&mut self, word: String) {
+        let count = self.counts.entry(word.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            return;
+        }
+        for delete in deletes(prefix(&word), MAX_EDIT_DISTANCE) {
+            self.deletes.entry(delete).or_default().push(word.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The closest known word within `max_distance` edits of `word`, the
+    /// most frequent on ties; `None` when `word` is known or nothing is close
+    pub fn suggest(&self, word: &str, max_distance: usize) -> Option<&str> {
+        if self.counts.contains_key(word) {
+            return None;
+        }
+        let max_distance = max_distance.min(MAX_EDIT_DISTANCE);
+
+        let mut best: Option<(usize, Reverse<u64>, &str)> = None;
+        for key in deletes(prefix(word), max_distance) {
+            for candidate in self.deletes.get(&key).into_iter().flatten() {
+                let distance = edit_distance(word, candidate);
+                if distance > max_distance {
+                    continue;
+                }
+                let rank = (distance, Reverse(self.counts[candidate]), candidate.as_str());
+                if best.is_none_or(|best| rank < best) {
+                    best = Some(rank);
+                }
+            }
+        }
+        best.map(|(_, _, word)| word)
+    }
+
+    /// `query` with each unknown term replaced by its suggestion, or `None`
+    /// when nothing was corrected
+    ///
+    /// Terms shorter than `min_word_len` or containing digits (model
+    /// numbers, sizes) are left alone.
+    pub fn correct(&self, query: &str, settings: &SpellCorrectionConfig) -> Option<String> {
+        let mut corrected = String::with_capacity(query.len());
+        let mut last = 0;
+        let mut changed = false;
+        for (start, word) in word_spans(query) {
+            if word.chars().count() < settings.min_word_len
+                || word.chars().any(|c| c.is_numeric())
+            {
+                continue;
+            }
+            if let Some(suggestion) = self.suggest(&word.to_lowercase(), settings.max_edit_distance) {
+                corrected.push_str(&query[last..start]);
+                corrected.push_str(suggestion);
+                last = start + word.len();
+                changed = true;
+            }
+        }
+        corrected.push_str(&query[last..]);
+        changed.then_some(corrected)
+    }
+}
+
+/// A store's [`Vocabulary`], rebuilt in the background from its reviews
+/// and kept up to date as reviews are added
+///
+/// Corrections read a snapshot of the vocabulary and never wait for a
+/// build: until the first one finishes, queries go uncorrected.
+#[derive(Debug, Default)]
+pub struct SpellChecker {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    vocabulary: Option<Arc<Vocabulary>>,
+    /// Reviews were removed or replaced since the vocabulary was built
+    stale: bool,
+    /// A rebuild is running; reviews observed meanwhile are kept here and
+    /// added to its result
+    building: Option<Vec<(String, String)>>,
+}
+
+impl SpellChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Correct `query` per `settings` with the current vocabulary
+    ///
+    /// `None` when nothing was corrected or the vocabulary isn't built yet.
+    pub fn correct(&self, query: &str, settings: &SpellCorrectionConfig) -> Option<String> {
+        if !settings.enabled {
+            return None;
+        }
+        let vocabulary = self.inner.lock().unwrap().vocabulary.clone()?;
+        vocabulary.correct(query, settings)
+    }
+
+    /// Rebuild the vocabulary from `load` on a blocking thread if it is
+    /// missing or stale and no rebuild is running already
+    pub fn refresh<F>(self: &Arc<Self>, load: F)
+    where
+        F: FnOnce() -> anyhow::Result<Vec<ReviewMetadata>> + Send + 'static,
+    {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.building.is_some() || (inner.vocabulary.is_some() && !inner.stale) {
+                return;
+            }
+            inner.building = Some(Vec::new());
+            inner.stale = false;
+        }
+        let checker = self.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = checker.rebuild(load) {
+                warn!("Spell-correction vocabulary not built: {:#}", e);
+            }
+        });
+    }
+
+    /// Build the vocabulary from `load` and swap it in
+    fn rebuild(
+        &self,
+        load: impl FnOnce() -> anyhow::Result<Vec<ReviewMetadata>>,
+    ) -> anyhow::Result<()> {
+        let built = load().map(|reviews| Vocabulary::from_reviews(&reviews));
+        let mut inner = self.inner.lock().unwrap();
+        let observed = inner.building.take().unwrap_or_default();
+        let mut vocabulary = match built {
+            Ok(vocabulary) => vocabulary,
+            Err(e) => {
+                inner.stale = true;
+                return Err(e);
+            }
+        };
+        for (title, body) in observed {
+            vocabulary.add_text(&title);
+            vocabulary.add_text(&body);
+        }
+        info!(words = vocabulary.len(), "📖 Spell-correction vocabulary built");
+        inner.vocabulary = Some(Arc::new(vocabulary));
+        Ok(())
+    }
+
+    /// Mark the vocabulary out of date after reviews were removed, replaced
+    /// or the store changed wholesale; the next refresh rebuilds it
+    pub fn invalidate(&self) {
+        self.inner.lock().unwrap().stale = true;
+    }
+
+    /// Learn a new review's words
+    pub fn observe(&self, title: &str, body: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(observed) = inner.building.as_mut() {
+            observed.push((title.to_string(), body.to_string()));
+        }
+        if let Some(vocabulary) = inner.vocabulary.as_mut() {
+            let vocabulary = Arc::make_mut(vocabulary);
+            vocabulary.add_text(title);
+            vocabulary.add_text(body);
+        }
+    }
+}
+
+/// Alphanumeric runs of `text` with their byte offsets
+fn word_spans(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+fn prefix(word: &str) -> &str {
+    word.char_indices()
+        .nth(PREFIX_LEN)
+        .map_or(word, |(end, _)| &word[..end])
+}
+
+/// `word` and every string made by deleting up to `distance` of its characters
+fn deletes(word: &str, distance: usize) -> HashSet<String> {
+    let mut all = HashSet::from([word.to_string()]);
+    let mut frontier = vec![word.to_string()];
+    for _ in 0..distance {
+        let mut next = Vec::new();
+        for item in &frontier {
+            for (i, c) in item.char_indices() {
+                let shorter = format!("{}{}", &item[..i], &item[i + c.len_utf8()..]);
+                if all.insert(shorter.clone()) {
+                    next.push(shorter);
+                }
+            }
+        }
+        frontier = next;
+    }
+    all
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and adjacent transpositions
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correct_query() {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.add_text("Sennheiser headphones with great battery life");
+        vocabulary.add_text("The battery died; the headphones did not");
+        vocabulary.add_text("Batter up");
+
+        assert_eq!(vocabulary.suggest("batery", 2), Some("battery"));
+        assert_eq!(vocabulary.suggest("sennhieser", 2), Some("sennheiser"));
+        assert_eq!(vocabulary.suggest("battery", 2), None);
+        assert_eq!(vocabulary.suggest("xylophone", 2), None);
+
+        let settings = SpellCorrectionConfig {
+            enabled: true,
+            ..SpellCorrectionConfig::default()
+        };
+        assert_eq!(
+            vocabulary.correct("Senheiser hedphones, batery X100", &settings).as_deref(),
+            Some("sennheiser headphones, battery X100")
+        );
+        assert_eq!(vocabulary.correct("great battery", &settings), None);
+    }
+
+    #[test]
+    fn test_rebuild() {
+        let settings = SpellCorrectionConfig {
+            enabled: true,
+            ..SpellCorrectionConfig::default()
+        };
+        let review = |title| crate::test_utils::review(title, "P1");
+        let checker = SpellChecker::new();
+        assert_eq!(checker.correct("batery", &settings), None);
+
+        checker.inner.lock().unwrap().building = Some(Vec::new());
+        checker.observe("Great hinge", "");
+        checker.rebuild(|| Ok(vec![review("Great battery")])).unwrap();
+        assert_eq!(checker.correct("batery hnge", &settings).as_deref(), Some("battery hinge"));
+
+        // A rebuild after a delete forgets the removed review's words
+        checker.invalidate();
+        assert!(checker.inner.lock().unwrap().stale);
+        checker.inner.lock().unwrap().building = Some(Vec::new());
+        checker.rebuild(|| Ok(vec![review("Great hinge")])).unwrap();
+        assert_eq!(checker.correct("batery", &settings), None);
+    }
+}
@@ -115,6 +115,35 @@ int spfresh_search(
     return count;
 }
 
+// Copy a stored vector into out (dimension floats)
+int spfresh_get_vector(void* index_ptr, int vector_id, float* out, int dimension) {
+    if (!index_ptr || !out) return -1;
+
+    auto index = *static_cast<std::shared_ptr<VectorIndex>*>(index_ptr);
+    if (vector_id < 0 || vector_id >= index->GetNumSamples() || dimension != index->GetFeatureDim()) {
+        return -1;
+    }
+
+    const void* sample = index->GetSample(vector_id);
+    if (!sample) return -1;
+
+    memcpy(out, sample, dimension * sizeof(float));
+    return 0;
+}
+
+// Mark a vector deleted; searches skip it, IDs of the others are unchanged
+int spfresh_delete_vector(void* index_ptr, int vector_id) {
+    if (!index_ptr) return -1;
+
+    auto index = *static_cast<std::shared_ptr<VectorIndex>*>(index_ptr);
+    if (vector_id < 0 || vector_id >= index->GetNumSamples()) return -1;
+
+    SizeType id = vector_id;
+    ErrorCode ret = index->DeleteIndex(id);
+
+    return (ret == ErrorCode::Success) ? 0 : -1;
+}
+
 // Save index to directory
 int spfresh_save_index(void* index_ptr, const char* folder_path) {
     if (!index_ptr || !folder_path) return -1;
@@ -148,6 +177,14 @@ int spfresh_get_num_vectors(void* index_ptr) {
     return index->GetNumSamples();
 }
 
+// Get number of vectors marked deleted
+int spfresh_get_num_deleted(void* index_ptr) {
+    if (!index_ptr) return -1;
+
+    auto index = *static_cast<std::shared_ptr<VectorIndex>*>(index_ptr);
+    return index->GetNumDeleted();
+}
+
 // Get dimension of vectors
 int spfresh_get_dimension(void* index_ptr) {
     if (!index_ptr) return -1;
@@ -0,0 +1,48 @@
+//! Single-file index storage: each backend writes its native files to a
+//! temp folder, which is packed into (or unpacked from) one tar.gz
+
+use anyhow::Result;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tar::{Archive, Builder};
+
+/// Distinguishes temp folders of concurrent saves/loads
+static TEMP_DIR_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Create an empty temp folder in /tmp (outside of data/); unique per call
+/// since a snapshot and a replica export can run at once
+pub fn temp_dir(prefix: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!(
+        "{}_{}_{}",
+        prefix,
+        std::process::id(),
+        TEMP_DIR_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Create a tar.gz archive at `path` from the contents of `dir`
+pub fn pack(dir: &Path, path: &Path) -> Result<()> {
+    let archive_file = File::create(path)?;
+    let encoder = GzEncoder::new(archive_file, Compression::default());
+    let mut tar = Builder::new(encoder);
+    tar.append_dir_all(".", dir)?;
+    tar.finish()?;
+    Ok(())
+}
+
+/// Extract the tar.gz archive at `path` into `dir`
+pub fn unpack(path: &Path, dir: &Path) -> Result<()> {
+    let archive_file = File::open(path)?;
+    let decoder = GzDecoder::new(archive_file);
+    Archive::new(decoder).unpack(dir)?;
+    Ok(())
+}
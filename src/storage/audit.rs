@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+pub use vector_search_client::models::AuditEntry;
+
+/// Append-only JSONL audit trail
+pub struct AuditLog {
+    path: PathBuf,
+    /// Serializes appends so concurrent entries never interleave
+    write_lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Append an entry
+    pub fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let json = serde_json::to_string(entry).context("Failed to serialize audit entry")?;
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create audit directory")?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("Failed to open audit log")?;
+        writeln!(file, "{}", json).context("Failed to write audit entry")
+    }
+
+    /// Append an entry, logging instead of failing
+    ///
+    /// Used after the audited mutation already succeeded.
+    pub fn record(&self, entry: AuditEntry) {
+        if let Err(e) = self.append(&entry) {
+            warn!(action = %entry.action, "Failed to write audit entry: {:#}", e);
+        }
+    }
+
+    /// Newest-first entries matching the filters
+    pub fn query(
+        &self,
+        action: Option<&str>,
+        since_ms: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<AuditEntry>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let file = File::open(&self.path).context("Failed to open audit log")?;
+        let mut entries = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.context("Failed to read audit log")?;
+            let entry: AuditEntry = serde_json::from_str(&line)
+                .with_context(|| format!("Failed to parse audit line {}", idx))?;
+
+            if action.is_some_and(|a| a != entry.action) {
+                continue;
+            }
+            if since_ms.is_some_and(|since| entry.timestamp_ms < since) {
+                continue;
+            }
+            entries.push(entry);
+        }
+
+        entries.reverse();
+        entries.truncate(limit);
+        Ok(entries)
+    }
+}
+
+/// Hex-encoded SHA-256 of `data`
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_append_and_query() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::new(temp_dir.path().join("audit.jsonl"));
+
+        log.append(&AuditEntry {
+            ids: vec![0],
+            ..AuditEntry::new("add")
+        })
+        .unwrap();
+        log.append(&AuditEntry::new("rebuild")).unwrap();
+        log.append(&AuditEntry {
+            ids: vec![1],
+            ..AuditEntry::new("add")
+        })
+        .unwrap();
+
+        let adds = log.query(Some("add"), None, 10).unwrap();
+        assert_eq!(adds.len(), 2);
+        assert_eq!(adds[0].ids, vec![1]); // newest first
+
+        assert_eq!(log.query(None, None, 1).unwrap().len(), 1);
+    }
+}
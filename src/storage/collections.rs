@@ -0,0 +1,555 @@
+use crate::config::{AppConfig, IndexConfig, TextNormalization};
+use crate::content_hash::ContentHashes;
+use crate::spelling::SpellChecker;
+use crate::storage::{DistanceMetric, IndexBackend, JsonlStorage, MetadataStore, VectorIndex};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+const INDEX_FILE: &str = "reviews.index";
+const METADATA_FILE: &str = "reviews.jsonl";
+const MANIFEST_FILE: &str = "collection.json";
+/// Alias -> collection map, kept in the collections root
+const ALIASES_FILE: &str = "aliases.json";
+
+/// Settings a collection is created with, kept in `collection.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollectionManifest {
+    pub model: String,
+    pub vector_dim: usize,
+    pub metric: DistanceMetric,
+    pub index_type: String,
+    pub num_trees: usize,
+
+    /// Absent for collections created before other backends existed
+    #[serde(default)]
+    pub backend: IndexBackend,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_params: Option<String>,
+
+    /// Off for collections created before normalization existed
+    #[serde(default)]
+    pub normalization: TextNormalization,
+}
+
+impl CollectionManifest {
+    /// The server's own embedding and index settings
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            model: config.embedding.model_name.clone(),
+            vector_dim: config.index.vector_dim,
+            metric: config.index.metric,
+            index_type: config.index.index_type.clone(),
+            num_trees: config.index.num_trees,
+            backend: config.index.backend,
+            search_params: config.index.search_params.clone(),
+            normalization: config.embedding.normalization.clone(),
+        }
+    }
+
+    /// Make `config`'s embedding and index settings describe this manifest
+    pub fn apply(&self, config: &mut AppConfig) {
+        config.embedding.model_name = self.model.clone();
+        config.embedding.normalization = self.normalization.clone();
+        config.index.vector_dim = self.vector_dim;
+        config.index.metric = self.metric;
+        config.index.index_type = self.index_type.clone();
+        config.index.num_trees = self.num_trees;
+        config.index.backend = self.backend;
+        config.index.search_params = self.search_params.clone();
+    }
+
+    /// The index settings this manifest describes
+    pub fn index_config(&self) -> IndexConfig {
+        IndexConfig {
+            backend: self.backend,
+            index_type: self.index_type.clone(),
+            vector_dim: self.vector_dim,
+            num_trees: self.num_trees,
+            metric: self.metric,
+            search_params: self.search_params.clone(),
+        }
+    }
+
+    /// The default collection's manifest, only written by a shadow cutover
+    pub fn read_default(index_path: &Path) -> anyhow::Result<Option<Self>> {
+        Self::read_file(&default_manifest_path(index_path))
+    }
+
+    pub fn write_default(&self, index_path: &Path) -> anyhow::Result<()> {
+        self.write_file(&default_manifest_path(index_path))
+    }
+
+    fn read(dir: &Path) -> anyhow::Result<Option<Self>> {
+        Self::read_file(&dir.join(MANIFEST_FILE))
+    }
+
+    fn write(&self, dir: &Path) -> anyhow::Result<()> {
+        self.write_file(&dir.join(MANIFEST_FILE))
+    }
+
+    fn read_file(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open {:?}", path))?;
+        let manifest = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to parse {:?}", path))?;
+        Ok(Some(manifest))
+    }
+
+    fn write_file(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {:?}", path))
+    }
+}
+
+/// `<index_path>.manifest.json`
+fn default_manifest_path(index_path: &Path) -> PathBuf {
+    let mut path = index_path.as_os_str().to_owned();
+    path.push(".manifest.json");
+    PathBuf::from(path)
+}
+
+/// A named collection: its own index and metadata file under one directory
+pub struct Collection {
+    name: String,
+    dir: PathBuf,
+    index_path: PathBuf,
+    manifest: CollectionManifest,
+    pub index: tokio::sync::RwLock<VectorIndex>,
+    pub metadata: JsonlStorage,
+    pub spelling: Arc<SpellChecker>,
+    /// Built from `metadata` on the first exact-duplicate check
+    pub content_hashes: Arc<ContentHashes>,
+}
+
+impl Collection {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn manifest(&self) -> &CollectionManifest {
+        &self.manifest
+    }
+
+    /// Where the index is saved: `storage.collection_index_paths` for this
+    /// collection, or `reviews.index` in its directory
+    pub fn index_path(&self) -> PathBuf {
+        self.index_path.clone()
+    }
+
+    /// Load the collection stored in `dir`, initializing a missing index
+    fn open(
+        name: String,
+        dir: PathBuf,
+        index_path: Option<PathBuf>,
+        manifest: CollectionManifest,
+    ) -> anyhow::Result<Self> {
+        let metadata = JsonlStorage::new(dir.join(METADATA_FILE));
+        metadata.initialize()?;
+
+        let mut index = VectorIndex::new(&manifest.index_config());
+        let index_path = index_path.unwrap_or_else(|| dir.join(INDEX_FILE));
+        if index_path.exists() {
+            index
+                .load(&index_path)
+                .with_context(|| format!("Failed to load index of collection {}", name))?;
+            if index.vector_count() > 0 && index.dimension() != manifest.vector_dim {
+                anyhow::bail!(
+                    "Index of collection {} has dimension {}, manifest says {}",
+                    name,
+                    index.dimension(),
+                    manifest.vector_dim
+                );
+            }
+        } else {
+            index.initialize()?;
+        }
+
+        Ok(Self {
+            name,
+            dir,
+            index_path,
+            manifest,
+            index: tokio::sync::RwLock::new(index),
+            metadata,
+            spelling: Arc::new(SpellChecker::new()),
+            content_hashes: Arc::new(ContentHashes::new()),
+        })
+    }
+}
+
+/// Error returned by [`CollectionManager`] operations
+#[derive(Debug, thiserror::Error)]
+pub enum CollectionError {
+    #[error("invalid collection name {0:?}: use 1-64 letters, digits, '-' or '_'")]
+    InvalidName(String),
+
+    #[error("collection {0} already exists")]
+    Exists(String),
+
+    #[error("collection {0} not found")]
+    NotFound(String),
+
+    #[error("alias {0} not found")]
+    AliasNotFound(String),
+
+    #[error("collection {collection} is the target of alias {alias}")]
+    Aliased { collection: String, alias: String },
+
+    #[error(transparent)]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Named collections, each kept in `<root>/<name>/`, and aliases for them
+///
+/// The default collection (`storage.index_path` / `storage.metadata_path`)
+/// is not managed here.
+pub struct CollectionManager {
+    root: PathBuf,
+    /// `storage.collection_index_paths`
+    index_paths: BTreeMap<String, PathBuf>,
+    /// Collections and aliases share a lock, so resolving an alias and
+    /// taking the collection it points at can't race a switch or a drop
+    entries: RwLock<Entries>,
+}
+
+struct Entries {
+    collections: BTreeMap<String, Arc<Collection>>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl Entries {
+    fn resolve(&self, name: &str) -> Option<Arc<Collection>> {
+        if let Some(collection) = self.collections.get(name) {
+            return Some(collection.clone());
+        }
+        self.collections.get(self.aliases.get(name)?).cloned()
+    }
+
+    fn alias_of(&self, collection: &str) -> Option<String> {
+        self.aliases
+            .iter()
+            .find(|(_, target)| *target == collection)
+            .map(|(alias, _)| alias.clone())
+    }
+}
+
+impl CollectionManager {
+    /// Open every collection found under `root`
+    ///
+    /// Collections without a manifest (created before manifests existed)
+    /// get one describing the server's current settings.
+    pub fn open(root: &Path, config: &AppConfig) -> anyhow::Result<Self> {
+        let mut collections = BTreeMap::new();
+
+        if root.exists() {
+            for entry in std::fs::read_dir(root)
+                .with_context(|| format!("Failed to read collections dir {:?}", root))?
+            {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name == ALIASES_FILE {
+                    continue;
+                }
+                if !entry.file_type()?.is_dir() || !is_valid_name(&name) {
+                    warn!("Skipping unexpected entry in collections dir: {:?}", entry.path());
+                    continue;
+                }
+                let manifest = match CollectionManifest::read(&entry.path())? {
+                    Some(manifest) => manifest,
+                    None => {
+                        let manifest = CollectionManifest::from_config(config);
+                        manifest.write(&entry.path())?;
+                        manifest
+                    }
+                };
+                let index_path = config.storage.collection_index_paths.get(&name).cloned();
+                let collection = Collection::open(name.clone(), entry.path(), index_path, manifest)?;
+                collections.insert(name, Arc::new(collection));
+            }
+        }
+
+        let aliases_path = root.join(ALIASES_FILE);
+        let aliases: BTreeMap<String, String> = if aliases_path.exists() {
+            let file = std::fs::File::open(&aliases_path)
+                .with_context(|| format!("Failed to open {:?}", aliases_path))?;
+            serde_json::from_reader(std::io::BufReader::new(file))
+                .with_context(|| format!("Failed to parse {:?}", aliases_path))?
+        } else {
+            BTreeMap::new()
+        };
+        for (alias, target) in &aliases {
+            if !collections.contains_key(target) {
+                warn!(alias = %alias, collection = %target, "Alias points at a missing collection");
+            }
+        }
+
+        info!(
+            "Loaded {} collection(s) and {} alias(es) from {:?}",
+            collections.len(),
+            aliases.len(),
+            root
+        );
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            index_paths: config.storage.collection_index_paths.clone(),
+            entries: RwLock::new(Entries { collections, aliases }),
+        })
+    }
+
+    /// Create an empty collection with the given settings
+    pub fn create(
+        &self,
+        name: &str,
+        manifest: CollectionManifest,
+    ) -> Result<Arc<Collection>, CollectionError> {
+        let mut entries = self.entries.write().unwrap();
+        if entries.collections.contains_key(name) {
+            return Err(CollectionError::Exists(name.to_string()));
+        }
+        self.create_locked(&mut entries, name, manifest)
+    }
+
+    /// The named collection, created with `manifest` if it doesn't exist
+    pub fn get_or_create(
+        &self,
+        name: &str,
+        manifest: CollectionManifest,
+    ) -> Result<Arc<Collection>, CollectionError> {
+        if let Some(collection) = self.get(name) {
+            return Ok(collection);
+        }
+        let mut entries = self.entries.write().unwrap();
+        if let Some(collection) = entries.resolve(name) {
+            return Ok(collection);
+        }
+        self.create_locked(&mut entries, name, manifest)
+    }
+
+    fn create_locked(
+        &self,
+        entries: &mut Entries,
+        name: &str,
+        manifest: CollectionManifest,
+    ) -> Result<Arc<Collection>, CollectionError> {
+        if !is_valid_name(name) {
+            return Err(CollectionError::InvalidName(name.to_string()));
+        }
+        if entries.aliases.contains_key(name) {
+            return Err(CollectionError::Exists(name.to_string()));
+        }
+
+        let dir = self.root.join(name);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create collection dir {:?}", dir))?;
+        let opened = manifest
+            .write(&dir)
+            .and_then(|_| {
+                let index_path = self.index_paths.get(name).cloned();
+                Collection::open(name.to_string(), dir.clone(), index_path, manifest)
+            });
+        let collection = match opened {
+            Ok(collection) => Arc::new(collection),
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                return Err(e.into());
+            }
+        };
+
+        entries.collections.insert(name.to_string(), collection.clone());
+        info!(collection = %name, "Created collection");
+        Ok(collection)
+    }
+
+    /// The collection called `name`, or the one alias `name` points at
+    pub fn get(&self, name: &str) -> Option<Arc<Collection>> {
+        self.entries.read().unwrap().resolve(name)
+    }
+
+    /// Every alias and the collection it points at, ordered by alias
+    pub fn aliases(&self) -> BTreeMap<String, String> {
+        self.entries.read().unwrap().aliases.clone()
+    }
+
+    /// Point `alias` at `collection`, creating or switching it in one step
+    ///
+    /// Returns the collection it pointed at before, if any.
+    pub fn set_alias(&self, alias: &str, collection: &str) -> Result<Option<String>, CollectionError> {
+        if !is_valid_name(alias) {
+            return Err(CollectionError::InvalidName(alias.to_string()));
+        }
+        let mut entries = self.entries.write().unwrap();
+        if entries.collections.contains_key(alias) {
+            return Err(CollectionError::Exists(alias.to_string()));
+        }
+        if !entries.collections.contains_key(collection) {
+            return Err(CollectionError::NotFound(collection.to_string()));
+        }
+
+        let mut updated = entries.aliases.clone();
+        let previous = updated.insert(alias.to_string(), collection.to_string());
+        self.write_aliases(&updated)?;
+        entries.aliases = updated;
+        info!(alias = %alias, collection = %collection, previous = ?previous, "Alias set");
+        Ok(previous)
+    }
+
+    /// Remove `alias`, returning the collection it pointed at
+    pub fn remove_alias(&self, alias: &str) -> Result<String, CollectionError> {
+        let mut entries = self.entries.write().unwrap();
+        let mut updated = entries.aliases.clone();
+        let target = updated
+            .remove(alias)
+            .ok_or_else(|| CollectionError::AliasNotFound(alias.to_string()))?;
+        self.write_aliases(&updated)?;
+        entries.aliases = updated;
+        info!(alias = %alias, collection = %target, "Alias removed");
+        Ok(target)
+    }
+
+    /// An alias pointing at `collection`, if any
+    pub fn alias_of(&self, collection: &str) -> Option<String> {
+        self.entries.read().unwrap().alias_of(collection)
+    }
+
+    /// Replace the aliases file in one rename, so readers never see half of it
+    fn write_aliases(&self, aliases: &BTreeMap<String, String>) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.root)
+            .with_context(|| format!("Failed to create collections dir {:?}", self.root))?;
+        let path = self.root.join(ALIASES_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(aliases)?)
+            .with_context(|| format!("Failed to write {:?}", tmp_path))?;
+        std::fs::rename(&tmp_path, &path).with_context(|| format!("Failed to replace {:?}", path))
+    }
+
+    /// All collections, ordered by name
+    pub fn list(&self) -> Vec<Arc<Collection>> {
+        self.entries.read().unwrap().collections.values().cloned().collect()
+    }
+
+    /// Remove a collection and delete its files
+    ///
+    /// Only by its own name, and not while an alias points at it (see
+    /// [`Self::alias_of`]). Requests
+    /// already holding the collection finish against the in-memory copy;
+    /// nothing is written back afterwards.
+    pub fn drop_collection(&self, name: &str) -> Result<(), CollectionError> {
+        let mut entries = self.entries.write().unwrap();
+        if let Some(alias) = entries.alias_of(name) {
+            return Err(CollectionError::Aliased {
+                collection: name.to_string(),
+                alias,
+            });
+        }
+        let collection = entries
+            .collections
+            .remove(name)
+            .ok_or_else(|| CollectionError::NotFound(name.to_string()))?;
+
+        if let Err(e) = std::fs::remove_dir_all(&collection.dir) {
+            entries.collections.insert(name.to_string(), collection);
+            return Err(anyhow::Error::from(e)
+                .context(format!("Failed to delete collection {}", name))
+                .into());
+        }
+        // An index kept outside the collection's directory
+        let index_path = &collection.index_path;
+        if !index_path.starts_with(&collection.dir) && index_path.exists() {
+            let removed = if index_path.is_dir() {
+                std::fs::remove_dir_all(index_path)
+            } else {
+                std::fs::remove_file(index_path)
+            };
+            if let Err(e) = removed {
+                warn!(collection = %name, "Failed to delete index {:?}: {}", index_path, e);
+            }
+        }
+
+        info!(collection = %name, "Dropped collection");
+        Ok(())
+    }
+}
+
+/// Names double as directory and file names, so keep them path-safe
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("reviews_2024-q1"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name(".."));
+        assert!(!is_valid_name("a/b"));
+        assert!(!is_valid_name(&"x".repeat(65)));
+    }
+
+    #[test]
+    fn test_default_manifest() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index_path = dir.path().join("reviews.index");
+        assert!(CollectionManifest::read_default(&index_path).unwrap().is_none());
+
+        let manifest = CollectionManifest {
+            model: "BAAI/bge-small-en-v1.5".to_string(),
+            vector_dim: 384,
+            metric: DistanceMetric::Cosine,
+            index_type: "BKT".to_string(),
+            num_trees: 1,
+            backend: IndexBackend::Spfresh,
+            search_params: None,
+            normalization: TextNormalization::default(),
+        };
+        manifest.write_default(&index_path).unwrap();
+        assert!(dir.path().join("reviews.index.manifest.json").exists());
+
+        let mut config = AppConfig::default();
+        CollectionManifest::read_default(&index_path)
+            .unwrap()
+            .unwrap()
+            .apply(&mut config);
+        assert_eq!(config.embedding.model_name, "BAAI/bge-small-en-v1.5");
+        assert_eq!(config.index.metric, DistanceMetric::Cosine);
+    }
+
+    #[test]
+    fn test_collection_index_paths() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let elsewhere = dir.path().join("elsewhere.index");
+        let mut config = AppConfig::default();
+        config.index.backend = IndexBackend::Memory;
+        config.storage.collection_index_paths.insert("moved".to_string(), elsewhere.clone());
+        let root = dir.path().join("collections");
+        let manager = CollectionManager::open(&root, &config).unwrap();
+
+        let manifest = CollectionManifest::from_config(&config);
+        let moved = manager.create("moved", manifest.clone()).unwrap();
+        let kept = manager.create("kept", manifest).unwrap();
+        assert_eq!(moved.index_path(), elsewhere);
+        assert_eq!(kept.index_path(), root.join("kept").join(INDEX_FILE));
+
+        moved.index.blocking_write().save(&moved.index_path()).unwrap();
+        assert!(elsewhere.exists());
+        drop(moved);
+        let reopened = CollectionManager::open(&root, &config).unwrap();
+        assert_eq!(reopened.get("moved").unwrap().index_path(), elsewhere);
+        reopened.drop_collection("moved").unwrap();
+        assert!(!elsewhere.exists());
+    }
+}
@@ -0,0 +1,162 @@
+//! Disk usage of the stored data, for `GET /admin/storage`
+
+use crate::api::models::DiskUsage;
+use crate::config::StorageConfig;
+use anyhow::Result;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Sizes of everything the server keeps on disk, and the space left for it
+pub fn usage(storage: &StorageConfig) -> Result<DiskUsage> {
+    let index_bytes = path_size(&storage.index_path)?;
+    let (free_bytes, total_bytes) = match free_space(&storage.data_dir)? {
+        Some((free, total)) => (Some(free), Some(total)),
+        None => (None, None),
+    };
+
+    let mut warnings = Vec::new();
+    // Saves write the new archive next to the old one before swapping
+    if let Some(free) = free_bytes
+        && free < index_bytes
+    {
+        warnings.push(format!(
+            "Only {} bytes free on the data volume; saving the index needs about {}",
+            free, index_bytes
+        ));
+    }
+
+    Ok(DiskUsage {
+        index_bytes,
+        metadata_bytes: path_size(&storage.metadata_path)?,
+        audit_bytes: path_size(&storage.audit_path)?,
+        collections_bytes: path_size(&storage.collections_dir)?,
+        snapshots_bytes: path_size(&storage.snapshots_dir)?,
+        temp_bytes: temp_size()?,
+        free_bytes,
+        total_bytes,
+        warnings,
+    })
+}
+
+/// Size of a file, or of everything under a directory; 0 if it's missing
+///
+/// Files removed during the walk (e.g. a finished save's temp files)
+/// count as 0 rather than failing it.
+pub fn path_size(path: &Path) -> Result<u64> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut total = 0;
+    for entry in entries {
+        match entry {
+            Ok(entry) => total += path_size(&entry.path())?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(total)
+}
+
+/// Saves, loads and snapshots in progress, and any a crash left behind
+fn temp_size() -> Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(std::env::temp_dir())? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if is_temp_name(&entry.file_name().to_string_lossy()) {
+            total += path_size(&entry.path())?;
+        }
+    }
+    Ok(total)
+}
+
+/// Prefixes of the folders and files the server puts in the temp directory
+const TEMP_PREFIXES: [&str; 10] = [
+    "spfresh_save",
+    "spfresh_load",
+    "faiss_save",
+    "faiss_load",
+    "usearch_save",
+    "usearch_load",
+    "named_snapshot",
+    "snapshot_restore",
+    "snapshot_upload",
+    "replica_snapshot",
+];
+
+/// Whether `name` looks like an [`archive::temp_dir`](super::archive::temp_dir)
+/// folder or a replication snapshot: `<prefix>_<pid>_<seq>[.ext]`
+fn is_temp_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name);
+    let mut parts = stem.rsplitn(3, '_');
+    let numeric = |part: Option<&str>| {
+        part.is_some_and(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+    };
+    numeric(parts.next())
+        && numeric(parts.next())
+        && parts
+            .next()
+            .is_some_and(|prefix| TEMP_PREFIXES.contains(&prefix))
+}
+
+/// Free and total bytes of the volume holding `path`
+#[cfg(unix)]
+fn free_space(path: &Path) -> Result<Option<(u64, u64)>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    // The data directory may not exist before the first save
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(Path::new("."));
+    let c_path = CString::new(existing.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let block = stat.f_frsize as u64;
+    Ok(Some((stat.f_bavail as u64 * block, stat.f_blocks as u64 * block)))
+}
+
+#[cfg(not(unix))]
+fn free_space(_path: &Path) -> Result<Option<(u64, u64)>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_temp_name() {
+        assert!(is_temp_name("spfresh_save_4242_7"));
+        assert!(is_temp_name("replica_snapshot_4242_0.tar.gz"));
+        assert!(is_temp_name("snapshot_upload_1_12"));
+        assert!(!is_temp_name("spfresh_save_4242"));
+        assert!(!is_temp_name("systemd-private-abc"));
+        assert!(!is_temp_name("other_app_1_2"));
+        assert!(!is_temp_name("spfresh_saved_1_2"));
+    }
+
+    #[test]
+    fn test_path_size() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a"), [0u8; 10]).unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/b"), [0u8; 5]).unwrap();
+        assert_eq!(path_size(dir.path()).unwrap(), 15);
+        assert_eq!(path_size(&dir.path().join("missing")).unwrap(), 0);
+    }
+}
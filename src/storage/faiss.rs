@@ -0,0 +1,713 @@
+use crate::storage::archive;
+use crate::storage::index::{AnnIndex, DistanceMetric, IndexBackend, SearchResult};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_float, c_int, c_void};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{info, warn};
+
+/// Factory string used when `index_type` names an SPFresh algorithm
+const DEFAULT_FACTORY: &str = "HNSW32";
+
+/// Files inside the index archive
+const INDEX_FILE: &str = "faiss.index";
+const DELETED_FILE: &str = "deleted.json";
+const STATE_FILE: &str = "state.json";
+
+/// A search first fetches `k` plus at most this many times `k` extra
+/// candidates to make up for deleted vectors, and widens only if too few
+/// survive
+const DELETED_OVERSAMPLE: usize = 4;
+
+/// Deleted vectors are dropped from the FAISS index (keeping the others'
+/// IDs) once they are this share of it...
+const COMPACT_RATIO: f64 = 0.25;
+/// ...and at least this many
+const COMPACT_MIN_DELETED: usize = 1000;
+
+/// Training points FAISS wants per IVF list (or PQ centroid); fewer give
+/// poor clusters
+const TRAIN_POINTS_PER_CENTROID: usize = 39;
+/// Centroids assumed for factories without an `IVF<n>` part (PQ's 256)
+const DEFAULT_CENTROIDS: usize = 256;
+
+/// FAISS `MetricType` values
+const METRIC_INNER_PRODUCT: c_int = 0;
+const METRIC_L2: c_int = 1;
+
+type IdxT = i64;
+
+// FFI declarations for the FAISS C API (libfaiss_c)
+#[link(name = "faiss_c")]
+unsafe extern "C" {
+    fn faiss_index_factory(
+        p_index: *mut *mut c_void,
+        d: c_int,
+        description: *const c_char,
+        metric: c_int,
+    ) -> c_int;
+
+    fn faiss_Index_is_trained(index: *const c_void) -> c_int;
+
+    fn faiss_Index_train(index: *mut c_void, n: IdxT, x: *const c_float) -> c_int;
+
+    fn faiss_Index_add(index: *mut c_void, n: IdxT, x: *const c_float) -> c_int;
+
+    fn faiss_Index_add_with_ids(index: *mut c_void, n: IdxT, x: *const c_float, xids: *const IdxT) -> c_int;
+
+    fn faiss_Index_search(
+        index: *const c_void,
+        n: IdxT,
+        x: *const c_float,
+        k: IdxT,
+        distances: *mut c_float,
+        labels: *mut IdxT,
+    ) -> c_int;
+
+    fn faiss_Index_reconstruct(index: *const c_void, key: IdxT, recons: *mut c_float) -> c_int;
+
+    fn faiss_Index_ntotal(index: *const c_void) -> IdxT;
+
+    fn faiss_Index_d(index: *const c_void) -> c_int;
+
+    fn faiss_Index_free(index: *mut c_void);
+
+    fn faiss_IndexIVF_cast(index: *mut c_void) -> *mut c_void;
+
+    fn faiss_IndexIVF_make_direct_map(index: *mut c_void, new_maintain: c_int) -> c_int;
+
+    fn faiss_IndexIDMap2_cast(index: *mut c_void) -> *mut c_void;
+
+    fn faiss_IndexIDMap2_sub_index(index: *mut c_void) -> *mut c_void;
+
+    fn faiss_ParameterSpace_new(space: *mut *mut c_void) -> c_int;
+
+    fn faiss_ParameterSpace_set_index_parameters(
+        space: *const c_void,
+        index: *mut c_void,
+        params: *const c_char,
+    ) -> c_int;
+
+    fn faiss_ParameterSpace_free(space: *mut c_void);
+
+    fn faiss_write_index_fname(index: *const c_void, fname: *const c_char) -> c_int;
+
+    fn faiss_read_index_fname(fname: *const c_char, io_flags: c_int, p_out: *mut *mut c_void)
+        -> c_int;
+
+    fn faiss_get_last_error() -> *const c_char;
+}
+
+/// Turn a FAISS return code into an error carrying FAISS's message
+fn check(ret: c_int, what: &str) -> Result<()> {
+    if ret == 0 {
+        return Ok(());
+    }
+    let message = unsafe {
+        let ptr = faiss_get_last_error();
+        if ptr.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        }
+    };
+    anyhow::bail!("{}: {}", what, message)
+}
+
+/// Saved next to the FAISS index; archives from before it have none
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    next_id: usize,
+    #[serde(default)]
+    purged: usize,
+    #[serde(default)]
+    id_mapped: bool,
+    #[serde(default)]
+    pending: Vec<Vec<f32>>,
+}
+
+/// FAISS vector index (IVF, HNSW, or anything `index_factory` builds)
+///
+/// Cosine indexes store normalized vectors and search by inner product.
+/// Deletions are kept on the Rust side (FAISS HNSW can't remove vectors)
+/// and filtered out of search results. Once enough pile up, the index is
+/// rebuilt without them as an `IDMap2` so the rest keep their IDs.
+///
+/// Indexes that need training (IVF, PQ) hold their first vectors aside and
+/// search them by brute force until there are enough to train on.
+pub struct FaissIndex {
+    factory: String,
+    vector_dim: usize,
+    metric: DistanceMetric,
+    /// ParameterSpace settings applied after create/load, e.g. "nprobe=16"
+    search_params: Option<String>,
+    index_ptr: *mut c_void,
+    deleted: BTreeSet<usize>,
+    /// Vector slots, deleted and pending ones included
+    next_id: usize,
+    /// Prepared vectors waiting for an untrained index, with the IDs up to
+    /// `next_id`
+    pending: Vec<Vec<f32>>,
+    /// Deleted vectors a compaction already dropped from the FAISS index
+    purged: usize,
+    /// The FAISS index carries vector IDs itself (after a compaction)
+    id_mapped: bool,
+    /// Bumped on every mutation
+    revision: u64,
+    /// Revision captured by the last successful save/load
+    saved_revision: AtomicU64,
+}
+
+unsafe impl Send for FaissIndex {}
+unsafe impl Sync for FaissIndex {}
+
+impl FaissIndex {
+    /// Create a new, uninitialized index
+    ///
+    /// `index_type` is a FAISS factory string such as "HNSW32" or
+    /// "IVF256,Flat"; SPFresh's "BKT"/"KDT" fall back to HNSW32.
+    pub fn new(
+        index_type: &str,
+        vector_dim: usize,
+        metric: DistanceMetric,
+        search_params: Option<String>,
+    ) -> Self {
+        let factory = match index_type {
+            "BKT" | "KDT" => DEFAULT_FACTORY.to_string(),
+            other => other.to_string(),
+        };
+        info!(
+            factory = %factory,
+            vector_dim = vector_dim,
+            metric = ?metric,
+            "Creating new FAISS index"
+        );
+
+        Self {
+            factory,
+            vector_dim,
+            metric,
+            search_params,
+            index_ptr: std::ptr::null_mut(),
+            deleted: BTreeSet::new(),
+            next_id: 0,
+            pending: Vec::new(),
+            purged: 0,
+            id_mapped: false,
+            revision: 0,
+            saved_revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Vectors in the FAISS index itself, purged and pending ones not
+    /// included
+    fn ntotal(&self) -> usize {
+        if self.index_ptr.is_null() {
+            return 0;
+        }
+        unsafe { faiss_Index_ntotal(self.index_ptr) as usize }
+    }
+
+    fn is_trained(&self) -> bool {
+        unsafe { faiss_Index_is_trained(self.index_ptr) != 0 }
+    }
+
+    /// Vectors to hold aside before training: enough for every IVF list
+    /// (`IVF<n>`), else for a PQ codebook
+    fn train_threshold(&self) -> usize {
+        let centroids = self
+            .factory
+            .split(',')
+            .find_map(|part| part.strip_prefix("IVF"))
+            .and_then(|rest| {
+                let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().ok()
+            })
+            .unwrap_or(DEFAULT_CENTROIDS);
+        centroids * TRAIN_POINTS_PER_CENTROID
+    }
+
+    /// A new, empty FAISS index from `description`
+    fn create(&self, description: &str) -> Result<*mut c_void> {
+        let metric = match self.metric {
+            DistanceMetric::L2 => METRIC_L2,
+            DistanceMetric::Cosine => METRIC_INNER_PRODUCT,
+        };
+        let description_cstr = CString::new(description)?;
+        let mut index_ptr = std::ptr::null_mut();
+        check(
+            unsafe {
+                faiss_index_factory(&mut index_ptr, self.vector_dim as c_int, description_cstr.as_ptr(), metric)
+            },
+            &format!("Failed to create FAISS index {:?}", description),
+        )?;
+        Ok(index_ptr)
+    }
+
+    /// Add prepared, concatenated vectors with IDs from `first_id` on
+    fn add_flat(&mut self, flat_vectors: &[f32], first_id: usize) -> Result<()> {
+        let count = flat_vectors.len() / self.vector_dim;
+        let ret = if self.id_mapped {
+            let ids: Vec<IdxT> = (first_id..first_id + count).map(|id| id as IdxT).collect();
+            unsafe { faiss_Index_add_with_ids(self.index_ptr, count as IdxT, flat_vectors.as_ptr(), ids.as_ptr()) }
+        } else {
+            unsafe { faiss_Index_add(self.index_ptr, count as IdxT, flat_vectors.as_ptr()) }
+        };
+        check(ret, "Failed to add vectors to index")
+    }
+
+    /// Train on the held-aside vectors and move them into the index, once
+    /// there are enough of them
+    fn train_pending(&mut self) -> Result<()> {
+        if self.pending.len() < self.train_threshold() {
+            return Ok(());
+        }
+        let flat_vectors: Vec<f32> = self.pending.concat();
+        info!(num_vectors = self.pending.len(), "Training FAISS index");
+        check(
+            unsafe { faiss_Index_train(self.index_ptr, self.pending.len() as IdxT, flat_vectors.as_ptr()) },
+            "Failed to train index",
+        )?;
+        self.add_flat(&flat_vectors, self.next_id - self.pending.len())?;
+        self.pending.clear();
+        // Direct maps are built for what the index holds
+        self.configure()
+    }
+
+    /// Deleted vectors the FAISS index still holds
+    fn masked(&self) -> usize {
+        self.deleted.len() - self.purged
+    }
+
+    /// Rebuild the FAISS index without its deleted vectors once they are
+    /// `COMPACT_RATIO` of it; the rest keep their IDs through an `IDMap2`
+    fn compact_if_needed(&mut self) -> Result<()> {
+        let masked = self.masked();
+        if !self.pending.is_empty()
+            || masked < COMPACT_MIN_DELETED
+            || (masked as f64) < self.ntotal() as f64 * COMPACT_RATIO
+        {
+            return Ok(());
+        }
+
+        let live: Vec<usize> = (0..self.next_id).filter(|id| !self.deleted.contains(id)).collect();
+        let mut flat_vectors = Vec::with_capacity(live.len() * self.vector_dim);
+        for &vector_id in &live {
+            flat_vectors.extend(self.get_vector(vector_id)?);
+        }
+        let ids: Vec<IdxT> = live.iter().map(|&id| id as IdxT).collect();
+
+        let index_ptr = self.create(&format!("IDMap2,{}", self.factory))?;
+        let built = (|| unsafe {
+            if faiss_Index_is_trained(index_ptr) == 0 {
+                check(
+                    faiss_Index_train(index_ptr, ids.len() as IdxT, flat_vectors.as_ptr()),
+                    "Failed to train compacted index",
+                )?;
+            }
+            check(
+                faiss_Index_add_with_ids(index_ptr, ids.len() as IdxT, flat_vectors.as_ptr(), ids.as_ptr()),
+                "Failed to fill compacted index",
+            )
+        })();
+        if let Err(e) = built {
+            unsafe { faiss_Index_free(index_ptr) };
+            return Err(e);
+        }
+
+        unsafe { faiss_Index_free(self.index_ptr) };
+        self.index_ptr = index_ptr;
+        self.id_mapped = true;
+        self.purged = self.deleted.len();
+        self.configure()?;
+        info!(live = live.len(), dropped = masked, "Compacted FAISS index");
+        Ok(())
+    }
+
+    /// Up to `fetch` (label, raw FAISS distance) pairs from the FAISS index
+    fn search_index(&self, query: &[f32], fetch: usize) -> Result<Vec<(IdxT, f32)>> {
+        let mut labels = vec![-1 as IdxT; fetch];
+        let mut distances = vec![0.0f32; fetch];
+        check(
+            unsafe {
+                faiss_Index_search(
+                    self.index_ptr,
+                    1,
+                    query.as_ptr(),
+                    fetch as IdxT,
+                    distances.as_mut_ptr(),
+                    labels.as_mut_ptr(),
+                )
+            },
+            "Search failed",
+        )?;
+        Ok(labels.into_iter().zip(distances).filter(|&(label, _)| label >= 0).collect())
+    }
+
+    /// What FAISS would report for `vector`: squared L2, or the inner
+    /// product for cosine
+    fn raw_distance(&self, query: &[f32], vector: &[f32]) -> f32 {
+        match self.metric {
+            DistanceMetric::L2 => query.iter().zip(vector).map(|(a, b)| (a - b) * (a - b)).sum(),
+            DistanceMetric::Cosine => query.iter().zip(vector).map(|(a, b)| a * b).sum(),
+        }
+    }
+
+    /// Copy of `vector` as the index stores it
+    fn prepare(&self, vector: &[f32]) -> Result<Vec<f32>> {
+        if vector.len() != self.vector_dim {
+            anyhow::bail!(
+                "Vector dimension mismatch: expected {}, got {}",
+                self.vector_dim,
+                vector.len()
+            );
+        }
+        let mut vector = vector.to_vec();
+        if self.metric == DistanceMetric::Cosine {
+            let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                vector.iter_mut().for_each(|x| *x /= norm);
+            }
+        }
+        Ok(vector)
+    }
+
+    /// Settings that aren't part of the saved index: IVF direct maps (for
+    /// `get_vector`) and the configured search parameters
+    fn configure(&mut self) -> Result<()> {
+        unsafe {
+            let id_map = faiss_IndexIDMap2_cast(self.index_ptr);
+            let inner = if id_map.is_null() { self.index_ptr } else { faiss_IndexIDMap2_sub_index(id_map) };
+            let ivf = faiss_IndexIVF_cast(inner);
+            if !ivf.is_null() {
+                check(faiss_IndexIVF_make_direct_map(ivf, 1), "Failed to build IVF direct map")?;
+            }
+
+            if let Some(params) = &self.search_params {
+                let params_cstr = CString::new(params.as_str())?;
+                let mut space = std::ptr::null_mut();
+                check(faiss_ParameterSpace_new(&mut space), "Failed to create ParameterSpace")?;
+                let ret =
+                    faiss_ParameterSpace_set_index_parameters(space, self.index_ptr, params_cstr.as_ptr());
+                faiss_ParameterSpace_free(space);
+                check(ret, &format!("Invalid search_params {:?}", params))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_archive(&self, path: &Path) -> Result<()> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        info!("Saving index to {:?}", path);
+
+        let temp_dir = archive::temp_dir("faiss_save")?;
+        let result = (|| {
+            let index_file = temp_dir.join(INDEX_FILE);
+            let fname = CString::new(index_file.to_str().context("Invalid temp path")?)?;
+            check(
+                unsafe { faiss_write_index_fname(self.index_ptr, fname.as_ptr()) },
+                "Failed to save FAISS index",
+            )?;
+            std::fs::write(temp_dir.join(DELETED_FILE), serde_json::to_vec(&self.deleted)?)?;
+            let state = State {
+                next_id: self.next_id,
+                purged: self.purged,
+                id_mapped: self.id_mapped,
+                pending: self.pending.clone(),
+            };
+            std::fs::write(temp_dir.join(STATE_FILE), serde_json::to_vec(&state)?)?;
+            archive::pack(&temp_dir, path)
+        })();
+
+        // Cleanup temp folder
+        std::fs::remove_dir_all(&temp_dir)?;
+        result
+    }
+}
+
+impl AnnIndex for FaissIndex {
+    fn backend(&self) -> IndexBackend {
+        IndexBackend::Faiss
+    }
+
+    fn initialize(&mut self) -> Result<()> {
+        info!(factory = %self.factory, "Initializing FAISS index");
+
+        let index_ptr = self.create(&self.factory)?;
+        if !self.index_ptr.is_null() {
+            unsafe { faiss_Index_free(self.index_ptr) };
+        }
+        self.index_ptr = index_ptr;
+        self.deleted.clear();
+        self.next_id = 0;
+        self.pending.clear();
+        self.purged = 0;
+        self.id_mapped = false;
+        self.configure()?;
+
+        info!("✅ FAISS index initialized successfully");
+        Ok(())
+    }
+
+    fn add_vector(&mut self, vector: &[f32]) -> Result<usize> {
+        let vector = self.prepare(vector)?;
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        let vector_id = self.next_id;
+        if self.is_trained() {
+            self.add_flat(&vector, vector_id)?;
+            self.next_id += 1;
+        } else {
+            self.pending.push(vector);
+            self.next_id += 1;
+            if let Err(e) = self.train_pending() {
+                self.pending.pop();
+                self.next_id -= 1;
+                return Err(e);
+            }
+        }
+
+        self.revision += 1;
+        info!(vector_id = vector_id, total = vector_id + 1, "Added vector to index");
+        Ok(vector_id)
+    }
+
+    fn build_from_vectors(&mut self, vectors: &[Vec<f32>]) -> Result<()> {
+        if vectors.is_empty() {
+            return Ok(());
+        }
+
+        // Start from an empty index so IDs are 0..n
+        self.initialize()?;
+
+        let num_vectors = vectors.len();
+        if self.is_trained() {
+            let mut flat_vectors = Vec::with_capacity(num_vectors * self.vector_dim);
+            for vector in vectors {
+                flat_vectors.extend(self.prepare(vector)?);
+            }
+            self.add_flat(&flat_vectors, 0)?;
+        } else {
+            self.pending = vectors.iter().map(|vector| self.prepare(vector)).collect::<Result<_>>()?;
+        }
+        self.next_id = num_vectors;
+        if !self.pending.is_empty() {
+            self.train_pending()?;
+        }
+
+        self.revision += 1;
+        info!(num_vectors = num_vectors, "Built index from vectors");
+        Ok(())
+    }
+
+    fn search(&self, query_vector: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        let query = self.prepare(query_vector)?;
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        let live = |&(label, _): &(IdxT, f32)| !self.deleted.contains(&(label as usize));
+        let mut hits: Vec<(IdxT, f32)> = Vec::new();
+
+        // Deleted vectors still in the index take up candidates: fetch a
+        // bounded number more, and widen only if too few are left
+        let ntotal = self.ntotal();
+        let mut fetch = (k + self.masked().min(k * DELETED_OVERSAMPLE)).min(ntotal);
+        while fetch > 0 {
+            hits = self.search_index(&query, fetch)?;
+            hits.retain(live);
+            if hits.len() >= k || fetch == ntotal {
+                break;
+            }
+            fetch = (fetch * 2).min(ntotal);
+        }
+
+        // Vectors waiting for training, by brute force
+        let first_pending = self.next_id - self.pending.len();
+        hits.extend(
+            self.pending
+                .iter()
+                .enumerate()
+                .map(|(i, vector)| ((first_pending + i) as IdxT, self.raw_distance(&query, vector)))
+                .filter(live),
+        );
+
+        // Inner product of normalized vectors is the cosine similarity
+        let mut results: Vec<SearchResult> = hits
+            .into_iter()
+            .map(|(label, distance)| SearchResult {
+                vector_id: label as usize,
+                distance: match self.metric {
+                    DistanceMetric::Cosine => 1.0 - distance,
+                    DistanceMetric::L2 => distance,
+                },
+            })
+            .collect();
+        results.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        results.truncate(k);
+
+        info!(query_results = results.len(), k = k, "Search completed");
+        Ok(results)
+    }
+
+    /// Cosine indexes hold (and so return) normalized vectors
+    fn get_vector(&self, vector_id: usize) -> Result<Vec<f32>> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+        if vector_id >= self.next_id {
+            anyhow::bail!("Vector {} out of range ({} vectors)", vector_id, self.next_id);
+        }
+        let first_pending = self.next_id - self.pending.len();
+        if vector_id >= first_pending {
+            return Ok(self.pending[vector_id - first_pending].clone());
+        }
+        if self.id_mapped && self.deleted.contains(&vector_id) {
+            anyhow::bail!("Vector {} was deleted", vector_id);
+        }
+
+        let mut vector = vec![0.0f32; self.vector_dim];
+        check(
+            unsafe { faiss_Index_reconstruct(self.index_ptr, vector_id as IdxT, vector.as_mut_ptr()) },
+            &format!("Failed to read vector {}", vector_id),
+        )?;
+        Ok(vector)
+    }
+
+    fn delete_vector(&mut self, vector_id: usize) -> Result<()> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+        if vector_id >= self.next_id {
+            anyhow::bail!("Vector {} out of range ({} vectors)", vector_id, self.next_id);
+        }
+
+        if self.deleted.insert(vector_id) {
+            self.revision += 1;
+            info!(vector_id, "Deleted vector from index");
+            // The delete stands either way; searches keep skipping the rest
+            if let Err(e) = self.compact_if_needed() {
+                warn!("FAISS compaction failed: {:#}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        self.write_archive(path)?;
+
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
+        info!("✅ Index saved successfully to single file");
+        Ok(())
+    }
+
+    fn export_archive(&self, path: &Path) -> Result<()> {
+        self.write_archive(path)
+    }
+
+    fn load(&mut self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            anyhow::bail!("Index path does not exist: {:?}", path);
+        }
+
+        info!("Loading index from {:?}", path);
+
+        let temp_dir = archive::temp_dir("faiss_load")?;
+        let loaded = (|| {
+            archive::unpack(path, &temp_dir)?;
+            let deleted: BTreeSet<usize> = match std::fs::read(temp_dir.join(DELETED_FILE)) {
+                Ok(bytes) => serde_json::from_slice(&bytes)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+                Err(e) => return Err(e.into()),
+            };
+            let state: Option<State> = match std::fs::read(temp_dir.join(STATE_FILE)) {
+                Ok(bytes) => Some(serde_json::from_slice(&bytes)?),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e.into()),
+            };
+
+            let index_file = temp_dir.join(INDEX_FILE);
+            let fname = CString::new(index_file.to_str().context("Invalid temp path")?)?;
+            let mut new_ptr = std::ptr::null_mut();
+            check(
+                unsafe { faiss_read_index_fname(fname.as_ptr(), 0, &mut new_ptr) },
+                "Failed to load FAISS index (was it saved by another backend?)",
+            )?;
+            anyhow::Ok((new_ptr, deleted, state))
+        })();
+
+        // Cleanup temp folder
+        std::fs::remove_dir_all(&temp_dir)?;
+        let (new_ptr, deleted, state) = loaded?;
+
+        // Destroy old index if exists
+        if !self.index_ptr.is_null() {
+            unsafe { faiss_Index_free(self.index_ptr) };
+        }
+        self.index_ptr = new_ptr;
+        self.deleted = deleted;
+        // Older archives held every vector in the index, by position
+        let state = state.unwrap_or_else(|| State {
+            next_id: self.ntotal(),
+            ..State::default()
+        });
+        self.next_id = state.next_id;
+        self.purged = state.purged;
+        self.id_mapped = state.id_mapped;
+        self.pending = state.pending;
+        self.revision += 1;
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
+
+        let dimension = unsafe { faiss_Index_d(self.index_ptr) } as usize;
+        if dimension != self.vector_dim {
+            warn!(
+                "Loaded index dimension ({}) differs from configured ({})",
+                dimension, self.vector_dim
+            );
+            self.vector_dim = dimension;
+        }
+        self.configure()?;
+
+        info!(
+            num_vectors = self.next_id,
+            dimension = self.vector_dim,
+            "✅ Index loaded successfully from single file"
+        );
+        Ok(())
+    }
+
+    fn vector_count(&self) -> usize {
+        self.next_id
+    }
+
+    fn live_count(&self) -> usize {
+        self.next_id - self.deleted.len()
+    }
+
+    fn dimension(&self) -> usize {
+        self.vector_dim
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.saved_revision.load(Ordering::SeqCst) != self.revision
+    }
+}
+
+impl Drop for FaissIndex {
+    fn drop(&mut self) {
+        if !self.index_ptr.is_null() {
+            unsafe {
+                faiss_Index_free(self.index_ptr);
+            }
+            info!("FAISS index destroyed");
+        }
+    }
+}
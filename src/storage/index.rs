@@ -0,0 +1,146 @@
+use crate::config::IndexConfig;
+#[cfg(feature = "faiss")]
+use crate::storage::faiss::FaissIndex;
+use crate::storage::spfresh::SpfreshIndex;
+#[cfg(feature = "usearch")]
+use crate::storage::usearch::UsearchIndex;
+#[cfg(any(test, feature = "test-utils"))]
+use crate::test_utils::MemoryIndex;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+pub use vector_search_client::models::DistanceMetric;
+
+/// Search result from vector index
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub vector_id: usize,
+    pub distance: f32,
+}
+
+/// Library behind the vector index, chosen by `index.backend`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexBackend {
+    /// SPFresh/SPTAG through the C++ wrapper
+    #[default]
+    Spfresh,
+
+    /// FAISS through its C API (`faiss` feature)
+    #[cfg(feature = "faiss")]
+    Faiss,
+
+    /// USearch through its C API (`usearch` feature)
+    #[cfg(feature = "usearch")]
+    Usearch,
+
+    /// Exact search held in memory (`test-utils` feature)
+    #[cfg(any(test, feature = "test-utils"))]
+    Memory,
+}
+
+/// An approximate nearest-neighbour index
+///
+/// Vector IDs are assigned sequentially from 0 and never reused: deleting a
+/// vector keeps its slot, so the IDs of later vectors (and the metadata
+/// lines they point at) don't move.
+pub trait AnnIndex: Send + Sync {
+    fn backend(&self) -> IndexBackend;
+
+    /// Create an empty index; not needed before `load`
+    fn initialize(&mut self) -> Result<()>;
+
+    /// Add a vector, returning its ID
+    fn add_vector(&mut self, vector: &[f32]) -> Result<usize>;
+
+    /// Replace the contents with `vectors`, given IDs 0..n (more efficient
+    /// than adding one-by-one)
+    fn build_from_vectors(&mut self, vectors: &[Vec<f32>]) -> Result<()>;
+
+    /// The k nearest live vectors, closest first
+    fn search(&self, query_vector: &[f32], k: usize) -> Result<Vec<SearchResult>>;
+
+    /// Copy a stored vector out of the index
+    fn get_vector(&self, vector_id: usize) -> Result<Vec<f32>>;
+
+    /// Mark a vector deleted so searches skip it
+    fn delete_vector(&mut self, vector_id: usize) -> Result<()>;
+
+    /// Save the index to a single file and mark it clean
+    fn save(&self, path: &Path) -> Result<()>;
+
+    /// Write a copy of the index (e.g. for a replica) without marking it saved
+    fn export_archive(&self, path: &Path) -> Result<()>;
+
+    /// Replace the contents with the index saved at `path`
+    fn load(&mut self, path: &Path) -> Result<()>;
+
+    /// Number of vector slots, deleted ones included
+    fn vector_count(&self) -> usize;
+
+    /// Number of vectors not deleted
+    fn live_count(&self) -> usize;
+
+    fn dimension(&self) -> usize;
+
+    /// Whether the index has changed since it was last saved or loaded
+    fn is_dirty(&self) -> bool;
+}
+
+/// The index handlers work with: whichever backend the config selects
+pub struct VectorIndex {
+    inner: Box<dyn AnnIndex>,
+}
+
+impl VectorIndex {
+    /// An uninitialized index of the configured backend
+    pub fn new(config: &IndexConfig) -> Self {
+        let inner: Box<dyn AnnIndex> = match config.backend {
+            IndexBackend::Spfresh => Box::new(SpfreshIndex::new(
+                config.index_type.clone(),
+                config.vector_dim,
+                config.num_trees,
+                config.metric,
+            )),
+            #[cfg(feature = "faiss")]
+            IndexBackend::Faiss => Box::new(FaissIndex::new(
+                &config.index_type,
+                config.vector_dim,
+                config.metric,
+                config.search_params.clone(),
+            )),
+            #[cfg(feature = "usearch")]
+            IndexBackend::Usearch => Box::new(UsearchIndex::new(
+                config.vector_dim,
+                config.metric,
+                config.search_params.as_deref(),
+            )),
+            #[cfg(any(test, feature = "test-utils"))]
+            IndexBackend::Memory => Box::new(MemoryIndex::new(config.vector_dim, config.metric)),
+        };
+        Self { inner }
+    }
+
+    /// Wrap an index built elsewhere (e.g. a test double)
+    pub fn from_index(index: impl AnnIndex + 'static) -> Self {
+        Self {
+            inner: Box::new(index),
+        }
+    }
+}
+
+impl Deref for VectorIndex {
+    type Target = dyn AnnIndex;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.as_ref()
+    }
+}
+
+impl DerefMut for VectorIndex {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner.as_mut()
+    }
+}
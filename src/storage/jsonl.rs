@@ -1,23 +1,20 @@
+use crate::storage::metadata::tombstones;
+use crate::storage::MetadataStore;
+use crate::storage::verify::{InvalidRecord, MetadataReport};
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
+use std::sync::Mutex;
 use tracing::{info, warn};
-
-/// Review metadata stored in JSONL format
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReviewMetadata {
-    pub review_title: String,
-    pub review_body: String,
-    pub product_id: String,
-    pub review_rating: u8,
-}
+pub use vector_search_client::models::{first_revision, now_ms, ReviewMetadata};
 
 /// JSONL storage for review metadata
 /// Each line corresponds to one vector in the index (line number = vector ID)
 pub struct JsonlStorage {
     path: std::path::PathBuf,
+    /// Serializes appends and rewrites, so a rewrite never drops an append
+    writes: Mutex<()>,
 }
 
 impl JsonlStorage {
@@ -25,11 +22,53 @@ impl JsonlStorage {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             path: path.as_ref().to_path_buf(),
+            writes: Mutex::new(()),
+        }
+    }
+
+    /// Overwrite several lines in one rewrite of the file; call holding
+    /// `writes`
+    fn replace_lines(&self, updates: &[(usize, ReviewMetadata)]) -> Result<()> {
+        let file = File::open(&self.path)
+            .context("Failed to open metadata file")?;
+        let mut lines: Vec<String> = BufReader::new(file)
+            .lines()
+            .collect::<std::io::Result<_>>()
+            .context("Failed to read metadata file")?;
+        for (vector_id, metadata) in updates {
+            let line = lines
+                .get_mut(*vector_id)
+                .with_context(|| format!("Vector ID {} not found", vector_id))?;
+            *line = serde_json::to_string(metadata)
+                .context("Failed to serialize metadata")?;
         }
+        self.rewrite(&lines)
     }
 
+    /// Replace the file with `lines`, written to a temp file and renamed
+    /// into place
+    fn rewrite(&self, lines: &[String]) -> Result<()> {
+        let mut tmp_path = self.path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let mut writer = std::io::BufWriter::new(
+            File::create(&tmp_path).context("Failed to create metadata temp file")?,
+        );
+        for line in lines {
+            writeln!(writer, "{}", line).context("Failed to write metadata to file")?;
+        }
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|file| file.sync_all())
+            .context("Failed to write metadata to file")?;
+        std::fs::rename(&tmp_path, &self.path)
+            .context("Failed to replace metadata file")
+    }
+}
+
+impl MetadataStore for JsonlStorage {
     /// Initialize storage (create file if not exists)
-    pub fn initialize(&self) -> Result<()> {
+    fn initialize(&self) -> Result<()> {
         if !self.path.exists() {
             if let Some(parent) = self.path.parent() {
                 std::fs::create_dir_all(parent)
@@ -46,7 +85,8 @@ impl JsonlStorage {
 
     /// Append a review to the JSONL file
     /// Returns the line number (0-indexed) which corresponds to vector ID
-    pub fn append(&self, metadata: &ReviewMetadata) -> Result<usize> {
+    fn append(&self, metadata: &ReviewMetadata) -> Result<usize> {
+        let _writes = self.writes.lock().unwrap();
         // Get current line count before appending
         let vector_id = self.count_lines()?;
 
@@ -73,8 +113,38 @@ impl JsonlStorage {
         Ok(vector_id)
     }
 
+    /// Append many reviews with a single file open
+    /// Returns the vector ID of the first appended review
+    fn append_batch(&self, records: &[ReviewMetadata]) -> Result<usize> {
+        let _writes = self.writes.lock().unwrap();
+        let first_id = self.count_lines()?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("Failed to open metadata file for appending")?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        for metadata in records {
+            let json = serde_json::to_string(metadata)
+                .context("Failed to serialize metadata")?;
+            writeln!(writer, "{}", json)
+                .context("Failed to write metadata to file")?;
+        }
+        writer.flush().context("Failed to write metadata to file")?;
+
+        info!(
+            first_id = first_id,
+            count = records.len(),
+            "Appended review metadata batch"
+        );
+
+        Ok(first_id)
+    }
+
     /// Read a review by line number (vector ID)
-    pub fn read_by_id(&self, vector_id: usize) -> Result<ReviewMetadata> {
+    fn read_by_id(&self, vector_id: usize) -> Result<ReviewMetadata> {
         let file = File::open(&self.path)
             .context("Failed to open metadata file")?;
         let reader = BufReader::new(file);
@@ -91,7 +161,7 @@ impl JsonlStorage {
     }
 
     /// Read multiple reviews by their vector IDs
-    pub fn read_batch(&self, vector_ids: &[usize]) -> Result<Vec<ReviewMetadata>> {
+    fn read_batch(&self, vector_ids: &[usize]) -> Result<Vec<ReviewMetadata>> {
         let file = File::open(&self.path)
             .context("Failed to open metadata file")?;
         let reader = BufReader::new(file);
@@ -117,8 +187,31 @@ impl JsonlStorage {
         Ok(results)
     }
 
+    /// Read up to `limit` reviews starting at vector ID `start`
+    fn read_range(&self, start: usize, limit: usize) -> Result<Vec<ReviewMetadata>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+
+        let file = File::open(&self.path)
+            .context("Failed to open metadata file")?;
+        let reader = BufReader::new(file);
+
+        reader
+            .lines()
+            .enumerate()
+            .skip(start)
+            .take(limit)
+            .map(|(idx, line)| {
+                let line = line.context("Failed to read line")?;
+                serde_json::from_str(&line)
+                    .context(format!("Failed to parse line {}", idx))
+            })
+            .collect()
+    }
+
     /// Count total number of lines (reviews)
-    pub fn count_lines(&self) -> Result<usize> {
+    fn count_lines(&self) -> Result<usize> {
         if !self.path.exists() {
             return Ok(0);
         }
@@ -129,8 +222,88 @@ impl JsonlStorage {
         Ok(reader.lines().count())
     }
 
+    /// Overwrite the line for `vector_id` (the whole file is rewritten)
+    ///
+    /// Callers must serialize writes; the server does so under the index
+    /// write lock.
+    fn replace(&self, vector_id: usize, metadata: &ReviewMetadata) -> Result<()> {
+        self.replace_many(&[(vector_id, metadata.clone())])?;
+        info!(vector_id, revision = metadata.revision, "Rewrote review metadata");
+        Ok(())
+    }
+
+    /// Overwrite several lines in one rewrite of the file
+    ///
+    /// Nothing is written if any vector ID is out of range.
+    fn replace_many(&self, updates: &[(usize, ReviewMetadata)]) -> Result<()> {
+        let _writes = self.writes.lock().unwrap();
+        self.replace_lines(updates)
+    }
+
+    /// Read, tombstone and rewrite in one go, holding off other writes
+    fn tombstone_matching(&self, pred: &dyn Fn(&ReviewMetadata) -> bool) -> Result<Vec<usize>> {
+        let _writes = self.writes.lock().unwrap();
+        let updates = tombstones(self.read_all()?, pred);
+        if !updates.is_empty() {
+            self.replace_lines(&updates)?;
+        }
+        Ok(updates.into_iter().map(|(id, _)| id).collect())
+    }
+
+    /// Write the new file next to the old one and rename it into place
+    fn replace_all(&self, records: &[ReviewMetadata]) -> Result<()> {
+        let _writes = self.writes.lock().unwrap();
+        let lines = records
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()
+            .context("Failed to serialize metadata")?;
+        self.rewrite(&lines)
+    }
+
+    /// Keep only the first `line_count` lines, dropping appends that never
+    /// made it into the index
+    fn truncate(&self, line_count: usize) -> Result<()> {
+        let _writes = self.writes.lock().unwrap();
+        let file = File::open(&self.path)
+            .context("Failed to open metadata file")?;
+        let mut reader = BufReader::new(file);
+
+        let mut offset = 0u64;
+        let mut line = Vec::new();
+        for _ in 0..line_count {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .context("Failed to read metadata file")?;
+            if read == 0 {
+                break;
+            }
+            offset += read as u64;
+        }
+
+        OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .context("Failed to open metadata file for truncation")?
+            .set_len(offset)
+            .context("Failed to truncate metadata file")
+    }
+
+    /// Flush appended metadata to stable storage
+    fn flush(&self) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        File::open(&self.path)
+            .context("Failed to open metadata file")?
+            .sync_all()
+            .context("Failed to sync metadata file")
+    }
+
     /// Get all reviews (for debugging/admin purposes)
-    pub fn read_all(&self) -> Result<Vec<ReviewMetadata>> {
+    fn read_all(&self) -> Result<Vec<ReviewMetadata>> {
         if !self.path.exists() {
             return Ok(vec![]);
         }
@@ -149,11 +322,48 @@ impl JsonlStorage {
             })
             .collect()
     }
+
+    /// Parse every line, collecting the ones that fail instead of stopping
+    fn verify(&self) -> Result<MetadataReport> {
+        let mut report = MetadataReport::default();
+        if !self.path.exists() {
+            return Ok(report);
+        }
+
+        let file = File::open(&self.path)
+            .context("Failed to open metadata file")?;
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .context("Failed to read metadata file")?;
+            if read == 0 {
+                break;
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            } else {
+                report.torn_tail = true;
+            }
+            match serde_json::from_slice::<ReviewMetadata>(&line) {
+                Ok(metadata) => report.deleted += metadata.deleted as usize,
+                Err(e) => report.invalid.push(InvalidRecord {
+                    line: report.records,
+                    error: e.to_string(),
+                }),
+            }
+            report.records += 1;
+        }
+        Ok(report)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::review;
     use tempfile::TempDir;
 
     #[test]
@@ -164,10 +374,9 @@ mod tests {
         storage.initialize().unwrap();
 
         let review = ReviewMetadata {
-            review_title: "Great product".to_string(),
             review_body: "Very satisfied".to_string(),
-            product_id: "P123".to_string(),
             review_rating: 5,
+            ..review("Great product", "P123")
         };
 
         let id = storage.append(&review).unwrap();
@@ -177,4 +386,176 @@ mod tests {
         assert_eq!(retrieved.review_title, review.review_title);
         assert_eq!(retrieved.product_id, review.product_id);
     }
+
+    #[test]
+    fn test_read_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(temp_dir.path().join("test.jsonl"));
+        storage.initialize().unwrap();
+
+        let reviews: Vec<ReviewMetadata> = (0..5)
+            .map(|i| review(&format!("Review {}", i), "P1"))
+            .collect();
+        storage.append_batch(&reviews).unwrap();
+
+        let range = storage.read_range(3, 10).unwrap();
+        assert_eq!(range.len(), 2);
+        assert_eq!(range[0].review_title, "Review 3");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(temp_dir.path().join("test.jsonl"));
+        storage.initialize().unwrap();
+
+        let reviews: Vec<ReviewMetadata> = (0..3)
+            .map(|i| review(&format!("Review {}", i), "P1"))
+            .collect();
+        storage.append_batch(&reviews).unwrap();
+
+        storage.truncate(2).unwrap();
+        assert_eq!(storage.count_lines().unwrap(), 2);
+        assert_eq!(storage.append(&reviews[0]).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_replace() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonlStorage::new(temp_dir.path().join("test.jsonl"));
+        storage.initialize().unwrap();
+
+        let mut review = review("Review", "P1");
+        storage.append_batch(&[review.clone(), review.clone()]).unwrap();
+
+        review.revision = 2;
+        review.deleted = true;
+        storage.replace(1, &review).unwrap();
+
+        assert_eq!(storage.count_lines().unwrap(), 2);
+        assert!(!storage.read_by_id(0).unwrap().deleted);
+        let replaced = storage.read_by_id(1).unwrap();
+        assert!(replaced.deleted);
+        assert_eq!(replaced.revision, 2);
+        assert!(storage.replace(2, &review).is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.jsonl");
+        let storage = JsonlStorage::new(&path);
+        assert_eq!(storage.verify().unwrap(), MetadataReport::default());
+
+        std::fs::write(
+            &path,
+            "{\"review_title\":\"a\",\"review_body\":\"b\",\"product_id\":\"P1\",\"review_rating\":4,\"deleted\":true}\n\
+             not json\n\
+             {\"review_title\":",
+        )
+        .unwrap();
+        let report = storage.verify().unwrap();
+        assert_eq!(report.records, 3);
+        assert_eq!(report.deleted, 1);
+        assert_eq!(
+            report.invalid.iter().map(|record| record.line).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(report.torn_tail);
+        assert_eq!(report.problems().len(), 3);
+    }
+
+    mod model {
+        use super::*;
+        use crate::test_utils::MemoryStore;
+        use proptest::prelude::*;
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Append(ReviewMetadata),
+            AppendBatch(Vec<ReviewMetadata>),
+            Replace(usize, ReviewMetadata),
+            Tombstone(usize),
+            Truncate(usize),
+        }
+
+        fn review() -> impl Strategy<Value = ReviewMetadata> {
+            // Titles and bodies include quotes, backslashes and newlines so
+            // escaping is exercised
+            (
+                "[a-z \"\\\\\n]{0,12}",
+                "[a-z \"\\\\\n]{0,24}",
+                1u8..=5,
+                any::<bool>(),
+                proptest::option::of("[a-z]{2}"),
+            )
+                .prop_map(|(title, body, rating, deleted, language)| ReviewMetadata {
+                    review_body: body,
+                    review_rating: rating,
+                    deleted,
+                    language,
+                    ..crate::test_utils::review(&title, "P1")
+                })
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            prop_oneof![
+                3 => review().prop_map(Op::Append),
+                2 => proptest::collection::vec(review(), 0..4).prop_map(Op::AppendBatch),
+                2 => (0usize..12, review()).prop_map(|(id, review)| Op::Replace(id, review)),
+                2 => (0usize..12).prop_map(Op::Tombstone),
+                1 => (0usize..12).prop_map(Op::Truncate),
+            ]
+        }
+
+        fn apply(store: &dyn MetadataStore, op: &Op) -> Result<()> {
+            match op {
+                Op::Append(review) => store.append(review).map(drop),
+                Op::AppendBatch(reviews) => store.append_batch(reviews).map(drop),
+                Op::Replace(id, review) => store.replace(*id, review),
+                Op::Tombstone(id) => {
+                    let mut review = store.read_by_id(*id)?;
+                    review.revision += 1;
+                    review.deleted = true;
+                    store.replace(*id, &review)
+                }
+                Op::Truncate(count) => store.truncate(*count),
+            }
+        }
+
+        fn snapshot(store: &dyn MetadataStore) -> serde_json::Value {
+            serde_json::to_value(store.read_all().unwrap()).unwrap()
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            /// Any sequence of writes leaves the file well-formed and holding
+            /// exactly what an in-memory store would
+            #[test]
+            fn prop_matches_model(ops in proptest::collection::vec(op(), 1..24)) {
+                let temp_dir = TempDir::new().unwrap();
+                let storage = JsonlStorage::new(temp_dir.path().join("test.jsonl"));
+                storage.initialize().unwrap();
+                let model = MemoryStore::new();
+
+                for op in &ops {
+                    let result = apply(&storage, op);
+                    prop_assert_eq!(result.is_ok(), apply(&model, op).is_ok(), "{:?}", op);
+
+                    let report = storage.verify().unwrap();
+                    prop_assert!(report.problems().is_empty(), "{:?}", report);
+                    prop_assert_eq!(report, model.verify().unwrap());
+                    prop_assert_eq!(snapshot(&storage), snapshot(&model));
+                }
+
+                let count = storage.count_lines().unwrap();
+                let ids: Vec<usize> = (0..count + 2).rev().collect();
+                prop_assert_eq!(
+                    serde_json::to_value(storage.read_batch(&ids).unwrap()).unwrap(),
+                    serde_json::to_value(model.read_batch(&ids).unwrap()).unwrap()
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,93 @@
+use crate::storage::ReviewMetadata;
+use crate::storage::verify::MetadataReport;
+use anyhow::Result;
+
+/// Review metadata, one record per vector ID
+///
+/// Records are only ever appended or rewritten in place, so a record's
+/// position is the ID of its vector in the index.
+pub trait MetadataStore: Send + Sync {
+    /// Create the backing storage if it doesn't exist
+    fn initialize(&self) -> Result<()>;
+
+    /// Append a review, returning its vector ID
+    fn append(&self, metadata: &ReviewMetadata) -> Result<usize>;
+
+    /// Append many reviews, returning the vector ID of the first
+    fn append_batch(&self, records: &[ReviewMetadata]) -> Result<usize>;
+
+    fn read_by_id(&self, vector_id: usize) -> Result<ReviewMetadata>;
+
+    /// The reviews for `vector_ids`, in order; out-of-range IDs are skipped
+    fn read_batch(&self, vector_ids: &[usize]) -> Result<Vec<ReviewMetadata>>;
+
+    /// Up to `limit` reviews starting at vector ID `start`
+    fn read_range(&self, start: usize, limit: usize) -> Result<Vec<ReviewMetadata>>;
+
+    /// Number of records, deleted ones included
+    fn count_lines(&self) -> Result<usize>;
+
+    /// Overwrite the record for `vector_id`
+    ///
+    /// Callers must serialize writes; the server does so under the index
+    /// write lock.
+    fn replace(&self, vector_id: usize, metadata: &ReviewMetadata) -> Result<()> {
+        self.replace_many(&[(vector_id, metadata.clone())])
+    }
+
+    /// Overwrite several records at once
+    ///
+    /// Nothing is written if any vector ID is out of range.
+    fn replace_many(&self, updates: &[(usize, ReviewMetadata)]) -> Result<()>;
+
+    /// Tombstone every live record matching `pred`, returning their vector
+    /// IDs
+    ///
+    /// The read and the rewrite are one write, so this is safe without the
+    /// index write lock.
+    fn tombstone_matching(&self, pred: &dyn Fn(&ReviewMetadata) -> bool) -> Result<Vec<usize>>;
+
+    /// Swap in `records` as the whole store, e.g. when restoring a snapshot
+    ///
+    /// Readers see either the old records or the new ones, never a mix.
+    fn replace_all(&self, records: &[ReviewMetadata]) -> Result<()>;
+
+    /// Keep only the first `line_count` records, dropping appends that never
+    /// made it into the index
+    fn truncate(&self, line_count: usize) -> Result<()>;
+
+    /// Flush appended metadata to stable storage
+    fn flush(&self) -> Result<()>;
+
+    fn read_all(&self) -> Result<Vec<ReviewMetadata>>;
+
+    /// Check the stored records are well-formed
+    ///
+    /// Stores that can't hold a malformed record only need to count them.
+    fn verify(&self) -> Result<MetadataReport> {
+        let records = self.read_all()?;
+        Ok(MetadataReport {
+            records: records.len(),
+            deleted: records.iter().filter(|record| record.deleted).count(),
+            ..MetadataReport::default()
+        })
+    }
+}
+
+/// `reviews` that are live and match `pred`, tombstoned with their next
+/// revision
+pub(crate) fn tombstones(
+    reviews: Vec<ReviewMetadata>,
+    pred: &dyn Fn(&ReviewMetadata) -> bool,
+) -> Vec<(usize, ReviewMetadata)> {
+    reviews
+        .into_iter()
+        .enumerate()
+        .filter(|(_, review)| !review.deleted && pred(review))
+        .map(|(id, mut review)| {
+            review.revision += 1;
+            review.deleted = true;
+            (id, review)
+        })
+        .collect()
+}
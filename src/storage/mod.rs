@@ -1,5 +1,27 @@
+pub mod archive;
+pub mod audit;
+pub mod collections;
+pub mod disk;
+#[cfg(feature = "faiss")]
+pub mod faiss;
+pub mod index;
 pub mod jsonl;
+pub mod metadata;
+pub mod purge;
 pub mod spfresh;
+#[cfg(feature = "usearch")]
+pub mod usearch;
+pub mod verify;
 
-pub use jsonl::{JsonlStorage, ReviewMetadata};
-pub use spfresh::VectorIndex;
+pub use audit::{AuditEntry, AuditLog};
+pub use collections::{Collection, CollectionError, CollectionManager, CollectionManifest};
+pub use index::{AnnIndex, DistanceMetric, IndexBackend, SearchResult, VectorIndex};
+pub use jsonl::{first_revision, now_ms, JsonlStorage, ReviewMetadata};
+pub use metadata::MetadataStore;
+pub use purge::{mask_and_save, tombstone_ids, tombstone_where};
+#[cfg(feature = "faiss")]
+pub use faiss::FaissIndex;
+pub use spfresh::SpfreshIndex;
+#[cfg(feature = "usearch")]
+pub use usearch::UsearchIndex;
+pub use verify::{MetadataReport, StorageReport};
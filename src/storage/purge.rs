@@ -0,0 +1,118 @@
+use crate::storage::{MetadataStore, ReviewMetadata, VectorIndex};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::warn;
+use vector_search_client::models::{DeleteOutcome, DeleteStatus};
+
+/// Tombstone every live review matching `pred` in one metadata pass, then
+/// mask their vectors and save the index
+///
+/// Must be called under the index write lock. Returns the tombstoned IDs.
+pub fn tombstone_where(
+    metadata_store: &dyn MetadataStore,
+    index: &mut VectorIndex,
+    index_path: &Path,
+    pred: impl Fn(&ReviewMetadata) -> bool,
+) -> Result<Vec<usize>> {
+    let ids = metadata_store.tombstone_matching(&pred)?;
+    if !ids.is_empty() {
+        mask_and_save(index, index_path, &ids);
+    }
+    Ok(ids)
+}
+
+/// Tombstone the reviews with `vector_ids` in one metadata pass, then mask
+/// their vectors and save the index
+///
+/// Must be called under the index write lock. Returns one outcome per
+/// requested ID, in order.
+pub fn tombstone_ids(
+    metadata_store: &dyn MetadataStore,
+    index: &mut VectorIndex,
+    index_path: &Path,
+    vector_ids: &[usize],
+) -> Result<Vec<DeleteOutcome>> {
+    let mut reviews = metadata_store.read_all()?;
+    let mut updates = BTreeMap::new();
+    let outcomes: Vec<DeleteOutcome> = vector_ids
+        .iter()
+        .map(|&vector_id| {
+            let status = match reviews.get_mut(vector_id) {
+                None => DeleteStatus::NotFound,
+                Some(review) if review.deleted => DeleteStatus::AlreadyDeleted,
+                Some(review) => {
+                    review.revision += 1;
+                    review.deleted = true;
+                    updates.insert(vector_id, review.clone());
+                    DeleteStatus::Deleted
+                }
+            };
+            DeleteOutcome { vector_id, status }
+        })
+        .collect();
+    if updates.is_empty() {
+        return Ok(outcomes);
+    }
+
+    let updates: Vec<(usize, ReviewMetadata)> = updates.into_iter().collect();
+    metadata_store.replace_many(&updates)?;
+
+    let ids: Vec<usize> = updates.into_iter().map(|(id, _)| id).collect();
+    mask_and_save(index, index_path, &ids);
+    Ok(outcomes)
+}
+
+/// Mask tombstoned vectors; the metadata is authoritative, so failures are
+/// only logged
+pub fn mask_and_save(index: &mut VectorIndex, index_path: &Path, ids: &[usize]) {
+    for &id in ids {
+        if let Err(e) = index.delete_vector(id) {
+            warn!(vector_id = id, "Masking tombstoned vector failed: {:#}", e);
+        }
+    }
+    if let Err(e) = index.save(index_path) {
+        warn!("Save index failed, retrying at the next snapshot: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DistanceMetric;
+    use crate::test_utils::{review, MemoryIndex, MemoryStore};
+
+    #[test]
+    fn test_tombstone_ids() {
+        let store = MemoryStore::new();
+        let mut index = VectorIndex::from_index(MemoryIndex::new(2, DistanceMetric::L2));
+        let review = review("Review", "P1");
+        for _ in 0..3 {
+            store.append(&review).unwrap();
+            index.add_vector(&[1.0, 0.0]).unwrap();
+        }
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let index_path = temp_dir.path().join("test.index");
+
+        let outcomes = tombstone_ids(&store, &mut index, &index_path, &[2, 7, 0, 2]).unwrap();
+        let statuses: Vec<DeleteStatus> = outcomes.iter().map(|outcome| outcome.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                DeleteStatus::Deleted,
+                DeleteStatus::NotFound,
+                DeleteStatus::Deleted,
+                DeleteStatus::AlreadyDeleted,
+            ]
+        );
+
+        let reviews = store.read_all().unwrap();
+        assert!(reviews[0].deleted && !reviews[1].deleted && reviews[2].deleted);
+        assert_eq!(reviews[2].revision, 2);
+        let live: Vec<usize> = index.search(&[1.0, 0.0], 3).unwrap().iter().map(|r| r.vector_id).collect();
+        assert_eq!(live, [1]);
+
+        let again = tombstone_ids(&store, &mut index, &index_path, &[0]).unwrap();
+        assert_eq!(again[0].status, DeleteStatus::AlreadyDeleted);
+    }
+}
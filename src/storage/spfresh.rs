@@ -1,21 +1,18 @@
+use crate::storage::archive;
+use crate::storage::index::{AnnIndex, DistanceMetric, IndexBackend, SearchResult};
 use anyhow::Result;
 use std::ffi::CString;
-use std::fs::File;
 use std::os::raw::{c_char, c_float, c_int, c_void};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tracing::{info, warn};
 
-// Archive support for single-file index storage
-use flate2::Compression;
-use flate2::read::GzDecoder;
-use flate2::write::GzEncoder;
-use tar::{Archive, Builder};
-
-/// Search result from vector index
-#[derive(Debug, Clone)]
-pub struct SearchResult {
-    pub vector_id: usize,
-    pub distance: f32,
+/// SPTAG's `DistCalcMethod` value for a metric
+fn dist_calc_method(metric: DistanceMetric) -> &'static str {
+    match metric {
+        DistanceMetric::L2 => "L2",
+        DistanceMetric::Cosine => "Cosine",
+    }
 }
 
 // FFI declarations for C++ wrapper functions
@@ -49,12 +46,23 @@ unsafe extern "C" {
         result_distances: *mut c_float,
     ) -> c_int;
 
+    fn spfresh_get_vector(
+        index: *mut c_void,
+        vector_id: c_int,
+        out: *mut c_float,
+        dimension: c_int,
+    ) -> c_int;
+
+    fn spfresh_delete_vector(index: *mut c_void, vector_id: c_int) -> c_int;
+
     fn spfresh_save_index(index: *mut c_void, folder_path: *const c_char) -> c_int;
 
     fn spfresh_load_index(folder_path: *const c_char) -> *mut c_void;
 
     fn spfresh_get_num_vectors(index: *mut c_void) -> c_int;
 
+    fn spfresh_get_num_deleted(index: *mut c_void) -> c_int;
+
     fn spfresh_get_dimension(index: *mut c_void) -> c_int;
 
     fn spfresh_set_parameter(
@@ -67,24 +75,35 @@ unsafe extern "C" {
 }
 
 /// SPFresh vector index
-pub struct VectorIndex {
+pub struct SpfreshIndex {
     index_type: String,
     vector_dim: usize,
     num_trees: usize,
+    metric: DistanceMetric,
     index_ptr: *mut c_void,
     vector_count: usize,
+    /// Bumped on every mutation
+    revision: u64,
+    /// Revision captured by the last successful save/load
+    saved_revision: AtomicU64,
 }
 
-unsafe impl Send for VectorIndex {}
-unsafe impl Sync for VectorIndex {}
-
-impl VectorIndex {
-    /// Create a new vector index
-    pub fn new(index_type: String, vector_dim: usize, num_trees: usize) -> Self {
+unsafe impl Send for SpfreshIndex {}
+unsafe impl Sync for SpfreshIndex {}
+
+impl SpfreshIndex {
+    /// Create a new, uninitialized index
+    pub fn new(
+        index_type: String,
+        vector_dim: usize,
+        num_trees: usize,
+        metric: DistanceMetric,
+    ) -> Self {
         info!(
             index_type = %index_type,
             vector_dim = vector_dim,
             num_trees = num_trees,
+            metric = ?metric,
             "Creating new vector index"
         );
 
@@ -92,13 +111,72 @@ impl VectorIndex {
             index_type,
             vector_dim,
             num_trees,
+            metric,
             index_ptr: std::ptr::null_mut(),
             vector_count: 0,
+            revision: 0,
+            saved_revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Set a parameter on the index
+    fn set_param(&self, name: &str, value: &str) -> Result<()> {
+        unsafe {
+            let param_name = CString::new(name)?;
+            let param_value = CString::new(value)?;
+
+            let ret = spfresh_set_parameter(
+                self.index_ptr,
+                param_name.as_ptr(),
+                param_value.as_ptr(),
+            );
+
+            if ret != 0 {
+                warn!("Failed to set parameter {}={}", name, value);
+            }
         }
+        Ok(())
+    }
+
+    fn write_archive(&self, path: &Path) -> Result<()> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        info!("Saving index to {:?}", path);
+
+        let temp_dir = archive::temp_dir("spfresh_save")?;
+
+        // Save to temp folder (SPFresh native format)
+        unsafe {
+            let temp_str = temp_dir.to_str().ok_or_else(|| anyhow::anyhow!("Invalid temp path"))?;
+            let temp_cstr = CString::new(temp_str)?;
+
+            let ret = spfresh_save_index(self.index_ptr, temp_cstr.as_ptr());
+
+            if ret != 0 {
+                std::fs::remove_dir_all(&temp_dir)?;
+                anyhow::bail!("Failed to save index to temp folder");
+            }
+        }
+
+        // Create tar.gz archive from temp folder
+        archive::pack(&temp_dir, path)?;
+
+        // Cleanup temp folder
+        std::fs::remove_dir_all(&temp_dir)?;
+
+        Ok(())
+    }
+}
+
+impl AnnIndex for SpfreshIndex {
+    fn backend(&self) -> IndexBackend {
+        IndexBackend::Spfresh
     }
 
     /// Initialize the index
-    pub fn initialize(&mut self) -> Result<()> {
+    fn initialize(&mut self) -> Result<()> {
         info!("Initializing SPFresh vector index");
 
         unsafe {
@@ -116,7 +194,7 @@ impl VectorIndex {
             }
 
             // Set index parameters
-            self.set_param("DistCalcMethod", "L2")?;
+            self.set_param("DistCalcMethod", dist_calc_method(self.metric))?;
             self.set_param("NumberOfThreads", "4")?;
             
             // BKT/KDT specific parameters
@@ -133,28 +211,9 @@ impl VectorIndex {
         Ok(())
     }
 
-    /// Set a parameter on the index
-    fn set_param(&self, name: &str, value: &str) -> Result<()> {
-        unsafe {
-            let param_name = CString::new(name)?;
-            let param_value = CString::new(value)?;
-
-            let ret = spfresh_set_parameter(
-                self.index_ptr,
-                param_name.as_ptr(),
-                param_value.as_ptr(),
-            );
-
-            if ret != 0 {
-                warn!("Failed to set parameter {}={}", name, value);
-            }
-        }
-        Ok(())
-    }
-
     /// Add a vector to the index
     /// Returns the vector ID (sequential, starting from 0)
-    pub fn add_vector(&mut self, vector: &[f32]) -> Result<usize> {
+    fn add_vector(&mut self, vector: &[f32]) -> Result<usize> {
         if vector.len() != self.vector_dim {
             anyhow::bail!(
                 "Vector dimension mismatch: expected {}, got {}",
@@ -179,6 +238,7 @@ impl VectorIndex {
             }
 
             self.vector_count += 1;
+            self.revision += 1;
             info!(vector_id = vector_id, total = self.vector_count, "Added vector to index");
 
             Ok(vector_id as usize)
@@ -186,7 +246,7 @@ impl VectorIndex {
     }
 
     /// Build index from vectors (more efficient than adding one-by-one)
-    pub fn build_from_vectors(&mut self, vectors: &[Vec<f32>]) -> Result<()> {
+    fn build_from_vectors(&mut self, vectors: &[Vec<f32>]) -> Result<()> {
         if vectors.is_empty() {
             return Ok(());
         }
@@ -219,6 +279,7 @@ impl VectorIndex {
             }
 
             self.vector_count = num_vectors;
+            self.revision += 1;
             info!(num_vectors = num_vectors, "Built index from vectors");
         }
 
@@ -226,7 +287,7 @@ impl VectorIndex {
     }
 
     /// Search for k-nearest neighbors
-    pub fn search(&self, query_vector: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+    fn search(&self, query_vector: &[f32], k: usize) -> Result<Vec<SearchResult>> {
         if query_vector.len() != self.vector_dim {
             anyhow::bail!(
                 "Query vector dimension mismatch: expected {}, got {}",
@@ -269,76 +330,80 @@ impl VectorIndex {
         }
     }
 
-    /// Save index to a single tar.gz file
-    pub fn save(&self, path: &Path) -> Result<()> {
+    /// Copy a stored vector out of the index
+    ///
+    /// Returns the vector as the index holds it (SPTAG normalizes vectors
+    /// for cosine distance).
+    fn get_vector(&self, vector_id: usize) -> Result<Vec<f32>> {
         if self.index_ptr.is_null() {
             anyhow::bail!("Index not initialized");
         }
-
-        info!("Saving index to {:?}", path);
-
-        // Create temp directory in /tmp (outside of data/)
-        let temp_dir = std::env::temp_dir().join(format!(
-            "spfresh_save_{}",
-            std::process::id()
-        ));
-        if temp_dir.exists() {
-            std::fs::remove_dir_all(&temp_dir)?;
+        if vector_id >= self.vector_count {
+            anyhow::bail!("Vector {} out of range ({} vectors)", vector_id, self.vector_count);
         }
-        std::fs::create_dir_all(&temp_dir)?;
 
-        // Save to temp folder (SPFresh native format)
-        unsafe {
-            let temp_str = temp_dir.to_str().ok_or_else(|| anyhow::anyhow!("Invalid temp path"))?;
-            let temp_cstr = CString::new(temp_str)?;
+        let mut vector = vec![0.0f32; self.vector_dim];
+        let ret = unsafe {
+            spfresh_get_vector(
+                self.index_ptr,
+                vector_id as c_int,
+                vector.as_mut_ptr(),
+                self.vector_dim as c_int,
+            )
+        };
+        if ret != 0 {
+            anyhow::bail!("Failed to read vector {}", vector_id);
+        }
+        Ok(vector)
+    }
 
-            let ret = spfresh_save_index(self.index_ptr, temp_cstr.as_ptr());
+    /// Mark a vector deleted so searches skip it
+    ///
+    /// The slot is kept (and still counted), so later vector IDs don't move.
+    fn delete_vector(&mut self, vector_id: usize) -> Result<()> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+        if vector_id >= self.vector_count {
+            anyhow::bail!("Vector {} out of range ({} vectors)", vector_id, self.vector_count);
+        }
 
-            if ret != 0 {
-                std::fs::remove_dir_all(&temp_dir)?;
-                anyhow::bail!("Failed to save index to temp folder");
-            }
+        let ret = unsafe { spfresh_delete_vector(self.index_ptr, vector_id as c_int) };
+        if ret != 0 {
+            anyhow::bail!("Failed to delete vector {}", vector_id);
         }
 
-        // Create tar.gz archive from temp folder
-        let archive_file = File::create(path)?;
-        let encoder = GzEncoder::new(archive_file, Compression::default());
-        let mut tar = Builder::new(encoder);
-        
-        tar.append_dir_all(".", &temp_dir)?;
-        tar.finish()?;
+        self.revision += 1;
+        info!(vector_id, "Deleted vector from index");
+        Ok(())
+    }
 
-        // Cleanup temp folder
-        std::fs::remove_dir_all(&temp_dir)?;
+    /// Save index to a single tar.gz file
+    fn save(&self, path: &Path) -> Result<()> {
+        self.write_archive(path)?;
 
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
         info!("✅ Index saved successfully to single file");
 
         Ok(())
     }
 
+    /// Write a copy of the index (e.g. for a replica) without marking it saved
+    fn export_archive(&self, path: &Path) -> Result<()> {
+        self.write_archive(path)
+    }
+
     /// Load index from a single tar.gz file
-    pub fn load(&mut self, path: &Path) -> Result<()> {
+    fn load(&mut self, path: &Path) -> Result<()> {
         if !path.exists() {
             anyhow::bail!("Index path does not exist: {:?}", path);
         }
 
         info!("Loading index from {:?}", path);
 
-        // Create temp directory in /tmp (outside of data/)
-        let temp_dir = std::env::temp_dir().join(format!(
-            "spfresh_load_{}",
-            std::process::id()
-        ));
-        if temp_dir.exists() {
-            std::fs::remove_dir_all(&temp_dir)?;
-        }
-        std::fs::create_dir_all(&temp_dir)?;
-
-        // Extract tar.gz archive to temp folder
-        let archive_file = File::open(path)?;
-        let decoder = GzDecoder::new(archive_file);
-        let mut tar = Archive::new(decoder);
-        tar.unpack(&temp_dir)?;
+        // Extract tar.gz archive to a temp folder
+        let temp_dir = archive::temp_dir("spfresh_load")?;
+        archive::unpack(path, &temp_dir)?;
 
         // Load from temp folder (SPFresh native format)
         unsafe {
@@ -358,6 +423,8 @@ impl VectorIndex {
             }
 
             self.index_ptr = new_ptr;
+            self.revision += 1;
+            self.saved_revision.store(self.revision, Ordering::SeqCst);
 
             // Update stats
             let num_vectors = spfresh_get_num_vectors(self.index_ptr);
@@ -388,12 +455,31 @@ impl VectorIndex {
     }
 
     /// Get number of vectors in the index
-    pub fn vector_count(&self) -> usize {
+    fn vector_count(&self) -> usize {
         self.vector_count
     }
+
+    /// Deleted vectors keep their slot until the index is rebuilt
+    fn live_count(&self) -> usize {
+        if self.index_ptr.is_null() {
+            return 0;
+        }
+        let deleted = unsafe { spfresh_get_num_deleted(self.index_ptr) };
+        self.vector_count.saturating_sub(deleted.max(0) as usize)
+    }
+
+    /// Get the vector dimension
+    fn dimension(&self) -> usize {
+        self.vector_dim
+    }
+
+    /// Whether the index has changed since it was last saved or loaded
+    fn is_dirty(&self) -> bool {
+        self.saved_revision.load(Ordering::SeqCst) != self.revision
+    }
 }
 
-impl Drop for VectorIndex {
+impl Drop for SpfreshIndex {
     fn drop(&mut self) {
         if !self.index_ptr.is_null() {
             unsafe {
@@ -0,0 +1,481 @@
+use crate::storage::archive;
+use crate::storage::index::{AnnIndex, DistanceMetric, IndexBackend, SearchResult};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_float, c_int, c_void};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{info, warn};
+
+/// Files inside the index archive
+const INDEX_FILE: &str = "usearch.index";
+const STATE_FILE: &str = "state.json";
+
+/// Capacity reserved up front; doubled whenever it fills
+const MIN_CAPACITY: usize = 1024;
+
+/// `usearch_metric_kind_t` values
+const METRIC_COS: c_int = 1;
+const METRIC_L2SQ: c_int = 3;
+
+/// `usearch_scalar_kind_t` value for f32
+const SCALAR_F32: c_int = 1;
+
+type Error = *const c_char;
+
+/// `usearch_init_options_t`
+#[repr(C)]
+struct InitOptions {
+    metric_kind: c_int,
+    metric: *const c_void,
+    quantization: c_int,
+    dimensions: usize,
+    connectivity: usize,
+    expansion_add: usize,
+    expansion_search: usize,
+    multi: bool,
+}
+
+// FFI declarations for the USearch C API (libusearch_c)
+#[link(name = "usearch_c")]
+unsafe extern "C" {
+    fn usearch_init(options: *mut InitOptions, error: *mut Error) -> *mut c_void;
+
+    fn usearch_free(index: *mut c_void, error: *mut Error);
+
+    fn usearch_save(index: *mut c_void, path: *const c_char, error: *mut Error);
+
+    fn usearch_load(index: *mut c_void, path: *const c_char, error: *mut Error);
+
+    fn usearch_size(index: *mut c_void, error: *mut Error) -> usize;
+
+    fn usearch_capacity(index: *mut c_void, error: *mut Error) -> usize;
+
+    fn usearch_dimensions(index: *mut c_void, error: *mut Error) -> usize;
+
+    fn usearch_reserve(index: *mut c_void, capacity: usize, error: *mut Error);
+
+    fn usearch_add(
+        index: *mut c_void,
+        key: u64,
+        vector: *const c_void,
+        vector_kind: c_int,
+        error: *mut Error,
+    );
+
+    fn usearch_search(
+        index: *mut c_void,
+        query: *const c_void,
+        query_kind: c_int,
+        count: usize,
+        keys: *mut u64,
+        distances: *mut c_float,
+        error: *mut Error,
+    ) -> usize;
+
+    fn usearch_get(
+        index: *mut c_void,
+        key: u64,
+        count: usize,
+        vector: *mut c_void,
+        vector_kind: c_int,
+        error: *mut Error,
+    ) -> usize;
+
+    fn usearch_remove(index: *mut c_void, key: u64, error: *mut Error) -> usize;
+
+    fn usearch_change_expansion_search(index: *mut c_void, expansion: usize, error: *mut Error);
+}
+
+/// Call a USearch function, turning its error out-parameter into a Result
+fn call<T>(what: &str, f: impl FnOnce(*mut Error) -> T) -> Result<T> {
+    let mut error: Error = std::ptr::null();
+    let value = f(&mut error);
+    if error.is_null() {
+        return Ok(value);
+    }
+    let message = unsafe { CStr::from_ptr(error) }.to_string_lossy();
+    anyhow::bail!("{}: {}", what, message)
+}
+
+/// Saved next to the USearch file: slots don't shrink when vectors are
+/// removed, but USearch's own size does
+#[derive(Debug, Serialize, Deserialize)]
+struct State {
+    next_id: usize,
+}
+
+/// USearch HNSW vector index
+///
+/// Vectors are keyed by their vector ID; deleting one removes it from the
+/// graph but keeps its slot counted.
+pub struct UsearchIndex {
+    vector_dim: usize,
+    metric: DistanceMetric,
+    /// Search-time expansion (USearch's `ef`); 0 keeps the library default
+    expansion_search: usize,
+    index_ptr: *mut c_void,
+    next_id: usize,
+    /// Bumped on every mutation
+    revision: u64,
+    /// Revision captured by the last successful save/load
+    saved_revision: AtomicU64,
+}
+
+unsafe impl Send for UsearchIndex {}
+unsafe impl Sync for UsearchIndex {}
+
+impl UsearchIndex {
+    /// Create a new, uninitialized index
+    ///
+    /// `search_params` may set `expansion_search=<n>`.
+    pub fn new(vector_dim: usize, metric: DistanceMetric, search_params: Option<&str>) -> Self {
+        let expansion_search = search_params
+            .into_iter()
+            .flat_map(|params| params.split(','))
+            .filter_map(|param| param.trim().strip_prefix("expansion_search="))
+            .find_map(|value| value.parse().ok())
+            .unwrap_or(0);
+        info!(
+            vector_dim = vector_dim,
+            metric = ?metric,
+            expansion_search = expansion_search,
+            "Creating new USearch index"
+        );
+
+        Self {
+            vector_dim,
+            metric,
+            expansion_search,
+            index_ptr: std::ptr::null_mut(),
+            next_id: 0,
+            revision: 0,
+            saved_revision: AtomicU64::new(0),
+        }
+    }
+
+    /// A new, empty USearch index for the configured settings
+    fn create(&self) -> Result<*mut c_void> {
+        let mut options = InitOptions {
+            metric_kind: match self.metric {
+                DistanceMetric::L2 => METRIC_L2SQ,
+                DistanceMetric::Cosine => METRIC_COS,
+            },
+            metric: std::ptr::null(),
+            quantization: SCALAR_F32,
+            dimensions: self.vector_dim,
+            // 0 picks USearch's defaults
+            connectivity: 0,
+            expansion_add: 0,
+            expansion_search: self.expansion_search,
+            multi: false,
+        };
+        let index_ptr = call("Failed to create USearch index", |error| unsafe {
+            usearch_init(&mut options, error)
+        })?;
+        if index_ptr.is_null() {
+            anyhow::bail!("Failed to create USearch index");
+        }
+        Ok(index_ptr)
+    }
+
+    fn replace(&mut self, index_ptr: *mut c_void) {
+        if !self.index_ptr.is_null() {
+            let _ = call("Failed to free USearch index", |error| unsafe {
+                usearch_free(self.index_ptr, error)
+            });
+        }
+        self.index_ptr = index_ptr;
+    }
+
+    /// Make room for `additional` more vectors
+    fn reserve(&mut self, additional: usize) -> Result<()> {
+        let capacity = call("Failed to read capacity", |error| unsafe {
+            usearch_capacity(self.index_ptr, error)
+        })?;
+        let size = call("Failed to read size", |error| unsafe { usearch_size(self.index_ptr, error) })?;
+        if size + additional > capacity {
+            let capacity = (size + additional).max(capacity * 2).max(MIN_CAPACITY);
+            call("Failed to reserve capacity", |error| unsafe {
+                usearch_reserve(self.index_ptr, capacity, error)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn check_dimension(&self, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.vector_dim {
+            anyhow::bail!(
+                "Vector dimension mismatch: expected {}, got {}",
+                self.vector_dim,
+                vector.len()
+            );
+        }
+        Ok(())
+    }
+
+    fn write_archive(&self, path: &Path) -> Result<()> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        info!("Saving index to {:?}", path);
+
+        let temp_dir = archive::temp_dir("usearch_save")?;
+        let result = (|| {
+            let index_file = temp_dir.join(INDEX_FILE);
+            let index_cstr = CString::new(index_file.to_str().context("Invalid temp path")?)?;
+            call("Failed to save USearch index", |error| unsafe {
+                usearch_save(self.index_ptr, index_cstr.as_ptr(), error)
+            })?;
+            let state = State { next_id: self.next_id };
+            std::fs::write(temp_dir.join(STATE_FILE), serde_json::to_vec(&state)?)?;
+            archive::pack(&temp_dir, path)
+        })();
+
+        // Cleanup temp folder
+        std::fs::remove_dir_all(&temp_dir)?;
+        result
+    }
+}
+
+impl AnnIndex for UsearchIndex {
+    fn backend(&self) -> IndexBackend {
+        IndexBackend::Usearch
+    }
+
+    fn initialize(&mut self) -> Result<()> {
+        info!("Initializing USearch index");
+
+        let index_ptr = self.create()?;
+        self.replace(index_ptr);
+        self.next_id = 0;
+        self.reserve(MIN_CAPACITY)?;
+
+        info!("✅ USearch index initialized successfully");
+        Ok(())
+    }
+
+    fn add_vector(&mut self, vector: &[f32]) -> Result<usize> {
+        self.check_dimension(vector)?;
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        self.reserve(1)?;
+        let vector_id = self.next_id;
+        call("Failed to add vector to index", |error| unsafe {
+            usearch_add(self.index_ptr, vector_id as u64, vector.as_ptr().cast(), SCALAR_F32, error)
+        })?;
+
+        self.next_id += 1;
+        self.revision += 1;
+        info!(vector_id = vector_id, total = self.next_id, "Added vector to index");
+        Ok(vector_id)
+    }
+
+    fn build_from_vectors(&mut self, vectors: &[Vec<f32>]) -> Result<()> {
+        if vectors.is_empty() {
+            return Ok(());
+        }
+        for vector in vectors {
+            self.check_dimension(vector)?;
+        }
+
+        // Start from an empty index so IDs are 0..n
+        self.initialize()?;
+        self.reserve(vectors.len())?;
+        for (vector_id, vector) in vectors.iter().enumerate() {
+            call("Failed to build index", |error| unsafe {
+                usearch_add(self.index_ptr, vector_id as u64, vector.as_ptr().cast(), SCALAR_F32, error)
+            })?;
+        }
+
+        self.next_id = vectors.len();
+        self.revision += 1;
+        info!(num_vectors = vectors.len(), "Built index from vectors");
+        Ok(())
+    }
+
+    fn search(&self, query_vector: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        self.check_dimension(query_vector)?;
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+
+        let mut keys = vec![0u64; k];
+        let mut distances = vec![0.0f32; k];
+        let count = call("Search failed", |error| unsafe {
+            usearch_search(
+                self.index_ptr,
+                query_vector.as_ptr().cast(),
+                SCALAR_F32,
+                k,
+                keys.as_mut_ptr(),
+                distances.as_mut_ptr(),
+                error,
+            )
+        })?;
+
+        // USearch's cosine distance is 1 - cosine similarity, like SPTAG's
+        let results: Vec<SearchResult> = keys
+            .iter()
+            .zip(&distances)
+            .take(count)
+            .map(|(&key, &distance)| SearchResult {
+                vector_id: key as usize,
+                distance,
+            })
+            .collect();
+
+        info!(query_results = count, k = k, "Search completed");
+        Ok(results)
+    }
+
+    fn get_vector(&self, vector_id: usize) -> Result<Vec<f32>> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+        if vector_id >= self.next_id {
+            anyhow::bail!("Vector {} out of range ({} vectors)", vector_id, self.next_id);
+        }
+
+        let mut vector = vec![0.0f32; self.vector_dim];
+        let found = call(&format!("Failed to read vector {}", vector_id), |error| unsafe {
+            usearch_get(
+                self.index_ptr,
+                vector_id as u64,
+                1,
+                vector.as_mut_ptr().cast(),
+                SCALAR_F32,
+                error,
+            )
+        })?;
+        if found == 0 {
+            anyhow::bail!("Vector {} was deleted", vector_id);
+        }
+        Ok(vector)
+    }
+
+    fn delete_vector(&mut self, vector_id: usize) -> Result<()> {
+        if self.index_ptr.is_null() {
+            anyhow::bail!("Index not initialized");
+        }
+        if vector_id >= self.next_id {
+            anyhow::bail!("Vector {} out of range ({} vectors)", vector_id, self.next_id);
+        }
+
+        call(&format!("Failed to delete vector {}", vector_id), |error| unsafe {
+            usearch_remove(self.index_ptr, vector_id as u64, error)
+        })?;
+
+        self.revision += 1;
+        info!(vector_id, "Deleted vector from index");
+        Ok(())
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        self.write_archive(path)?;
+
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
+        info!("✅ Index saved successfully to single file");
+        Ok(())
+    }
+
+    fn export_archive(&self, path: &Path) -> Result<()> {
+        self.write_archive(path)
+    }
+
+    fn load(&mut self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            anyhow::bail!("Index path does not exist: {:?}", path);
+        }
+
+        info!("Loading index from {:?}", path);
+
+        let temp_dir = archive::temp_dir("usearch_load")?;
+        let loaded = (|| {
+            archive::unpack(path, &temp_dir)?;
+            let state: State = serde_json::from_slice(
+                &std::fs::read(temp_dir.join(STATE_FILE))
+                    .context("Not a USearch index archive (was it saved by another backend?)")?,
+            )?;
+
+            let index_ptr = self.create()?;
+            let index_file = temp_dir.join(INDEX_FILE);
+            let index_cstr = CString::new(index_file.to_str().context("Invalid temp path")?)?;
+            if let Err(e) = call("Failed to load USearch index", |error| unsafe {
+                usearch_load(index_ptr, index_cstr.as_ptr(), error)
+            }) {
+                let _ = call("Failed to free USearch index", |error| unsafe {
+                    usearch_free(index_ptr, error)
+                });
+                return Err(e);
+            }
+            anyhow::Ok((index_ptr, state))
+        })();
+
+        // Cleanup temp folder
+        std::fs::remove_dir_all(&temp_dir)?;
+        let (index_ptr, state) = loaded?;
+
+        self.replace(index_ptr);
+        self.next_id = state.next_id;
+        self.revision += 1;
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
+
+        let dimension = call("Failed to read dimensions", |error| unsafe {
+            usearch_dimensions(self.index_ptr, error)
+        })?;
+        if dimension != self.vector_dim {
+            warn!(
+                "Loaded index dimension ({}) differs from configured ({})",
+                dimension, self.vector_dim
+            );
+            self.vector_dim = dimension;
+        }
+        if self.expansion_search > 0 {
+            // Not kept in the saved file
+            call("Failed to set expansion_search", |error| unsafe {
+                usearch_change_expansion_search(self.index_ptr, self.expansion_search, error)
+            })?;
+        }
+
+        info!(
+            num_vectors = self.next_id,
+            dimension = self.vector_dim,
+            "✅ Index loaded successfully from single file"
+        );
+        Ok(())
+    }
+
+    fn vector_count(&self) -> usize {
+        self.next_id
+    }
+
+    fn live_count(&self) -> usize {
+        if self.index_ptr.is_null() {
+            return 0;
+        }
+        call("Failed to read index size", |error| unsafe { usearch_size(self.index_ptr, error) })
+            .unwrap_or(self.next_id)
+    }
+
+    fn dimension(&self) -> usize {
+        self.vector_dim
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.saved_revision.load(Ordering::SeqCst) != self.revision
+    }
+}
+
+impl Drop for UsearchIndex {
+    fn drop(&mut self) {
+        if !self.index_ptr.is_null() {
+            self.replace(std::ptr::null_mut());
+            info!("USearch index destroyed");
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Structural checks of the stored index and metadata, used by the `verify`
+//! command and by the storage tests
+
+use crate::storage::{AnnIndex, MetadataStore};
+use anyhow::Result;
+use serde::Serialize;
+
+/// A metadata record that doesn't parse
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvalidRecord {
+    /// 0-based, so also the vector ID the record belongs to
+    pub line: usize,
+    pub error: String,
+}
+
+/// What a metadata store holds, and anything structurally wrong with it
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MetadataReport {
+    /// Records, deleted and unparsable ones included
+    pub records: usize,
+    /// Tombstoned records
+    pub deleted: usize,
+    pub invalid: Vec<InvalidRecord>,
+    /// The last record isn't newline-terminated (an interrupted append)
+    pub torn_tail: bool,
+}
+
+impl MetadataReport {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .invalid
+            .iter()
+            .map(|record| format!("metadata line {}: {}", record.line + 1, record.error))
+            .collect();
+        if self.torn_tail {
+            problems.push("metadata ends in a partially written line".to_string());
+        }
+        problems
+    }
+}
+
+/// The index and metadata checked against each other
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageReport {
+    pub metadata: MetadataReport,
+    pub index_vectors: usize,
+    pub index_dimension: usize,
+}
+
+impl StorageReport {
+    /// Everything wrong, given the dimension the index should have
+    pub fn problems(&self, expected_dimension: usize) -> Vec<String> {
+        let mut problems = self.metadata.problems();
+        if self.index_dimension != expected_dimension {
+            problems.push(format!(
+                "index dimension {} differs from configured {}",
+                self.index_dimension, expected_dimension
+            ));
+        }
+        if self.index_vectors != self.metadata.records {
+            problems.push(format!(
+                "index has {} vectors but metadata has {} reviews",
+                self.index_vectors, self.metadata.records
+            ));
+        }
+        problems
+    }
+}
+
+/// Check `metadata` and that `index` holds one vector per record
+pub fn verify(index: &dyn AnnIndex, metadata: &dyn MetadataStore) -> Result<StorageReport> {
+    Ok(StorageReport {
+        metadata: metadata.verify()?,
+        index_vectors: index.vector_count(),
+        index_dimension: index.dimension(),
+    })
+}
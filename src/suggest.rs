@@ -0,0 +1,239 @@
+//! Search-box autocompletion: review titles and product IDs by prefix
+//!
+//! Every title is indexed from the start of each of its words, so "bat"
+//! completes "Great battery life" as well as "Battery died". Keys are kept
+//! in a sorted set, which turns a prefix lookup into a range scan.
+
+use crate::storage::{now_ms, ReviewMetadata};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::RwLock;
+use tracing::info;
+use vector_search_client::models::{Suggestion, SuggestionKind};
+
+/// Keys scanned for one lookup; very short prefixes stop here and rank
+/// what they found
+const MAX_SCANNED_KEYS: usize = 10_000;
+
+/// Distinct titles and product IDs of the live reviews, by prefix
+#[derive(Debug, Default)]
+pub struct SuggestIndex {
+    /// (kind, lower-cased text) -> suggestion, as first seen
+    entries: HashMap<(SuggestionKind, String), Suggestion>,
+    /// (lower-cased key, kind, lower-cased text); a title has one key per word
+    keys: BTreeSet<(String, SuggestionKind, String)>,
+}
+
+impl SuggestIndex {
+    pub fn from_reviews(reviews: &[ReviewMetadata]) -> Self {
+        let now = now_ms();
+        let mut index = Self::default();
+        for review in reviews.iter().filter(|review| review.is_live(now)) {
+            index.add(review);
+        }
+        index
+    }
+
+    pub fn add(&mut self, review: &ReviewMetadata) {
+        for (kind, text) in completions(review) {
+            let folded = text.to_lowercase();
+            let entry = self
+                .entries
+                .entry((kind, folded.clone()))
+                .or_insert_with(|| Suggestion {
+                    text: text.to_string(),
+                    kind,
+                    count: 0,
+                });
+            entry.count += 1;
+            if entry.count == 1 {
+                for key in keys(kind, &folded) {
+                    self.keys.insert((key.to_string(), kind, folded.clone()));
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, review: &ReviewMetadata) {
+        for (kind, text) in completions(review) {
+            let folded = text.to_lowercase();
+            let Some(entry) = self.entries.get_mut(&(kind, folded.clone())) else {
+                continue;
+            };
+            entry.count -= 1;
+            if entry.count == 0 {
+                self.entries.remove(&(kind, folded.clone()));
+                for key in keys(kind, &folded) {
+                    self.keys.remove(&(key.to_string(), kind, folded.clone()));
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Up to `limit` completions of `prefix`, most reviews first
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let prefix = prefix.trim_start().to_lowercase();
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let start = (prefix.clone(), SuggestionKind::Title, String::new());
+        let mut seen = HashSet::new();
+        let mut matches: Vec<&Suggestion> = self
+            .keys
+            .range(start..)
+            .take(MAX_SCANNED_KEYS)
+            .take_while(|(key, _, _)| key.starts_with(&prefix))
+            .filter(|(_, kind, text)| seen.insert((*kind, text.as_str())))
+            .filter_map(|(_, kind, text)| self.entries.get(&(*kind, text.clone())))
+            .collect();
+        matches.sort_by(|a, b| {
+            (Reverse(a.count), &a.text, a.kind).cmp(&(Reverse(b.count), &b.text, b.kind))
+        });
+        matches.into_iter().take(limit).cloned().collect()
+    }
+}
+
+/// A store's [`SuggestIndex`], built from its reviews on first use and kept
+/// up to date as reviews are added and deleted
+#[derive(Debug, Default)]
+pub struct Suggester {
+    index: RwLock<Option<SuggestIndex>>,
+}
+
+impl Suggester {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Completions of `prefix`, building the index from `load` if this is
+    /// the first lookup
+    pub fn suggest(
+        &self,
+        prefix: &str,
+        limit: usize,
+        load: impl FnOnce() -> anyhow::Result<Vec<ReviewMetadata>>,
+    ) -> anyhow::Result<Vec<Suggestion>> {
+        if let Some(index) = self.index.read().unwrap().as_ref() {
+            return Ok(index.suggest(prefix, limit));
+        }
+
+        let mut slot = self.index.write().unwrap();
+        let index = match &mut *slot {
+            Some(index) => index,
+            None => {
+                let index = SuggestIndex::from_reviews(&load()?);
+                info!(entries = index.len(), "🔤 Suggestion index built");
+                slot.insert(index)
+            }
+        };
+        Ok(index.suggest(prefix, limit))
+    }
+
+    /// Index a new review; a no-op until the index is built
+    pub fn observe(&self, review: &ReviewMetadata) {
+        if let Some(index) = self.index.write().unwrap().as_mut() {
+            index.add(review);
+        }
+    }
+
+    /// Unindex a tombstoned review
+    pub fn forget(&self, review: &ReviewMetadata) {
+        if let Some(index) = self.index.write().unwrap().as_mut() {
+            index.remove(review);
+        }
+    }
+
+    /// Drop the index after a bulk delete; the next lookup rebuilds it
+    pub fn invalidate(&self) {
+        *self.index.write().unwrap() = None;
+    }
+}
+
+/// What `review` can be completed to
+fn completions(review: &ReviewMetadata) -> impl Iterator<Item = (SuggestionKind, &str)> {
+    [
+        (SuggestionKind::Title, review.review_title.trim()),
+        (SuggestionKind::ProductId, review.product_id.trim()),
+    ]
+    .into_iter()
+    .filter(|(_, text)| !text.is_empty())
+}
+
+/// Where a lookup may enter `text`: the start of every word of a title, the
+/// whole of a product ID
+fn keys(kind: SuggestionKind, text: &str) -> Vec<&str> {
+    match kind {
+        SuggestionKind::ProductId => vec![text],
+        SuggestionKind::Title => text
+            .char_indices()
+            .filter(|&(i, c)| {
+                c.is_alphanumeric()
+                    && text[..i].chars().next_back().is_none_or(|prev| !prev.is_alphanumeric())
+            })
+            .map(|(i, _)| &text[i..])
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn review(title: &str, product_id: &str) -> ReviewMetadata {
+        ReviewMetadata {
+            review_body: String::new(),
+            review_rating: 5,
+            ..test_utils::review(title, product_id)
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut index = SuggestIndex::from_reviews(&[
+            review("Great battery life", "B0BATTERY"),
+            review("Battery died", "B0BATTERY"),
+            review("great BATTERY life", "B0CASE"),
+        ]);
+
+        let texts = |suggestions: Vec<Suggestion>| {
+            suggestions
+                .into_iter()
+                .map(|s| (s.text, s.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts(index.suggest("bat", 10)),
+            vec![
+                ("Great battery life".to_string(), 2),
+                ("Battery died".to_string(), 1),
+            ]
+        );
+        assert_eq!(texts(index.suggest(" Great b", 1)), vec![("Great battery life".to_string(), 2)]);
+        assert_eq!(
+            texts(index.suggest("b0", 10)),
+            vec![("B0BATTERY".to_string(), 2), ("B0CASE".to_string(), 1)]
+        );
+        assert!(index.suggest("ttery", 10).is_empty());
+        assert!(index.suggest("", 10).is_empty());
+
+        index.remove(&review("Battery died", "B0BATTERY"));
+        assert_eq!(
+            texts(index.suggest("b", 10)),
+            vec![
+                ("Great battery life".to_string(), 2),
+                ("B0BATTERY".to_string(), 1),
+                ("B0CASE".to_string(), 1),
+            ]
+        );
+    }
+}
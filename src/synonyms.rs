@@ -0,0 +1,140 @@
+//! Synonym groups for query expansion, loaded from `storage.synonyms_path`
+//!
+//! One group of equivalent terms per line, comma-separated; terms may be
+//! phrases. Blank lines and lines starting with `#` are skipped:
+//!
+//! ```text
+//! # brand nicknames
+//! airpods, apple earbuds
+//! tv, television
+//! ```
+
+use anyhow::Context;
+use std::path::Path;
+
+/// Most query variants embedded for one fused search, the query included
+pub const MAX_QUERY_VARIANTS: usize = 8;
+
+#[derive(Debug, Default)]
+pub struct Synonyms {
+    /// Each group's terms, lower-cased and split into words
+    groups: Vec<Vec<Vec<String>>>,
+}
+
+impl Synonyms {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read synonyms file {}", path.display()))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let groups = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split(',')
+                    .map(words)
+                    .filter(|term| !term.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| group.len() > 1)
+            .collect();
+        Self { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The query followed by the synonyms of every term it contains, or
+    /// `None` when nothing matched
+    pub fn append(&self, query: &str) -> Option<String> {
+        let query_words = words(query);
+        let extra: Vec<String> = self
+            .matches(&query_words)
+            .flat_map(|(_, group)| group.iter())
+            .filter(|term| find(&query_words, term).is_none())
+            .map(|term| term.join(" "))
+            .collect();
+        (!extra.is_empty()).then(|| format!("{} {}", query, extra.join(" ")))
+    }
+
+    /// Rewrites of the query with one matched term swapped for a synonym,
+    /// at most `limit` of them
+    pub fn variants(&self, query: &str, limit: usize) -> Vec<String> {
+        let query_words = words(query);
+        let mut variants = Vec::new();
+        for (start, group) in self.matches(&query_words) {
+            let matched = group
+                .iter()
+                .find(|term| find(&query_words, term) == Some(start))
+                .map_or(0, Vec::len);
+            for synonym in group.iter().filter(|term| find(&query_words, term).is_none()) {
+                let mut rewritten = query_words[..start].to_vec();
+                rewritten.extend(synonym.iter().cloned());
+                rewritten.extend(query_words[start + matched..].iter().cloned());
+                variants.push(rewritten.join(" "));
+            }
+        }
+        variants.dedup();
+        variants.truncate(limit);
+        variants
+    }
+
+    /// Groups with a term in `query_words`, with where that term starts
+    fn matches<'a>(
+        &'a self,
+        query_words: &'a [String],
+    ) -> impl Iterator<Item = (usize, &'a Vec<Vec<String>>)> + 'a {
+        self.groups.iter().filter_map(move |group| {
+            group
+                .iter()
+                .find_map(|term| find(query_words, term))
+                .map(|start| (start, group))
+        })
+    }
+}
+
+/// Lower-cased words of `text`
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Where `term` occurs in `haystack` as whole words
+fn find(haystack: &[String], term: &[String]) -> Option<usize> {
+    if term.is_empty() || term.len() > haystack.len() {
+        return None;
+    }
+    haystack.windows(term.len()).position(|window| window == term)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expansion() {
+        let synonyms = Synonyms::parse("# nicknames\nairpods, apple earbuds\n\ntv, television, telly\nlonely\n");
+        assert_eq!(synonyms.groups.len(), 2);
+
+        assert_eq!(
+            synonyms.append("Cheap AirPods case").as_deref(),
+            Some("Cheap AirPods case apple earbuds")
+        );
+        assert_eq!(synonyms.append("battery life"), None);
+
+        assert_eq!(
+            synonyms.variants("wall mount for apple earbuds", MAX_QUERY_VARIANTS),
+            vec!["wall mount for airpods"]
+        );
+        assert_eq!(
+            synonyms.variants("TV stand", 1),
+            vec!["television stand"]
+        );
+    }
+}
@@ -0,0 +1,588 @@
+//! In-process test harness: a deterministic embedder, an in-memory index
+//! and metadata store, and [`TestApp`], which serves the real router over
+//! them
+//!
+//! Nothing here loads a model or touches the SPFresh index, so handler
+//! tests run end to end without model files or the C++ library at runtime.
+//! Available to this crate's tests, and to others with the `test-utils`
+//! feature.
+
+use crate::api::analytics::SearchAnalytics;
+use crate::api::backpressure::Backpressure;
+use crate::api::group_commit::GroupCommit;
+use crate::api::idempotency::IdempotencyCache;
+use crate::api::search_cache::SearchCache;
+use crate::api::timing::LatencyHistograms;
+use crate::api::jobs::JobRegistry;
+use crate::api::readiness::{ComponentState, Readiness};
+use crate::api::usage::UsageTracker;
+use crate::api::write_gate::WriteGate;
+use crate::api::{build_router, AppState};
+use crate::config::{AppConfig, ConfigHandle};
+use crate::content_hash::ContentHashes;
+use crate::api::qdrant::ids::PointIds;
+use crate::field_vectors::FieldVectors;
+use crate::ingest::IngestQueue;
+use crate::embedding::{EmbeddingProvider, EmbeddingService, ModelCache};
+use crate::logging::LogControl;
+use crate::memory::MemoryWatchdog;
+use crate::redaction::RedactorCache;
+use crate::spelling::SpellChecker;
+use crate::storage::metadata::tombstones;
+use crate::storage::{
+    archive, AnnIndex, AuditLog, CollectionManager, DistanceMetric, IndexBackend, MetadataStore,
+    ReviewMetadata, SearchResult, VectorIndex,
+};
+use crate::suggest::Suggester;
+use crate::synonyms::Synonyms;
+use crate::webhooks::WebhookDispatcher;
+use anyhow::{Context, Result};
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+/// Embeds text as a signed bag of hashed words: the same text always gets
+/// the same unit vector, and texts sharing words land close together
+pub struct HashEmbedder {
+    dimension: usize,
+    seed: u64,
+}
+
+impl HashEmbedder {
+    pub fn new(dimension: usize, seed: u64) -> Self {
+        Self { dimension, seed }
+    }
+
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let hash = fnv1a(self.seed, &word.to_lowercase());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimension as u64) as usize] += sign;
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        } else {
+            // Wordless text still gets a valid unit vector
+            vector[0] = 1.0;
+        }
+        vector
+    }
+}
+
+impl EmbeddingProvider for HashEmbedder {
+    fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.into_iter().map(|text| self.embed(text)).collect())
+    }
+}
+
+/// FNV-1a, seeded; stable across platforms and Rust versions
+fn fnv1a(seed: u64, text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325 ^ seed, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Exact (brute-force) vector index held in memory
+///
+/// Saves to a JSON file, so save/load round trips work too.
+#[derive(Serialize, Deserialize)]
+pub struct MemoryIndex {
+    vector_dim: usize,
+    metric: DistanceMetric,
+    vectors: Vec<Vec<f32>>,
+    deleted: Vec<bool>,
+    /// Bumped on every mutation
+    #[serde(skip)]
+    revision: u64,
+    /// Revision captured by the last successful save/load
+    #[serde(skip)]
+    saved_revision: AtomicU64,
+}
+
+impl MemoryIndex {
+    pub fn new(vector_dim: usize, metric: DistanceMetric) -> Self {
+        Self {
+            vector_dim,
+            metric,
+            vectors: Vec::new(),
+            deleted: Vec::new(),
+            revision: 0,
+            saved_revision: AtomicU64::new(0),
+        }
+    }
+
+    fn check_dimension(&self, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.vector_dim {
+            anyhow::bail!(
+                "Vector dimension mismatch: expected {}, got {}",
+                self.vector_dim,
+                vector.len()
+            );
+        }
+        Ok(())
+    }
+
+    fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self.metric {
+            DistanceMetric::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+            DistanceMetric::Cosine => {
+                let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+                let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt()
+                    * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if norms > 0.0 { 1.0 - dot / norms } else { 1.0 }
+            }
+        }
+    }
+}
+
+impl AnnIndex for MemoryIndex {
+    fn backend(&self) -> IndexBackend {
+        IndexBackend::Memory
+    }
+
+    fn initialize(&mut self) -> Result<()> {
+        self.vectors.clear();
+        self.deleted.clear();
+        Ok(())
+    }
+
+    fn add_vector(&mut self, vector: &[f32]) -> Result<usize> {
+        self.check_dimension(vector)?;
+        self.vectors.push(vector.to_vec());
+        self.deleted.push(false);
+        self.revision += 1;
+        Ok(self.vectors.len() - 1)
+    }
+
+    fn build_from_vectors(&mut self, vectors: &[Vec<f32>]) -> Result<()> {
+        for vector in vectors {
+            self.check_dimension(vector)?;
+        }
+        self.vectors = vectors.to_vec();
+        self.deleted = vec![false; vectors.len()];
+        self.revision += 1;
+        Ok(())
+    }
+
+    fn search(&self, query_vector: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        self.check_dimension(query_vector)?;
+        let mut results: Vec<SearchResult> = self
+            .vectors
+            .iter()
+            .enumerate()
+            .filter(|&(vector_id, _)| !self.deleted[vector_id])
+            .map(|(vector_id, vector)| SearchResult {
+                vector_id,
+                distance: self.distance(query_vector, vector),
+            })
+            .collect();
+        results.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.vector_id.cmp(&b.vector_id)));
+        results.truncate(k);
+        Ok(results)
+    }
+
+    fn get_vector(&self, vector_id: usize) -> Result<Vec<f32>> {
+        self.vectors.get(vector_id).cloned().with_context(|| {
+            format!("Vector {} out of range ({} vectors)", vector_id, self.vectors.len())
+        })
+    }
+
+    fn delete_vector(&mut self, vector_id: usize) -> Result<()> {
+        let deleted = self.deleted.get_mut(vector_id).with_context(|| {
+            format!("Vector {} out of range ({} vectors)", vector_id, self.vectors.len())
+        })?;
+        *deleted = true;
+        self.revision += 1;
+        Ok(())
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        self.export_archive(path)?;
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn export_archive(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    fn load(&mut self, path: &Path) -> Result<()> {
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let loaded: MemoryIndex = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{:?} is not a saved in-memory index", path))?;
+        self.vector_dim = loaded.vector_dim;
+        self.vectors = loaded.vectors;
+        self.deleted = loaded.deleted;
+        self.revision += 1;
+        self.saved_revision.store(self.revision, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn vector_count(&self) -> usize {
+        self.vectors.len()
+    }
+
+    fn live_count(&self) -> usize {
+        self.deleted.iter().filter(|&&deleted| !deleted).count()
+    }
+
+    fn dimension(&self) -> usize {
+        self.vector_dim
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.saved_revision.load(Ordering::SeqCst) != self.revision
+    }
+}
+
+/// Review metadata held in memory
+#[derive(Default)]
+pub struct MemoryStore {
+    records: Mutex<Vec<ReviewMetadata>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MetadataStore for MemoryStore {
+    fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn append(&self, metadata: &ReviewMetadata) -> Result<usize> {
+        let mut records = self.records.lock().unwrap();
+        records.push(metadata.clone());
+        Ok(records.len() - 1)
+    }
+
+    fn append_batch(&self, batch: &[ReviewMetadata]) -> Result<usize> {
+        let mut records = self.records.lock().unwrap();
+        let first_id = records.len();
+        records.extend_from_slice(batch);
+        Ok(first_id)
+    }
+
+    fn read_by_id(&self, vector_id: usize) -> Result<ReviewMetadata> {
+        self.records
+            .lock()
+            .unwrap()
+            .get(vector_id)
+            .cloned()
+            .with_context(|| format!("Vector ID {} not found", vector_id))
+    }
+
+    fn read_batch(&self, vector_ids: &[usize]) -> Result<Vec<ReviewMetadata>> {
+        let records = self.records.lock().unwrap();
+        Ok(vector_ids.iter().filter_map(|&id| records.get(id).cloned()).collect())
+    }
+
+    fn read_range(&self, start: usize, limit: usize) -> Result<Vec<ReviewMetadata>> {
+        let records = self.records.lock().unwrap();
+        Ok(records.iter().skip(start).take(limit).cloned().collect())
+    }
+
+    fn count_lines(&self) -> Result<usize> {
+        Ok(self.records.lock().unwrap().len())
+    }
+
+    fn replace_many(&self, updates: &[(usize, ReviewMetadata)]) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        if let Some((vector_id, _)) = updates.iter().find(|(id, _)| *id >= records.len()) {
+            anyhow::bail!("Vector ID {} not found", vector_id);
+        }
+        for (vector_id, metadata) in updates {
+            records[*vector_id] = metadata.clone();
+        }
+        Ok(())
+    }
+
+    fn tombstone_matching(&self, pred: &dyn Fn(&ReviewMetadata) -> bool) -> Result<Vec<usize>> {
+        let mut records = self.records.lock().unwrap();
+        let updates = tombstones(records.clone(), pred);
+        for (vector_id, review) in &updates {
+            records[*vector_id] = review.clone();
+        }
+        Ok(updates.into_iter().map(|(id, _)| id).collect())
+    }
+
+    fn replace_all(&self, records: &[ReviewMetadata]) -> Result<()> {
+        *self.records.lock().unwrap() = records.to_vec();
+        Ok(())
+    }
+
+    fn truncate(&self, line_count: usize) -> Result<()> {
+        self.records.lock().unwrap().truncate(line_count);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_all(&self) -> Result<Vec<ReviewMetadata>> {
+        Ok(self.records.lock().unwrap().clone())
+    }
+}
+
+/// Builds a [`TestApp`]
+pub struct TestAppBuilder {
+    config: AppConfig,
+    seed: u64,
+}
+
+impl TestAppBuilder {
+    /// Adjust the config the app starts with
+    pub fn config(mut self, configure: impl FnOnce(&mut AppConfig)) -> Self {
+        configure(&mut self.config);
+        self
+    }
+
+    /// Seed of the [`HashEmbedder`] (default 0)
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn build(self) -> Result<TestApp> {
+        let dir = archive::temp_dir("test_app")?;
+        let mut config = self.config;
+        config.storage.data_dir = dir.clone();
+        config.storage.index_path = dir.join("reviews.index");
+        config.storage.metadata_path = dir.join("reviews.jsonl");
+        config.storage.audit_path = dir.join("audit.jsonl");
+        config.storage.collections_dir = dir.join("collections");
+        config.storage.snapshots_dir = dir.join("snapshots");
+        config.storage.dead_letter_path = dir.join("dead_letter.jsonl");
+        config.index.backend = IndexBackend::Memory;
+
+        let mut index = VectorIndex::new(&config.index);
+        index.initialize()?;
+        let embedder = EmbeddingService::from_provider(
+            &config.embedding.model_name,
+            config.index.vector_dim,
+            HashEmbedder::new(config.index.vector_dim, self.seed),
+        );
+        let embedding_service = Arc::new(OnceLock::new());
+        let _ = embedding_service.set(Arc::new(embedder));
+        let readiness = Arc::new(Readiness::new(&["embedding", "index"]));
+        readiness.set("embedding", ComponentState::Ready);
+        readiness.set("index", ComponentState::Ready);
+
+        let config_handle = Arc::new(ConfigHandle::new(config.clone()));
+        let state = AppState {
+            vector_index: Arc::new(RwLock::new(index)),
+            metadata_store: Arc::new(MemoryStore::new()),
+            collections: Arc::new(CollectionManager::open(&config.storage.collections_dir, &config)?),
+            embedding_service,
+            models: Arc::new(ModelCache::new(config.embedding.max_length, config.embedding.instances)),
+            readiness,
+            write_gate: Arc::new(WriteGate::new()),
+            config: config_handle.clone(),
+            log_control: Arc::new(LogControl::detached()),
+            audit_log: Arc::new(AuditLog::new(&config.storage.audit_path)),
+            webhooks: Arc::new(WebhookDispatcher::new(config_handle)),
+            jobs: Arc::new(JobRegistry::new()),
+            usage: Arc::new(UsageTracker::new()),
+            redactor: Arc::new(RedactorCache::new()),
+            synonyms: Arc::new(Synonyms::default()),
+            spelling: Arc::new(SpellChecker::new()),
+            suggester: Arc::new(Suggester::new()),
+            content_hashes: Arc::new(ContentHashes::new()),
+            point_ids: Arc::new(PointIds::new()),
+            field_vectors: Arc::new(FieldVectors::new()),
+            analytics: Arc::new(SearchAnalytics::new()),
+            idempotency: Arc::new(IdempotencyCache::new()),
+            search_cache: Arc::new(SearchCache::new()),
+            memory: Arc::new(MemoryWatchdog::new()),
+            backpressure: Arc::new(Backpressure::new()),
+            ingest: Arc::new(IngestQueue::new()),
+            group_commit: Arc::new(GroupCommit::new()),
+            latency: Arc::new(LatencyHistograms::new()),
+        };
+
+        Ok(TestApp {
+            router: build_router(state.clone()),
+            state,
+            dir,
+        })
+    }
+}
+
+/// The real router over an in-memory index, metadata store and embedder
+///
+/// Files the app still writes (audit log, collections) go to a temp
+/// folder removed on drop.
+pub struct TestApp {
+    pub state: AppState,
+    router: Router,
+    dir: PathBuf,
+}
+
+impl TestApp {
+    /// Defaults: `AppConfig::default()` and seed 0
+    pub fn builder() -> TestAppBuilder {
+        TestAppBuilder {
+            config: AppConfig::default(),
+            seed: 0,
+        }
+    }
+
+    /// Send a request through the router
+    pub async fn request(&self, request: Request<Body>) -> Response {
+        self.router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router is infallible")
+    }
+
+    pub async fn get(&self, uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        json_response(self.request(request).await).await
+    }
+
+    pub async fn post(&self, uri: &str, body: &impl Serialize) -> (StatusCode, serde_json::Value) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(body).unwrap()))
+            .unwrap();
+        json_response(self.request(request).await).await
+    }
+
+    /// Temp folder holding the app's files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Send a request and read the JSON response
+    pub async fn send(&self, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        json_response(self.request(request).await).await
+    }
+
+    /// Serve the router on a local port, for clients that need a real server
+    pub async fn serve(&self) -> std::net::SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = self.router.clone();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        addr
+    }
+
+    /// The `/metrics` exposition text
+    pub async fn metrics(&self) -> String {
+        let response = self.request(Request::get("/metrics").body(Body::empty()).unwrap()).await;
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A stored review with placeholder fields; adjust the rest with struct
+/// update syntax
+pub fn review(title: &str, product_id: &str) -> ReviewMetadata {
+    ReviewMetadata {
+        review_title: title.to_string(),
+        review_body: "Body".to_string(),
+        product_id: product_id.to_string(),
+        review_rating: 4,
+        revision: 1,
+        deleted: false,
+        expires_at: None,
+        created_at: None,
+        language: None,
+        attributes: Default::default(),
+    }
+}
+
+/// Body of a valid `POST /reviews` for product P1
+pub fn review_request(title: &str) -> serde_json::Value {
+    serde_json::json!({
+        "review_title": title,
+        "review_body": "Bought it last week",
+        "product_id": "P1",
+        "review_rating": 4,
+    })
+}
+
+/// Status and JSON body (`Null` when the body is empty or not JSON)
+async fn json_response(response: Response) -> (StatusCode, serde_json::Value) {
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap_or_default();
+    (status, serde_json::from_slice(&bytes).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_hash_embedder() {
+        let embedder = HashEmbedder::new(64, 7);
+        let battery = embedder.embed("Great battery life");
+        assert_eq!(battery, embedder.embed("great  BATTERY life"));
+        assert_ne!(battery, HashEmbedder::new(64, 8).embed("Great battery life"));
+
+        let norm: f32 = battery.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_add_and_search() {
+        let app = TestApp::builder().build().unwrap();
+        for (title, product) in [("Great battery life", "P1"), ("Terrible screen", "P2")] {
+            let (status, body) = app
+                .post(
+                    "/reviews",
+                    &json!({
+                        "review_title": title,
+                        "review_body": "Bought it last week",
+                        "product_id": product,
+                        "review_rating": 4,
+                    }),
+                )
+                .await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+
+        let (status, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "top_k": 1 }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["results"][0]["product_id"], "P1");
+        assert!(body["results"][0].get("distance").is_none());
+        assert_eq!(app.state.metadata_store.count_lines().unwrap(), 2);
+
+        let (_, body) = app
+            .post("/reviews/search", &json!({ "query": "battery", "include_distance": true }))
+            .await;
+        let top = &body["results"][0];
+        assert_eq!(top["metric"], "l2");
+        let (distance, score) = (top["distance"].as_f64().unwrap(), top["similarity_score"].as_f64().unwrap());
+        assert!(distance >= 0.0 && score > 0.0);
+    }
+}
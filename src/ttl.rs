@@ -0,0 +1,129 @@
+use crate::api::models::AppState;
+use crate::storage::{mask_and_save, now_ms, AuditEntry, MetadataStore};
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Spawn the expiry sweeper
+///
+/// Every `ttl.sweep_interval_secs` (re-read each round; 0 pauses it),
+/// reviews past their `expires_at` are tombstoned in the default collection
+/// and every named one. Searches already skip them before the sweep.
+pub fn spawn_ttl_sweeper(
+    state: AppState,
+    mut shutdown_rx: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let interval = state.config.current().ttl.sweep_interval_secs;
+            tokio::select! {
+                // Paused: check again in a minute in case it was re-enabled
+                _ = tokio::time::sleep(Duration::from_secs(if interval == 0 { 60 } else { interval })) => {}
+                _ = shutdown_rx.wait_for(|&stop| stop) => break,
+            }
+            if interval == 0 || !state.readiness.is_component_ready("index") {
+                continue;
+            }
+
+            sweep(&state).await;
+        }
+    })
+}
+
+/// Tombstone everything that has expired, auditing each collection's batch
+async fn sweep(state: &AppState) {
+    let Some(_write_guard) = state.write_gate.enter() else {
+        return;
+    };
+
+    // The metadata pass doesn't hold the index lock; only masking the
+    // expired vectors takes it
+    let metadata_store = state.metadata_store.clone();
+    let expired = blocking(move || expire(metadata_store.as_ref())).await;
+    if let Ok(ids) = &expired
+        && !ids.is_empty()
+    {
+        state.suggester.invalidate();
+        state.spelling.invalidate();
+        match state.ingest.write_index_owned(&state.vector_index).await {
+            Ok(mut index) => {
+                let (ids, index_path) = (ids.clone(), state.index_path());
+                let _ = tokio::task::spawn_blocking(move || mask_and_save(&mut index, &index_path, &ids)).await;
+            }
+            // Searches skip expired reviews; a rebuild drops their vectors
+            Err(e) => warn!("Expired vectors left unmasked: {}", e.message()),
+        }
+    }
+    record(state, None, expired);
+
+    for collection in state.collections.list() {
+        let task_collection = collection.clone();
+        let expired = blocking(move || {
+            let collection = task_collection;
+            let ids = expire(&collection.metadata)?;
+            if !ids.is_empty() {
+                mask_and_save(&mut collection.index.blocking_write(), &collection.index_path(), &ids);
+            }
+            Ok(ids)
+        })
+        .await;
+        if expired.as_ref().is_ok_and(|ids| !ids.is_empty()) {
+            collection.spelling.invalidate();
+        }
+        record(state, Some(collection.name()), expired);
+    }
+}
+
+fn record(state: &AppState, collection: Option<&str>, expired: Result<Vec<usize>>) {
+    match expired {
+        Ok(ids) if ids.is_empty() => {}
+        Ok(ids) => {
+            info!(collection = ?collection, count = ids.len(), "⌛ Expired reviews removed");
+            state.record_mutation(AuditEntry {
+                ids,
+                detail: collection.map(|name| serde_json::json!({ "collection": name })),
+                ..AuditEntry::new("expire")
+            });
+        }
+        Err(e) => error!(collection = ?collection, "TTL sweep failed: {:#}", e),
+    }
+}
+
+/// Tombstone expired reviews in one store, returning their IDs
+///
+/// Needs no index lock; the caller masks the vectors afterwards.
+pub fn expire(metadata_store: &dyn MetadataStore) -> Result<Vec<usize>> {
+    let now = now_ms();
+    metadata_store.tombstone_matching(&|review| review.is_expired(now))
+}
+
+async fn blocking<T: Send + 'static>(task: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(task).await.context("Sweep task failed")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{review_request, TestApp};
+    use axum::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_sweep() {
+        let app = TestApp::builder().build().unwrap();
+        for title in ["Great battery", "Loud fan"] {
+            let (status, body) = app.post("/reviews", &review_request(title)).await;
+            assert_eq!(status, StatusCode::OK, "{}", body);
+        }
+        let mut expired = app.state.metadata_store.read_by_id(1).unwrap();
+        expired.expires_at = Some(now_ms() - 1);
+        app.state.metadata_store.replace(1, &expired).unwrap();
+
+        sweep(&app.state).await;
+        let stored = app.state.metadata_store.read_all().unwrap();
+        assert_eq!(stored.iter().map(|review| review.deleted).collect::<Vec<_>>(), [false, true]);
+        assert_eq!(stored[1].revision, 2);
+        assert_eq!(app.state.vector_index.read().await.live_count(), 1);
+    }
+}
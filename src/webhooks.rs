@@ -0,0 +1,171 @@
+use crate::config::{ConfigHandle, WebhookEndpoint};
+use crate::storage::AuditEntry;
+use axum::body::Bytes;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Body POSTed to each endpoint
+#[derive(Debug, Serialize)]
+pub struct WebhookEvent<'a> {
+    /// Unique per notification (shared by its retries)
+    pub delivery_id: String,
+    /// Audit action, e.g. "add", "import", "rebuild"
+    pub event: &'a str,
+    pub timestamp_ms: u64,
+    pub ids: &'a [usize],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<&'a serde_json::Value>,
+}
+
+/// Fire-and-forget delivery of mutation events to the configured webhooks
+///
+/// Endpoints and retry settings are read per event, so config reloads apply
+/// to the next mutation. Each delivery runs in its own task and retries with
+/// exponential backoff; failures are logged, never surfaced to the caller.
+pub struct WebhookDispatcher {
+    config: Arc<ConfigHandle>,
+    client: reqwest::Client,
+    next_delivery: AtomicU64,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: Arc<ConfigHandle>) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            next_delivery: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue a notification for `entry` to every subscribed endpoint
+    pub fn notify(&self, entry: &AuditEntry) {
+        let settings = self.config.current().webhooks.clone();
+        let endpoints: Vec<WebhookEndpoint> = settings
+            .endpoints
+            .into_iter()
+            .filter(|e| e.events.is_empty() || e.events.contains(&entry.action))
+            .collect();
+        if endpoints.is_empty() {
+            return;
+        }
+
+        let delivery_id = format!(
+            "{}-{}",
+            entry.timestamp_ms,
+            self.next_delivery.fetch_add(1, Ordering::Relaxed)
+        );
+        let body = match serde_json::to_vec(&WebhookEvent {
+            delivery_id: delivery_id.clone(),
+            event: &entry.action,
+            timestamp_ms: entry.timestamp_ms,
+            ids: &entry.ids,
+            detail: entry.detail.as_ref(),
+        }) {
+            Ok(body) => Bytes::from(body),
+            Err(e) => {
+                warn!("Failed to serialize webhook event: {}", e);
+                return;
+            }
+        };
+
+        for endpoint in endpoints {
+            let client = self.client.clone();
+            let body = body.clone();
+            let event = entry.action.clone();
+            let delivery_id = delivery_id.clone();
+            let retry = Retry {
+                max_retries: settings.max_retries,
+                backoff: Duration::from_millis(settings.backoff_ms),
+                timeout: Duration::from_millis(settings.timeout_ms),
+            };
+            tokio::spawn(async move {
+                deliver(&client, &endpoint, body, &event, &delivery_id, retry).await;
+            });
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Retry {
+    max_retries: u32,
+    backoff: Duration,
+    timeout: Duration,
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    endpoint: &WebhookEndpoint,
+    body: Bytes,
+    event: &str,
+    delivery_id: &str,
+    retry: Retry,
+) {
+    let signature = endpoint.secret.as_deref().map(|secret| sign(secret, &body));
+
+    for attempt in 0..=retry.max_retries {
+        if attempt > 0 {
+            tokio::time::sleep(retry.backoff * 2u32.saturating_pow(attempt - 1)).await;
+        }
+
+        let mut request = client
+            .post(&endpoint.url)
+            .timeout(retry.timeout)
+            .header("content-type", "application/json")
+            .header("x-webhook-event", event)
+            .header("x-webhook-delivery", delivery_id)
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header("x-signature-256", signature);
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!(url = %endpoint.url, event, delivery_id, attempt, "Webhook delivered");
+                return;
+            }
+            Ok(response) => warn!(
+                url = %endpoint.url, event, delivery_id, attempt,
+                status = %response.status(), "Webhook rejected"
+            ),
+            Err(e) => warn!(
+                url = %endpoint.url, event, delivery_id, attempt,
+                "Webhook delivery failed: {}", e
+            ),
+        }
+    }
+
+    warn!(url = %endpoint.url, event, delivery_id, "Giving up on webhook after {} retries", retry.max_retries);
+}
+
+/// `sha256=<hex HMAC-SHA256 of body>`, as sent in `X-Signature-256`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}